@@ -10,8 +10,10 @@
   var ws = null;
   var gameId = "";
   var playerId = "";
+  var playerName = "";
   var playerOrder = [];
   var lobbyRngSeed = null; // Seed from lobby (set at game start by host)
+  var lobbySettings = null; // Match settings the host picked in the lobby
 
   function getApiBase() {
     if (typeof window !== "undefined" && window.__BALLS_WS_BASE) return window.__BALLS_WS_BASE;
@@ -35,6 +37,7 @@
     gameId = match ? match[1] : "";
     var params = new URLSearchParams(window.location.search || "");
     playerId = params.get("playerId") || "";
+    playerName = params.get("playerName") || "";
     try {
       var key = "balls:" + gameId;
       var stored = window.sessionStorage && sessionStorage.getItem(key);
@@ -47,6 +50,9 @@
           if (typeof parsed.rngSeed === "number") {
             lobbyRngSeed = parsed.rngSeed;
           }
+          if (parsed.settings && typeof parsed.settings === "object") {
+            lobbySettings = parsed.settings;
+          }
         }
       }
     } catch (e) {
@@ -105,33 +111,71 @@
     };
   }
 
+  // Lets a JS renderer draw the shell in flight itself: the latest projectile the game
+  // published ({x, y, vx, vy, weapon, trail: [[x, y], ...]}), or null when there isn't one.
+  if (typeof window !== "undefined") {
+    window.__ballsProjectile = function () {
+      if (typeof wasm_exports === "undefined" || !wasm_exports.get_projectile_json) return null;
+      var ptr = wasm_exports.get_projectile_json();
+      var len = wasm_exports.get_projectile_json_len();
+      return JSON.parse(UTF8ToString(new Uint8Array(wasm_memory.buffer, ptr, len), 0, len));
+    };
+
+    // Arsenal UI: arm a weapon by name (false if the game doesn't know it) and read back
+    // which one is armed, so the page needn't track the selection on its own.
+    window.__ballsSetWeapon = function (name) {
+      if (typeof wasm_exports === "undefined" || !wasm_exports.set_weapon) return false;
+      var buf = new TextEncoder().encode(String(name));
+      var ptr = wasm_exports.alloc_buffer(buf.length);
+      if (!ptr) return false;
+      new Uint8Array(wasm_memory.buffer, ptr, buf.length).set(buf);
+      return !!wasm_exports.set_weapon(ptr, buf.length);
+    };
+    window.__ballsSelectedWeapon = function () {
+      if (typeof wasm_exports === "undefined" || !wasm_exports.get_selected_weapon_name) return "";
+      var ptr = wasm_exports.get_selected_weapon_name();
+      var len = wasm_exports.get_selected_weapon_name_len();
+      return UTF8ToString(new Uint8Array(wasm_memory.buffer, ptr, len), 0, len);
+    };
+  }
+
   function on_init() {
     if (!gameId || !playerOrder.length) return;
     console.log("[ws_plugin] on_init called: gameId=" + gameId + ", playerOrder.length=" + playerOrder.length);
 
-    var names = [];
-    var bots = [];
     var serverMyPlayerIndex = null;
     var serverRngSeed = null;
+    var serverSettings = null;
+    var serverLateJoins = 0;
     // Calculate fallback seed from gameId (used only if server doesn't provide one)
     var fallbackSeed = 0;
     for (var i = 0; i < gameId.length; i++) fallbackSeed = ((fallbackSeed << 5) - fallbackSeed + gameId.charCodeAt(i)) | 0;
     fallbackSeed = Math.abs(fallbackSeed >>> 0);
 
-    // Prepare player names and bot flags
-    if (Array.isArray(playerOrder)) {
+    function isSeated() {
       for (var i = 0; i < playerOrder.length; i++) {
-        var p = playerOrder[i];
-        var name = (p && p.name) || ("Player " + (i + 1));
-        var isBot = (p && p.isBot) ? true : false;
-        names.push(name);
-        bots.push(isBot ? "1" : "0");
+        if (playerOrder[i] && playerOrder[i].playerId === playerId) return true;
       }
+      return false;
     }
 
     function sendGameInit() {
       if (serverMyPlayerIndex === null || serverRngSeed === null) return; // Wait for server identity and seed
       console.log("[ws_plugin] sendGameInit: myPlayerIndex=" + serverMyPlayerIndex + ", seed=" + serverRngSeed);
+      // Player names and bot flags
+      var names = [];
+      var bots = [];
+      var ballNames = [];
+      for (var i = 0; i < playerOrder.length; i++) {
+        var p = playerOrder[i];
+        var name = (p && p.name) || ("Player " + (i + 1));
+        var isBot = (p && p.isBot) ? true : false;
+        names.push(name);
+        bots.push(isBot ? "1" : "0");
+        // Optional custom ball names; separators are stripped so they can't split the list
+        var squad = (p && Array.isArray(p.ballNames)) ? p.ballNames : [];
+        ballNames.push(squad.map(function (n) { return String(n).replace(/[,;]/g, ""); }).join(","));
+      }
       var initData = JSON.stringify({
         gameId: gameId,
         playerId: playerId,
@@ -140,6 +184,11 @@
         myPlayerIndex: serverMyPlayerIndex,
         playerNames: names.join(","),
         playerBots: bots.join(","),
+        ballNames: ballNames.join(";"),
+        // The server's copy wins so every player (and late joiners) build the same match
+        settings: serverSettings || lobbySettings || {},
+        // Players at the end of the order who joined mid-match
+        lateJoins: serverLateJoins,
       });
       if (typeof wasm_exports !== "undefined" && wasm_exports.on_game_init) {
         var buf = new TextEncoder().encode(initData);
@@ -180,9 +229,13 @@
         fetch(getHttpBase() + "/game/" + gameId + "/init", {
           method: "POST",
           headers: { "Content-Type": "application/json" },
-          body: JSON.stringify({ playerOrder: playerOrder, rngSeed: seedToSend, terrainId: 0 }),
+          body: JSON.stringify({ playerOrder: playerOrder, rngSeed: seedToSend, terrainId: 0, settings: lobbySettings || {} }),
         }).then(function(r) {
           console.log("[ws_plugin] /init response:", r.status);
+          // Joined by invite after the match started: ask for a seat as a new team
+          if (!isSeated() && ws && ws.readyState === WebSocket.OPEN) {
+            ws.send(JSON.stringify({ type: "join", name: playerName }));
+          }
         }).catch(function (e) {
           console.warn("[ws_plugin] init POST failed", e);
         });
@@ -211,6 +264,10 @@
           console.log("[ws_plugin] Received identity: myPlayerIndex=" + parsed.myPlayerIndex + ", rngSeed=" + parsed.rngSeed);
           serverMyPlayerIndex = parsed.myPlayerIndex;
           if (typeof parsed.rngSeed === "number") serverRngSeed = parsed.rngSeed;
+          if (parsed.settings && typeof parsed.settings === "object") serverSettings = parsed.settings;
+          // The server's roster includes anyone who joined mid-match
+          if (Array.isArray(parsed.playerOrder) && parsed.playerOrder.length) playerOrder = parsed.playerOrder;
+          if (typeof parsed.lateJoins === "number") serverLateJoins = parsed.lateJoins;
           sendGameInit();
           return;
         }
@@ -10,6 +10,16 @@ const API_BASE =
     ? process.env.NEXT_PUBLIC_WS_BASE ?? process.env.NEXT_PUBLIC_API_BASE ?? "https://api.balls.bne.sh"
     : process.env.NEXT_PUBLIC_API_BASE ?? "https://api.balls.bne.sh";
 
+/** What to tell the player when the engine refuses an action (`GameError::name`) */
+const REFUSAL_TEXT: Record<string, string> = {
+  not_your_turn: "It's not your turn",
+  already_fired: "You've already fired this turn",
+  invalid_ball: "That ball can't act",
+  weapon_locked: "That weapon isn't available",
+  out_of_terrain_budget: "Out of terrain budget this turn",
+  wrong_phase: "You can't do that right now",
+};
+
 export default function GameView({ overrideId }: { overrideId?: string } = {}) {
   const params = useParams();
   const gameId = (overrideId ?? params?.id) as string | undefined;
@@ -21,7 +31,7 @@ export default function GameView({ overrideId }: { overrideId?: string } = {}) {
   // Listen for game events emitted by the WASM engine via js_game_event → CustomEvent
   useEffect(() => {
     function handleGameEvent(e: Event) {
-      const ev = (e as CustomEvent<{ type: string; name?: string; damage?: number; hp?: number; winner?: string; ball?: string }>).detail;
+      const ev = (e as CustomEvent<{ type: string; name?: string; damage?: number; hp?: number; winner?: string; ball?: string; contents?: string; victim?: string; count?: number; draw?: boolean; reason?: string }>).detail;
       switch (ev.type) {
         case "hit":
           if (ev.name && ev.damage != null && ev.hp != null) {
@@ -33,14 +43,50 @@ export default function GameView({ overrideId }: { overrideId?: string } = {}) {
             addToast(`${ev.name} has been eliminated!`, "error");
           }
           break;
+        case "forfeit":
+          if (ev.name) {
+            addToast(`${ev.name} forfeited`, "error");
+          }
+          break;
+        case "crate":
+          if (ev.name && ev.contents) {
+            addToast(`${ev.name} picked up a ${ev.contents} crate`, "success");
+          }
+          break;
+        case "first_blood":
+          if (ev.name && ev.victim) {
+            addToast(`First blood! ${ev.name} took out ${ev.victim}`, "success");
+          }
+          break;
+        case "multi_kill":
+          if (ev.name && ev.count != null) {
+            const label = ev.count === 2 ? "Double kill" : ev.count === 3 ? "Triple kill" : `${ev.count}x kill`;
+            addToast(`${label}! ${ev.name}`, "success");
+          }
+          break;
+        case "streak":
+          if (ev.name && ev.count != null) {
+            addToast(`${ev.name} is on a ${ev.count}-kill streak!`, "success");
+          }
+          break;
+        case "zone_shrink":
+          addToast("The safe zone is closing in!", "error");
+          break;
         case "turn_start":
           if (ev.name) {
             const label = ev.ball && ev.ball !== ev.name ? `${ev.name} (${ev.ball})` : ev.name;
             addToast(`${label}'s turn`, "info");
           }
           break;
+        case "action_refused":
+          if (ev.reason) {
+            addToast(REFUSAL_TEXT[ev.reason] ?? "That action was refused", "error");
+          }
+          break;
         case "game_over":
-          if (ev.winner) {
+          if (ev.draw) {
+            addToast("It's a draw!", "info");
+          } else if (ev.winner) {
             addToast(`${ev.winner} wins!`, "success");
           }
           break;
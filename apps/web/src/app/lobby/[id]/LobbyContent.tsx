@@ -15,6 +15,11 @@ import {
 import { getWsUrl, useWebSocket } from "@/lib/ws";
 import { LobbyLoading } from "@/components/LobbyLoading";
 import { useToast } from "@/components/Toast";
+import {
+  MATCH_SETTING_FIELDS,
+  defaultMatchSettings,
+  type MatchSettings,
+} from "@/lib/matchSettings";
 
 interface Player {
   id: string;
@@ -51,6 +56,7 @@ export default function LobbyContent({
   const [copied, setCopied] = useState(false);
   const [myEditName, setMyEditName] = useState(playerNameFromUrl);
   const [isStarting, setIsStarting] = useState(false);
+  const [settings, setSettings] = useState<MatchSettings>(defaultMatchSettings);
   const code = search.get("code") ?? null;
   const hasRedirectedRef = useRef(false);
 
@@ -116,7 +122,7 @@ export default function LobbyContent({
     messageQueueRef.current = [];
 
     let latestPlayerList: Player[] | null = null;
-    let gameStarted: { gameId: string; playerOrder?: unknown; rngSeed?: number } | null = null;
+    let gameStarted: { gameId: string; playerOrder?: unknown; rngSeed?: number; settings?: unknown } | null = null;
 
     for (const msg of queue) {
       if (!msg || typeof msg !== "object") continue;
@@ -127,6 +133,7 @@ export default function LobbyContent({
         gameId?: string;
         playerOrder?: unknown;
         rngSeed?: number;
+        settings?: unknown;
       };
       if (m.type === "error" && m.message) {
         setIsStarting(false);
@@ -134,7 +141,7 @@ export default function LobbyContent({
       } else if (m.type === "player_list" && m.players) {
         latestPlayerList = m.players;
       } else if (m.type === "game_started" && m.gameId) {
-        gameStarted = { gameId: m.gameId, playerOrder: m.playerOrder, rngSeed: m.rngSeed };
+        gameStarted = { gameId: m.gameId, playerOrder: m.playerOrder, rngSeed: m.rngSeed, settings: m.settings };
       }
     }
 
@@ -147,9 +154,10 @@ export default function LobbyContent({
         gameId?: string;
         playerOrder?: unknown;
         rngSeed?: number;
+        settings?: unknown;
       };
       if (m.type === "player_list" && Array.isArray(m.players)) latestPlayerList = m.players;
-      else if (m.type === "game_started" && m.gameId) gameStarted = { gameId: m.gameId, playerOrder: m.playerOrder, rngSeed: m.rngSeed };
+      else if (m.type === "game_started" && m.gameId) gameStarted = { gameId: m.gameId, playerOrder: m.playerOrder, rngSeed: m.rngSeed, settings: m.settings };
       else if (m.type === "error" && m.message) {
         setIsStarting(false);
         addToast(m.message, "error");
@@ -192,6 +200,7 @@ export default function LobbyContent({
         sessionStorage.setItem(`balls:${gameStarted.gameId}`, JSON.stringify({ 
           playerOrder: gameStarted.playerOrder ?? [],
           rngSeed: gameStarted.rngSeed,
+          settings: gameStarted.settings,
         }));
       } catch (_) {}
       window.location.href = `/game/${gameStarted.gameId}?playerId=${encodeURIComponent(playerId)}`;
@@ -240,7 +249,7 @@ export default function LobbyContent({
       return;
     }
     setIsStarting(true);
-    send({ type: "start_game" });
+    send({ type: "start_game", settings });
   };
   const addBot = () => {
     if (readyState !== WebSocket.OPEN) {
@@ -335,6 +344,51 @@ export default function LobbyContent({
               ))}
             </ul>
           </div>
+          {isHost && (
+            <div>
+              <p className="font-display text-sm text-emerald-600/90 tracking-wider mb-3">
+                MATCH SETTINGS
+              </p>
+              <div className="space-y-2 text-sm text-stone-200">
+                {MATCH_SETTING_FIELDS.map((f) => (
+                  <label key={f.key} className="flex items-center justify-between gap-3">
+                    <span>{f.label}</span>
+                    {f.kind === "toggle" ? (
+                      <input
+                        type="checkbox"
+                        checked={settings[f.key] === true}
+                        onChange={(e) => setSettings({ ...settings, [f.key]: e.target.checked })}
+                        className="h-4 w-4 accent-emerald-500"
+                      />
+                    ) : f.kind === "choice" ? (
+                      <select
+                        value={String(settings[f.key])}
+                        onChange={(e) => setSettings({ ...settings, [f.key]: e.target.value })}
+                        className="rounded-lg border-2 border-stone-600 bg-stone-950/80 px-2 py-1 text-stone-100"
+                      >
+                        {f.options.map((o) => (
+                          <option key={o} value={o}>{o}</option>
+                        ))}
+                      </select>
+                    ) : (
+                      <input
+                        type="number"
+                        min={f.min}
+                        max={f.max}
+                        step={f.step}
+                        value={Number(settings[f.key])}
+                        onChange={(e) => {
+                          const v = Number(e.target.value);
+                          if (Number.isFinite(v)) setSettings({ ...settings, [f.key]: Math.min(f.max, Math.max(f.min, v)) });
+                        }}
+                        className="w-24 rounded-lg border-2 border-stone-600 bg-stone-950/80 px-2 py-1 text-stone-100"
+                      />
+                    )}
+                  </label>
+                ))}
+              </div>
+            </div>
+          )}
           {isHost && (
             <>
               <Button
@@ -0,0 +1,37 @@
+/** Match options the host picks in the lobby. Keys are the ones game-core reads from
+ *  the init message; anything left out keeps the game's default. */
+export type MatchSettings = Record<string, string | number | boolean>;
+
+export type MatchSettingField =
+  | { key: string; label: string; kind: "toggle"; default: boolean }
+  | { key: string; label: string; kind: "number"; default: number; min: number; max: number; step: number }
+  | { key: string; label: string; kind: "choice"; default: string; options: string[] };
+
+export const MATCH_SETTING_FIELDS: MatchSettingField[] = [
+  { key: "friendlyFire", label: "Friendly fire", kind: "toggle", default: true },
+  { key: "ownTeamPassThrough", label: "Shots pass own team", kind: "toggle", default: false },
+  { key: "randomLoadout", label: "Random loadouts", kind: "toggle", default: false },
+  { key: "lastStand", label: "Last stand", kind: "toggle", default: false },
+  { key: "mirrorMap", label: "Mirrored map", kind: "toggle", default: false },
+  { key: "movingPlatforms", label: "Moving platforms", kind: "toggle", default: false },
+  { key: "gasPockets", label: "Gas pockets", kind: "toggle", default: false },
+  { key: "fixedPhysics", label: "Fixed-point physics", kind: "toggle", default: false },
+  { key: "halfResTerrain", label: "Low-res map (weak devices)", kind: "toggle", default: false },
+  { key: "terrainTheme", label: "Theme", kind: "choice", default: "grassland", options: ["grassland", "desert", "snow", "volcanic", "random"] },
+  { key: "ballSize", label: "Ball size", kind: "choice", default: "normal", options: ["small", "normal", "large"] },
+  { key: "spawnMode", label: "Spawns", kind: "choice", default: "spread", options: ["spread", "zones"] },
+  { key: "terrainDensity", label: "Terrain density", kind: "number", default: 0.5, min: 0, max: 1, step: 0.1 },
+  { key: "movementRegen", label: "Movement regen", kind: "number", default: 1, min: 0, max: 1, step: 0.1 },
+  { key: "craterPush", label: "Crater rims", kind: "number", default: 0, min: 0, max: 1, step: 0.1 },
+  { key: "blastPush", label: "Blast push", kind: "number", default: 0, min: 0, max: 1, step: 0.1 },
+  { key: "knockbackScale", label: "Knockback", kind: "number", default: 1, min: 0.25, max: 4, step: 0.25 },
+  { key: "weaponDamage", label: "Weapon damage", kind: "number", default: 1, min: 0, max: 1, step: 0.1 },
+  { key: "earthquakeEvery", label: "Earthquake every N turns (0 = off)", kind: "number", default: 0, min: 0, max: 50, step: 1 },
+  { key: "crateRainAfter", label: "Crate rain from turn (0 = off)", kind: "number", default: 0, min: 0, max: 100, step: 1 },
+  { key: "shrinkZoneAfter", label: "Safe zone shrinks from turn (0 = off)", kind: "number", default: 0, min: 0, max: 100, step: 1 },
+  { key: "terrainBudget", label: "Terrain budget per turn (0 = unlimited)", kind: "number", default: 0, min: 0, max: 20000, step: 500 },
+];
+
+export function defaultMatchSettings(): MatchSettings {
+  return Object.fromEntries(MATCH_SETTING_FIELDS.map((f) => [f.key, f.default]));
+}
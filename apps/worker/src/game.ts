@@ -1,14 +1,19 @@
+import { sanitizeSettings } from "./settings";
 import type { GameState } from "./types";
 
 const TURN_TIME_MS = 45_000;
 
 interface BallSnapshot {
-  x: number; y: number; vx: number; vy: number; hp: number; alive: boolean;
+  /** Unset for a late joiner's balls until a client reports where they spawned */
+  x?: number; y?: number; vx?: number; vy?: number; hp: number; alive: boolean; shield: boolean;
 }
 
 interface PersistedGameData {
   gameState: GameState;
   terrainDamageLog: number[][];
+  terrainLogVersion?: number;
+  turnsPlayed?: number;
+  lateJoins?: number;
   ballSnapshots: BallSnapshot[];
   playerIdToIndex: [string, number][];
   phaseStartTime: number;
@@ -17,6 +22,10 @@ interface PersistedGameData {
 const WATCHDOG_GRACE_MS = 5_000;
 /** Max time (ms) a "projectile" phase can last before the server force-advances */
 const PROJECTILE_TIMEOUT_MS = 20_000;
+/** Balls each team fields; matches the game's BALLS_PER_TEAM */
+const BALLS_PER_TEAM = 3;
+/** Most players a match takes, counting those who join mid-match */
+const MAX_PLAYERS = 8;
 
 export class Game implements DurableObject {
   private state: DurableObjectState;
@@ -33,10 +42,22 @@ export class Game implements DurableObject {
   private playerIdToIndex: Map<string, number> = new Map();
   /** Accumulated terrain damage events [[cx,cy,r], ...] for replay on reconnect */
   private terrainDamageLog: number[][] = [];
+  /** Times the clients have compacted the terrain log into a snapshot; a higher version wins */
+  private terrainLogVersion: number = 0;
+  /** Turns started since the match began, as the clients count them (they drive the
+   *  scheduled map events), so a reconnecting client can pick up the count */
+  private turnsPlayed: number = 0;
+  /** Players at the end of playerOrder who joined mid-match as new teams. Clients
+   *  rebuild the starting teams from the seed and add these on top. */
+  private lateJoins: number = 0;
   /** Latest per-ball snapshot (positions + health) for reconnect sync */
   private ballSnapshots: BallSnapshot[] = [];
   /** Timestamp (ms) when the current phase last changed – used by watchdog */
   private phaseStartTime: number = 0;
+  /** Player indices that asked for a rematch since the last restart */
+  private readyPlayers: Set<number> = new Set();
+  /** Player indices that conceded this game; their turns are skipped */
+  private forfeited: Set<number> = new Set();
 
   constructor(state: DurableObjectState, _env: unknown) {
     this.state = state;
@@ -47,6 +68,9 @@ export class Game implements DurableObject {
         if (saved) {
           this.gameState = saved.gameState;
           this.terrainDamageLog = saved.terrainDamageLog ?? [];
+          this.terrainLogVersion = saved.terrainLogVersion ?? 0;
+          this.turnsPlayed = saved.turnsPlayed ?? 0;
+          this.lateJoins = saved.lateJoins ?? 0;
           this.ballSnapshots = saved.ballSnapshots ?? [];
           this.phaseStartTime = saved.phaseStartTime ?? 0;
           this.playerIdToIndex = new Map(saved.playerIdToIndex ?? []);
@@ -60,6 +84,9 @@ export class Game implements DurableObject {
     void this.state.storage.put<PersistedGameData>("gameData", {
       gameState: this.gameState,
       terrainDamageLog: this.terrainDamageLog,
+      terrainLogVersion: this.terrainLogVersion,
+      turnsPlayed: this.turnsPlayed,
+      lateJoins: this.lateJoins,
       ballSnapshots: this.ballSnapshots,
       playerIdToIndex: [...this.playerIdToIndex.entries()],
       phaseStartTime: this.phaseStartTime,
@@ -86,11 +113,14 @@ export class Game implements DurableObject {
       playerOrder?: { playerId: string; isBot: boolean; name: string }[];
       rngSeed?: number;
       terrainId?: number;
+      settings?: unknown;
     };
     this.gameState.playerOrder = body.playerOrder ?? [];
     // Use seed from lobby (always provided via start_game)
     this.gameState.rngSeed = body.rngSeed ?? Math.floor(Math.random() * 0xFFFFFFFF);
     this.gameState.terrainId = body.terrainId ?? 0;
+    // Every client builds its map from these, so the first init's settings stick
+    this.gameState.settings = sanitizeSettings(body.settings);
     this.gameState.inputLog = [];
     this.gameState.currentTurnIndex = 0;
     this.gameState.phase = "aiming";
@@ -98,24 +128,14 @@ export class Game implements DurableObject {
     this.playerIdToIndex.clear();
     this.gameState.playerOrder.forEach((p, i) => this.playerIdToIndex.set(p.playerId, i));
     // Initialise blank ball snapshots — will be filled once ball_state arrives
-    const ballsPerTeam = 3;
-    const totalBalls = (body.playerOrder ?? []).length * ballsPerTeam;
+    const totalBalls = (body.playerOrder ?? []).length * BALLS_PER_TEAM;
     this.ballSnapshots = Array.from({ length: totalBalls }, () => ({
-      x: 0, y: 0, vx: 0, vy: 0, hp: 100, alive: true,
+      x: 0, y: 0, vx: 0, vy: 0, hp: 100, alive: true, shield: false,
     }));
     // Send identity to all already-connected sockets (they connected before /init was called)
     for (const [pid, ws] of this.sockets) {
       const idx = this.playerIdToIndex.get(pid);
-      if (idx !== undefined) {
-        try {
-          ws.send(JSON.stringify({
-            type: "identity",
-            myPlayerIndex: idx,
-            playerId: pid,
-            rngSeed: this.gameState.rngSeed,
-          }));
-        } catch (_) {}
-      }
+      if (idx !== undefined) this.sendIdentity(ws, pid, idx);
     }
     this.broadcast({ type: "state", state: this.gameState });
 
@@ -142,14 +162,7 @@ export class Game implements DurableObject {
     // Send authoritative player identity and game seed
     const myPlayerIndex = this.playerIdToIndex.get(playerId);
     if (myPlayerIndex !== undefined) {
-      try {
-        server.send(JSON.stringify({ 
-          type: "identity", 
-          myPlayerIndex,
-          playerId,
-          rngSeed: this.gameState.rngSeed
-        }));
-      } catch (_) {}
+      this.sendIdentity(server, playerId, myPlayerIndex);
 
       // On reconnect, send terrain damage log and a comprehensive resync message
       // so the client can fully restore game state without a reset.
@@ -157,6 +170,7 @@ export class Game implements DurableObject {
         try {
           server.send(JSON.stringify({
             type: "terrain_sync",
+            version: this.terrainLogVersion,
             log: this.terrainDamageLog,
           }));
         } catch (_) {}
@@ -174,6 +188,7 @@ export class Game implements DurableObject {
           phase: this.gameState.phase,
           currentTurnIndex: this.gameState.currentTurnIndex,
           turnTimeRemainingMs,
+          turnsPlayed: this.turnsPlayed > 0 ? this.turnsPlayed : undefined,
           // Only ship authoritative ball data once we have real positions from clients
           balls: gameHasProgressed ? this.ballSnapshots : undefined,
         }));
@@ -185,6 +200,42 @@ export class Game implements DurableObject {
     return new Response(null, { status: 101, webSocket: client });
   }
 
+  /** The player's seat, the game seed and everything else a client needs to build the
+   *  same match: settings, and the roster including anyone who joined mid-match. */
+  private sendIdentity(ws: WebSocket, playerId: string, myPlayerIndex: number): void {
+    try {
+      ws.send(JSON.stringify({
+        type: "identity",
+        myPlayerIndex,
+        playerId,
+        rngSeed: this.gameState.rngSeed,
+        settings: this.gameState.settings,
+        playerOrder: this.gameState.playerOrder,
+        lateJoins: this.lateJoins,
+      }));
+    } catch (_) {}
+  }
+
+  /** Seat a player who wasn't in the match when it started as a new team, after the
+   *  existing ones. Every client adds the team when the `join` broadcast arrives. */
+  private joinAsNewTeam(ws: WebSocket, playerId: string, rawName: unknown): void {
+    const player = this.gameState.playerOrder.length;
+    if (player === 0 || player >= MAX_PLAYERS) return;
+    // Quotes and backslashes would break the game's JSON reading; commas separate names
+    // in its init message
+    const name = String(rawName ?? "").replace(/[",;\\]/g, "").trim().slice(0, 32) || `Player ${player + 1}`;
+    this.gameState.playerOrder.push({ playerId, isBot: false, name });
+    this.playerIdToIndex.set(playerId, player);
+    this.lateJoins++;
+    for (let i = 0; i < BALLS_PER_TEAM; i++) {
+      this.ballSnapshots.push({ hp: 100, alive: true, shield: false });
+    }
+    this.persistState();
+    this.sendIdentity(ws, playerId, player);
+    this.broadcast({ type: "join", player, name, bot: false });
+    this.broadcast({ type: "state", state: this.gameState });
+  }
+
   private broadcast(msg: { type: string; [k: string]: unknown }): void {
     // Inject a relative turnTimeRemainingMs alongside any absolute turnEndTime so
     // WASM clients don't need wall-clock math to compute the remaining time.
@@ -202,9 +253,52 @@ export class Game implements DurableObject {
     }
   }
 
+  /** Start a new game with `seed` on every client, clearing per-game server state. */
+  private restartGame(seed: number): void {
+    this.gameState.rngSeed = seed;
+    this.gameState.inputLog = [];
+    this.gameState.currentTurnIndex = 0;
+    this.gameState.phase = "aiming";
+    this.gameState.turnEndTime = Date.now() + TURN_TIME_MS;
+    this.phaseStartTime = Date.now();
+    this.ballSnapshots = [];
+    this.terrainDamageLog = [];
+    this.terrainLogVersion = 0;
+    this.turnsPlayed = 0;
+    // Clients restart with every current team as a starting one
+    this.lateJoins = 0;
+    this.readyPlayers.clear();
+    this.forfeited.clear();
+    this.broadcast({ type: "restart", seed });
+    this.broadcast({ type: "state", state: this.gameState });
+    this.scheduleWatchdog();
+    this.persistState();
+  }
+
+  /** Whether the match is over: at most one team still has a ball standing, going by the
+   *  latest ball_state, with conceded teams counted as out. */
+  private isGameOver(): boolean {
+    // Starting teams' balls interleave; each late joiner's follow on as a block
+    const starting = this.gameState.playerOrder.length - this.lateJoins;
+    const standing = new Set<number>();
+    this.ballSnapshots.forEach((b, i) => {
+      const team = i < starting * BALLS_PER_TEAM
+        ? i % starting
+        : starting + Math.floor((i - starting * BALLS_PER_TEAM) / BALLS_PER_TEAM);
+      if (b.alive && !this.forfeited.has(team)) standing.add(team);
+    });
+    return standing.size <= 1;
+  }
+
   private advanceTurn(): void {
-    this.gameState.currentTurnIndex =
-      (this.gameState.currentTurnIndex + 1) % this.gameState.playerOrder.length;
+    const count = this.gameState.playerOrder.length;
+    let next = this.gameState.currentTurnIndex;
+    for (let i = 0; i < count; i++) {
+      next = (next + 1) % count;
+      if (!this.forfeited.has(next)) break;
+    }
+    this.gameState.currentTurnIndex = next;
+    this.turnsPlayed++;
     this.gameState.phase = "aiming";
     this.gameState.turnEndTime = Date.now() + TURN_TIME_MS;
     this.phaseStartTime = Date.now();
@@ -351,8 +445,9 @@ export class Game implements DurableObject {
     fireInput: string;
   } {
     const idx = this.gameState.currentTurnIndex;
-    const numPlayers = this.gameState.playerOrder.length;
-    const ballsPerTeam = 3;
+    // Only the starting teams interleave; late joiners' balls come after theirs
+    const numPlayers = this.gameState.playerOrder.length - this.lateJoins;
+    const ballsPerTeam = BALLS_PER_TEAM;
 
     interface BallData { x: number; y: number; hp: number; alive: boolean }
     const balls: BallData[] = [];
@@ -361,7 +456,8 @@ export class Game implements DurableObject {
     const snapshots = this.ballSnapshots;
     if (snapshots.length > 0) {
       for (const b of snapshots) {
-        balls.push({ x: b.x, y: b.y, hp: b.hp, alive: b.alive });
+        // A late joiner's ball isn't a target until a client reports where it is
+        balls.push({ x: b.x ?? 0, y: b.y ?? 0, hp: b.hp, alive: b.alive && b.x !== undefined });
       }
     }
 
@@ -455,6 +551,24 @@ export class Game implements DurableObject {
     }
     if (!playerId) return;
     const idx = this.playerIdToIndex.get(playerId);
+
+    // Map pings are purely cosmetic, so anyone connected (spectators too) may send one.
+    // They're relayed with the sender's team, or -1 for spectators.
+    try {
+      const parsed = JSON.parse(data) as { type: string; x?: unknown; y?: unknown; name?: unknown };
+      if (parsed.type === "ping") {
+        if (typeof parsed.x === "number" && typeof parsed.y === "number") {
+          this.broadcast({ type: "ping", x: Math.round(parsed.x), y: Math.round(parsed.y), team: idx ?? -1 });
+        }
+        return;
+      }
+      // A spectator asking to play drops in as a new team
+      if (parsed.type === "join") {
+        if (idx === undefined) this.joinAsNewTeam(ws, playerId, parsed.name);
+        return;
+      }
+    } catch (_) {}
+
     if (idx === undefined) return;
 
     // Accept terrain_damages from ANY connected player (not just current turn)
@@ -462,9 +576,14 @@ export class Game implements DurableObject {
     try {
       const parsed = JSON.parse(data) as { type: string; [k: string]: unknown };
       if (parsed.type === "terrain_damages") {
-        const dmgMsg = parsed as { type: string; log?: number[][] };
-        if (Array.isArray(dmgMsg.log) && dmgMsg.log.length >= this.terrainDamageLog.length) {
+        const dmgMsg = parsed as { type: string; version?: number; log?: number[][] };
+        // A compacted log (higher version) is shorter but newer, so it replaces ours outright
+        const version = typeof dmgMsg.version === "number" ? dmgMsg.version : 0;
+        const newer = version > this.terrainLogVersion
+          || (version === this.terrainLogVersion && (dmgMsg.log?.length ?? 0) >= this.terrainDamageLog.length);
+        if (Array.isArray(dmgMsg.log) && newer) {
           this.terrainDamageLog = dmgMsg.log;
+          this.terrainLogVersion = version;
           this.persistState();
         }
         return;
@@ -486,6 +605,42 @@ export class Game implements DurableObject {
         this.broadcast(parsed as { type: string; [k: string]: unknown });
         return;
       }
+      // Terrain heal handshake: the active player sends block hashes as its turn ends, a
+      // client that disagrees asks for those blocks and the hasher answers with them.
+      // Requests come from players waiting for their turn and the answer arrives after
+      // the turn has moved on, so all three are relayed from any seated player.
+      if (
+        parsed.type === "terrain_hashes"
+        || parsed.type === "terrain_heal_request"
+        || parsed.type === "terrain_block_patch"
+      ) {
+        this.broadcast(parsed as { type: string; [k: string]: unknown });
+        return;
+      }
+      // Rematch handshake after game over: any player may confirm, and once every
+      // human player has, restart everyone with a fresh seed. Bots never need to confirm.
+      // A ready sent mid-match is dropped, so nobody can restart a game still being played.
+      if (parsed.type === "ready") {
+        if (!this.isGameOver()) return;
+        this.readyPlayers.add(idx);
+        this.broadcast({ type: "ready", ready: [...this.readyPlayers].sort((a, b) => a - b) });
+        const allReady = this.gameState.playerOrder.every((p, i) => p.isBot || this.readyPlayers.has(i));
+        if (allReady) {
+          this.restartGame(Math.floor(Math.random() * 0xffffffff));
+        }
+        return;
+      }
+      // Any player may concede at any time. Clients remove that team themselves; if it
+      // was their turn, play moves on to the next player still in the game.
+      if (parsed.type === "forfeit") {
+        if (this.forfeited.has(idx)) return;
+        this.forfeited.add(idx);
+        this.broadcast({ type: "forfeit", player: idx });
+        if (this.gameState.currentTurnIndex === idx) {
+          this.advanceTurnAndMaybeBot();
+        }
+        return;
+      }
     } catch (_) {}
 
     // All other message types require it to be the current turn player
@@ -496,24 +651,6 @@ export class Game implements DurableObject {
 
     try {
       const msg = JSON.parse(data) as { type: string; input?: string; aim?: number };
-        // Allow clients to request a restart which we broadcast to all clients
-        if (msg.type === "restart" && typeof (msg as any).seed === "number") {
-          const seed = (msg as any).seed as number;
-          // Reset server-side minimal state for the new game
-          this.gameState.rngSeed = seed;
-          this.gameState.inputLog = [];
-          this.gameState.currentTurnIndex = 0;
-          this.gameState.phase = "aiming";
-          this.gameState.turnEndTime = Date.now() + TURN_TIME_MS;
-          this.phaseStartTime = Date.now();
-          this.ballSnapshots = [];
-          this.terrainDamageLog = [];
-          this.broadcast({ type: "restart", seed });
-          this.broadcast({ type: "state", state: this.gameState });
-          this.scheduleWatchdog();
-          this.persistState();
-          return;
-        }
       if (msg.type === "input" && typeof msg.input === "string") {
         // Check if this is a firing action (not movement)
         const isFiring = msg.input.includes('"Fire"');
@@ -537,9 +674,12 @@ export class Game implements DurableObject {
       } else if (msg.type === "aim" && typeof msg.aim === "number") {
         // Broadcast aim angle updates without changing game state
         this.broadcast({ type: "aim", aim: msg.aim, turnIndex: this.gameState.currentTurnIndex });
+      } else if (msg.type === "weapon_select" && typeof (msg as any).weapon === "string") {
+        // Relay the armed weapon so other clients show the right aim preview
+        this.broadcast({ type: "weapon_select", weapon: (msg as any).weapon, turnIndex: this.gameState.currentTurnIndex });
       } else if (msg.type === "ball_state") {
         // Update per-ball snapshots (health + alive + positions) from active player
-        const bs = msg as { balls?: Array<{x?: number; y?: number; vx?: number; vy?: number; hp?: number; alive?: boolean}> };
+        const bs = msg as { balls?: Array<{x?: number; y?: number; vx?: number; vy?: number; hp?: number; alive?: boolean; shield?: boolean}> };
         if (Array.isArray(bs.balls)) {
           bs.balls.forEach((b, i) => {
             if (i < this.ballSnapshots.length) {
@@ -550,6 +690,7 @@ export class Game implements DurableObject {
               if (typeof b.vy === "number") s.vy = b.vy;
               if (typeof b.hp === "number") s.hp = b.hp;
               if (typeof b.alive === "boolean") s.alive = b.alive;
+              if (typeof b.shield === "boolean") s.shield = b.shield;
             }
           });
         }
@@ -557,6 +698,9 @@ export class Game implements DurableObject {
         this.broadcast(msg as { type: string; [k: string]: unknown });
         this.persistState();
       } else if (msg.type === "end_turn") {
+        // The client's count of the turn that just ended; the next one starts after it
+        const turn = (msg as { turn?: unknown }).turn;
+        if (typeof turn === "number") this.turnsPlayed = Math.max(this.turnsPlayed, turn);
         this.advanceTurn();
         this.maybeBotTurn();
       }
@@ -1,4 +1,5 @@
-import type { Player } from "./types";
+import { sanitizeSettings } from "./settings";
+import type { MatchSettings, Player } from "./types";
 
 const TURN_TIME_MS = 45_000;
 
@@ -32,6 +33,8 @@ export class Lobby implements DurableObject {
   private gameId: string | null = null;
   private gamePlayerOrder: { playerId: string; isBot: boolean; name: string }[] = [];
   private rngSeed: number = 0;
+  /** Match settings the host started the game with */
+  private settings: MatchSettings = {};
   private sockets: Map<string, WebSocket> = new Map();
   private playerIdToSocket: Map<string, string> = new Map();
 
@@ -47,6 +50,7 @@ export class Lobby implements DurableObject {
         gameId: string | null;
         gamePlayerOrder: { playerId: string; isBot: boolean; name: string }[];
         rngSeed?: number;
+        settings?: MatchSettings;
       }>("lobby");
       if (stored) {
         this.lobbyCode = stored.lobbyCode;
@@ -56,6 +60,7 @@ export class Lobby implements DurableObject {
         this.gameId = stored.gameId;
         this.gamePlayerOrder = stored.gamePlayerOrder ?? [];
         this.rngSeed = stored.rngSeed ?? 0;
+        this.settings = stored.settings ?? {};
       }
     });
   }
@@ -108,9 +113,6 @@ export class Lobby implements DurableObject {
     if (code !== this.lobbyCode) {
       return Response.json({ error: "Invalid or expired code" }, { status: 404 });
     }
-    if (this.started) {
-      return Response.json({ error: "Game already started" }, { status: 400 });
-    }
     const usedNames = new Set(this.players.map((p) => p.name));
     const rawName = (body.playerName ?? "").trim().slice(0, 32);
     const playerName = rawName || pickFunnyName(usedNames);
@@ -138,9 +140,6 @@ export class Lobby implements DurableObject {
 
   /** Internal: add a player (called by Worker when join targets this lobby by code). */
   async handleAddPlayer(body: { playerName?: string }): Promise<Response> {
-    if (this.started) {
-      return Response.json({ error: "Game already started" }, { status: 400 });
-    }
     const usedNames = new Set(this.players.map((p) => p.name));
     const rawName = (body.playerName ?? "").trim().slice(0, 32);
     const playerName = rawName || pickFunnyName(usedNames);
@@ -198,6 +197,7 @@ export class Lobby implements DurableObject {
       gameId: this.gameId,
       gamePlayerOrder: this.gamePlayerOrder,
       rngSeed: this.rngSeed,
+      settings: this.settings,
     });
   }
 
@@ -232,7 +232,7 @@ export class Lobby implements DurableObject {
     }
     if (!playerId) return;
     try {
-      const msg = JSON.parse(data) as { type: string; ready?: boolean; playerId?: string; playerName?: string };
+      const msg = JSON.parse(data) as { type: string; ready?: boolean; playerId?: string; playerName?: string; settings?: unknown };
       if (msg.type === "get_player_list") {
         this.sendPlayerListTo(playerId);
         return;
@@ -267,8 +267,15 @@ export class Lobby implements DurableObject {
         }));
         // Generate random seed for consistent terrain/randomness across all players
         this.rngSeed = Math.floor(Math.random() * 0xFFFFFFFF);
+        this.settings = sanitizeSettings(msg.settings);
         await this.persist();
-        this.broadcast({ type: "game_started", gameId, playerOrder: this.gamePlayerOrder, rngSeed: this.rngSeed });
+        this.broadcast({
+          type: "game_started",
+          gameId,
+          playerOrder: this.gamePlayerOrder,
+          rngSeed: this.rngSeed,
+          settings: this.settings,
+        });
       } else if (msg.type === "add_bot") {
         if (this.hostId !== playerId) {
           this.sendTo(playerId, { type: "error", message: "Only host can add bots" });
@@ -0,0 +1,19 @@
+import type { MatchSettings } from "./types";
+
+const MAX_SETTINGS = 32;
+
+/** Keep only flat key -> string/number/boolean pairs from a client's match settings,
+ *  so nothing else gets stored and forwarded to every player's game. */
+export function sanitizeSettings(raw: unknown): MatchSettings {
+  const out: MatchSettings = {};
+  if (!raw || typeof raw !== "object" || Array.isArray(raw)) return out;
+  for (const [key, value] of Object.entries(raw as Record<string, unknown>).slice(0, MAX_SETTINGS)) {
+    if (!/^[A-Za-z]{1,32}$/.test(key)) continue;
+    if (typeof value === "boolean" || (typeof value === "number" && Number.isFinite(value))) {
+      out[key] = value;
+    } else if (typeof value === "string" && /^[a-z]{1,16}$/.test(value)) {
+      out[key] = value;
+    }
+  }
+  return out;
+}
@@ -5,6 +5,9 @@ export interface Player {
   isBot?: boolean;
 }
 
+/** Match options picked by the lobby host, keyed as game-core reads them */
+export type MatchSettings = Record<string, string | number | boolean>;
+
 export interface LobbyState {
   lobbyCode: string;
   hostId: string;
@@ -20,12 +23,12 @@ export type LobbyMessage =
   | { type: "set_ready"; playerId: string; ready: boolean }
   | { type: "add_bot"; player: Player }
   | { type: "remove_bot"; playerId: string }
-  | { type: "game_started"; gameId: string; playerOrder?: { playerId: string; isBot: boolean; name: string }[]; rngSeed?: number }
+  | { type: "game_started"; gameId: string; playerOrder?: { playerId: string; isBot: boolean; name: string }[]; rngSeed?: number; settings?: MatchSettings }
   | { type: "error"; message: string };
 
 export type LobbyClientMessage =
   | { type: "set_ready"; ready: boolean }
-  | { type: "start_game" }
+  | { type: "start_game"; settings?: MatchSettings }
   | { type: "add_bot" }
   | { type: "remove_bot"; playerId: string };
 
@@ -37,6 +40,7 @@ export interface GameState {
   phase: string;
   rngSeed: number;
   terrainId: number;
+  settings?: MatchSettings;
 }
 
 export type GameMessage =
@@ -49,4 +53,4 @@ export type GameMessage =
 export type GameClientMessage =
   | { type: "input"; input: string }
   | { type: "aim"; aim: number }
-  | { type: "end_turn" };
+  | { type: "end_turn"; turn?: number };
@@ -0,0 +1,165 @@
+use crate::physics::Ball;
+use crate::projectile::Explosion;
+use crate::terrain::{self, Terrain};
+use macroquad::prelude::*;
+
+/// Damage a barrel soaks up before it goes off
+pub const BARREL_HP: i32 = 20;
+/// Half the barrel's width and height, used for resting on the ground and direct hits
+pub const BARREL_HALF_W: f32 = 5.0;
+pub const BARREL_HALF_H: f32 = 7.0;
+
+const BARREL_RADIUS: f32 = 30.0;
+const BARREL_DAMAGE: i32 = 35;
+const BARREL_GRAVITY: f32 = 300.0;
+const BARREL_MAX_FALL: f32 = 400.0;
+/// Barrels keep this far from every spawn so nobody starts the match next to one
+const SPAWN_CLEARANCE: f32 = 40.0;
+
+/// A neutral explosive barrel placed with the map. Blasts wear it down and a direct
+/// hit from a projectile sets it off; once its HP is gone it explodes on the next tick,
+/// so one barrel can set off its neighbours.
+pub struct Barrel {
+    pub x: f32,
+    pub y: f32,
+    pub vy: f32,
+    pub hp: i32,
+    pub alive: bool,
+}
+
+impl Barrel {
+    /// Whether the barrel has taken enough damage to go off.
+    pub fn primed(&self) -> bool {
+        self.alive && self.hp <= 0
+    }
+
+    /// Whether a projectile at (`x`, `y`) is touching the barrel.
+    pub fn struck_by(&self, x: f32, y: f32) -> bool {
+        self.alive && (x - self.x).abs() <= BARREL_HALF_W + 2.0 && (y - self.y).abs() <= BARREL_HALF_H + 2.0
+    }
+
+    /// Wear the barrel down by a blast, falling off to nothing at the same 1.5x radius
+    /// reach as ball damage. A blast right next to it always sets it off.
+    pub fn take_blast(&mut self, exp: &Explosion) {
+        if !self.alive || exp.is_water || exp.radius <= 0.0 {
+            return;
+        }
+        let reach = exp.radius * 1.5;
+        let (dx, dy) = (self.x - exp.x, self.y - exp.y);
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist < reach {
+            self.hp -= (BARREL_HP as f32 * 2.0 * (1.0 - dist / reach)).ceil() as i32;
+        }
+    }
+
+    /// Fall until resting on terrain (and again if the ground is blasted away). A barrel
+    /// that sinks into the water is lost without exploding.
+    pub fn tick(&mut self, terrain: &Terrain, dt: f32) {
+        if !self.alive {
+            return;
+        }
+        let below = (self.y + BARREL_HALF_H) as i32;
+        if terrain.is_solid(self.x as i32, below + 1) {
+            self.vy = 0.0;
+            while terrain.is_solid(self.x as i32, (self.y + BARREL_HALF_H) as i32) && self.y > 0.0 {
+                self.y -= 1.0;
+            }
+        } else {
+            self.vy = (self.vy + BARREL_GRAVITY * dt).min(BARREL_MAX_FALL);
+            self.y += self.vy * dt;
+        }
+        if self.y - BARREL_HALF_H >= terrain.water_level() || self.y > terrain.height as f32 + 50.0 {
+            self.alive = false;
+        }
+    }
+
+    pub fn explode(&mut self, terrain: &mut Terrain, balls: &mut [Ball]) -> Explosion {
+        self.alive = false;
+        terrain.apply_damage(self.x as i32, self.y as i32, BARREL_RADIUS as i32);
+
+        let blast_radius = BARREL_RADIUS * 1.8;
+        let r2 = blast_radius * blast_radius;
+        for w in balls.iter_mut() {
+            if !w.alive {
+                continue;
+            }
+            let dx = w.x - self.x;
+            let dy = w.y - self.y;
+            let dist_sq = dx * dx + dy * dy;
+            if dist_sq < r2 {
+                let dist = dist_sq.sqrt().max(1.0);
+                let factor = 1.0 - (dist / blast_radius).min(1.0);
+                let dmg = (BARREL_DAMAGE as f32 * factor) as i32;
+                if dmg > 0 {
+                    w.take_weapon_damage(dmg, None);
+                }
+                let knock = 280.0 * factor;
+                w.apply_knockback(dx / dist * knock, dy / dist * knock - 150.0 * factor);
+            }
+        }
+
+        Explosion { x: self.x, y: self.y, radius: BARREL_RADIUS, is_water: false }
+    }
+
+    pub fn draw(&self) {
+        let (x, y) = (self.x - BARREL_HALF_W, self.y - BARREL_HALF_H);
+        let (w, h) = (BARREL_HALF_W * 2.0, BARREL_HALF_H * 2.0);
+        draw_rectangle(x, y, w, h, Color::new(0.75, 0.18, 0.12, 1.0));
+        // Hoops, with a hazard stripe that goes yellow once the barrel is about to blow
+        let stripe = if self.hp <= BARREL_HP / 2 {
+            Color::new(1.0, 0.85, 0.1, 1.0)
+        } else {
+            Color::new(0.35, 0.08, 0.06, 1.0)
+        };
+        draw_rectangle(x, y + 3.0, w, 1.5, Color::new(0.35, 0.08, 0.06, 1.0));
+        draw_rectangle(x, self.y - 1.0, w, 2.0, stripe);
+        draw_rectangle(x, y + h - 4.5, w, 1.5, Color::new(0.35, 0.08, 0.06, 1.0));
+        draw_rectangle_lines(x, y, w, h, 1.0, Color::new(0.25, 0.05, 0.04, 1.0));
+    }
+}
+
+/// Seeded barrels resting on the land surface, one per evenly spaced band with a few
+/// bands left empty. Spots over water, on lava or close to a spawned ball are skipped.
+/// On a mirrored map the left half is generated and reflected so both sides match.
+pub fn generate(seed: u32, terrain: &Terrain, balls: &[Ball], mirror: bool) -> Vec<Barrel> {
+    let mut s = crate::lcg(seed ^ 0xBA22_E151);
+    let mut rand = || {
+        s = crate::lcg(s);
+        ((s >> 16) & 0x7FFF) as f32 / 32768.0
+    };
+    let width = terrain.width as f32;
+    let land_end = if mirror { width / 2.0 } else { terrain.land_end_x() };
+    let land_w = land_end - terrain.land_start_x();
+    let bands = if mirror { 4 } else { 7 };
+    let mut barrels = Vec::new();
+    for i in 0..bands {
+        let keep = rand() < 0.7;
+        let x = (terrain.land_start_x() + land_w * (i as f32 + 0.2 + rand() * 0.6) / bands as f32) as i32;
+        if !keep {
+            continue;
+        }
+        let Some(surface) = terrain.find_surface_y(x) else { continue };
+        let y = surface as f32 - BARREL_HALF_H - 1.0;
+        if y + BARREL_HALF_H >= terrain.water_level()
+            || terrain.get(x, surface - 1) == terrain::WATER
+            || terrain.get(x, surface) == terrain::LAVA
+        {
+            continue;
+        }
+        let mut spots = vec![x as f32];
+        if mirror {
+            spots.push((terrain.width as i32 - 1 - x) as f32);
+        }
+        // Drop the pair together so the mirrored sides stay identical
+        let near_ball = spots.iter().any(|&sx| {
+            balls.iter().any(|b| (b.x - sx).abs() < SPAWN_CLEARANCE && (b.y - y).abs() < SPAWN_CLEARANCE * 2.0)
+        });
+        if near_ball {
+            continue;
+        }
+        for sx in spots {
+            barrels.push(Barrel { x: sx, y, vy: 0.0, hp: BARREL_HP, alive: true });
+        }
+    }
+    barrels
+}
@@ -0,0 +1,140 @@
+use crate::net_input::NetInput;
+use crate::physics::Ball;
+use crate::projectile;
+use crate::terrain::Terrain;
+use crate::weapons::Weapon;
+
+/// The built-in bot fires a homing missile at this power, or a bazooka when its
+/// team's loadout doesn't include the missile.
+pub const BOT_WEAPON: Weapon = Weapon::HomingMissile;
+pub const BOT_POWER: f32 = 80.0;
+/// Charges the bot tries, in order, when looking for a shot that isn't blocked
+const BOT_POWERS: [f32; 6] = [BOT_POWER, 70.0, 60.0, 50.0, 40.0, 30.0];
+/// Aim this far (radians) above the straight line to the target so the missile arcs in
+const AIM_LIFT: f32 = 0.25;
+/// Furthest (radians) either side of the straight line the bot looks for a clear shot,
+/// and the step it looks in
+const AIM_SEARCH: f32 = 1.2;
+const AIM_SEARCH_STEP: f32 = 0.05;
+
+/// Angle (radians) from `shooter` toward the nearest living ball on another team.
+/// Returns None when no enemies are left.
+pub fn aim_at_nearest_enemy(balls: &[Ball], shooter: usize) -> Option<f32> {
+    let me = balls.get(shooter)?;
+    let mut best: Option<(f32, f32)> = None;
+    for w in balls {
+        if !w.alive || w.team == me.team { continue; }
+        let dx = w.x - me.x;
+        let dy = w.y - me.y;
+        let dist = (dx * dx + dy * dy).sqrt();
+        if best.is_none_or(|(d, _)| dist < d) {
+            best = Some((dist, dy.atan2(dx) - AIM_LIFT));
+        }
+    }
+    best.map(|(_, angle)| angle)
+}
+
+/// An aim and charge for `weapon` whose simulated flight from `shooter` gets to a living
+/// enemy before it hits terrain. Enemies are tried nearest first, and for each the bot's
+/// usual charge before softer ones and the tilts closest to its usual lift first.
+/// None when every shot is blocked.
+pub fn clear_shot(balls: &[Ball], shooter: usize, terrain: &Terrain, wind: f32, weapon: Weapon) -> Option<(f32, f32)> {
+    let me = balls.get(shooter)?;
+    let dist = |w: &Ball| (w.x - me.x).hypot(w.y - me.y);
+    let mut enemies: Vec<&Ball> = balls.iter().filter(|w| w.alive && w.team != me.team).collect();
+    enemies.sort_by(|a, b| dist(a).total_cmp(&dist(b)));
+
+    let steps = (AIM_SEARCH / AIM_SEARCH_STEP) as i32;
+    let mut lifts: Vec<f32> = (-steps..=steps).map(|i| i as f32 * AIM_SEARCH_STEP).collect();
+    lifts.sort_by(|a, b| (a - AIM_LIFT).abs().total_cmp(&(b - AIM_LIFT).abs()));
+
+    let offset = me.radius + 4.0;
+    for target in enemies {
+        let reach = (weapon.explosion_radius() * 0.5).max(target.radius);
+        let straight = (target.y - me.y).atan2(target.x - me.x);
+        for power in BOT_POWERS {
+            for &lift in &lifts {
+                let angle = weapon.clamp_aim(straight - lift);
+                let (sx, sy) = (me.x + angle.cos() * offset, me.y + angle.sin() * offset);
+                let path = projectile::simulate_trajectory(sx, sy, angle, power, weapon, wind, terrain);
+                if path.iter().any(|&(x, y)| (x - target.x).hypot(y - target.y) <= reach) {
+                    return Some((angle, power));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The Fire input the bot controlling `shooter` plays, in the same form a human
+/// client sends over the network. `loadout` is the weapons the shooter's team may use.
+/// It takes a clear shot, or fires straight at the nearest enemy when there isn't one.
+pub fn fire_input(balls: &[Ball], shooter: usize, loadout: &[Weapon], terrain: &Terrain, wind: f32) -> Option<NetInput> {
+    let weapon = if loadout.contains(&BOT_WEAPON) { BOT_WEAPON } else { Weapon::Bazooka };
+    let (angle, power) = clear_shot(balls, shooter, terrain, wind, weapon)
+        .or_else(|| Some((aim_at_nearest_enemy(balls, shooter)?, BOT_POWER)))?;
+    Some(NetInput::Fire { weapon: Some(weapon), angle_deg: angle.to_degrees(), power_percent: power })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ball(x: f32, y: f32, team: u32) -> Ball {
+        Ball::new(x, y, team, String::new())
+    }
+
+    #[test]
+    fn aims_at_the_nearest_living_enemy() {
+        let mut balls = vec![ball(500.0, 400.0, 0), ball(520.0, 400.0, 0), ball(300.0, 400.0, 1), ball(800.0, 400.0, 1)];
+        // Straight left to the enemy at x=300, lifted above the line
+        let angle = aim_at_nearest_enemy(&balls, 0).unwrap();
+        assert!((angle - (std::f32::consts::PI - AIM_LIFT)).abs() < 1e-5, "{}", angle);
+        // With that one dead, the one on the right is next
+        balls[2].alive = false;
+        let angle = aim_at_nearest_enemy(&balls, 0).unwrap();
+        assert!((angle + AIM_LIFT).abs() < 1e-5, "{}", angle);
+        balls[3].alive = false;
+        assert_eq!(aim_at_nearest_enemy(&balls, 0), None);
+    }
+
+    #[test]
+    fn fires_the_missile_only_when_the_loadout_has_it() {
+        let terrain = Terrain::new(1400, 800);
+        let balls = [ball(500.0, 400.0, 0), ball(800.0, 400.0, 1)];
+        let missile = fire_input(&balls, 0, &[Weapon::Bazooka, BOT_WEAPON], &terrain, 0.0);
+        assert!(matches!(missile, Some(NetInput::Fire { weapon: Some(BOT_WEAPON), power_percent: BOT_POWER, .. })));
+        let bazooka = fire_input(&balls, 0, &[Weapon::Grenade], &terrain, 0.0);
+        assert!(matches!(bazooka, Some(NetInput::Fire { weapon: Some(Weapon::Bazooka), .. })));
+        assert_eq!(fire_input(&balls, 5, &[], &terrain, 0.0), None);
+    }
+
+    #[test]
+    fn lobs_over_a_wall_the_straight_aim_would_hit() {
+        // Flat floor at y = 520 with a wall rising to y = 380 between the two balls
+        let mut terrain = Terrain::new(1400, 800);
+        for y in 380..800 {
+            for x in 0..1400 {
+                if y >= 520 || (480..520).contains(&x) {
+                    terrain.set(x, y, crate::terrain::DIRT);
+                }
+            }
+        }
+        let balls = [ball(300.0, 512.0, 0), ball(700.0, 512.0, 1)];
+        let reaches = |angle: f32, power: f32| {
+            let path = projectile::simulate_trajectory(300.0 + angle.cos() * 12.0, 512.0 + angle.sin() * 12.0, angle, power, BOT_WEAPON, 0.0, &terrain);
+            path.iter().any(|&(x, y)| (x - 700.0).hypot(y - 512.0) <= 17.5)
+        };
+        let straight = aim_at_nearest_enemy(&balls, 0).unwrap();
+        assert!(!reaches(straight, BOT_POWER), "the wall should block the usual aim");
+
+        let (angle, power) = clear_shot(&balls, 0, &terrain, 0.0, BOT_WEAPON).expect("no way over the wall");
+        assert!(reaches(angle, power));
+        // Up and to the right, towards the enemy
+        assert!(angle.cos() > 0.0 && angle.sin() < 0.0 && angle < straight, "{}", angle);
+        let Some(NetInput::Fire { angle_deg, power_percent, .. }) = fire_input(&balls, 0, &[BOT_WEAPON], &terrain, 0.0) else {
+            panic!("bot didn't fire");
+        };
+        assert_eq!((angle_deg, power_percent), (angle.to_degrees(), power));
+    }
+}
@@ -1,5 +1,7 @@
 use macroquad::prelude::*;
 
+use crate::physics::Ball;
+
 /// World units visible along the shorter screen axis at zoom 1.0.
 /// This anchors the view to screen shape rather than terrain width, so the
 /// camera stays tight and action-focused on any orientation or window size.
@@ -7,9 +9,117 @@ use macroquad::prelude::*;
 /// short axis, giving a close-up view of the current player / projectile.
 const BASE_SHORT_AXIS: f32 = 350.0;
 
+/// Window size in px. Unit tests simulate games without a window, so they get a fixed
+/// landscape view instead.
+fn screen_size() -> (f32, f32) {
+    if cfg!(test) {
+        (1280.0, 720.0)
+    } else {
+        (screen_width(), screen_height())
+    }
+}
+
 /// Maximum inertia speed in world-units/second.
 const MAX_VEL: f32 = 2500.0;
 
+/// Default half-size of the follow dead-zone box, in world units.
+pub const DEFAULT_DEAD_ZONE: f32 = 12.0;
+/// Once this close to the target the camera stops and the dead-zone applies again.
+const RECENTERED: f32 = 1.0;
+
+/// Seconds the director lingers on an explosion before cutting away.
+const IMPACT_HOLD: f32 = 1.2;
+/// Seconds the director lingers on a ball that was just hurt.
+const DAMAGE_HOLD: f32 = 1.5;
+
+/// What the director camera is framing, from highest priority to lowest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DirectorShot {
+    Projectile,
+    Impact,
+    Damage,
+    ActiveBall,
+}
+
+/// Hands-off "TV director" for spectators: each tick picks the most interesting
+/// point (projectile in flight, then its impact, then whoever got hurt, then the
+/// active ball) and tells the game where to ease the camera and how far to zoom.
+pub struct Director {
+    pub enabled: bool,
+    pub shot: DirectorShot,
+    /// Time left on the current Impact/Damage shot before falling back
+    hold: f32,
+    focus: (f32, f32),
+    /// Ball hurt during the current impact, shown once the impact hold ends
+    victim: Option<usize>,
+}
+
+impl Director {
+    pub fn new() -> Self {
+        Director {
+            enabled: false,
+            shot: DirectorShot::ActiveBall,
+            hold: 0.0,
+            focus: (0.0, 0.0),
+            victim: None,
+        }
+    }
+
+    /// Choose this tick's shot. `impact` is an explosion that happened this tick and
+    /// `hurt` a ball that lost health this tick. Returns (x, y, follow speed, zoom).
+    pub fn update(
+        &mut self,
+        dt: f32,
+        balls: &[Ball],
+        projectile: Option<(f32, f32)>,
+        impact: Option<(f32, f32)>,
+        hurt: Option<usize>,
+        active: usize,
+    ) -> Option<(f32, f32, f32, f32)> {
+        let ball_pos = |i: usize| balls.get(i).filter(|b| b.alive).map(|b| (b.x, b.y - 30.0));
+        self.hold = (self.hold - dt).max(0.0);
+        if hurt.is_some() {
+            self.victim = hurt;
+        }
+
+        if let Some(p) = impact {
+            self.cut(DirectorShot::Impact, p, IMPACT_HOLD);
+        } else if self.hold > 0.0 && self.shot != DirectorShot::Projectile {
+            // Stay on the impact/victim; a victim flying from knockback stays in frame
+            if self.shot == DirectorShot::Damage {
+                if let Some(p) = self.victim.and_then(ball_pos) {
+                    self.focus = p;
+                }
+            }
+        } else if let Some(p) = projectile {
+            self.victim = None;
+            self.cut(DirectorShot::Projectile, p, 0.0);
+        } else if let (DirectorShot::Impact, Some(p)) = (self.shot, self.victim.and_then(ball_pos)) {
+            // Once the dust settles, cut to whoever took the hit
+            self.cut(DirectorShot::Damage, p, DAMAGE_HOLD);
+        } else if let Some(p) = ball_pos(active) {
+            self.victim = None;
+            self.cut(DirectorShot::ActiveBall, p, 0.0);
+        } else {
+            return None;
+        }
+
+        let (speed, zoom) = match self.shot {
+            DirectorShot::Projectile => (7.0, 1.6),
+            DirectorShot::Impact => (5.0, 2.2),
+            DirectorShot::Damage => (4.0, 2.4),
+            DirectorShot::ActiveBall => (3.0, 2.0),
+        };
+        Some((self.focus.0, self.focus.1, speed, zoom))
+    }
+
+    fn cut(&mut self, shot: DirectorShot, focus: (f32, f32), hold: f32) {
+        self.shot = shot;
+        self.focus = focus;
+        self.hold = hold;
+    }
+}
+
 pub struct GameCamera {
     pub x: f32,
     pub y: f32,
@@ -19,6 +129,15 @@ pub struct GameCamera {
     /// Momentum velocity in world-units/second. Applied every tick by apply_momentum().
     pub vel_x: f32,
     pub vel_y: f32,
+    /// Seconds of screen shake remaining (purely visual).
+    pub shake_timer: f32,
+    /// Half-size of the box around the view center a followed target can move in
+    /// without the camera moving, so small settling wobbles don't jitter the view.
+    /// 0 follows every movement.
+    pub dead_zone: f32,
+    /// Whether the camera is catching up to a target that left the dead-zone. Stays
+    /// set until it's re-centered, so it doesn't stop at the edge of the box.
+    tracking: bool,
 }
 
 impl GameCamera {
@@ -31,20 +150,39 @@ impl GameCamera {
             target_y: y,
             vel_x: 0.0,
             vel_y: 0.0,
+            shake_timer: 0.0,
+            dead_zone: DEFAULT_DEAD_ZONE,
+            tracking: false,
         }
     }
 
-    /// Smoothly follow a world-space target. Also bleeds off any residual momentum
-    /// so it doesn't fight the auto-follow interpolation.
+    /// Smoothly follow a world-space target once it leaves the dead-zone, until the
+    /// view is centered on it again. Also bleeds off any residual momentum so it
+    /// doesn't fight the auto-follow interpolation.
     pub fn follow(&mut self, tx: f32, ty: f32, speed: f32, dt: f32) {
         self.target_x = tx;
         self.target_y = ty;
         let rate = (speed * dt).min(1.0);
-        self.x += (self.target_x - self.x) * rate;
-        self.y += (self.target_y - self.y) * rate;
         // Drain momentum toward zero at the same rate so inertia doesn't fight the follow
         self.vel_x *= 1.0 - rate;
         self.vel_y *= 1.0 - rate;
+        if !self.tracking {
+            if (tx - self.x).abs() <= self.dead_zone && (ty - self.y).abs() <= self.dead_zone {
+                return;
+            }
+            self.tracking = true;
+        }
+        self.x += (self.target_x - self.x) * rate;
+        self.y += (self.target_y - self.y) * rate;
+        if (tx - self.x).abs() < RECENTERED && (ty - self.y).abs() < RECENTERED {
+            self.tracking = false;
+        }
+    }
+
+    /// Follow all the way back to the next target even if it's inside the dead-zone,
+    /// e.g. when gliding back after free-look or snapping to a hit.
+    pub fn recenter(&mut self) {
+        self.tracking = true;
     }
 
     /// Pan by a screen-pixel delta, imparting inertial velocity proportional to swipe speed.
@@ -87,6 +225,15 @@ impl GameCamera {
         self.vel_y *= friction;
     }
 
+    /// Start (or extend) a screen shake lasting `seconds`.
+    pub fn shake(&mut self, seconds: f32) {
+        self.shake_timer = self.shake_timer.max(seconds);
+    }
+
+    pub fn tick_shake(&mut self, dt: f32) {
+        self.shake_timer = (self.shake_timer - dt).max(0.0);
+    }
+
     pub fn zoom_by(&mut self, factor: f32) {
         self.zoom = (self.zoom * factor).clamp(0.3, 4.0);
     }
@@ -118,24 +265,26 @@ impl GameCamera {
     /// handled correctly because we anchor to the shorter screen dimension.
     pub fn visible_width(&self) -> f32 {
         let short_axis = BASE_SHORT_AXIS / self.zoom;
-        if screen_width() <= screen_height() {
+        let (sw, sh) = screen_size();
+        if sw <= sh {
             // Portrait / square: width is the short axis
             short_axis
         } else {
             // Landscape: height is the short axis, scale width proportionally
-            short_axis * screen_width() / screen_height()
+            short_axis * sw / sh
         }
     }
 
     /// World units visible vertically. Always `visible_width * (h/w)`.
     pub fn visible_height(&self) -> f32 {
         let short_axis = BASE_SHORT_AXIS / self.zoom;
-        if screen_height() <= screen_width() {
+        let (sw, sh) = screen_size();
+        if sh <= sw {
             // Landscape / square: height is the short axis
             short_axis
         } else {
             // Portrait: width is the short axis, scale height proportionally
-            short_axis * screen_height() / screen_width()
+            short_axis * sh / sw
         }
     }
 
@@ -150,14 +299,16 @@ impl GameCamera {
         )
     }
 
-    pub fn clamp_to_world(&mut self, world_w: f32, world_h: f32) {
+    /// Keep the view over the land (`land_x` is its left and right edge) and inside the
+    /// map's height.
+    pub fn clamp_to_world(&mut self, land_x: (f32, f32), world_h: f32) {
         let vw = self.visible_width();
         let vh = self.visible_height();
         let half_vw = vw / 2.0;
         let half_vh = vh / 2.0;
         // Clamp camera to stay within the playable land area
-        let min_x = crate::terrain::LAND_START_X + half_vw;
-        let max_x = crate::terrain::LAND_END_X - half_vw;
+        let min_x = land_x.0 + half_vw;
+        let max_x = land_x.1 - half_vw;
         self.x = self.x.clamp(min_x, max_x.max(min_x));
         self.y = self.y.clamp(half_vh, (world_h - half_vh).max(half_vh));
     }
@@ -165,11 +316,20 @@ impl GameCamera {
     pub fn to_macroquad(&self) -> Camera2D {
         let vw = self.visible_width();
         let vh = self.visible_height();
+        // Shake offset decays with the remaining timer
+        let (sx, sy) = if self.shake_timer > 0.0 {
+            let t = get_time() as f32;
+            let amp = 6.0 * self.shake_timer.min(1.0);
+            ((t * 53.0).sin() * amp, (t * 41.0).cos() * amp)
+        } else {
+            (0.0, 0.0)
+        };
+        let (cx, cy) = (self.x + sx, self.y + sy);
         #[cfg(not(target_arch = "wasm32"))]
-        let rect = Rect::new(self.x - vw / 2.0, self.y - vh / 2.0, vw, vh);
+        let rect = Rect::new(cx - vw / 2.0, cy - vh / 2.0, vw, vh);
         // WebGL: flip Y so Y-down world (terrain at bottom) displays right-side up
         #[cfg(target_arch = "wasm32")]
-        let rect = Rect::new(self.x - vw / 2.0, self.y + vh / 2.0, vw, -vh);
+        let rect = Rect::new(cx - vw / 2.0, cy + vh / 2.0, vw, -vh);
         Camera2D::from_display_rect(rect)
     }
 
@@ -0,0 +1,124 @@
+use crate::physics::Ball;
+use crate::terrain::Terrain;
+use crate::weapons::Weapon;
+use macroquad::prelude::*;
+
+/// Half the side length of a crate's box, used for landing and pickup
+pub const CRATE_HALF: f32 = 6.0;
+/// Health restored by a health crate, never past the ball's max health
+pub const CRATE_HEAL: i32 = 25;
+/// Crates lying on the map at once; the oldest make way for new drops
+pub const MAX_CRATES: usize = 12;
+
+const CRATE_GRAVITY: f32 = 300.0;
+/// Crates drift down under a little parachute instead of plummeting
+const CRATE_MAX_FALL: f32 = 120.0;
+
+/// What a mystery crate may turn out to hold, as (weapon, weight): a weapon's chance
+/// is its weight over the total. Tune drop rates here; weapons left out never drop.
+/// The Bazooka is missing on purpose, as every loadout already has it.
+pub const MYSTERY_WEIGHTS: &[(Weapon, u32)] = &[
+    (Weapon::Grenade, 10),
+    (Weapon::Shotgun, 10),
+    (Weapon::ClusterBomb, 8),
+    (Weapon::Dynamite, 8),
+    (Weapon::Mine, 8),
+    (Weapon::Uzi, 8),
+    (Weapon::Teleport, 6),
+    (Weapon::Drill, 6),
+    (Weapon::BuildWall, 6),
+    (Weapon::Shield, 6),
+    (Weapon::Foxhole, 6),
+    (Weapon::BaseballBat, 5),
+    (Weapon::Flares, 5),
+    (Weapon::HomingMissile, 5),
+    (Weapon::Mortar, 5),
+    (Weapon::Sheep, 5),
+    (Weapon::BananaBomb, 4),
+    (Weapon::Airstrike, 4),
+    (Weapon::NapalmStrike, 4),
+    (Weapon::SniperRifle, 4),
+    (Weapon::HolyHandGrenade, 2),
+    (Weapon::BananaBonanza, 2),
+    (Weapon::SuperSheep, 2),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CrateKind {
+    Health,
+    /// Unlocks a weapon for the team that picks it up (random-loadout matches). Which
+    /// one is rolled from the seed only when it's opened, see `mystery_weapon`.
+    Mystery(u32),
+}
+
+/// The weapon a mystery crate with `seed` holds, picked from MYSTERY_WEIGHTS. Depends
+/// on nothing but the seed, so every client opens the same crate.
+pub fn mystery_weapon(seed: u32) -> Weapon {
+    let total: u32 = MYSTERY_WEIGHTS.iter().map(|&(_, weight)| weight).sum();
+    let mut roll = (crate::lcg(seed) >> 16) % total.max(1);
+    for &(weapon, weight) in MYSTERY_WEIGHTS {
+        if roll < weight {
+            return weapon;
+        }
+        roll -= weight;
+    }
+    Weapon::Bazooka
+}
+
+pub struct SupplyCrate {
+    pub x: f32,
+    pub y: f32,
+    pub vy: f32,
+    pub kind: CrateKind,
+    pub alive: bool,
+}
+
+impl SupplyCrate {
+    pub fn new(x: f32, kind: CrateKind) -> Self {
+        Self { x, y: -CRATE_HALF * 2.0, vy: 0.0, kind, alive: true }
+    }
+
+    /// Fall until resting on terrain (and again if the ground is blasted away), sink in
+    /// water, and return the index of the first living ball touching the crate.
+    pub fn tick(&mut self, terrain: &Terrain, balls: &[Ball], dt: f32) -> Option<usize> {
+        if !self.alive {
+            return None;
+        }
+        let below = (self.y + CRATE_HALF) as i32;
+        if terrain.is_solid(self.x as i32, below + 1) {
+            self.vy = 0.0;
+            // Settle onto the surface if a fall step overshot into it
+            while terrain.is_solid(self.x as i32, (self.y + CRATE_HALF) as i32) && self.y > 0.0 {
+                self.y -= 1.0;
+            }
+        } else {
+            self.vy = (self.vy + CRATE_GRAVITY * dt).min(CRATE_MAX_FALL);
+            self.y += self.vy * dt;
+        }
+        if self.y - CRATE_HALF >= terrain.water_level() || self.y > terrain.height as f32 + 50.0 {
+            self.alive = false;
+            return None;
+        }
+        balls.iter().position(|b| {
+            b.alive && (b.x - self.x).abs() < b.radius + CRATE_HALF && (b.y - self.y).abs() < b.radius + CRATE_HALF
+        })
+    }
+
+    pub fn draw(&self) {
+        let (x, y, s) = (self.x - CRATE_HALF, self.y - CRATE_HALF, CRATE_HALF * 2.0);
+        if self.vy > 0.0 {
+            // Parachute while falling
+            draw_line(self.x, y, self.x - 8.0, y - 12.0, 1.0, Color::new(0.9, 0.9, 0.9, 0.8));
+            draw_line(self.x, y, self.x + 8.0, y - 12.0, 1.0, Color::new(0.9, 0.9, 0.9, 0.8));
+            draw_rectangle(self.x - 10.0, y - 16.0, 20.0, 4.0, Color::new(0.95, 0.95, 0.95, 0.9));
+        }
+        draw_rectangle(x, y, s, s, Color::new(0.55, 0.38, 0.2, 1.0));
+        draw_rectangle_lines(x, y, s, s, 1.5, Color::new(0.3, 0.2, 0.1, 1.0));
+        let mark = match self.kind {
+            CrateKind::Health => Color::new(0.9, 0.15, 0.15, 1.0),
+            CrateKind::Mystery(_) => Color::new(0.95, 0.8, 0.2, 1.0),
+        };
+        draw_rectangle(self.x - 1.0, y + 2.0, 2.0, s - 4.0, mark);
+        draw_rectangle(x + 2.0, self.y - 1.0, s - 4.0, 2.0, mark);
+    }
+}
@@ -1,8 +1,8 @@
 use macroquad::prelude::*;
 
-use crate::physics::{Ball, TEAM_COLORS, BALL_RADIUS};
-use crate::state::Phase;
-use crate::weapons::{Weapon, WeaponCategory};
+use crate::physics::{Ball, TEAM_COLORS};
+use crate::state::{self, Phase};
+use crate::weapons::{Weapon, WeaponCategory, MENU_CATEGORIES};
 
 /// Shared layout constants for the weapon menu (used by hud rendering and click hit-testing).
 pub struct WeaponMenuLayout {
@@ -64,50 +64,92 @@ impl WeaponMenuLayout {
     }
 
     /// Total height of all content (categories + weapons) to determine max scroll.
-    pub fn total_content_height(&self) -> f32 {
-        let categories = [
-            WeaponCategory::Explosives,
-            WeaponCategory::Ballistics,
-            WeaponCategory::Special,
-            WeaponCategory::Utilities,
-        ];
-        let all_weapons = Weapon::all();
-        let mut by_category: std::collections::HashMap<WeaponCategory, Vec<&Weapon>> = std::collections::HashMap::new();
-        for w in all_weapons {
-            by_category.entry(w.category()).or_insert_with(Vec::new).push(w);
-        }
+    pub fn total_content_height(&self, filter: &str) -> f32 {
+        let groups = weapons_by_category(filter);
         let mut h = 0.0_f32;
-        for (i, cat) in categories.iter().enumerate() {
-            if let Some(weapons) = by_category.get(cat) {
-                h += self.cat_header_h + self.item_padding;
-                h += weapons.len() as f32 * (self.item_h + self.item_padding);
-                if i < categories.len() - 1 {
-                    h += self.cat_spacing;
-                }
+        for (i, (_, weapons)) in groups.iter().enumerate() {
+            h += self.cat_header_h + self.item_padding;
+            h += weapons.len() as f32 * (self.item_h + self.item_padding);
+            if i < groups.len() - 1 {
+                h += self.cat_spacing;
             }
         }
         h
     }
 
-    pub fn max_scroll(&self) -> f32 {
-        (self.total_content_height() - self.content_h).max(0.0)
+    pub fn max_scroll(&self, filter: &str) -> f32 {
+        (self.total_content_height(filter) - self.content_h).max(0.0)
     }
 }
 
-pub fn draw_hud(
-    balls: &[Ball],
-    current_ball: usize,
-    phase: Phase,
-    selected_weapon: Weapon,
-    charge_power: f32,
-    turn_timer: f32,
-    wind: f32,
-    winning_team: Option<u32>,
-    is_my_turn: bool,
-    turn_owner_name: &str,
-    weapon_menu_open: bool,
-    weapon_menu_scroll: f32,
-) {
+/// Weapons whose name contains `filter` (case-insensitive), grouped by category in menu
+/// order. Categories with no matches are left out. Shared by drawing and click hit-testing
+/// so the two always agree on the layout.
+pub fn weapons_by_category(filter: &str) -> Vec<(WeaponCategory, Vec<Weapon>)> {
+    let filter = filter.to_lowercase();
+    MENU_CATEGORIES
+        .iter()
+        .map(|cat| {
+            let weapons: Vec<Weapon> = Weapon::all()
+                .iter()
+                .copied()
+                .filter(|w| w.category() == *cat && w.name().to_lowercase().contains(&filter))
+                .collect();
+            (*cat, weapons)
+        })
+        .filter(|(_, weapons)| !weapons.is_empty())
+        .collect()
+}
+
+/// Turn and menu state the HUD is drawn from, gathered by the game each frame.
+#[derive(Clone, Copy)]
+pub struct HudState<'a> {
+    pub balls: &'a [Ball],
+    pub current_ball: usize,
+    pub phase: Phase,
+    pub selected_weapon: Weapon,
+    pub charge_power: f32,
+    /// Seconds left on the turn, or on the retreat once the shot is away
+    pub turn_timer: f32,
+    pub wind: f32,
+    pub winning_team: Option<u32>,
+    pub is_my_turn: bool,
+    pub turn_owner_name: &'a str,
+    /// Upcoming turns as (team, ball index)
+    pub turn_preview: &'a [(u32, usize)],
+    pub weapon_menu_open: bool,
+    pub weapon_menu_scroll: f32,
+    /// Weapons the current team may pick from
+    pub loadout: &'a [Weapon],
+    pub compare_weapon: Option<Weapon>,
+    pub menu_filter: &'a str,
+    /// Seconds left showing a weapon picked by keyboard cycling
+    pub weapon_flash: f32,
+    /// Online rematch vote after game over: (ready, needed, whether this player is ready)
+    pub rematch: Option<(usize, usize, bool)>,
+}
+
+pub fn draw_hud(hud: HudState) {
+    let HudState {
+        balls,
+        current_ball,
+        phase,
+        selected_weapon,
+        charge_power,
+        turn_timer,
+        wind,
+        winning_team,
+        is_my_turn,
+        turn_owner_name,
+        turn_preview,
+        weapon_menu_open,
+        weapon_menu_scroll,
+        loadout,
+        compare_weapon,
+        menu_filter,
+        weapon_flash,
+        rematch,
+    } = hud;
     let sw = screen_width();
     let sh = screen_height();
 
@@ -125,10 +167,15 @@ pub fn draw_hud(
             draw_text(text, sw / 2.0 - tw / 2.0, 32.0, 36.0, WHITE);
         }
 
-        let hint = "Press R to restart";
-        let hw = measure_text(hint, None, 24, 1.0).width;
+        // Networked games restart once everyone confirms: (ready, needed, we're ready)
+        let hint = match rematch {
+            None => "Press R to restart".to_string(),
+            Some((ready, needed, false)) => format!("Press R for a rematch ({}/{} ready)", ready, needed),
+            Some((ready, needed, true)) => format!("Waiting for rematch ({}/{} ready)", ready, needed),
+        };
+        let hw = measure_text(&hint, None, 24, 1.0).width;
         draw_text(
-            hint,
+            &hint,
             sw / 2.0 - hw / 2.0,
             sh / 2.0 + 40.0,
             24.0,
@@ -256,10 +303,20 @@ pub fn draw_hud(
         }
     }
 
+    // Weapon just picked with [ / ], fading out
+    if weapon_flash > 0.0 {
+        let alpha = (weapon_flash / 0.3).min(1.0);
+        let text = selected_weapon.name();
+        let tw = measure_text(text, None, 28, 1.0).width;
+        draw_text(text, sw / 2.0 - tw / 2.0, sh - 110.0, 28.0, Color::new(1.0, 0.95, 0.6, alpha));
+    }
+
+    draw_turn_order_panel(balls, current_ball, turn_preview, is_mobile_hud);
+
     // Bottom hint — desktop only
     if !is_mobile_hud {
         draw_text(
-            "WASD/Arrows move  Space jump  TAB weapons  Scroll zoom  Right-drag pan",
+            "WASD/Arrows move  Space jump  TAB weapons  [ ] cycle weapon  E prod  Scroll zoom  Right-drag pan  Alt+click ping  End x2 forfeit",
             10.0,
             sh - 6.0,
             13.0,
@@ -269,11 +326,75 @@ pub fn draw_hud(
     
     // Draw weapon menu
     if weapon_menu_open {
-        draw_weapon_menu(selected_weapon, weapon_menu_scroll);
+        draw_weapon_menu(selected_weapon, weapon_menu_scroll, loadout, compare_weapon, menu_filter);
+    }
+}
+
+/// Side panel listing every team's living balls (name + HP pip), highlighting the
+/// active ball and marking the upcoming turns. Mobile only gets a one-line "Next" hint
+/// because the left edge is taken by the touch controls.
+fn draw_turn_order_panel(balls: &[Ball], current_ball: usize, turn_preview: &[(u32, usize)], is_mobile: bool) {
+    if is_mobile {
+        if let Some(&(team, bi)) = turn_preview.first() {
+            if let Some(ball) = balls.get(bi) {
+                let (r, g, b) = TEAM_COLORS[team as usize % TEAM_COLORS.len()];
+                let text = format!("Next: {}", ball.name);
+                draw_text(&text, 8.0, 60.0, 16.0, Color::new(r, g, b, 0.95));
+            }
+        }
+        return;
+    }
+
+    let teams = state::team_summaries(balls);
+
+    let row_h = 16.0;
+    let team_header_h = 18.0;
+    let panel_x = 8.0;
+    let panel_y = 52.0;
+    let panel_w = 150.0;
+    let living = balls.iter().filter(|b| b.alive).count() as f32;
+    let panel_h = 8.0 + teams.len() as f32 * team_header_h + living * row_h + 6.0;
+    draw_rectangle(panel_x, panel_y, panel_w, panel_h, Color::new(0.0, 0.0, 0.0, 0.55));
+
+    let mut y = panel_y + 6.0;
+    for summary in &teams {
+        let team = summary.team;
+        let (r, g, b) = TEAM_COLORS[team as usize % TEAM_COLORS.len()];
+        let team_color = Color::new(r, g, b, if summary.eliminated { 0.4 } else { 1.0 });
+        y += team_header_h;
+        draw_text(&format!("Team {}", team + 1), panel_x + 6.0, y - 4.0, 16.0, team_color);
+        let total = if summary.eliminated { "OUT".to_string() } else { format!("{} HP", summary.total_hp) };
+        let tw = measure_text(&total, None, 14, 1.0).width;
+        draw_text(&total, panel_x + panel_w - tw - 6.0, y - 4.0, 14.0, team_color);
+
+        for (i, ball) in balls.iter().enumerate() {
+            if ball.team != team || !ball.alive {
+                continue;
+            }
+            if i == current_ball {
+                draw_rectangle(panel_x + 2.0, y + 1.0, panel_w - 4.0, row_h, Color::new(r, g, b, 0.25));
+            }
+
+            // HP pip: a small bar scaled to remaining health
+            let frac = (ball.health as f32 / ball.max_health as f32).clamp(0.0, 1.0);
+            let pip_w = 28.0;
+            draw_rectangle(panel_x + 8.0, y + 6.0, pip_w, 5.0, Color::new(0.2, 0.2, 0.2, 0.9));
+            draw_rectangle(panel_x + 8.0, y + 6.0, pip_w * frac, 5.0, team_color);
+
+            draw_text(&ball.name, panel_x + 42.0, y + 12.0, 14.0, WHITE);
+
+            // Upcoming-turn marker: 1 = next, 2 = after that, ...
+            if let Some(pos) = turn_preview.iter().position(|&(_, bi)| bi == i) {
+                let marker = format!("{}", pos + 1);
+                let alpha = if pos == 0 { 1.0 } else { 0.6 };
+                draw_text(&marker, panel_x + panel_w - 14.0, y + 12.0, 14.0, Color::new(1.0, 0.9, 0.3, alpha));
+            }
+            y += row_h;
+        }
     }
 }
 
-fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
+fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32, loadout: &[Weapon], compare_weapon: Option<Weapon>, filter: &str) {
     let sw = screen_width();
     let sh = screen_height();
     let layout = WeaponMenuLayout::new();
@@ -320,6 +441,20 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
         Color::new(0.9, 0.95, 1.0, 1.0),
     );
     
+    // Search text typed while the menu is open
+    if !filter.is_empty() {
+        let search = format!("Search: {}_", filter);
+        let search_size = if is_mobile { 12.0 } else { 14.0 };
+        let search_w = measure_text(&search, None, search_size as u16, 1.0).width;
+        draw_text(
+            &search,
+            menu_x + menu_w - search_w - 12.0,
+            menu_y + header_h - 14.0,
+            search_size,
+            Color::new(0.5, 0.9, 0.7, 1.0),
+        );
+    }
+    
     // Content area dimensions
     let content_y = layout.content_y;
     let content_h = layout.content_h;
@@ -329,20 +464,6 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
     let item_padding = layout.item_padding;
     let cat_spacing = layout.cat_spacing;
     
-    // Organize weapons by category
-    let all_weapons = Weapon::all();
-    let mut by_category: std::collections::HashMap<WeaponCategory, Vec<&Weapon>> = std::collections::HashMap::new();
-    for w in all_weapons {
-        by_category.entry(w.category()).or_insert_with(Vec::new).push(w);
-    }
-    
-    let categories = [
-        WeaponCategory::Explosives,
-        WeaponCategory::Ballistics,
-        WeaponCategory::Special,
-        WeaponCategory::Utilities,
-    ];
-    
     // Enable scissor clipping for the scrollable content area.
     // Scissor coordinates must be in PHYSICAL pixels; drawing coords are CSS px.
     let phys_clip_x = (menu_x * dpi) as i32;
@@ -354,138 +475,172 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
     }
     
     let mut current_y = content_y - scroll_offset;
+    let (mouse_x, mouse_y) = mouse_position();
+    let mut hovered = None;
     
-    for cat in &categories {
-        if let Some(weapons) = by_category.get(cat) {
-            // Category header
-            let cat_y = current_y;
+    let groups = weapons_by_category(filter);
+    if groups.is_empty() {
+        let msg = "No matching weapons";
+        let msg_w = measure_text(msg, None, 16, 1.0).width;
+        draw_text(msg, menu_x + menu_w / 2.0 - msg_w / 2.0, content_y + 40.0, 16.0, Color::new(0.6, 0.65, 0.75, 0.9));
+    }
+    for (cat, weapons) in groups {
+        // Category header
+        let cat_y = current_y;
+        
+        if cat_y + cat_header_h > content_y - 10.0 && cat_y < content_y + content_h + 10.0 {
+            draw_rectangle(
+                menu_x + padding,
+                cat_y,
+                menu_w - padding * 2.0,
+                cat_header_h,
+                Color::new(0.15, 0.2, 0.28, 0.8),
+            );
             
-            if cat_y + cat_header_h > content_y - 10.0 && cat_y < content_y + content_h + 10.0 {
-                draw_rectangle(
-                    menu_x + padding,
-                    cat_y,
-                    menu_w - padding * 2.0,
-                    cat_header_h,
-                    Color::new(0.15, 0.2, 0.28, 0.8),
-                );
+            let cat_size = if is_mobile { 12.0 } else { 14.0 };
+            draw_text(
+                cat.name(),
+                menu_x + padding + 8.0,
+                cat_y + cat_header_h - 6.0,
+                cat_size,
+                Color::new(0.7, 0.8, 0.95, 1.0),
+            );
+        }
+        
+        current_y += cat_header_h + item_padding;
+        
+        // Weapon items
+        for w in weapons {
+            let item_y = current_y;
+            let item_x = menu_x + padding;
+            let item_w = menu_w - padding * 2.0;
+            
+            if item_y + item_h > content_y - 10.0 && item_y < content_y + content_h + 10.0 {
+                let is_selected = w == selected_weapon;
+                if mouse_x >= item_x && mouse_x <= item_x + item_w
+                    && mouse_y >= item_y.max(content_y) && mouse_y <= (item_y + item_h).min(content_y + content_h)
+                {
+                    hovered = Some(w);
+                }
+                
+                let bg_color = if is_selected {
+                    Color::new(0.2, 0.45, 0.35, 0.95)
+                } else {
+                    Color::new(0.12, 0.14, 0.18, 0.7)
+                };
+                
+                draw_rectangle(item_x, item_y, item_w, item_h, bg_color);
                 
-                let cat_size = if is_mobile { 12.0 } else { 14.0 };
+                if is_selected {
+                    draw_rectangle_lines(item_x, item_y, item_w, item_h, 2.0, Color::new(0.3, 0.9, 0.5, 1.0));
+                    draw_rectangle_lines(item_x - 1.0, item_y - 1.0, item_w + 2.0, item_h + 2.0, 1.0, Color::new(0.5, 1.0, 0.7, 0.5));
+                } else {
+                    draw_rectangle_lines(item_x, item_y, item_w, item_h, 1.0, Color::new(0.25, 0.28, 0.35, 0.7));
+                }
+                
+                // Weapon icon
+                let icon_size = if is_mobile { 16.0 } else { 20.0 };
+                let icon_x = item_x + 8.0;
                 draw_text(
-                    cat.name(),
-                    menu_x + padding + 8.0,
-                    cat_y + cat_header_h - 6.0,
-                    cat_size,
-                    Color::new(0.7, 0.8, 0.95, 1.0),
+                    w.icon(),
+                    icon_x,
+                    item_y + item_h / 2.0 + icon_size / 3.0,
+                    icon_size,
+                    Color::new(1.0, 1.0, 1.0, 1.0),
                 );
-            }
-            
-            current_y += cat_header_h + item_padding;
-            
-            // Weapon items
-            for w in weapons {
-                let item_y = current_y;
-                let item_x = menu_x + padding;
-                let item_w = menu_w - padding * 2.0;
                 
-                if item_y + item_h > content_y - 10.0 && item_y < content_y + content_h + 10.0 {
-                    let is_selected = **w == selected_weapon;
-                    
-                    let bg_color = if is_selected {
-                        Color::new(0.2, 0.45, 0.35, 0.95)
-                    } else {
-                        Color::new(0.12, 0.14, 0.18, 0.7)
-                    };
-                    
-                    draw_rectangle(item_x, item_y, item_w, item_h, bg_color);
-                    
+                // Weapon name — vertically centred in item
+                let name_x = icon_x + if is_mobile { 26.0 } else { 32.0 };
+                let name_size = if is_mobile { 15.0 } else { 17.0 };
+                let name_y = if is_mobile {
+                    // On mobile: show name only, centred; show description below if selected
+                    item_y + item_h / 2.0 + name_size * 0.35
+                } else {
+                    item_y + item_h / 2.0 + name_size * 0.35
+                };
+                draw_text(
+                    w.name(),
+                    name_x,
+                    name_y,
+                    name_size,
                     if is_selected {
-                        draw_rectangle_lines(item_x, item_y, item_w, item_h, 2.0, Color::new(0.3, 0.9, 0.5, 1.0));
-                        draw_rectangle_lines(item_x - 1.0, item_y - 1.0, item_w + 2.0, item_h + 2.0, 1.0, Color::new(0.5, 1.0, 0.7, 0.5));
+                        Color::new(1.0, 1.0, 1.0, 1.0)
                     } else {
-                        draw_rectangle_lines(item_x, item_y, item_w, item_h, 1.0, Color::new(0.25, 0.28, 0.35, 0.7));
-                    }
-                    
-                    // Weapon icon
-                    let icon_size = if is_mobile { 16.0 } else { 20.0 };
-                    let icon_x = item_x + 8.0;
-                    draw_text(
-                        w.icon(),
-                        icon_x,
-                        item_y + item_h / 2.0 + icon_size / 3.0,
-                        icon_size,
-                        Color::new(1.0, 1.0, 1.0, 1.0),
-                    );
-                    
-                    // Weapon name — vertically centred in item
-                    let name_x = icon_x + if is_mobile { 26.0 } else { 32.0 };
-                    let name_size = if is_mobile { 15.0 } else { 17.0 };
-                    let name_y = if is_mobile {
-                        // On mobile: show name only, centred; show description below if selected
-                        item_y + item_h / 2.0 + name_size * 0.35
-                    } else {
-                        item_y + item_h / 2.0 + name_size * 0.35
-                    };
-                    draw_text(
-                        w.name(),
-                        name_x,
-                        name_y,
-                        name_size,
-                        if is_selected {
-                            Color::new(1.0, 1.0, 1.0, 1.0)
-                        } else {
-                            Color::new(0.85, 0.88, 0.95, 1.0)
-                        },
-                    );
+                        Color::new(0.85, 0.88, 0.95, 1.0)
+                    },
+                );
 
-                    if is_mobile {
-                        // Mobile: show a short description line below name when selected
-                        if is_selected {
-                            let desc_size = 10.0;
-                            draw_text(
-                                w.description(),
-                                name_x,
-                                item_y + item_h - 8.0,
-                                desc_size,
-                                Color::new(0.6, 0.85, 0.7, 0.9),
-                            );
-                        }
-                    } else {
-                        // Desktop: always show description + stats badges
-                        let desc_size = 12.0;
+                if is_mobile {
+                    // Mobile: show a short description line below name when selected
+                    if is_selected {
+                        let desc_size = 10.0;
                         draw_text(
                             w.description(),
                             name_x,
-                            item_y + 42.0,
+                            item_y + item_h - 8.0,
                             desc_size,
-                            Color::new(0.55, 0.6, 0.7, 0.95),
+                            Color::new(0.6, 0.85, 0.7, 0.9),
                         );
+                    }
+                } else {
+                    // Desktop: always show description + stats badges
+                    let desc_size = 12.0;
+                    draw_text(
+                        w.description(),
+                        name_x,
+                        item_y + 42.0,
+                        desc_size,
+                        Color::new(0.55, 0.6, 0.7, 0.95),
+                    );
+
+                    let stats_x = item_x + item_w - 120.0;
+                    let badge_y = item_y + 16.0;
+                    let badge_w = 52.0;
+                    let badge_h = 24.0;
+                    let badge_spacing = 4.0;
 
-                        let stats_x = item_x + item_w - 120.0;
-                        let badge_y = item_y + 16.0;
-                        let badge_w = 52.0;
-                        let badge_h = 24.0;
-                        let badge_spacing = 4.0;
-
-                        if w.base_damage() > 0 {
-                            draw_rectangle(stats_x, badge_y, badge_w, badge_h, Color::new(0.7, 0.2, 0.2, 0.7));
-                            let dmg_text = format!("{}", w.base_damage());
-                            let dmg_w = measure_text(&dmg_text, None, desc_size as u16, 1.0).width;
-                            draw_text(&dmg_text, stats_x + badge_w / 2.0 - dmg_w / 2.0, badge_y + badge_h - 5.0, desc_size, WHITE);
-                        }
-                        if w.explosion_radius() > 0.0 {
-                            draw_rectangle(stats_x + badge_w + badge_spacing, badge_y, badge_w, badge_h, Color::new(0.2, 0.4, 0.7, 0.7));
-                            let rad_text = format!("{:.0}", w.explosion_radius());
-                            let rad_w = measure_text(&rad_text, None, desc_size as u16, 1.0).width;
-                            draw_text(&rad_text, stats_x + badge_w + badge_spacing + badge_w / 2.0 - rad_w / 2.0, badge_y + badge_h - 5.0, desc_size, WHITE);
-                        }
+                    if w.base_damage() > 0 {
+                        draw_rectangle(stats_x, badge_y, badge_w, badge_h, Color::new(0.7, 0.2, 0.2, 0.7));
+                        let dmg_text = format!("{}", w.base_damage());
+                        let dmg_w = measure_text(&dmg_text, None, desc_size as u16, 1.0).width;
+                        draw_text(&dmg_text, stats_x + badge_w / 2.0 - dmg_w / 2.0, badge_y + badge_h - 5.0, desc_size, WHITE);
+                    }
+                    if w.explosion_radius() > 0.0 {
+                        draw_rectangle(stats_x + badge_w + badge_spacing, badge_y, badge_w, badge_h, Color::new(0.2, 0.4, 0.7, 0.7));
+                        let rad_text = format!("{:.0}", w.explosion_radius());
+                        let rad_w = measure_text(&rad_text, None, desc_size as u16, 1.0).width;
+                        draw_text(&rad_text, stats_x + badge_w + badge_spacing + badge_w / 2.0 - rad_w / 2.0, badge_y + badge_h - 5.0, desc_size, WHITE);
                     }
                 }
-                
-                current_y += item_h + item_padding;
+
+                // Weapons outside this team's loadout stay listed but grayed out
+                if !loadout.contains(&w) {
+                    draw_rectangle(item_x, item_y, item_w, item_h, Color::new(0.05, 0.06, 0.08, 0.7));
+                    let lock_size = if is_mobile { 11.0 } else { 13.0 };
+                    let lock_w = measure_text("LOCKED", None, lock_size as u16, 1.0).width;
+                    draw_text(
+                        "LOCKED",
+                        item_x + item_w - lock_w - 10.0,
+                        item_y + item_h / 2.0 + lock_size * 0.35,
+                        lock_size,
+                        Color::new(0.6, 0.6, 0.65, 0.9),
+                    );
+                }
+
+                // The weapon whose arc is overlaid on the aim preview while X is held
+                if compare_weapon == Some(w) {
+                    let cyan = Color::new(0.3, 0.9, 1.0, 0.9);
+                    draw_rectangle_lines(item_x, item_y, item_w, item_h, 2.0, cyan);
+                    let tag_size = if is_mobile { 10.0 } else { 12.0 };
+                    let tag_w = measure_text("COMPARE", None, tag_size as u16, 1.0).width;
+                    draw_text("COMPARE", item_x + item_w - tag_w - 10.0, item_y + tag_size + 2.0, tag_size, cyan);
+                }
             }
             
-            current_y += cat_spacing;
+            current_y += item_h + item_padding;
         }
+        
+        current_y += cat_spacing;
     }
     
     // Disable scissor clipping before drawing footer and scrollbar
@@ -493,8 +648,14 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
         get_internal_gl().quad_gl.scissor(None);
     }
     
+    // Full stats for the hovered weapon (or the selected one) beside the menu, when there's room
+    let info_w = 230.0;
+    if !is_mobile && sw - (menu_x + menu_w) >= info_w + 16.0 {
+        draw_weapon_info(hovered.unwrap_or(selected_weapon), menu_x + menu_w + 12.0, menu_y, info_w);
+    }
+    
     // Scrollbar
-    let max_scroll = layout.max_scroll();
+    let max_scroll = layout.max_scroll(filter);
     if max_scroll > 0.0 {
         let track_w = 6.0;
         let track_x = menu_x + menu_w - track_w - 4.0;
@@ -503,7 +664,7 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
         
         draw_rectangle(track_x, track_y, track_w, track_h, Color::new(0.15, 0.18, 0.22, 0.6));
         
-        let visible_ratio = (content_h / layout.total_content_height()).min(1.0);
+        let visible_ratio = (content_h / layout.total_content_height(filter)).min(1.0);
         let thumb_h = (track_h * visible_ratio).max(24.0);
         let scroll_ratio = scroll_offset / max_scroll;
         let thumb_y = track_y + scroll_ratio * (track_h - thumb_h);
@@ -519,7 +680,7 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
     let hint = if is_mobile {
         "Tap to select  •  Swipe to scroll  •  Tap outside to close"
     } else {
-        "Click to select • Scroll to browse • TAB/ESC to close"
+        "Type to search • Enter picks top match • Right-click to compare • TAB/ESC to close"
     };
     let hint_size = if is_mobile { 11.0 } else { 13.0 };
     let hint_w = measure_text(hint, None, hint_size as u16, 1.0).width;
@@ -532,25 +693,93 @@ fn draw_weapon_menu(selected_weapon: Weapon, scroll_offset: f32) {
     );
 }
 
-pub fn draw_ball_world(balls: &[Ball], current_ball: usize) {
+/// Side panel listing everything `Weapon::stats` knows about `w`.
+fn draw_weapon_info(w: Weapon, x: f32, y: f32, width: f32) {
+    let stats = w.stats();
+    let mut rows: Vec<(&str, String)> = vec![
+        ("Type", stats.weapon_type.name().to_string()),
+        ("Category", stats.category.name()[2..].to_string()),
+    ];
+    if stats.damage > 0 {
+        rows.push(("Damage", stats.damage.to_string()));
+    }
+    if stats.explosion_radius > 0.0 {
+        rows.push(("Blast radius", format!("{:.0} px", stats.explosion_radius)));
+        rows.push(("Falloff", stats.falloff.name().to_string()));
+    }
+    if let Some(fuse) = stats.fuse {
+        rows.push(("Fuse", format!("{:.1} s", fuse)));
+    }
+    if stats.bounces > 0 {
+        rows.push(("Bounces", stats.bounces.to_string()));
+    }
+    if let Some(speed) = stats.max_speed {
+        rows.push(("Max speed", format!("{:.0} px/s", speed)));
+    }
+    if stats.cluster_count > 0 {
+        rows.push(("Bomblets", stats.cluster_count.to_string()));
+        rows.push(("Bomblet damage", stats.cluster_damage.to_string()));
+        rows.push(("Bomblet radius", format!("{:.0} px", stats.cluster_radius)));
+    }
+
+    let header_h = 40.0;
+    let row_h = 22.0;
+    let height = header_h + 30.0 + rows.len() as f32 * row_h + 10.0;
+    draw_rectangle(x, y, width, height, Color::new(0.08, 0.1, 0.14, 0.98));
+    draw_rectangle(x, y, width, 2.0, Color::new(0.3, 0.6, 0.9, 0.8));
+    draw_rectangle_lines(x, y, width, height, 2.0, Color::new(0.25, 0.45, 0.65, 0.9));
+
+    draw_text(w.icon(), x + 12.0, y + 28.0, 20.0, WHITE);
+    draw_text(w.name(), x + 44.0, y + 28.0, 18.0, Color::new(0.9, 0.95, 1.0, 1.0));
+    draw_text(w.description(), x + 12.0, y + header_h + 14.0, 11.0, Color::new(0.55, 0.6, 0.7, 0.95));
+
+    let mut row_y = y + header_h + 30.0;
+    for (label, value) in rows {
+        draw_rectangle(x + 8.0, row_y + row_h - 1.0, width - 16.0, 1.0, Color::new(0.25, 0.28, 0.35, 0.6));
+        draw_text(label, x + 12.0, row_y + 15.0, 13.0, Color::new(0.6, 0.65, 0.75, 0.95));
+        let value_w = measure_text(&value, None, 13, 1.0).width;
+        draw_text(&value, x + width - 12.0 - value_w, row_y + 15.0, 13.0, WHITE);
+        row_y += row_h;
+    }
+}
+
+/// Draw every ball, remote ones at their interpolated `Ball::draw_pos`. `cast_aims`
+/// lists (ball, aim angle, weapon) arrows to draw on top, for the spectator cast view;
+/// pass an empty slice otherwise.
+pub fn draw_ball_world(balls: &[Ball], current_ball: usize, cast_aims: &[(usize, f32, Weapon)]) {
+    let now = get_time() as f32;
     for (i, ball) in balls.iter().enumerate() {
         if !ball.alive {
             continue;
         }
+        let (x, y) = ball.draw_pos(now);
         let (r, g, b) = TEAM_COLORS[ball.team as usize % TEAM_COLORS.len()];
         let color = Color::new(r, g, b, 1.0);
         let outline = Color::new(r * 0.4, g * 0.4, b * 0.4, 1.0);
-        let rad = BALL_RADIUS;
+        let rad = ball.radius;
 
-        draw_circle(ball.x, ball.y, rad + 1.5, outline);
-        draw_circle(ball.x, ball.y, rad, color);
+        draw_circle(x, y, rad + 1.5, outline);
+        draw_circle(x, y, rad, color);
 
         if i == current_ball {
-            draw_circle_lines(ball.x, ball.y, rad + 3.0, 1.5, WHITE);
+            draw_circle_lines(x, y, rad + 3.0, 1.5, WHITE);
         }
 
-        let eye_x_base = ball.x + ball.facing * 2.5;
-        let eye_y = ball.y - 1.5;
+        if ball.last_stand {
+            // Flickering ember aura for a team's last ball standing
+            let flicker = (get_time() as f32 * 7.0 + x * 0.1).sin() * 0.2 + 0.6;
+            draw_circle_lines(x, y, rad + 4.0, 1.5, Color::new(1.0, 0.35, 0.1, flicker));
+            draw_circle(x, y, rad + 4.0, Color::new(1.0, 0.3, 0.05, flicker * 0.15));
+        }
+
+        if ball.shield {
+            let pulse = (get_time() as f32 * 4.0).sin() * 0.15 + 0.65;
+            draw_circle(x, y, rad + 6.0, Color::new(0.4, 0.8, 1.0, pulse * 0.25));
+            draw_circle_lines(x, y, rad + 6.0, 2.0, Color::new(0.5, 0.9, 1.0, pulse));
+        }
+
+        let eye_x_base = x + ball.facing * 2.5;
+        let eye_y = y - 1.5;
         draw_circle(eye_x_base - 1.5, eye_y, 2.2, WHITE);
         draw_circle(eye_x_base + 1.5, eye_y, 2.2, WHITE);
         draw_circle(
@@ -568,8 +797,8 @@ pub fn draw_ball_world(balls: &[Ball], current_ball: usize) {
 
         let bar_w = 26.0;
         let bar_h = 4.0;
-        let bar_x = ball.x - bar_w / 2.0;
-        let bar_y = ball.y - rad - 14.0;
+        let bar_x = x - bar_w / 2.0;
+        let bar_y = y - rad - 14.0;
         draw_rectangle(
             bar_x - 1.0,
             bar_y - 1.0,
@@ -578,6 +807,11 @@ pub fn draw_ball_world(balls: &[Ball], current_ball: usize) {
             Color::new(0.0, 0.0, 0.0, 0.6),
         );
         let hp_frac = ball.health as f32 / ball.max_health as f32;
+        // Lag ghost: the HP just lost, draining away behind the live bar
+        let ghost_frac = (ball.display_health / ball.max_health as f32).min(1.0);
+        if ghost_frac > hp_frac {
+            draw_rectangle(bar_x, bar_y, bar_w * ghost_frac, bar_h, Color::new(0.95, 0.25, 0.2, 0.75));
+        }
         let hp_color = if hp_frac > 0.5 {
             Color::new(0.15, 0.8, 0.15, 1.0)
         } else if hp_frac > 0.25 {
@@ -591,26 +825,45 @@ pub fn draw_ball_world(balls: &[Ball], current_ball: usize) {
         let nm = measure_text(&ball.name, None, name_size as u16, 1.0);
         draw_text(
             &ball.name,
-            ball.x - nm.width / 2.0,
+            x - nm.width / 2.0,
             bar_y - 3.0,
             name_size,
             Color::new(1.0, 1.0, 1.0, 0.85),
         );
 
         if ball.damage_timer > 0.0 && ball.last_damage > 0 {
-            let popup_y = ball.y - rad - 22.0 - (2.0 - ball.damage_timer) * 20.0;
+            let popup_y = y - rad - 22.0 - (2.0 - ball.damage_timer) * 20.0;
             let alpha = ball.damage_timer.min(1.0);
             let txt = format!("-{}", ball.last_damage);
             let tw = measure_text(&txt, None, 18, 1.0).width;
             draw_text(
                 &txt,
-                ball.x - tw / 2.0,
+                x - tw / 2.0,
                 popup_y,
                 18.0,
                 Color::new(1.0, 0.2, 0.1, alpha),
             );
         }
     }
+
+    for &(i, angle, weapon) in cast_aims {
+        let Some(ball) = balls.get(i).filter(|b| b.alive) else { continue };
+        let (r, g, b) = TEAM_COLORS[ball.team as usize % TEAM_COLORS.len()];
+        let color = Color::new(r, g, b, 0.9);
+        let (dx, dy) = (angle.cos(), angle.sin());
+        let (x, y) = ball.draw_pos(now);
+        let (sx, sy) = (x + dx * (ball.radius + 4.0), y + dy * (ball.radius + 4.0));
+        let (tx, ty) = (x + dx * (ball.radius + 30.0), y + dy * (ball.radius + 30.0));
+        draw_line(sx, sy, tx, ty, 2.0, color);
+        // Arrowhead
+        for side in [-1.0f32, 1.0] {
+            let a = angle + std::f32::consts::PI - side * 0.5;
+            draw_line(tx, ty, tx + a.cos() * 7.0, ty + a.sin() * 7.0, 2.0, color);
+        }
+        let label = weapon.name();
+        let lw = measure_text(label, None, 10, 1.0).width;
+        draw_text(label, tx - lw / 2.0 + dx * 10.0, ty + dy * 10.0 + 4.0, 10.0, color);
+    }
 }
 
 // Returns (x, y, width, height) of weapon button
@@ -1,20 +1,38 @@
+mod barrels;
+mod bot;
 mod camera;
+mod crates;
 mod hud;
+mod net_input;
 mod network;
 mod physics;
+mod physics_backend;
+mod platforms;
 mod projectile;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
+#[cfg(not(target_arch = "wasm32"))]
+mod save;
+mod settings;
+mod sim;
 mod special_weapons;
 mod state;
 mod terrain;
+mod terrain_texture;
 mod weapons;
 
-use camera::GameCamera;
+use barrels::Barrel;
+use camera::{Director, GameCamera};
+use crates::{CrateKind, SupplyCrate};
+use net_input::NetInput;
 use macroquad::prelude::*;
 use physics::{Ball, BALL_RADIUS};
 use projectile::{Projectile, ClusterBomblet, ShotgunPellet};
-use special_weapons::{AirstrikeDroplet, FirePool, UziBullet, PlacedExplosive, AirstrikeType};
-use state::Phase;
-use terrain::Terrain;
+use settings::{BallSize, MatchSettings, SpawnMode};
+use sim::GameSim;
+use special_weapons::{AirstrikeDroplet, Decoy, FirePool, NinjaRope, UziBullet, PlacedExplosive, AirstrikeType};
+use state::{ActionOutcome, BufferedInput, FireCheck, GameError, MatchOutcome, Phase, ProjectileView};
+use terrain::{CustomMap, Terrain, TerrainTheme};
 use weapons::Weapon;
 
 const TURN_TIME: f32 = 55.0;
@@ -23,6 +41,100 @@ const SETTLE_TIMEOUT: f32 = 0.0;
 const CHARGE_SPEED: f32 = 55.0;
 /// Default camera zoom level. Values > 1 mean “more zoomed in” relative to BASE_SHORT_AXIS.
 const DEFAULT_ZOOM: f32 = 2.0;
+/// Number of upcoming turns shown in the HUD turn-order panel.
+const TURN_PREVIEW_LEN: usize = 4;
+/// Hit-stop after a kill or an explosion at least HIT_STOP_RADIUS wide: gameplay runs at
+/// HIT_STOP_SCALE for HIT_STOP_TIME real seconds, then recovers at HIT_STOP_RECOVERY per second.
+const HIT_STOP_RADIUS: f32 = 60.0;
+const HIT_STOP_SCALE: f32 = 0.25;
+const HIT_STOP_TIME: f32 = 0.35;
+const HIT_STOP_RECOVERY: f32 = 4.0;
+/// Final-kill cinematic: zoom while following a shot that can end the match, follow speed
+/// multiplier, and real seconds held on the kill once the game is over before the banner
+const FINAL_CAM_ZOOM: f32 = 2.8;
+const FINAL_CAM_FOLLOW: f32 = 1.5;
+const FINAL_CAM_HOLD: f32 = 2.0;
+/// Fixed step and step cap for fast-forwarding in-flight effects when a turn is forced
+/// to end (20 simulated seconds, longer than any fuse)
+const RESOLVE_STEP: f32 = 1.0 / 60.0;
+const RESOLVE_MAX_STEPS: usize = 1200;
+/// Most exposed dirt cells a turn's grass regrowth pass turns to grass
+const GRASS_GROWTH_PER_TURN: usize = 40;
+/// World distance from a previewed target that still counts as tapping it to confirm
+const CONFIRM_TAP_RADIUS: f32 = 20.0;
+/// Speed (px/s) a full-strength `blast_push` gives a shell right at the blast centre
+const BLAST_PUSH_SPEED: f32 = 420.0;
+/// Wind offset either side of the current value for the aim preview's confidence band
+const WIND_BAND_DELTA: f32 = 0.5;
+/// Minimum seconds between floating-chunk scans; each one floods the whole map
+const STABILITY_RECHECK: f32 = 0.5;
+/// Seconds unsupported terrain stays outlined after the terrain changes
+const UNSTABLE_FLASH_TIME: f32 = 2.5;
+/// Column spacing (px) of the skyline overlay's samples
+const SKYLINE_STRIDE: i32 = 4;
+/// Foxhole pit: half-width and depth below the ball's feet
+const FOXHOLE_HALF_W: i32 = 16;
+const FOXHOLE_DEPTH: i32 = 20;
+/// How far above the ball a flare decoy hangs, clear of the ball it protects
+const FLARE_HEIGHT: i32 = 90;
+/// Crate rain drops one more crate per turn every this many turns...
+const CRATE_RAIN_ESCALATE_EVERY: u32 = 5;
+/// ...up to this many
+const CRATE_RAIN_MAX_PER_TURN: u32 = 4;
+/// How far the safe zone closes in from each side per turn once it starts shrinking
+/// (on a full-resolution map; both zone sizes scale with the map)
+const ZONE_SHRINK_STEP: f32 = 30.0;
+/// The safe zone stops shrinking at this width, centred on the map
+const ZONE_MIN_WIDTH: f32 = 240.0;
+/// Terrain ops logged before they're folded into a snapshot, which caps the size of
+/// the log the server replays to reconnecting clients
+const TERRAIN_LOG_COMPACT_AT: usize = 1024;
+/// Side of the square blocks the end-of-turn terrain check hashes and patches, in cells
+const TERRAIN_BLOCK_SIZE: u32 = 100;
+/// Most divergent blocks one heal request asks for; the rest wait for the next turn end
+const MAX_HEAL_BLOCKS: usize = 16;
+/// Most sand cells that fall per frame while settling; the rest keep falling next frame
+const SAND_MOVES_PER_TICK: usize = 2000;
+/// How much wider than a ball the patch of sand that crumbles under it is
+const SAND_CRUMBLE_MARGIN: i32 = 3;
+/// Cave gas: seconds from catching to bursting, so a pocket goes up in a quick ripple
+const GAS_FUSE: f32 = 0.1;
+const GAS_BURST_RADIUS: f32 = 16.0;
+const GAS_DAMAGE: i32 = 20;
+/// Points one blast or fire can light, spaced a burst radius apart
+const GAS_IGNITIONS_PER_BLAST: usize = 4;
+/// Safety cap on gas bursts waiting to go off at once
+const MAX_GAS_FUSES: usize = 32;
+/// Seconds a mystery crate's reveal shows above it, the first CRATE_REVEAL_SPIN of
+/// them flicking through the arsenal before it settles on the contents
+const CRATE_REVEAL_TIME: f32 = 1.8;
+const CRATE_REVEAL_SPIN: f32 = 0.6;
+/// Seconds the HUD shows a weapon picked by keyboard cycling
+const WEAPON_FLASH_TIME: f32 = 1.2;
+/// Seconds the second End press has to arrive in to confirm a forfeit
+const FORFEIT_CONFIRM_TIME: f32 = 2.0;
+/// Prod (E): gap between ball edges it reaches across, shove, damage and cooldown
+const PROD_REACH: f32 = 6.0;
+const PROD_KNOCK_X: f32 = 140.0;
+const PROD_KNOCK_Y: f32 = 80.0;
+const PROD_DAMAGE: i32 = 2;
+const PROD_COOLDOWN: f32 = 1.5;
+/// How long a fire/jump/backflip pressed too early is held for the phase to allow it
+const INPUT_BUFFER_TIME: f32 = 0.25;
+/// Seconds a map ping stays on screen
+const PING_TIME: f32 = 3.0;
+/// Pings shown at once; a new one pushes out the oldest so spam can't pile up
+const MAX_PINGS: usize = 16;
+/// Enemy kills in a row, without losing a ball, before a team's streak is announced
+const KILL_STREAK_MIN: u32 = 3;
+/// Block size (px) for drawing terrain without a texture on low-memory devices.
+const CPU_TERRAIN_BLOCK: i32 = 4;
+/// Low-spec mode: downscale factor for the terrain texture
+const LOW_SPEC_TERRAIN_SCALE: u16 = 2;
+/// Low-spec mode: keep one explosion particle in this many
+const LOW_SPEC_PARTICLE_STRIDE: usize = 4;
+/// Low-spec mode: projectile trail points drawn
+const LOW_SPEC_TRAIL_LEN: usize = 10;
 
 #[cfg(target_arch = "wasm32")]
 extern "C" {
@@ -40,12 +152,19 @@ struct Particle {
 }
 
 struct Game {
-    terrain: Terrain,
+    /// Terrain, balls, wind and the tick count: what every client simulates identically
+    sim: GameSim,
     terrain_image: Image,
-    terrain_texture: Texture2D,
+    /// None when the GPU couldn't allocate even a reduced texture; terrain is then
+    /// drawn on the CPU (see `Terrain::draw_blocks`)
+    terrain_texture: Option<Texture2D>,
+    /// Resolution divisor the terrain texture was uploaded at (1 = full size)
+    terrain_texture_scale: u16,
+    /// Cheaper drawing for weak devices: half-resolution terrain texture, fewer particles,
+    /// shorter trails and a flat sky. Drawing only, so clients may differ.
+    low_spec: bool,
     terrain_dirty: bool,
 
-    balls: Vec<Ball>,
     current_ball: usize,
 
     phase: Phase,
@@ -71,8 +190,16 @@ struct Game {
     shotgun_pellets: Vec<ShotgunPellet>,
     airstrike_droplets: Vec<AirstrikeDroplet>,
     fire_pools: Vec<FirePool>,
+    /// Flare decoys still burning; kept across turns until a homing missile uses them up
+    decoys: Vec<Decoy>,
+    /// Ninja rope the current ball is hanging from, dropped when its turn's movement ends
+    rope: Option<NinjaRope>,
+    /// Supply crates dropped by the crate-rain finale, lying on the map until picked up
+    crates: Vec<SupplyCrate>,
     uzi_bullets: Vec<UziBullet>,
     placed_explosives: Vec<PlacedExplosive>,
+    /// Cave gas alight and about to burst, as (x, y, seconds left), in the order it caught
+    gas_fuses: Vec<(i32, i32, f32)>,
     teleport_mode: bool,
     baseball_bat_mode: bool,
     build_wall_mode: bool,
@@ -80,10 +207,37 @@ struct Game {
     build_wall_anchor: Option<(f32, f32)>,
     /// Airstrike or NapalmStrike waiting for a click-target. Stores which weapon.
     airstrike_mode: Option<Weapon>,
+    /// Second weapon whose arc is overlaid on the aim preview while X is held, to compare
+    /// shots; set by right-clicking it in the weapon menu
+    compare_weapon: Option<Weapon>,
+    /// Tap-to-preview for the click-target weapons: the first tap only places the target
+    /// and a second tap on it commits, so a stray tap can't fire (`confirmTargets`)
+    confirm_targets: bool,
+    /// Target placed by the first tap while `confirm_targets` is on, waiting for the confirm tap
+    pending_target: Option<(f32, f32)>,
     /// Cumulative log of wall placements for reconnect sync: (ax, ay, angle_mrad)
     wall_log: Vec<(i32, i32, i32)>,
     /// Cumulative log of drill tunnels for reconnect sync: (bx, by, angle_mrad)
     drill_log: Vec<(i32, i32, i32)>,
+    /// Cumulative log of dug foxholes for reconnect sync: (bx, by)
+    foxhole_log: Vec<(i32, i32)>,
+    /// Sand that crumbled under a ball, as the (x, y) it gave way at
+    crumble_log: Vec<(i32, i32)>,
+    /// Kind of every logged terrain op (the type numbers of the sync format) in the order
+    /// they happened, so a reconnect replays them in that order rather than by kind
+    terrain_op_order: Vec<u8>,
+    /// Terrain at the last terrain-log compaction as (cell, count) runs. The ops logs
+    /// only hold what happened since, so a reconnect replays them on top of this.
+    terrain_snapshot: Option<Vec<(u8, usize)>>,
+    /// Number of compactions so far; the server keeps the log with the highest version
+    terrain_log_version: u32,
+    /// Block hashes this client broadcast at the end of its turn, with that turn number.
+    /// Heal requests are only answered for blocks that still hash the same.
+    sent_terrain_hashes: Option<(u32, Vec<u64>)>,
+    /// Block hashes from the active player's turn end, compared once our own effects settle
+    pending_terrain_hashes: Option<(u32, Vec<u64>)>,
+    /// Blocks asked for in our last heal request, with the turn and our hash of each at the time
+    terrain_heal_requests: Vec<(u32, usize, u64)>,
     /// Countdown before bot fires (resets each turn)
     bot_think_timer: f32,
 
@@ -104,7 +258,6 @@ struct Game {
     /// Reset to DEFAULT_ZOOM on every turn start.
     cam_target_zoom: f32,
 
-    wind: f32,
     rng_state: u32,
 
     particles: Vec<Particle>,
@@ -112,12 +265,16 @@ struct Game {
     
     weapon_menu_open: bool,
     weapon_menu_scroll: f32,
+    /// Search text typed while the weapon menu is open
+    menu_filter: String,
 
     net: network::NetworkState,
     /// Current turn index from server (which player's turn it is: 0, 1, etc.)
     current_turn_index: usize,
     /// Number of teams/players in the game
     num_teams: usize,
+    /// Match options shared by all clients (terrain density, rule toggles)
+    settings: MatchSettings,
     /// When we receive turn_advanced during ProjectileFlying/Settling, apply when settling ends
     pending_turn_sync: Option<usize>,
     /// Deferred restart seed
@@ -143,129 +300,205 @@ struct Game {
     stuck_phase_timer: f32,
     /// Per-ball cooldown (seconds) for game-event toasts — prevents spam from fires/DoT
     ball_event_cooldown: Vec<f32>,
+    /// Seed the map was generated from; drives seeded map events
+    map_seed: u32,
+    /// Turns started since the match began (drives scheduled map events)
+    turns_played: u32,
+    /// Turn numbers at which an earthquake collapsed the terrain, for reconnect sync
+    quake_log: Vec<u32>,
+    /// Turns whose grass regrowth pass changed the terrain; replayed on reconnect
+    grass_log: Vec<u32>,
+    /// Last charged shot (weapon, aim angle, power) per team, recalled with R
+    last_shot: Vec<Option<(Weapon, f32, f32)>>,
+    /// Power a recalled shot fires at: holding F auto-releases here instead of at 100
+    charge_target: Option<f32>,
+    /// Automatic spectator camera (toggled with V while spectating)
+    director: Director,
+    /// Centre of an explosion since the director last looked, so it can cut to the impact
+    last_impact: Option<(f32, f32)>,
+    /// Weapons each team may use; every weapon unless the random-loadout option is on
+    loadouts: Vec<Vec<Weapon>>,
+    /// Campaign progression from the front-end: the weapons the player has unlocked so
+    /// far, on top of (not instead of) the loadouts. None outside a campaign.
+    unlocked_weapons: Option<Vec<Weapon>>,
+    /// Multiplier on the gameplay dt; below 1.0 during hit-stop
+    time_scale: f32,
+    /// Real seconds of hit-stop left
+    hit_stop_timer: f32,
+    /// Client preference (`finalKillCam`, on by default): play the final-kill cinematic.
+    /// Camera and HUD only; the match state moves on exactly as without it.
+    final_cam: bool,
+    /// The shot in flight can end the match, or just did: letterboxed, zoomed-in view
+    final_cam_active: bool,
+    /// Where the last enemy ball died, held in view until the game-over banner
+    final_cam_focus: Option<(f32, f32)>,
+    /// Real seconds left holding on the kill after the game ended
+    final_cam_hold: f32,
+    /// Seconds left showing the weapon just picked with [ / ]
+    weapon_flash_timer: f32,
+    /// Mystery crate just opened, as (x, y, weapon, seconds left), for the reveal above it
+    crate_reveal: Option<(f32, f32, Weapon, f32)>,
+    /// Edge cells of terrain chunks the next earthquake will drop
+    unstable_outline: Vec<(i32, i32)>,
+    /// Terrain changed since `unstable_outline` was computed
+    stability_stale: bool,
+    /// Cooldown before the floating-chunk scan may run again
+    stability_recheck: f32,
+    /// Seconds left outlining unstable terrain after the last change
+    unstable_flash: f32,
+    /// Draw the terrain's top-surface profile as a line (toggled with P)
+    skyline_overlay: bool,
+    /// Spectator cast view: every team's last known aim and weapon drawn at once (toggled with C)
+    cast_aims_overlay: bool,
+    /// Training aid: the team's previous shell flight drawn faintly while aiming (toggled with G)
+    ghost_overlay: bool,
+    /// Per team, the whole flight path of its last shell, for the ghost overlay
+    ghost_shots: Vec<Vec<(f32, f32)>>,
+    /// Per team, the last aim and weapon broadcast on its turn as (ball, angle, weapon)
+    team_aims: Vec<Option<(usize, f32, Weapon)>>,
+    /// Debug view of the terrain ops logs: a marker where each logged op happened (F3)
+    terrain_ops_overlay: bool,
+    /// Teams that conceded; their balls were removed rather than killed
+    forfeited_teams: Vec<u32>,
+    /// Per team: the sim tick it lost its last ball, None while it has one. Forfeits
+    /// count as tick 0 so they never outlast a team that was actually killed.
+    team_out_ticks: Vec<Option<u32>>,
+    /// Seconds left to press End again and confirm the forfeit
+    forfeit_confirm_timer: f32,
+    /// Seconds until the active ball may prod again
+    prod_cooldown: f32,
+    /// Map pings as (x, y, seconds left, team); team is None for spectators. Cosmetic only.
+    pings: Vec<(f32, f32, f32, Option<u32>)>,
+    /// Enemy kills credited to the active team this turn, for multi-kill announcements
+    kills_this_turn: u32,
+    /// Enemy kills in the match so far; the first one is announced as first blood
+    total_kills: u32,
+    /// Per team: enemy kills since it last lost a ball
+    kill_streaks: Vec<u32>,
+    /// Action pressed before the phase (or our turn) allowed it, and seconds left to apply it
+    pending_action: Option<(BufferedInput, f32)>,
+    /// Cursor position when the turn began. Mouse aim waits until the cursor leaves it so
+    /// the turn-start aim at the nearest enemy isn't overwritten straight away.
+    turn_start_mouse: Option<(f32, f32)>,
+    /// Moving platforms of a platforms map; empty on normal maps
+    platforms: Vec<platforms::MovingPlatform>,
+    /// Platform steps simulated so far; platform positions are derived from it
+    platform_ticks: u32,
+    /// Game time not yet folded into a whole platform step
+    platform_clock: f32,
+    /// Neutral explosive barrels placed with the map; destroyed ones stay in the list
+    /// (not alive) so indices match across clients for reconnect sync
+    barrels: Vec<Barrel>,
+    /// x-range balls may be in; they die outside it. The whole map unless the zone
+    /// has started shrinking (`shrink_zone_after`).
+    safe_zone: (f32, f32),
+    /// Hand-made map this match is played on, kept for rematches; None for generated maps
+    custom_map: Option<CustomMap>,
 }
 
 impl Game {
     fn new(seed: u32) -> Self {
         // Default to 2 teams for offline play
-        Self::new_with_teams(seed, 2)
+        Self::new_with_teams(seed, 2, MatchSettings::default())
     }
 
-    fn new_with_teams(seed: u32, num_teams: usize) -> Self {
-        let t = terrain::generate(seed);
+    fn new_with_teams(seed: u32, num_teams: usize, settings: MatchSettings) -> Self {
+        Self::new_with_map(seed, num_teams, settings, None)
+    }
+
+    /// Like `new_with_teams`, but plays `custom` instead of a generated map when given.
+    /// Its spawn points replace the spawn mode's layout when there's one per ball.
+    fn new_with_map(seed: u32, num_teams: usize, settings: MatchSettings, custom: Option<CustomMap>) -> Self {
+        let theme = settings.terrain_theme.unwrap_or_else(|| TerrainTheme::from_seed(seed));
+        let mut t = match &custom {
+            Some(map) => map.terrain(),
+            None => terrain::generate(seed, settings.terrain_density, settings.mirror_map, theme, settings.gas_pockets),
+        };
+        if settings.half_res_terrain {
+            t = t.half_resolution();
+        }
+        t.theme = theme;
+        t.push_ratio = settings.crater_push;
+        let moving_platforms = if settings.moving_platforms { platforms::generate(seed, &t) } else { Vec::new() };
         let img = t.bake_image();
-        let tex = Texture2D::from_image(&img);
-        tex.set_filter(FilterMode::Nearest);
-
-        let team_names = [
-            ["Spike", "Tank", "Blaze"],
-            ["Frost", "Storm", "Shadow"],
-            ["Viper", "Ghost", "Flash"],
-            ["Rex", "Duke", "Scout"],
-        ];
-        let balls_per_team: usize = 3;
+        let uploaded = terrain_texture::upload(&img, 1);
+
+        let balls_per_team = BALLS_PER_TEAM;
+        let radius = settings.ball_size.radius();
         let total = num_teams * balls_per_team;
         let mut balls = Vec::new();
 
-        // Spawn balls within the playable land area only
-        let mut positions: Vec<f32> = Vec::new();
-        for i in 0..total {
-            let x = terrain::LAND_START_X + (i + 1) as f32 * terrain::PLAYABLE_LAND_WIDTH / (total + 1) as f32;
-            positions.push(x);
-        }
         let mut interleaved: Vec<(usize, usize)> = Vec::new();
         for wi in 0..balls_per_team {
             for ti in 0..num_teams {
                 interleaved.push((ti, wi));
             }
         }
+        // Spawn balls within the playable land area only
+        let custom_spawns = custom.as_ref().map(|m| &m.spawns).filter(|s| s.len() >= total);
+        let positions: Vec<f32> = match (custom_spawns, settings.spawn_mode) {
+            // Slot order, like the layouts below; custom maps are drawn at full resolution
+            (Some(spawns), _) => spawns[..total].iter().map(|x| x * t.scale()).collect(),
+            // Evenly across the map, teams alternating
+            (None, SpawnMode::Spread) => (0..total)
+                .map(|i| t.land_start_x() + (i + 1) as f32 * t.playable_land_width() / (total + 1) as f32)
+                .collect(),
+            // Each team evenly across its own strip of the map, team 0 leftmost
+            (None, SpawnMode::Zones) => {
+                let zone_w = t.playable_land_width() / num_teams.max(1) as f32;
+                interleaved
+                    .iter()
+                    .map(|&(ti, wi)| {
+                        t.land_start_x() + ti as f32 * zone_w
+                            + (wi + 1) as f32 * zone_w / (balls_per_team + 1) as f32
+                    })
+                    .collect()
+            }
+        };
 
         for (slot, &(ti, wi)) in interleaved.iter().enumerate() {
-            let x = positions[slot];
-            
-            // Find safe spawn position (avoid lava)
-            let mut spawn_y = None;
-            let mut search_x = x as i32;
-            
-            // Try original position first
-            if let Some(surface_y) = t.find_surface_y(search_x) {
-                // Check if there's lava at or near where the ball would spawn
-                let ball_y = surface_y - (BALL_RADIUS as i32) - 2;
-                let mut is_safe = true;
-                
-                // Check area around spawn position for lava
-                for dy in -2..3 {
-                    for dx in -2..3 {
-                        let check_x = search_x + dx;
-                        let check_y = ball_y + dy;
-                        if t.get(check_x, check_y) == terrain::LAVA {
-                            is_safe = false;
-                            break;
-                        }
-                    }
-                    if !is_safe { break; }
-                }
-                
-                if is_safe {
-                    spawn_y = Some(surface_y as f32 - BALL_RADIUS - 2.0);
-                }
-            }
-            
-            // If original position has lava, search nearby for safe spot
-            if spawn_y.is_none() {
-                for offset in 1..50 {
-                    for dir in [-1, 1] {
-                        let test_x = (x as i32 + offset * dir).max(terrain::LAND_START_X as i32).min(terrain::LAND_END_X as i32);
-                        if let Some(surface_y) = t.find_surface_y(test_x) {
-                            let ball_y = surface_y - (BALL_RADIUS as i32) - 2;
-                            let mut is_safe = true;
-                            
-                            // Check area around spawn position for lava
-                            for dy in -2..3 {
-                                for dx in -2..3 {
-                                    let check_x = test_x + dx;
-                                    let check_y = ball_y + dy;
-                                    if t.get(check_x, check_y) == terrain::LAVA {
-                                        is_safe = false;
-                                        break;
-                                    }
-                                }
-                                if !is_safe { break; }
-                            }
-                            
-                            if is_safe {
-                                spawn_y = Some(surface_y as f32 - BALL_RADIUS - 2.0);
-                                search_x = test_x;
-                                break;
-                            }
-                        }
-                    }
-                    if spawn_y.is_some() {
-                        break;
-                    }
-                }
+            let (spawn_x, y) = safe_spawn(&t, positions[slot], radius);
+            let mut ball = Ball::new(spawn_x, y, ti as u32, default_ball_name(ti, wi));
+            ball.radius = radius;
+            ball.knockback_scale = settings.knockback_scale;
+            ball.weapon_damage_scale = settings.weapon_damage;
+            balls.push(ball);
+        }
+        // On a mirrored map both spawn layouts are symmetric, with slot i opposite slot
+        // total-1-i. Copy the reflection of each earlier slot over its later partner so
+        // the lava/water spawn search can't nudge the two sides apart.
+        if settings.mirror_map {
+            for slot in total.div_ceil(2)..total {
+                let partner = &balls[total - 1 - slot];
+                let (x, y) = ((t.width as i32 - 1 - partner.x as i32) as f32, partner.y);
+                balls[slot].x = x;
+                balls[slot].y = y;
             }
-            
-            let y = spawn_y.unwrap_or(400.0);
-            let spawn_x = search_x as f32;
-            let name = team_names[ti % team_names.len()][wi % 3].to_string();
-            balls.push(Ball::new(spawn_x, y, ti as u32, name));
         }
 
+        let barrels = barrels::generate(seed, &t, &balls, settings.mirror_map);
+
         // Center camera on playable land area
-        let cam_x = terrain::LAND_START_X + terrain::PLAYABLE_LAND_WIDTH / 2.0;
+        let cam_x = t.land_start_x() + t.playable_land_width() / 2.0;
         let cam_y = t.height as f32 * 0.45;
+        let safe_zone = (0.0, t.width as f32);
         let mut rng = seed;
         rng = lcg(rng);
         let wind = ((rng >> 16) as f32 / 65536.0 - 0.5) * 6.0;
+        // Balls are interleaved by team, so team t's first ball is ball t
+        let first_team = if settings.random_first_turn && num_teams > 0 {
+            (lcg(seed ^ 0xC01F_11F5) >> 16) as usize % num_teams
+        } else {
+            0
+        };
 
         Game {
-            terrain: t,
             terrain_image: img,
-            terrain_texture: tex,
+            terrain_texture_scale: uploaded.as_ref().map_or(0, |(_, scale)| *scale),
+            low_spec: false,
+            terrain_texture: uploaded.map(|(tex, _)| tex),
             terrain_dirty: false,
-            balls,
-            current_ball: 0,
+            current_ball: first_team,
             phase: Phase::Aiming,
             turn_timer: TURN_TIME,
             settle_timer: 0.0,
@@ -282,15 +515,30 @@ impl Game {
             shotgun_pellets: Vec::new(),
             airstrike_droplets: Vec::new(),
             fire_pools: Vec::new(),
+            decoys: Vec::new(),
+            rope: None,
+            crates: Vec::new(),
             uzi_bullets: Vec::new(),
             placed_explosives: Vec::new(),
+            gas_fuses: Vec::new(),
             teleport_mode: false,
             baseball_bat_mode: false,
             build_wall_mode: false,
             build_wall_anchor: None,
             airstrike_mode: None,
+            compare_weapon: None,
+            confirm_targets: false,
+            pending_target: None,
             wall_log: Vec::new(),
             drill_log: Vec::new(),
+            foxhole_log: Vec::new(),
+            crumble_log: Vec::new(),
+            terrain_op_order: Vec::new(),
+            terrain_snapshot: None,
+            terrain_log_version: 0,
+            sent_terrain_hashes: None,
+            pending_terrain_hashes: None,
+            terrain_heal_requests: Vec::new(),
             bot_think_timer: 3.0,
             cam: GameCamera::new(cam_x, cam_y),
             panning: false,
@@ -300,49 +548,94 @@ impl Game {
             cam_free_timer: 0.0,
             cam_return_timer: 0.0,
             cam_target_zoom: DEFAULT_ZOOM,
-            wind,
             rng_state: rng,
             particles: Vec::new(),
             winning_team: None,
             weapon_menu_open: false,
             weapon_menu_scroll: 0.0,
+            menu_filter: String::new(),
             net: network::NetworkState::new(),
-            current_turn_index: 0,
+            current_turn_index: first_team,
             num_teams,
+            settings,
             pending_turn_sync: None,
             restart_seed: None,
             just_reconnected: false,
             last_aim_send: 0.0,
             last_pos_send: 0.0,
             last_pos_sent: None,
-            ball_lerp_targets: {
-                let total = num_teams * 3; // balls_per_team = 3
-                vec![None; total]
-            },
+            ball_lerp_targets: vec![None; total],
             last_logged_turn_state: (0, None),
             retreat_timer: 0.0,
             stuck_phase_timer: 0.0,
-            ball_event_cooldown: vec![0.0; num_teams * 3],
+            ball_event_cooldown: vec![0.0; total],
             last_ball_per_team: {
-                // Pre-record that ball 0 (team 0's first ball) is the initial
-                // current_ball, so the next sync_to_player_turn(0) knows to
-                // advance to the second ball instead of re-picking the first.
+                // Pre-record the starting team's first ball as the initial
+                // current_ball, so the next sync_to_player_turn(first_team) knows to
+                // advance to its second ball instead of re-picking the first.
                 let mut v = vec![None; num_teams];
                 if num_teams > 0 {
-                    v[0] = Some(0); // current_ball starts at 0 which belongs to team 0
+                    v[first_team] = Some(first_team);
                 }
                 v
             },
+            map_seed: seed,
+            turns_played: 0,
+            quake_log: Vec::new(),
+            grass_log: Vec::new(),
+            last_shot: vec![None; num_teams],
+            charge_target: None,
+            director: Director::new(),
+            last_impact: None,
+            loadouts: (0..num_teams as u32).map(|team| starting_loadout(seed, team, settings)).collect(),
+            unlocked_weapons: None,
+            time_scale: 1.0,
+            hit_stop_timer: 0.0,
+            final_cam: true,
+            final_cam_active: false,
+            final_cam_focus: None,
+            final_cam_hold: 0.0,
+            weapon_flash_timer: 0.0,
+            crate_reveal: None,
+            unstable_outline: Vec::new(),
+            stability_stale: true,
+            stability_recheck: 0.0,
+            unstable_flash: 0.0,
+            skyline_overlay: false,
+            cast_aims_overlay: false,
+            ghost_overlay: false,
+            ghost_shots: Vec::new(),
+            team_aims: Vec::new(),
+            terrain_ops_overlay: false,
+            forfeited_teams: Vec::new(),
+            sim: GameSim::new(t, balls, wind, settings.fixed_physics),
+            team_out_ticks: vec![None; num_teams],
+            forfeit_confirm_timer: 0.0,
+            prod_cooldown: 0.0,
+            pings: Vec::new(),
+            kills_this_turn: 0,
+            total_kills: 0,
+            kill_streaks: vec![0; num_teams],
+            pending_action: None,
+            turn_start_mouse: None,
+            platforms: moving_platforms,
+            platform_ticks: 0,
+            platform_clock: 0.0,
+            barrels,
+            custom_map: custom,
+            safe_zone,
         }
     }
 
     /// Auto-follow helper that respects cam_free_timer and applies smooth glide-back easing.
     /// Call this in place of cam.follow() at every follow site.
     fn auto_follow(&mut self, tx: f32, ty: f32, speed: f32, dt: f32) {
-        if self.cam_free_timer > 0.0 {
-            return; // user is looking around, don't fight them
+        if self.cam_free_timer > 0.0 || self.director.enabled || self.final_cam_focus.is_some() {
+            return; // user is looking around (or the director or final-kill hold has the camera), don't fight them
         }
         let ease = if self.cam_return_timer > 0.0 {
+            // The glide back ends centered on the action, not at the dead-zone's edge
+            self.cam.recenter();
             // Ramp from ~5 % at the start of the glide to 100 % as timer reaches 0
             (1.0 - self.cam_return_timer / 2.0).max(0.05)
         } else {
@@ -351,6 +644,58 @@ impl Game {
         self.cam.follow(tx, ty, speed * ease, dt);
     }
 
+    /// Follow a shell in flight, switching to the final-kill cinematic (closer, faster,
+    /// zoomed in) while it's the active team's shot at the last enemy ball standing.
+    fn follow_shell(&mut self, x: f32, y: f32, speed: f32, dt: f32) {
+        if self.final_cam && self.final_cam_focus.is_none() && self.enemies_left() == 1 {
+            self.final_cam_active = true;
+        }
+        if !self.final_cam_active {
+            self.auto_follow(x, y, speed, dt);
+            return;
+        }
+        self.auto_follow(x, y, speed * FINAL_CAM_FOLLOW, dt);
+        if self.cam_free_timer <= 0.0 {
+            self.cam.zoom += (FINAL_CAM_ZOOM - self.cam.zoom) * (2.0 * dt).min(1.0);
+        }
+    }
+
+    /// Living balls not on the active ball's team.
+    fn enemies_left(&self) -> usize {
+        let Some(team) = self.sim.balls.get(self.current_ball).map(|b| b.team) else {
+            return 0;
+        };
+        self.sim.balls.iter().filter(|b| b.alive && b.team != team).count()
+    }
+
+    /// Whether the final-kill cinematic is on screen (HUD swapped for letterbox bars).
+    fn final_cam_showing(&self) -> bool {
+        self.final_cam_active && (self.phase != Phase::GameOver || self.final_cam_hold > 0.0)
+    }
+
+    /// Connected to a match without a player slot, i.e. watching
+    fn is_spectator(&self) -> bool {
+        self.net.connected && self.net.my_player_index.is_none()
+    }
+
+    /// Drive the camera from the director when it's enabled. `hurt` is a ball that
+    /// lost health this tick. A manual pan still takes precedence for a moment.
+    fn update_director(&mut self, dt: f32, hurt: Option<usize>) {
+        let impact = self.last_impact.take();
+        if !self.director.enabled || self.cam_free_timer > 0.0 || self.final_cam_focus.is_some() {
+            return;
+        }
+        let projectile = self.proj.as_ref().map(|p| (p.x, p.y))
+            .or_else(|| self.airstrike_droplets.iter().find(|d| d.alive).map(|d| (d.x, d.y)))
+            .or_else(|| self.cluster_bomblets.iter().find(|b| b.alive).map(|b| (b.x, b.y)));
+        if let Some((x, y, speed, zoom)) =
+            self.director.update(dt, &self.sim.balls, projectile, impact, hurt, self.current_ball)
+        {
+            self.cam.follow(x, y, speed, dt);
+            self.cam.zoom += (zoom - self.cam.zoom) * (2.0 * dt).min(1.0);
+        }
+    }
+
     /// Returns true if it's currently our turn (or if offline/native)
     fn is_my_turn(&self) -> bool {
         // In WASM builds, NEVER allow control until server tells us who we are
@@ -378,22 +723,88 @@ impl Game {
         self.current_turn_index == my_player_index
     }
     
+    /// Terrain regions changed since the last call, flattened as `[x, y, w, h, ...]` in
+    /// cells, so a renderer can re-upload just those parts of its terrain texture.
+    pub fn drain_terrain_dirty_rects(&mut self) -> Vec<i32> {
+        self.sim.terrain
+            .take_dirty_rects()
+            .into_iter()
+            .flat_map(|(x, y, w, h)| [x, y, w, h])
+            .collect()
+    }
+
+    /// The Fire input the built-in bot would play for `player_index` this turn, or None
+    /// if that player has no ball or no enemy left. `to_json()` gives the network form,
+    /// so a front-end can drive offline bot matches through the normal input path.
+    pub fn bot_fire_input(&self, player_index: usize) -> Option<NetInput> {
+        let shooter = self.find_ball_for_player(player_index)?;
+        bot::fire_input(&self.sim.balls, shooter, &self.team_loadout(self.sim.balls[shooter].team), &self.sim.terrain, self.sim.wind)
+    }
+
+    /// The shell in flight as a JS renderer draws it, or "null" when there isn't one.
+    fn projectile_json(&self) -> String {
+        self.proj.as_ref().map_or_else(|| String::from("null"), |p| ProjectileView::new(p).to_json())
+    }
+
+    /// Last aim and weapon seen from `team`, for the cast view.
+    fn team_aim(&self, team: usize) -> Option<(usize, f32, Weapon)> {
+        self.team_aims.get(team).copied().flatten()
+    }
+
+    fn set_team_aim(&mut self, team: usize, aim: (usize, f32, Weapon)) {
+        if self.team_aims.len() <= team {
+            self.team_aims.resize(team + 1, None);
+        }
+        self.team_aims[team] = Some(aim);
+    }
+
     /// Find the first alive ball for a given team/player
     fn find_ball_for_player(&self, player_index: usize) -> Option<usize> {
         let team = player_index as u32;
-        self.balls
+        self.sim.balls
             .iter()
             .enumerate()
             .find(|(_, w)| w.alive && w.team == team)
             .map(|(idx, _)| idx)
     }
 
+    /// Remote balls whose position the network streams (pos_update); local physics would
+    /// fight the lerp, so they're left out of the physics step.
+    fn network_driven_balls(&self) -> Vec<usize> {
+        if !self.net.connected {
+            return Vec::new();
+        }
+        let mine = self.net.my_player_index.and_then(|pi| self.find_ball_for_player(pi));
+        (0..self.sim.balls.len())
+            .filter(|&bi| Some(bi) != mine && self.ball_lerp_targets.get(bi).copied().flatten().is_some())
+            .collect()
+    }
+
     fn handle_input(&mut self) {
         if let Some(seed) = self.restart_seed.take() {
-            // Restart with same team count
-            *self = Game::new_with_teams(seed, self.num_teams);
+            // Restart with same team count, staying connected for a networked rematch
+            let mut net = std::mem::replace(&mut self.net, network::NetworkState::new());
+            net.ready_players.clear();
+            let (low_spec, dead_zone, confirm_targets, final_cam) = (self.low_spec, self.cam.dead_zone, self.confirm_targets, self.final_cam);
+            let unlocked = self.unlocked_weapons.take();
+            *self = Game::new_with_map(seed, self.num_teams, self.settings, self.custom_map.take());
+            self.net = net;
+            // Viewing preferences and campaign progress outlive the match
+            self.low_spec = low_spec;
+            self.cam.dead_zone = dead_zone;
+            self.confirm_targets = confirm_targets;
+            self.final_cam = final_cam;
+            self.unlocked_weapons = unlocked;
+            self.terrain_dirty = low_spec;
             return;
         }
+
+        // A weapon armed from the JS arsenal counts as a pick from the weapon menu
+        if let Some(weapon) = network::take_weapon_request() {
+            if self.is_my_turn() && !self.has_fired && self.current_loadout().contains(&weapon) {
+                self.pick_menu_weapon(weapon);
+            }
+        }
         
         // Log turn state changes (not every frame)
         if self.net.connected {
@@ -411,6 +822,10 @@ impl Game {
         }
         
         let (mx, my) = mouse_position();
+        // Drain typed characters every frame so nothing stale reaches the menu search.
+        // The queue pops newest first.
+        let mut typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+        typed.reverse();
 
         if is_mouse_button_pressed(MouseButton::Right) || is_mouse_button_pressed(MouseButton::Middle) {
             self.panning = true;
@@ -432,7 +847,10 @@ impl Game {
             let was_tap = self.left_drag_start.is_some(); // Some = drag never started
             self.left_drag_panning = false;
             self.left_drag_start = None;
-            if was_tap
+            if was_tap && (self.is_spectator() || ping_modifier_down()) {
+                let world_pos = self.cam.to_macroquad().screen_to_world(vec2(mx, my));
+                self.send_ping(world_pos.x, world_pos.y);
+            } else if was_tap
                 && !self.charging
                 && self.is_my_turn()
                 && self.phase.allows_input()
@@ -498,7 +916,7 @@ impl Game {
                     wheel * (item_step / 3.0)    // smooth trackpad / touch swipe
                 };
                 self.weapon_menu_scroll = (self.weapon_menu_scroll - delta)
-                    .clamp(0.0, layout.max_scroll());
+                    .clamp(0.0, layout.max_scroll(&self.menu_filter));
             } else {
                 // Smooth trackpad pinch / fine scroll wheel use a proportional factor;
                 // discrete mouse clicks (large delta) snap by a fixed step.
@@ -516,6 +934,48 @@ impl Game {
             }
         }
 
+        // P toggles the skyline overlay for planning lobs over hills
+        if is_key_pressed(KeyCode::P) && !self.weapon_menu_open {
+            self.skyline_overlay = !self.skyline_overlay;
+        }
+
+        // F3 toggles the terrain ops debug view, for checking reconnect replay against the map
+        if is_key_pressed(KeyCode::F3) {
+            self.terrain_ops_overlay = !self.terrain_ops_overlay;
+        }
+
+        // V toggles the director camera for spectators (not while playing)
+        if is_key_pressed(KeyCode::V) && self.is_spectator() {
+            self.director.enabled = !self.director.enabled;
+        }
+
+        // C toggles the cast view of every team's aim, also spectator-only
+        if is_key_pressed(KeyCode::C) && self.is_spectator() {
+            self.cast_aims_overlay = !self.cast_aims_overlay;
+        }
+
+        // G toggles the ghost of the team's last shot while aiming
+        if is_key_pressed(KeyCode::G) && !self.is_spectator() && !self.weapon_menu_open {
+            self.ghost_overlay = !self.ghost_overlay;
+        }
+
+        // F5 saves an offline match between shots, F9 resumes the last save (native only)
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.net.connected {
+            if is_key_pressed(KeyCode::F5) && self.can_save() {
+                let _ = std::fs::write(save::SAVE_FILE, self.save_to_string());
+            }
+            if is_key_pressed(KeyCode::F9) {
+                if let Some(game) = std::fs::read_to_string(save::SAVE_FILE)
+                    .ok()
+                    .and_then(|s| Game::load_from_string(&s))
+                {
+                    *self = game;
+                    return;
+                }
+            }
+        }
+
         // Keyboard zoom: + / = to zoom in, - to zoom out (toward screen centre)
         if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
             self.cam.zoom_by(1.25);
@@ -526,12 +986,29 @@ impl Game {
             self.cam_target_zoom = self.cam.zoom;
         }
 
+        // End twice forfeits: our own team when connected, otherwise the team whose turn it is
+        if is_key_pressed(KeyCode::End) && self.phase != Phase::GameOver && !self.is_spectator() && !self.weapon_menu_open {
+            if self.forfeit_confirm_timer > 0.0 {
+                self.forfeit_confirm_timer = 0.0;
+                if self.net.connected {
+                    // Applied when the server echoes it back, like every other client
+                    self.net.send_message("{\"type\":\"forfeit\"}");
+                } else if let Some(team) = self.sim.balls.get(self.current_ball).map(|w| w.team) {
+                    self.forfeit_team(team);
+                }
+            } else {
+                self.forfeit_confirm_timer = FORFEIT_CONFIRM_TIME;
+            }
+        }
+
         if self.phase == Phase::GameOver {
             if is_key_pressed(KeyCode::R) {
                 let seed = lcg(self.rng_state);
                 if self.net.connected {
-                    let msg = format!("{{\"type\":\"restart\",\"seed\":{}}}", seed);
-                    self.net.send_message(&msg);
+                    // Ask for a rematch; the server restarts once every player is ready
+                    if !self.net.is_ready() && !self.is_spectator() {
+                        self.net.send_message("{\"type\":\"ready\"}");
+                    }
                 } else {
                     self.restart_seed = Some(seed);
                 }
@@ -539,6 +1016,14 @@ impl Game {
             return;
         }
 
+        // Hold on to a fire/jump/backflip pressed just before the phase or turn flips over
+        // (typically a laggy turn start) and apply it once allowed, see `buffered`
+        if let Some(action) = buffered_input_pressed() {
+            if !self.is_spectator() && (!self.is_my_turn() || !action.allowed_in(self.phase)) {
+                self.pending_action = Some((action, INPUT_BUFFER_TIME));
+            }
+        }
+
         // During Retreat or ProjectileFlying phase: allow movement for local player's ball
         if self.phase == Phase::Retreat || self.phase == Phase::ProjectileFlying {
             // During Retreat the active player moves the ball that just fired,
@@ -555,32 +1040,34 @@ impl Game {
             };
 
             if let Some(wi) = ball_idx_opt {
-                if wi < self.balls.len() && self.balls[wi].alive {
-                    let ball = &mut self.balls[wi];
+                if wi < self.sim.balls.len() && self.sim.balls[wi].alive {
+                    let ball = &mut self.sim.balls[wi];
                     let can_move = ball.can_move();
 
                     if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
-                        physics::walk(ball, &self.terrain, -1.0);
+                        physics::walk(ball, &self.sim.terrain, -1.0);
                     }
                     if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
-                        physics::walk(ball, &self.terrain, 1.0);
+                        physics::walk(ball, &self.sim.terrain, 1.0);
                     }
 
                     if can_move {
-                        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Space) {
+                        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Space)
+                            || take_buffered(&mut self.pending_action, BufferedInput::Jump)
+                        {
                             physics::jump(ball);
                             ball.movement_used += 20.0;
                             if self.net.connected {
-                                let msg = r#"{"type":"input","input":"{\"Jump\":{}}"}"#;
-                                self.net.send_message(msg);
+                                self.net.send_input(NetInput::Jump);
                             }
                         }
-                        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+                        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down)
+                            || take_buffered(&mut self.pending_action, BufferedInput::Backflip)
+                        {
                             physics::backflip(ball);
                             ball.movement_used += 30.0;
                             if self.net.connected {
-                                let msg = r#"{"type":"input","input":"{\"Backflip\":{}}"}"#;
-                                self.net.send_message(msg);
+                                self.net.send_input(NetInput::Backflip);
                             }
                         }
                     }
@@ -605,14 +1092,17 @@ impl Game {
 
         // Toggle weapon menu with Tab or Q (only on your turn).
         // If currently charging, cancel the charge first so the player can switch weapon.
-        if self.is_my_turn() && (is_key_pressed(KeyCode::Tab) || is_key_pressed(KeyCode::Q)) {
+        // Q is typed into the search box while the menu is open, so only Tab closes it.
+        if self.is_my_turn() && (is_key_pressed(KeyCode::Tab) || (is_key_pressed(KeyCode::Q) && !self.weapon_menu_open)) {
             if self.charging {
                 self.charging = false;
                 self.charge_power = 0.0;
                 self.phase = Phase::Aiming;
             }
             self.weapon_menu_open = !self.weapon_menu_open;
-            if !self.weapon_menu_open {
+            if self.weapon_menu_open {
+                self.menu_filter.clear();
+            } else {
                 self.weapon_menu_scroll = 0.0;
             }
         }
@@ -627,6 +1117,7 @@ impl Game {
                 self.firing_by_key = false;
             }
             self.baseball_bat_mode = false;
+            self.pending_target = None;
         }
         
         // Toggle weapon menu with mouse click on button (only on your turn)
@@ -636,6 +1127,7 @@ impl Game {
             if mx >= button.0 && mx <= button.0 + button.2 
                 && my >= button.1 && my <= button.1 + button.3 {
                 self.weapon_menu_open = true;
+                self.menu_filter.clear();
             }
         }
         
@@ -645,65 +1137,45 @@ impl Game {
             self.weapon_menu_scroll = 0.0;
         }
         
+        // Typing while the menu is open filters it; Enter picks the top match
+        if self.is_my_turn() && self.weapon_menu_open {
+            for c in typed {
+                if c.is_alphanumeric() || c == ' ' {
+                    self.menu_filter.push(c);
+                    self.weapon_menu_scroll = 0.0;
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) && self.menu_filter.pop().is_some() {
+                self.weapon_menu_scroll = 0.0;
+            }
+            if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+                let top = hud::weapons_by_category(&self.menu_filter)
+                    .into_iter()
+                    .flat_map(|(_, weapons)| weapons)
+                    .find(|w| self.current_loadout().contains(w));
+                if let Some(w) = top {
+                    self.pick_menu_weapon(w);
+                }
+                return;
+            }
+        }
+
+        // Right-clicking a menu weapon makes it the compare weapon (again to clear it)
+        if self.is_my_turn() && self.weapon_menu_open && is_mouse_button_pressed(MouseButton::Right) {
+            if let Some(w) = self.menu_weapon_at(mx, my) {
+                self.compare_weapon = if self.compare_weapon == Some(w) { None } else { Some(w) };
+            }
+            return;
+        }
+
         // Handle weapon menu clicks (only on your turn)
         if self.is_my_turn() && self.weapon_menu_open && is_mouse_button_pressed(MouseButton::Left) {
             let layout = hud::WeaponMenuLayout::new();
-            
-            // Organize weapons by category (same as hud.rs)
-            use weapons::WeaponCategory;
-            let all_weapons = Weapon::all();
-            let mut by_category: std::collections::HashMap<WeaponCategory, Vec<&Weapon>> = std::collections::HashMap::new();
-            for w in all_weapons {
-                by_category.entry(w.category()).or_insert_with(Vec::new).push(w);
-            }
-            
-            let categories = [
-                WeaponCategory::Explosives,
-                WeaponCategory::Ballistics,
-                WeaponCategory::Special,
-                WeaponCategory::Utilities,
-            ];
-            
-            let mut current_y = layout.content_y - self.weapon_menu_scroll;
-            
-            // Only accept clicks within the content viewport
-            let content_top = layout.content_y;
-            let content_bottom = layout.content_y + layout.content_h;
-            
-            for cat in &categories {
-                if let Some(weapons) = by_category.get(cat) {
-                    // Skip category header
-                    current_y += layout.cat_header_h + layout.item_padding;
-                    
-                    // Check weapon item clicks
-                    for w in weapons {
-                        let item_y = current_y;
-                        let item_x = layout.menu_x + layout.padding;
-                        let item_w = layout.menu_w - layout.padding * 2.0;
-                        
-                        // Only register clicks within the visible content area
-                        if item_y + layout.item_h > content_top && item_y < content_bottom {
-                            if mx >= item_x && mx <= item_x + item_w && my >= item_y && my <= item_y + layout.item_h {
-                                self.selected_weapon = **w;
-                                self.weapon_menu_open = false;
-                                self.weapon_menu_scroll = 0.0;
-                // Auto-enter click modes immediately — no charge/fire needed
-                                match self.selected_weapon {
-                                    Weapon::Teleport => { self.teleport_mode = true; }
-                                    Weapon::BuildWall => { self.build_wall_mode = true; }
-                                    Weapon::Airstrike => { self.airstrike_mode = Some(Weapon::Airstrike); }
-                                    Weapon::NapalmStrike => { self.airstrike_mode = Some(Weapon::NapalmStrike); }
-                                    _ => {}
-                                }
-                                return;
-                            }
-                        }
-                        
-                        current_y += layout.item_h + layout.item_padding;
-                    }
-                    
-                    current_y += layout.cat_spacing; // Space between categories
+            if let Some(w) = self.menu_weapon_at(mx, my) {
+                if self.current_loadout().contains(&w) {
+                    self.pick_menu_weapon(w);
                 }
+                return;
             }
             
             // Close menu if clicking outside
@@ -716,14 +1188,17 @@ impl Game {
 
         // Update aim angle unless locked or charging.
         // Click to lock/unlock; locked aim lets you move camera freely before firing.
-        if self.is_my_turn() && !self.charging && !self.aim_locked {
-            if let Some(ball) = self.balls.get(self.current_ball) {
+        if self.turn_start_mouse.is_some_and(|p| p != (mx, my)) {
+            self.turn_start_mouse = None;
+        }
+        if self.is_my_turn() && !self.charging && !self.aim_locked && self.turn_start_mouse.is_none() {
+            if let Some(ball) = self.sim.balls.get(self.current_ball) {
                 if ball.alive {
                     let (wx, wy) = (ball.x, ball.y);
                     let (world_mx, world_my) = self.cam.screen_to_world(mx, my);
                     let dx = world_mx - wx;
                     let dy = world_my - wy;
-                    let new_angle = dy.atan2(dx);
+                    let new_angle = self.selected_weapon.clamp_aim(dy.atan2(dx));
                     
                     // Only update if angle changed significantly
                     if (new_angle - self.aim_angle).abs() > 0.01 {
@@ -742,39 +1217,62 @@ impl Game {
         }
 
         // Only allow movement if it's the player's turn and phase allows it
-        if self.is_my_turn() && self.phase.allows_movement() && self.current_ball < self.balls.len() && self.balls[self.current_ball].alive && !self.weapon_menu_open {
-            let ball = &mut self.balls[self.current_ball];
-            let can_move = ball.can_move();
-            
-            if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
-                physics::walk(ball, &self.terrain, -1.0);
-            }
-            if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
-                physics::walk(ball, &self.terrain, 1.0);
+        if self.is_my_turn() && self.phase.allows_movement() && self.current_ball < self.sim.balls.len() && self.sim.balls[self.current_ball].alive && !self.weapon_menu_open {
+            let ball = &mut self.sim.balls[self.current_ball];
+            let can_move = ball.can_move() && self.rope.is_none();
+
+            // On the rope A/D pump the swing and W/S reel in and out instead of walking
+            if let Some(rope) = &mut self.rope {
+                let held = |a: KeyCode, b: KeyCode| if is_key_down(a) || is_key_down(b) { 1.0 } else { 0.0 };
+                let swing = held(KeyCode::D, KeyCode::Right) - held(KeyCode::A, KeyCode::Left);
+                let reel = held(KeyCode::S, KeyCode::Down) - held(KeyCode::W, KeyCode::Up);
+                rope.steer(ball, swing, reel, 1.0 / 60.0);
+            } else {
+                if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+                    physics::walk(ball, &self.sim.terrain, -1.0);
+                }
+                if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+                    physics::walk(ball, &self.sim.terrain, 1.0);
+                }
             }
-            
+
             // Only allow jumping if there's movement budget
             if can_move {
-                if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Space) {
+                if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Space)
+                    || take_buffered(&mut self.pending_action, BufferedInput::Jump)
+                {
                     physics::jump(ball);
                     ball.movement_used += 20.0; // Jumping costs movement
                     if self.net.connected {
-                        let msg = r#"{"type":"input","input":"{\"Jump\":{}}"}"#;
-                        self.net.send_message(msg);
+                        self.net.send_input(NetInput::Jump);
                     }
                 }
-                if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+                if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down)
+                    || take_buffered(&mut self.pending_action, BufferedInput::Backflip)
+                {
                     physics::backflip(ball);
                     ball.movement_used += 30.0; // Backflip costs more
                     if self.net.connected {
-                        let msg = r#"{"type":"input","input":"{\"Backflip\":{}}"}"#;
-                        self.net.send_message(msg);
+                        self.net.send_input(NetInput::Backflip);
                     }
                 }
             }
         }
 
-        if is_mouse_button_pressed(MouseButton::Left) && !self.has_fired && self.is_my_turn() && self.phase.allows_input() && !self.weapon_menu_open && !self.left_drag_panning {
+        // E prods an adjacent enemy: a small free shove that doesn't use up the shot
+        if is_key_pressed(KeyCode::E) && self.prod_cooldown <= 0.0 && !self.has_fired && self.is_my_turn()
+            && self.phase.allows_input() && !self.weapon_menu_open
+        {
+            if let Some((target, dir)) = self.prod_target(self.current_ball) {
+                self.apply_prod(self.current_ball, target, dir);
+                self.prod_cooldown = PROD_COOLDOWN;
+                if self.net.connected {
+                    self.net.send_input(NetInput::Prod { target, dir });
+                }
+            }
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) && !self.has_fired && self.is_my_turn() && self.phase.allows_input() && !self.weapon_menu_open && !self.left_drag_panning && !ping_modifier_down() {
             // Handle Build Wall mode: two clicks — first sets position, second sets rotation
             if self.build_wall_mode {
                 let (mx, my) = mouse_position();
@@ -783,31 +1281,18 @@ impl Game {
                 if self.build_wall_anchor.is_none() {
                     // First click: lock in the wall's centre position
                     self.build_wall_anchor = Some((world_pos.x, world_pos.y));
-                } else {
+                } else if let Some((tx, ty)) = self.confirm_target(world_pos.x, world_pos.y) {
                     // Second click: derive angle from anchor → mouse, then stamp terrain
                     let (ax, ay) = self.build_wall_anchor.unwrap();
-                    let dx = world_pos.x - ax;
-                    let dy = world_pos.y - ay;
+                    let dx = tx - ax;
+                    let dy = ty - ay;
                     let angle = if dx.abs() < 0.5 && dy.abs() < 0.5 {
                         self.aim_angle // fallback if both clicks are on same pixel
                     } else {
                         dy.atan2(dx)
                     };
-                    let cos_a = angle.cos();
-                    let sin_a = angle.sin();
-                    let half_len = 35i32;
-                    let half_thick = 4i32;
-                    for i in -half_len..=half_len {
-                        for j in -half_thick..=half_thick {
-                            let wx = (ax + i as f32 * cos_a - j as f32 * sin_a).round() as i32;
-                            let wy = (ay + i as f32 * sin_a + j as f32 * cos_a).round() as i32;
-                            if wx >= 0 && wx < self.terrain.width as i32
-                                && wy >= 0 && wy < self.terrain.height as i32 {
-                                self.terrain.set(wx, wy, terrain::WOOD);
-                            }
-                        }
-                    }
-                    self.terrain_dirty = true;
+                    let placed = self.apply_wall_at(ax, ay, angle);
+                    self.sim.terrain.modified_cells += placed;
                     self.build_wall_anchor = None;
                     self.build_wall_mode = false;
                     self.has_fired = true;
@@ -815,22 +1300,10 @@ impl Game {
                     self.settle_timer = 0.0;
                     // Record in wall log for reconnect sync
                     self.wall_log.push((ax as i32, ay as i32, (angle * 1000.0) as i32));
+                    self.note_terrain_op(2);
                     // Sync wall placement to other players
                     if self.net.connected {
-                        let input_json = format!(
-                            r#"{{"BuildWallPlace":{{"ax":{},"ay":{},"angle":{}}}}}"#,
-                            ax, ay, angle
-                        );
-                        let mut escaped = String::new();
-                        for c in input_json.chars() {
-                            match c {
-                                '"' => escaped.push_str("\\\""),
-                                '\\' => escaped.push_str("\\\\"),
-                                _ => escaped.push(c),
-                            }
-                        }
-                        let msg = format!(r#"{{"type":"input","input":"{}"}}"#, escaped);
-                        self.net.send_message(&msg);
+                        self.net.send_input(NetInput::BuildWallPlace { ax, ay, angle });
                     }
                 }
             }
@@ -838,65 +1311,53 @@ impl Game {
             else if let Some(airstrike_weapon) = self.airstrike_mode {
                 let (mx, my) = mouse_position();
                 let world_pos = self.cam.to_macroquad().screen_to_world(vec2(mx, my));
-                let target_x = world_pos.x;
+                if let Some((target_x, _)) = self.confirm_target(world_pos.x, world_pos.y) {
+                    let spare_team = self.spare_team(self.current_ball);
+                    let damage_scale = self.damage_scale(self.current_ball);
 
-                self.airstrike_droplets.clear();
-                match airstrike_weapon {
-                    Weapon::Airstrike => {
-                        let spacing = 80.0;
-                        for i in 0..5 {
-                            let x = target_x + (i as f32 - 2.0) * spacing;
-                            self.airstrike_droplets.push(AirstrikeDroplet {
-                                x,
-                                y: -50.0,
-                                vy: 0.0,
-                                alive: true,
-                                weapon_type: AirstrikeType::Explosive,
-                            });
-                        }
-                    },
-                    Weapon::NapalmStrike => {
-                        let spacing = 60.0;
-                        for i in 0..7 {
-                            let x = target_x + (i as f32 - 3.0) * spacing;
-                            self.airstrike_droplets.push(AirstrikeDroplet {
-                                x,
-                                y: -50.0,
-                                vy: 0.0,
-                                alive: true,
-                                weapon_type: AirstrikeType::Napalm,
-                            });
-                        }
-                    },
-                    _ => {}
-                }
-                self.airstrike_mode = None;
-                self.has_fired = true;
-                self.phase = Phase::ProjectileFlying;
-                // Fresh budget so the active player can dodge during the airstrike
-                if self.current_ball < self.balls.len() {
-                    self.balls[self.current_ball].reset_movement_budget();
-                }
-                // Sync airstrike target to other players
-                if self.net.connected {
-                    let weapon_name = match airstrike_weapon {
-                        Weapon::NapalmStrike => "NapalmStrike",
-                        _ => "Airstrike",
-                    };
-                    let input_json = format!(
-                        r#"{{"AirstrikeTarget":{{"weapon":"{}","x":{}}}}}"#,
-                        weapon_name, target_x
-                    );
-                    let mut escaped = String::new();
-                    for c in input_json.chars() {
-                        match c {
-                            '"' => escaped.push_str("\\\""),
-                            '\\' => escaped.push_str("\\\\"),
-                            _ => escaped.push(c),
-                        }
+                    self.airstrike_droplets.clear();
+                    match airstrike_weapon {
+                        Weapon::Airstrike => {
+                            let spacing = 80.0;
+                            for i in 0..5 {
+                                let x = target_x + (i as f32 - 2.0) * spacing;
+                                self.airstrike_droplets.push(AirstrikeDroplet {
+                                    x,
+                                    y: -50.0,
+                                    vy: 0.0,
+                                    alive: true,
+                                    weapon_type: AirstrikeType::Explosive,
+                                    spare_team,
+                                    damage_scale,
+                                });
+                            }
+                        },
+                        Weapon::NapalmStrike => {
+                            let spacing = 60.0;
+                            for i in 0..7 {
+                                let x = target_x + (i as f32 - 3.0) * spacing;
+                                self.airstrike_droplets.push(AirstrikeDroplet {
+                                    x,
+                                    y: -50.0,
+                                    vy: 0.0,
+                                    alive: true,
+                                    weapon_type: AirstrikeType::Napalm,
+                                    spare_team,
+                                    damage_scale,
+                                });
+                            }
+                        },
+                        _ => {}
+                    }
+                    self.airstrike_mode = None;
+                    self.has_fired = true;
+                    self.phase = Phase::ProjectileFlying;
+                    // Fresh budget so the active player can dodge during the airstrike
+                    self.refresh_dodge_budget(self.current_ball);
+                    // Sync airstrike target to other players
+                    if self.net.connected {
+                        self.net.send_input(NetInput::AirstrikeTarget { weapon: airstrike_weapon, x: target_x });
                     }
-                    let msg = format!(r#"{{"type":"input","input":"{}"}}"#, escaped);
-                    self.net.send_message(&msg);
                 }
             }
             // Handle Teleport mode
@@ -905,16 +1366,21 @@ impl Game {
                 let world_pos = self.cam.to_macroquad().screen_to_world(vec2(mx, my));
                 
                 let idx = self.current_ball;
-                if idx < self.balls.len() && self.balls[idx].alive {
+                let target = if idx < self.sim.balls.len() && self.sim.balls[idx].alive {
+                    self.confirm_target(world_pos.x, world_pos.y)
+                } else {
+                    None
+                };
+                if let Some((tx, ty)) = target {
                     // Check if the destination is valid (not inside solid terrain)
-                    let target_x = world_pos.x.clamp(0.0, self.terrain.width as f32);
-                    let target_y = world_pos.y.clamp(0.0, self.terrain.height as f32);
+                    let target_x = tx.clamp(0.0, self.sim.terrain.width as f32);
+                    let target_y = ty.clamp(0.0, self.sim.terrain.height as f32);
                     
                     // Simple teleport - place ball at clicked location
-                    self.balls[idx].x = target_x;
-                    self.balls[idx].y = target_y;
-                    self.balls[idx].vx = 0.0;
-                    self.balls[idx].vy = 0.0;
+                    self.sim.balls[idx].x = target_x;
+                    self.sim.balls[idx].y = target_y;
+                    self.sim.balls[idx].vx = 0.0;
+                    self.sim.balls[idx].vy = 0.0;
                     
                     self.teleport_mode = false;
                     self.has_fired = true;
@@ -922,30 +1388,82 @@ impl Game {
                     self.settle_timer = 0.0;
                     // Sync teleport destination to other players
                     if self.net.connected {
-                        let input_json = format!(
-                            r#"{{"TeleportTo":{{"x":{},"y":{}}}}}"#,
-                            target_x, target_y
-                        );
-                        let mut escaped = String::new();
-                        for c in input_json.chars() {
-                            match c {
-                                '"' => escaped.push_str("\\\""),
-                                '\\' => escaped.push_str("\\\\"),
-                                _ => escaped.push(c),
-                            }
-                        }
-                        let msg = format!(r#"{{"type":"input","input":"{}"}}"#, escaped);
-                        self.net.send_message(&msg);
+                        self.net.send_input(NetInput::TeleportTo { x: target_x, y: target_y });
                     }
                 }
             }
             // Left-click only handles placement weapons (teleport, airstrike, build wall).
             // Normal charging and baseball bat are triggered exclusively by the F key.
         }
+        // [ and ] cycle weapons in menu order without opening the menu, skipping locked ones
+        let cycle_back = is_key_pressed(KeyCode::LeftBracket);
+        if (cycle_back || is_key_pressed(KeyCode::RightBracket))
+            && !self.has_fired
+            && self.is_my_turn()
+            && self.phase == Phase::Aiming
+            && !self.weapon_menu_open
+        {
+            let loadout = self.current_loadout();
+            let allowed = |w: Weapon| loadout.contains(&w);
+            let weapon = if cycle_back {
+                self.selected_weapon.prev_weapon(allowed)
+            } else {
+                self.selected_weapon.next_weapon(allowed)
+            };
+            self.teleport_mode = false;
+            self.baseball_bat_mode = false;
+            self.build_wall_mode = false;
+            self.build_wall_anchor = None;
+            self.airstrike_mode = None;
+            self.pick_menu_weapon(weapon);
+            self.weapon_flash_timer = WEAPON_FLASH_TIME;
+        }
+        // R recalls this team's last charged shot: weapon and angle are restored (aim is
+        // locked so the mouse doesn't overwrite it — click to unlock and adjust), and the
+        // next F charge auto-releases at the remembered power. Letting go of F earlier
+        // still fires at whatever was charged, so the shot can be walked in.
+        if is_key_pressed(KeyCode::R)
+            && !self.has_fired
+            && self.is_my_turn()
+            && !self.weapon_menu_open
+            && !self.charging
+        {
+            let team = self.sim.balls.get(self.current_ball).map(|b| b.team as usize);
+            if let Some((weapon, angle, power)) = team
+                .and_then(|t| self.last_shot.get(t).copied().flatten())
+                .filter(|(w, _, _)| self.current_loadout().contains(w))
+            {
+                self.select_weapon(weapon);
+                self.teleport_mode = false;
+                self.baseball_bat_mode = false;
+                self.build_wall_mode = false;
+                self.build_wall_anchor = None;
+                self.airstrike_mode = None;
+                self.pending_target = None;
+                self.aim_angle = angle;
+                self.aim_locked = true;
+                self.charge_target = Some(power);
+                if self.net.connected {
+                    let msg = format!("{{\"type\":\"aim\",\"aim\":{}}}", self.aim_angle);
+                    self.net.send_message(&msg);
+                }
+            }
+        }
         // F-key fire button: the ONLY way to start a charge or swing the bat.
         // Skips click-targeting weapons (airstrike, teleport, build wall) that need a canvas click.
-        if is_key_pressed(KeyCode::F)
-            && !self.has_fired
+        // On the rope it lets go instead, even after the turn's shot.
+        if self.rope.is_some()
+            && self.is_my_turn()
+            && self.phase.allows_movement()
+            && !self.weapon_menu_open
+            && !self.charging
+            && is_key_pressed(KeyCode::F)
+        {
+            self.rope = None;
+            if self.net.connected {
+                self.net.send_input(NetInput::RopeDetach);
+            }
+        } else if !self.has_fired
             && self.is_my_turn()
             && self.phase.allows_input()
             && !self.weapon_menu_open
@@ -953,25 +1471,27 @@ impl Game {
             && !self.build_wall_mode
             && !self.teleport_mode
             && self.airstrike_mode.is_none()
+            && (is_key_pressed(KeyCode::F) || take_buffered(&mut self.pending_action, BufferedInput::Fire))
         {
             if self.baseball_bat_mode {
                 // Baseball bat fires instantly on F press (no charge needed).
                 let idx = self.current_ball;
-                if idx < self.balls.len() && self.balls[idx].alive {
-                    let ball_x = self.balls[idx].x;
-                    let ball_y = self.balls[idx].y;
+                if idx < self.sim.balls.len() && self.sim.balls[idx].alive {
+                    let ball_x = self.sim.balls[idx].x;
+                    let ball_y = self.sim.balls[idx].y;
                     let bat_range = 100.0;
                     let angle = self.aim_angle;
                     let knock_x = angle.cos() * 850.0;
                     let knock_y = angle.sin() * 850.0 - 300.0;
-                    for i in 0..self.balls.len() {
-                        if i == idx || !self.balls[i].alive { continue; }
-                        let dx = self.balls[i].x - ball_x;
-                        let dy = self.balls[i].y - ball_y;
+                    let spare_team = self.spare_team(idx);
+                    let damage = physics::scale_damage(Weapon::BaseballBat.base_damage(), self.damage_scale(idx));
+                    for i in 0..self.sim.balls.len() {
+                        if i == idx || !self.sim.balls[i].alive { continue; }
+                        let dx = self.sim.balls[i].x - ball_x;
+                        let dy = self.sim.balls[i].y - ball_y;
                         if (dx*dx + dy*dy).sqrt() < bat_range {
-                            self.balls[i].apply_knockback(knock_x, knock_y);
-                            self.balls[i].health = self.balls[i].health.saturating_sub(20);
-                            if self.balls[i].health == 0 { self.balls[i].alive = false; }
+                            self.sim.balls[i].apply_knockback(knock_x, knock_y);
+                            self.sim.balls[i].take_weapon_damage(damage, spare_team);
                         }
                     }
                     self.baseball_bat_mode = false;
@@ -979,18 +1499,14 @@ impl Game {
                     self.phase = Phase::Settling;
                     self.settle_timer = 0.0;
                     if self.net.connected {
-                        let input_json = format!(r#"{{"BatSwing":{{"angle":{}}}}}"#, angle);
-                        let mut escaped = String::new();
-                        for c in input_json.chars() {
-                            match c {
-                                '"' => escaped.push_str("\\\""),
-                                '\\' => escaped.push_str("\\\\"),
-                                _ => escaped.push(c),
-                            }
-                        }
-                        self.net.send_message(&format!(r#"{{"type":"input","input":"{}"}}"#, escaped));
+                        self.net.send_input(NetInput::BatSwing { angle });
                     }
                 }
+            } else if matches!(self.selected_weapon, Weapon::Shield | Weapon::Foxhole | Weapon::Flares | Weapon::Rope) {
+                // Nothing to aim or charge — use it straight away
+                if let Err(err) = self.fire() {
+                    self.report_refused("fire", err);
+                }
             } else {
                 // Normal weapon: hold F to charge, release to fire.
                 self.charging = true;
@@ -1006,87 +1522,558 @@ impl Game {
             } else {
                 is_mouse_button_released(MouseButton::Left)
             };
-            if fire_released || self.charge_power >= 100.0 {
+            let target = self.charge_target.unwrap_or(100.0);
+            if fire_released || self.charge_power >= target {
+                self.charge_power = self.charge_power.min(target);
                 self.firing_by_key = false;
-                self.fire();
+                if let Err(err) = self.fire() {
+                    self.report_refused("fire", err);
+                }
             }
         }
     }
 
-    fn fire(&mut self) {
-        self.charging = false;
-        if self.has_fired {
-            return;
-        }
-        let idx = self.current_ball;
-        if idx >= self.balls.len() || !self.balls[idx].alive {
-            return;
+    /// Rename balls from a per-team list (`names[team][slot]`). Missing or empty entries keep
+    /// the generated name.
+    fn assign_ball_names(&mut self, names: &[Vec<String>]) {
+        let mut slot = vec![0usize; self.num_teams.max(1)];
+        for ball in &mut self.sim.balls {
+            let team = ball.team as usize;
+            let Some(next) = slot.get_mut(team) else { continue };
+            if let Some(name) = names.get(team).and_then(|n| n.get(*next)).filter(|n| !n.is_empty()) {
+                ball.name = name.clone();
+            }
+            *next += 1;
         }
+    }
 
-        let power = self.charge_power.clamp(0.0, 100.0);
-        let angle = self.aim_angle;
-        let weapon = self.selected_weapon;
-
-        self.cam_free_timer = 0.0;    // always follow the action when firing
-        self.cam_return_timer = 0.0;   // skip the glide-back phase too
-        self.do_fire(idx, angle, power, weapon);
-
-        // Give the firing player a fresh movement budget so they can dodge
-        // while the projectile is in the air.
-        if self.phase == Phase::ProjectileFlying && idx < self.balls.len() {
-            self.balls[idx].reset_movement_budget();
+    /// Briefly slow the simulation for a dramatic beat. Offline only: a local change to the
+    /// integration step would desync multiplayer clients.
+    fn start_hit_stop(&mut self) {
+        if !self.net.connected {
+            self.hit_stop_timer = HIT_STOP_TIME;
         }
+    }
 
-        // Don't set has_fired for Baseball Bat, Teleport, and BuildWall - they need a second click
-        if weapon != Weapon::BaseballBat && weapon != Weapon::Teleport && weapon != Weapon::BuildWall
-            && weapon != Weapon::Airstrike && weapon != Weapon::NapalmStrike {
-            self.has_fired = true;
+    /// The weapon menu item under the screen point (`mx`, `my`), if any. Mirrors the
+    /// layout hud.rs draws, counting only items inside the scrolled content area.
+    fn menu_weapon_at(&self, mx: f32, my: f32) -> Option<Weapon> {
+        let layout = hud::WeaponMenuLayout::new();
+        let mut current_y = layout.content_y - self.weapon_menu_scroll;
+        let content_top = layout.content_y;
+        let content_bottom = layout.content_y + layout.content_h;
+        let item_x = layout.menu_x + layout.padding;
+        let item_w = layout.menu_w - layout.padding * 2.0;
+
+        // Same grouping and filter as hud.rs draws
+        for (_, weapons) in hud::weapons_by_category(&self.menu_filter) {
+            // Skip category header
+            current_y += layout.cat_header_h + layout.item_padding;
+            for w in weapons {
+                let item_y = current_y;
+                if item_y + layout.item_h > content_top && item_y < content_bottom
+                    && mx >= item_x && mx <= item_x + item_w && my >= item_y && my <= item_y + layout.item_h
+                {
+                    return Some(w);
+                }
+                current_y += layout.item_h + layout.item_padding;
+            }
+            current_y += layout.cat_spacing; // Space between categories
         }
-        self.charge_power = 0.0;
-        #[cfg(target_arch = "wasm32")]
-        {
-            let msg = format!("[FIRE] Fired {:?}, has_fired={}\0", weapon, self.has_fired);
-            unsafe { console_log(msg.as_ptr()); }
+        None
+    }
+
+    /// Select `weapon` from the menu, close it, and enter the weapon's click mode.
+    fn pick_menu_weapon(&mut self, weapon: Weapon) {
+        self.select_weapon(weapon);
+        self.weapon_menu_open = false;
+        self.weapon_menu_scroll = 0.0;
+        self.pending_target = None;
+        // Auto-enter click modes immediately — no charge/fire needed
+        match weapon {
+            Weapon::Teleport => { self.teleport_mode = true; }
+            Weapon::BuildWall => { self.build_wall_mode = true; }
+            Weapon::Airstrike => { self.airstrike_mode = Some(Weapon::Airstrike); }
+            Weapon::NapalmStrike => { self.airstrike_mode = Some(Weapon::NapalmStrike); }
+            _ => {}
         }
+    }
 
-        if self.net.connected {
-            // Drill: send exact ball origin so all clients carve the identical tunnel.
-            // Generic Fire message would make remotes use their own (potentially different)
-            // ball position. DrillFire is broadcast just like any other input type.
-            let input_json = if weapon == Weapon::Drill && idx < self.balls.len() {
-                let bx = self.balls[idx].x as i32;
-                let by = self.balls[idx].y as i32;
-                format!(
-                    r#"{{"DrillFire":{{"bx":{},"by":{},"angle":{}}}}}"#,
-                    bx, by, angle
-                )
-            } else {
-                let angle_deg = angle.to_degrees();
-                let weapon_name = weapon.name();
-                format!(
-                    r#"{{"Fire":{{"weapon":"{}","angle_deg":{},"power_percent":{}}}}}"#,
-                    weapon_name, angle_deg, power
-                )
-            };
-            let mut escaped = String::new();
-            for c in input_json.chars() {
-                match c {
-                    '"' => escaped.push_str("\\\""),
-                    '\\' => escaped.push_str("\\\\"),
-                    _ => escaped.push(c),
-                }
+    /// Resolve a click-target tap at (`x`, `y`) to the target to commit. With
+    /// `confirm_targets` off every tap commits; with it on, a tap places (or moves) the
+    /// preview and only a tap on the preview commits it.
+    fn confirm_target(&mut self, x: f32, y: f32) -> Option<(f32, f32)> {
+        if !self.confirm_targets {
+            return Some((x, y));
+        }
+        match self.pending_target {
+            Some((px, py)) if (x - px).hypot(y - py) <= CONFIRM_TAP_RADIUS => self.pending_target.take(),
+            _ => {
+                self.pending_target = Some((x, y));
+                None
             }
-            let msg = format!(r#"{{"type":"input","input":"{}"}}"#, escaped);
-            self.net.send_message(&msg);
         }
     }
 
-    fn do_fire(&mut self, idx: usize, angle: f32, power: f32, weapon: Weapon) {
-        if idx >= self.balls.len() || !self.balls[idx].alive {
-            return;
+    /// Where the click-target previews draw: the pending target once one is placed,
+    /// otherwise under the cursor.
+    fn target_preview_pos(&self) -> Vec2 {
+        match self.pending_target {
+            Some((x, y)) => vec2(x, y),
+            None => {
+                let (mx, my) = mouse_position();
+                self.cam.to_macroquad().screen_to_world(vec2(mx, my))
+            }
+        }
+    }
+
+    /// Whether `team` has `weapon` in its loadout and the campaign has unlocked it.
+    fn weapon_allowed(&self, team: u32, weapon: Weapon) -> bool {
+        self.loadouts.get(team as usize).is_none_or(|l| l.contains(&weapon)) && self.weapon_unlocked(weapon)
+    }
+
+    fn weapon_unlocked(&self, weapon: Weapon) -> bool {
+        self.unlocked_weapons.as_ref().is_none_or(|u| u.contains(&weapon))
+    }
+
+    /// Usable weapons of `team`: its loadout minus anything the campaign still locks.
+    fn team_loadout(&self, team: u32) -> Vec<Weapon> {
+        self.loadouts
+            .get(team as usize)
+            .map_or(Weapon::all(), |l| l.as_slice())
+            .iter()
+            .copied()
+            .filter(|&w| self.weapon_unlocked(w))
+            .collect()
+    }
+
+    /// Loadout of the team whose turn it is, for the weapon menu.
+    fn current_loadout(&self) -> Vec<Weapon> {
+        self.sim.balls.get(self.current_ball).map_or_else(|| Weapon::all().to_vec(), |b| self.team_loadout(b.team))
+    }
+
+    /// Start campaign progression with `weapons` unlocked; every other weapon stays grayed
+    /// out in the menu and refuses to fire. The Bazooka is always unlocked, so a turn
+    /// never starts without something to shoot.
+    pub fn set_unlocked_weapons(&mut self, weapons: &[Weapon]) {
+        let mut unlocked = vec![Weapon::Bazooka];
+        unlocked.extend(weapons.iter().copied().filter(|&w| w != Weapon::Bazooka));
+        self.unlocked_weapons = Some(unlocked);
+        if !self.weapon_unlocked(self.selected_weapon) {
+            self.selected_weapon = Weapon::Bazooka;
+        }
+    }
+
+    /// Unlock `weapon` for the rest of the campaign. Does nothing outside a campaign.
+    pub fn unlock_weapon(&mut self, weapon: Weapon) {
+        if let Some(unlocked) = &mut self.unlocked_weapons {
+            if !unlocked.contains(&weapon) {
+                unlocked.push(weapon);
+            }
+        }
+    }
+
+    /// Weapons the campaign hasn't unlocked yet, in menu order; empty outside a campaign.
+    pub fn locked_weapons(&self) -> Vec<Weapon> {
+        Weapon::all().iter().copied().filter(|&w| !self.weapon_unlocked(w)).collect()
+    }
+
+    /// Arm a weapon for the current ball. On our networked turn the choice is
+    /// broadcast so opponents and spectators see the right weapon and aim preview.
+    fn select_weapon(&mut self, weapon: Weapon) {
+        if self.selected_weapon == weapon {
+            return;
+        }
+        self.selected_weapon = weapon;
+        self.charge_target = None;
+        self.aim_angle = weapon.clamp_aim(self.aim_angle);
+        if self.net.connected && self.is_my_turn() {
+            let msg = format!("{{\"type\":\"weapon_select\",\"weapon\":\"{}\"}}", weapon.name());
+            self.net.send_message(&msg);
+        }
+    }
+
+    /// Team whose balls a shot fired by `idx` must not damage: the shooter's own team
+    /// when friendly fire is disabled, otherwise nobody.
+    fn spare_team(&self, idx: usize) -> Option<u32> {
+        if self.settings.friendly_fire {
+            return None;
+        }
+        self.sim.balls.get(idx).map(|b| b.team)
+    }
+
+    /// Team whose balls a projectile fired by `idx` flies through instead of hitting:
+    /// the shooter's own team when own-team pass-through is enabled, otherwise nobody.
+    fn pass_team(&self, idx: usize) -> Option<u32> {
+        if !self.settings.own_team_pass_through {
+            return None;
+        }
+        self.sim.balls.get(idx).map(|b| b.team)
+    }
+
+    /// Damage multiplier for shots fired by `idx` (boosted while on a last stand).
+    fn damage_scale(&self, idx: usize) -> f32 {
+        self.sim.balls.get(idx).map_or(1.0, |b| b.damage_scale())
+    }
+
+    /// Point at a spot on the map. Online the server echoes it to everyone (us included)
+    /// tagged with our team; offline it shows straight away for the active team.
+    fn send_ping(&mut self, x: f32, y: f32) {
+        if self.net.connected {
+            let msg = format!("{{\"type\":\"ping\",\"x\":{:.0},\"y\":{:.0}}}", x, y);
+            self.net.send_message(&msg);
+        } else {
+            self.add_ping(x, y, Some(self.current_turn_index as u32));
+        }
+    }
+
+    fn add_ping(&mut self, x: f32, y: f32, team: Option<u32>) {
+        if self.pings.len() >= MAX_PINGS {
+            self.pings.remove(0);
+        }
+        self.pings.push((x, y, PING_TIME, team));
+    }
+
+    /// Nearest enemy ball touching (within PROD_REACH of) ball `idx` on the side it
+    /// faces, and the direction to shove it.
+    fn prod_target(&self, idx: usize) -> Option<(usize, f32)> {
+        let ball = self.sim.balls.get(idx).filter(|b| b.alive)?;
+        let dir = if ball.facing < 0.0 { -1.0 } else { 1.0 };
+        self.sim.balls
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| w.alive && w.team != ball.team)
+            .filter_map(|(i, w)| {
+                let (dx, dy) = (w.x - ball.x, w.y - ball.y);
+                let gap = (dx * dx + dy * dy).sqrt() - ball.radius - w.radius;
+                (dx * dir >= 0.0 && gap <= PROD_REACH).then_some((i, gap))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| (i, dir))
+    }
+
+    /// Shove `target` away from prodder `idx` in direction `dir` (±1).
+    fn apply_prod(&mut self, idx: usize, target: usize, dir: f32) {
+        let spare_team = self.spare_team(idx);
+        let damage = physics::scale_damage(PROD_DAMAGE, self.damage_scale(idx));
+        if let Some(w) = self.sim.balls.get_mut(target).filter(|w| w.alive) {
+            w.apply_knockback(dir * PROD_KNOCK_X, -PROD_KNOCK_Y);
+            w.take_weapon_damage(damage, spare_team);
+        }
+    }
+
+    /// Flag each team's sole surviving ball for the last-stand rule. Derived only from
+    /// alive counts, so every client reaches the same result.
+    fn update_last_stands(&mut self) {
+        if !self.settings.last_stand {
+            return;
+        }
+        for i in 0..self.sim.balls.len() {
+            let team = self.sim.balls[i].team;
+            let alive_on_team = self.sim.balls.iter().filter(|b| b.alive && b.team == team).count();
+            self.sim.balls[i].last_stand = self.sim.balls[i].alive && alive_on_team == 1;
+        }
+    }
+
+    /// Give a ball a fresh movement budget to dodge after firing. With partial
+    /// regeneration the leftover budget is kept instead so it carries into the next turn.
+    fn refresh_dodge_budget(&mut self, idx: usize) {
+        if self.settings.movement_regen < 1.0 {
+            return;
+        }
+        if let Some(ball) = self.sim.balls.get_mut(idx) {
+            ball.reset_movement_budget();
+        }
+    }
+
+    /// Fire the selected weapon from the current ball at the charged power and
+    /// broadcast the shot. A refused shot leaves the turn as it was, back to aiming.
+    fn fire(&mut self) -> Result<ActionOutcome, GameError> {
+        self.charging = false;
+        let idx = self.current_ball;
+        let weapon = self.selected_weapon;
+        let ball = self.sim.balls.get(idx).filter(|b| b.alive);
+        let check = FireCheck {
+            my_turn: self.is_my_turn(),
+            phase: self.phase,
+            has_fired: self.has_fired,
+            ball_alive: ball.is_some(),
+            weapon_allowed: ball.is_some_and(|b| self.weapon_allowed(b.team, weapon)),
+            budget_spent: Self::alters_terrain(weapon) && self.terrain_budget_left() == Some(0),
+        };
+        if let Err(err) = check.check() {
+            if matches!(err, GameError::WeaponLocked | GameError::OutOfTerrainBudget) {
+                // The turn's shot is still to come: back to aiming to pick another weapon
+                self.charge_power = 0.0;
+                self.phase = Phase::Aiming;
+            }
+            return Err(err);
+        }
+
+        let power = self.charge_power.clamp(0.0, 100.0);
+        let angle = weapon.clamp_aim(self.aim_angle);
+
+        if let Err(err) = self.do_fire(idx, angle, power, weapon) {
+            self.charge_power = 0.0;
+            self.phase = Phase::Aiming;
+            return Err(err);
+        }
+        self.cam_free_timer = 0.0;    // always follow the action when firing
+        self.cam_return_timer = 0.0;   // skip the glide-back phase too
+
+        // Give the firing player a fresh movement budget so they can dodge
+        // while the projectile is in the air.
+        if self.phase == Phase::ProjectileFlying {
+            self.refresh_dodge_budget(idx);
+        }
+
+        // Don't set has_fired for Baseball Bat, Teleport, and BuildWall - they need a second click.
+        // The rope is only a way of moving, so the turn's shot is still to come.
+        let outcome = if weapon == Weapon::Rope {
+            ActionOutcome::Moved
+        } else if weapon != Weapon::BaseballBat && weapon != Weapon::Teleport && weapon != Weapon::BuildWall
+            && weapon != Weapon::Airstrike && weapon != Weapon::NapalmStrike {
+            self.has_fired = true;
+            let team = self.sim.balls[idx].team as usize;
+            if let Some(slot) = self.last_shot.get_mut(team) {
+                *slot = Some((weapon, angle, power));
+            }
+            ActionOutcome::Fired
+        } else {
+            ActionOutcome::AwaitingTarget
+        };
+        self.charge_power = 0.0;
+        self.charge_target = None;
+        #[cfg(target_arch = "wasm32")]
+        {
+            let msg = format!("[FIRE] Fired {:?}, has_fired={}\0", weapon, self.has_fired);
+            unsafe { console_log(msg.as_ptr()); }
+        }
+
+        if self.net.connected {
+            // Drill: send exact ball origin so all clients carve the identical tunnel.
+            // Generic Fire message would make remotes use their own (potentially different)
+            // ball position. DrillFire is broadcast just like any other input type.
+            let input = if weapon == Weapon::Drill && idx < self.sim.balls.len() {
+                NetInput::DrillFire {
+                    bx: self.sim.balls[idx].x as i32,
+                    by: self.sim.balls[idx].y as i32,
+                    angle,
+                }
+            } else if weapon == Weapon::Foxhole && idx < self.sim.balls.len() {
+                NetInput::FoxholeDig { bx: self.sim.balls[idx].x as i32, by: self.sim.balls[idx].y as i32 }
+            } else if weapon == Weapon::Flares && idx < self.sim.balls.len() {
+                NetInput::FlaresDrop { bx: self.sim.balls[idx].x as i32, by: self.sim.balls[idx].y as i32 }
+            } else if let Some(rope) = self.rope.as_ref().filter(|_| weapon == Weapon::Rope) {
+                // The exact hook point, so remotes draw the rope from the same spot
+                NetInput::RopeAttach { x: rope.anchor_x, y: rope.anchor_y }
+            } else if weapon == Weapon::Rope {
+                return Ok(outcome); // missed: nothing to tell the others
+            } else {
+                NetInput::Fire { weapon: Some(weapon), angle_deg: angle.to_degrees(), power_percent: power }
+            };
+            self.net.send_input(input);
+        }
+        Ok(outcome)
+    }
+
+    /// Play a remote player's input on `ball_idx`, the ball whose turn it is.
+    fn apply_input(&mut self, ball_idx: usize, input: NetInput) {
+        match input {
+            NetInput::Fire { weapon, angle_deg, power_percent } => {
+                // No weapon in the input: the one their weapon_select armed
+                let weapon = weapon.unwrap_or(self.selected_weapon);
+                if let Err(_err) = self.do_fire(ball_idx, angle_deg.to_radians(), power_percent, weapon) {
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        let msg = format!("[FIRE] Remote shot refused: {:?}\0", _err);
+                        unsafe { console_log(msg.as_ptr()); }
+                    }
+                }
+                self.has_fired = true;
+                // Reset budget on the firing ball so remote players also get
+                // a fresh dodge window once their shot is in the air.
+                if self.phase == Phase::ProjectileFlying {
+                    self.refresh_dodge_budget(ball_idx);
+                }
+            }
+            NetInput::Walk { .. } | NetInput::Jump | NetInput::Backflip => {
+                self.sim.apply_input(ball_idx, input);
+            }
+            NetInput::Prod { target, dir } => {
+                self.apply_prod(ball_idx, target, dir);
+            }
+            NetInput::AirstrikeTarget { weapon, x: target_x } => {
+                // Spawn airstrike/napalm droplets for the remote player's click
+                let spare_team = self.spare_team(ball_idx);
+                let damage_scale = self.damage_scale(ball_idx);
+                self.airstrike_droplets.clear();
+                if weapon == Weapon::NapalmStrike {
+                    let spacing = 60.0;
+                    for i in 0..7 {
+                        let x = target_x + (i as f32 - 3.0) * spacing;
+                        self.airstrike_droplets.push(AirstrikeDroplet {
+                            x, y: -50.0, vy: 0.0, alive: true,
+                            weapon_type: AirstrikeType::Napalm,
+                            spare_team,
+                            damage_scale,
+                        });
+                    }
+                } else {
+                    let spacing = 80.0;
+                    for i in 0..5 {
+                        let x = target_x + (i as f32 - 2.0) * spacing;
+                        self.airstrike_droplets.push(AirstrikeDroplet {
+                            x, y: -50.0, vy: 0.0, alive: true,
+                            weapon_type: AirstrikeType::Explosive,
+                            spare_team,
+                            damage_scale,
+                        });
+                    }
+                }
+                self.has_fired = true;
+                self.phase = Phase::ProjectileFlying;
+                self.refresh_dodge_budget(ball_idx);
+            }
+            NetInput::BuildWallPlace { ax, ay, angle } => {
+                // Stamp the wall onto terrain for the remote player's placement
+                let placed = self.apply_wall_at(ax, ay, angle);
+                self.sim.terrain.modified_cells += placed;
+                self.has_fired = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+                // Record for reconnect sync
+                self.wall_log.push((ax as i32, ay as i32, (angle * 1000.0) as i32));
+                self.note_terrain_op(2);
+            }
+            NetInput::RopeAttach { x, y } if ball_idx < self.sim.balls.len() => {
+                let ball = &self.sim.balls[ball_idx];
+                let length = ((ball.x - x).powi(2) + (ball.y - y).powi(2)).sqrt();
+                self.rope = Some(NinjaRope {
+                    anchor_x: x,
+                    anchor_y: y,
+                    length: length.clamp(special_weapons::ROPE_MIN_LENGTH, special_weapons::ROPE_RANGE),
+                    ball: ball_idx,
+                });
+            }
+            NetInput::RopeDetach => {
+                self.rope = None;
+            }
+            NetInput::TeleportTo { x: tx, y: ty } => {
+                // Move the remote player's ball to target position
+                if ball_idx < self.sim.balls.len() && self.sim.balls[ball_idx].alive {
+                    self.sim.balls[ball_idx].x = tx.clamp(0.0, self.sim.terrain.width as f32);
+                    self.sim.balls[ball_idx].y = ty.clamp(0.0, self.sim.terrain.height as f32);
+                    self.sim.balls[ball_idx].vx = 0.0;
+                    self.sim.balls[ball_idx].vy = 0.0;
+                }
+                self.has_fired = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            }
+            NetInput::BatSwing { angle } => {
+                // Apply baseball bat knockback for the remote player's swing
+                if ball_idx < self.sim.balls.len() && self.sim.balls[ball_idx].alive {
+                    let ball_x = self.sim.balls[ball_idx].x;
+                    let ball_y = self.sim.balls[ball_idx].y;
+                    let bat_range = 100.0;
+                    let knock_x = angle.cos() * 850.0;
+                    let knock_y = angle.sin() * 850.0 - 300.0;
+                    let spare_team = self.spare_team(ball_idx);
+                    let damage = physics::scale_damage(Weapon::BaseballBat.base_damage(), self.damage_scale(ball_idx));
+                    for i in 0..self.sim.balls.len() {
+                        if i == ball_idx || !self.sim.balls[i].alive { continue; }
+                        let dx = self.sim.balls[i].x - ball_x;
+                        let dy = self.sim.balls[i].y - ball_y;
+                        if (dx*dx + dy*dy).sqrt() < bat_range {
+                            self.sim.balls[i].apply_knockback(knock_x, knock_y);
+                            self.sim.balls[i].take_weapon_damage(damage, spare_team);
+                        }
+                    }
+                }
+                self.has_fired = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            }
+            NetInput::FlaresDrop { bx, by } => {
+                if let Some(team) = self.sim.balls.get(self.current_ball).map(|b| b.team) {
+                    self.drop_flare(bx, by, team);
+                }
+                self.has_fired = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            }
+            NetInput::FoxholeDig { bx, by } => {
+                let dug = self.apply_foxhole_at(bx, by);
+                self.sim.terrain.modified_cells += dug;
+                if !self.foxhole_log.contains(&(bx, by)) {
+                    self.foxhole_log.push((bx, by));
+                    self.note_terrain_op(4);
+                }
+                self.has_fired = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            }
+            NetInput::DrillFire { bx, by, angle } => {
+                let angle = Weapon::Drill.clamp_aim(angle);
+                // Carve drill tunnel using the exact origin the active player sent
+                let carved = self.apply_drill_at(bx as f32, by as f32, angle);
+                self.sim.terrain.modified_cells += carved;
+                // Track for reconnect sync (dedup)
+                let amrad = (angle * 1000.0) as i32;
+                if !self.drill_log.iter().any(|&(x,y,a)| x==bx && y==by && a==amrad) {
+                    self.drill_log.push((bx, by, amrad));
+                    self.note_terrain_op(1);
+                }
+                self.has_fired = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Hold the roped ball to its rope after this frame's physics. The rope lets go when
+    /// the turn's movement ends, the ball's turn is over or the terrain it hooked is gone.
+    /// A network-driven remote ball is only drawn with the rope, as its owner streams
+    /// where the swing puts it.
+    fn tick_rope(&mut self) {
+        let Some(rope) = &self.rope else { return };
+        let keep = rope.ball == self.current_ball
+            && self.phase.allows_movement()
+            && self.sim.balls.get(rope.ball).is_some_and(|b| b.alive)
+            && self.sim.terrain.is_solid(rope.anchor_x as i32, rope.anchor_y as i32);
+        if !keep {
+            self.rope = None;
+            return;
+        }
+        let network_driven = self.net.connected
+            && self.ball_lerp_targets.get(rope.ball).copied().flatten().is_some();
+        if !network_driven {
+            rope.constrain(&mut self.sim.balls[rope.ball], &self.sim.terrain);
+        }
+    }
+
+    /// Tell the front-end a local action was refused and why.
+    fn report_refused(&self, action: &str, err: GameError) {
+        let event = format!("{{\"type\":\"action_refused\",\"action\":\"{}\",\"reason\":\"{}\"}}", action, err.name());
+        self.net.send_game_event(&event);
+    }
+
+    fn do_fire(&mut self, idx: usize, angle: f32, power: f32, weapon: Weapon) -> Result<(), GameError> {
+        // Every client clamps, so a remote shot from outside the limits lands the same way
+        let angle = weapon.clamp_aim(angle);
+        if idx >= self.sim.balls.len() || !self.sim.balls[idx].alive {
+            return Err(GameError::InvalidBall);
+        }
+        // Locked weapons never fire, whichever client the input came from.
+        if !self.weapon_allowed(self.sim.balls[idx].team, weapon) {
+            return Err(GameError::WeaponLocked);
         }
-        let ball = &self.balls[idx];
-        let offset = BALL_RADIUS + 4.0;
+        let spare_team = self.spare_team(idx);
+        let damage_scale = self.damage_scale(idx);
+        let ball = &self.sim.balls[idx];
+        let offset = ball.radius + 4.0;
         let sx = ball.x + angle.cos() * offset;
         let sy = ball.y + angle.sin() * offset;
         
@@ -1096,7 +2083,7 @@ impl Game {
                 self.shotgun_pellets.clear();
                 let pellet_count = 6;
                 let spread = 0.25;
-                let base_speed = power * 12.0;
+                let base_speed = weapon.launch_speed(power);
                 
                 for i in 0..pellet_count {
                     let offset_angle = (i as f32 - (pellet_count as f32 / 2.0)) * (spread / pellet_count as f32);
@@ -1110,7 +2097,8 @@ impl Game {
                         vx: pellet_angle.cos() * speed,
                         vy: pellet_angle.sin() * speed,
                         alive: true,
-                        damage: 10,
+                        damage: physics::scale_damage(10, damage_scale),
+                        spare_team,
                     });
                 }
                 self.phase = Phase::ProjectileFlying;
@@ -1121,12 +2109,14 @@ impl Game {
                 self.uzi_bullets.clear();
                 let bullet_count = 10;
                 let spread = 0.15;
-                let base_speed = power * 15.0;
-                
-                for i in 0..bullet_count {
-                    let offset_angle = (rand::gen_range(0.0, 1.0) - 0.5) * spread;
+                let base_speed = weapon.launch_speed(power);
+                // Seeded from state every client shares at the shot (not macroquad's rand),
+                // so the shooter and remotes fire the same burst
+                let seed = self.map_seed ^ self.turns_played.wrapping_mul(0x85EB_CA6B) ^ (idx as u32).wrapping_mul(0xC2B2_AE35);
+
+                for (offset_angle, speed_factor) in weapons::uzi_burst(seed, bullet_count, spread) {
                     let bullet_angle = angle + offset_angle;
-                    let speed = base_speed * (0.95 + rand::gen_range(0.0, 0.1));
+                    let speed = base_speed * speed_factor;
                     
                     self.uzi_bullets.push(UziBullet {
                         x: sx,
@@ -1134,6 +2124,8 @@ impl Game {
                         vx: bullet_angle.cos() * speed,
                         vy: bullet_angle.sin() * speed,
                         alive: true,
+                        damage: physics::scale_damage(5, damage_scale),
+                        spare_team,
                     });
                 }
                 self.phase = Phase::ProjectileFlying;
@@ -1155,17 +2147,16 @@ impl Game {
             Weapon::Dynamite => {
                 self.placed_explosives.push(PlacedExplosive {
                     x: ball.x,
-                    y: ball.y + BALL_RADIUS - 2.0,
+                    y: ball.y + ball.radius - 2.0,
                     fuse: 5.0,
                     alive: true,
                     radius: 45.0,
-                    damage: 50,
+                    damage: physics::scale_damage(50, damage_scale),
+                    spare_team,
                 });
                 self.phase = Phase::Retreat;
                 self.retreat_timer = 5.0;
-                if self.current_ball < self.balls.len() {
-                    self.balls[self.current_ball].reset_movement_budget();
-                }
+                self.refresh_dodge_budget(self.current_ball);
             },
             
             // Baseball Bat - enter melee mode
@@ -1182,13 +2173,42 @@ impl Game {
 
             // Drill - carve a large tunnel instantly along aim direction
             Weapon::Drill => {
-                let bx = self.balls[idx].x;
-                let by = self.balls[idx].y;
-                self.apply_drill_at(bx, by, angle);
+                let bx = self.sim.balls[idx].x;
+                let by = self.sim.balls[idx].y;
+                let carved = self.apply_drill_at(bx, by, angle);
+                self.sim.terrain.modified_cells += carved;
                 self.phase = Phase::Settling;
                 self.settle_timer = 0.0;
                 // Record in drill log for reconnect sync
                 self.drill_log.push((bx as i32, by as i32, (angle * 1000.0) as i32));
+                self.note_terrain_op(1);
+            },
+
+            // Foxhole - dig a pit under the ball and let it drop in; ends the turn
+            Weapon::Foxhole => {
+                let bx = self.sim.balls[idx].x as i32;
+                let by = self.sim.balls[idx].y as i32;
+                let dug = self.apply_foxhole_at(bx, by);
+                self.sim.terrain.modified_cells += dug;
+                self.foxhole_log.push((bx, by));
+                self.note_terrain_op(4);
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            },
+
+            // Flares - leave a decoy above the ball for enemy homing missiles; ends the turn
+            Weapon::Flares => {
+                let (bx, by, team) = (ball.x as i32, ball.y as i32, ball.team);
+                self.drop_flare(bx, by, team);
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            },
+
+            // Ninja rope - hook into the terrain along the aim; a miss just falls short
+            Weapon::Rope => {
+                let (bx, by) = (ball.x, ball.y);
+                self.rope = NinjaRope::cast(&self.sim.terrain, idx, bx, by, angle);
+                self.phase = Phase::Aiming;
             },
 
             // Teleport - enter teleport mode
@@ -1202,7 +2222,7 @@ impl Game {
                 let cos_a = angle.cos();
                 let sin_a = angle.sin();
                 let step = 3.0_f32;
-                let max_dist = (self.terrain.width.max(self.terrain.height) as f32) * 2.0;
+                let max_dist = (self.sim.terrain.width.max(self.sim.terrain.height) as f32) * 2.0;
 
                 let mut hit_x = sx;
                 let mut hit_y = sy;
@@ -1215,8 +2235,8 @@ impl Game {
                     let ry = sy + sin_a * dist;
 
                     // Left the terrain bounds
-                    if rx < 0.0 || rx >= self.terrain.width as f32
-                        || ry < 0.0 || ry >= self.terrain.height as f32 {
+                    if rx < 0.0 || rx >= self.sim.terrain.width as f32
+                        || ry < 0.0 || ry >= self.sim.terrain.height as f32 {
                         hit_x = rx;
                         hit_y = ry;
                         beam_len = dist;
@@ -1224,7 +2244,7 @@ impl Game {
                     }
 
                     // Hit terrain
-                    if self.terrain.is_solid(rx as i32, ry as i32) {
+                    if self.sim.terrain.is_solid(rx as i32, ry as i32) {
                         hit_x = rx;
                         hit_y = ry;
                         beam_len = dist;
@@ -1233,11 +2253,11 @@ impl Game {
 
                     // Hit a ball
                     let mut found = false;
-                    for (bi, w) in self.balls.iter().enumerate() {
+                    for (bi, w) in self.sim.balls.iter().enumerate() {
                         if !w.alive || bi == idx { continue; }
                         let dx = w.x - rx;
                         let dy = w.y - ry;
-                        if dx * dx + dy * dy < (BALL_RADIUS * 1.4) * (BALL_RADIUS * 1.4) {
+                        if dx * dx + dy * dy < (w.radius * 1.4) * (w.radius * 1.4) {
                             hit_x = rx;
                             hit_y = ry;
                             hit_ball = Some(bi);
@@ -1253,9 +2273,9 @@ impl Game {
 
                 // Deal damage + knockback to hit ball
                 if let Some(bi) = hit_ball {
-                    self.balls[bi].take_damage(weapon.base_damage());
+                    self.sim.balls[bi].take_weapon_damage(physics::scale_damage(weapon.base_damage(), damage_scale), spare_team);
                     let knock = 320.0;
-                    self.balls[bi].apply_knockback(
+                    self.sim.balls[bi].apply_knockback(
                         cos_a * knock,
                         sin_a * knock - 80.0,
                     );
@@ -1291,50 +2311,69 @@ impl Game {
                 }
 
                 // Pan camera to hit point so the player can see where the shot landed
+                self.cam.recenter();
                 self.cam.follow(hit_x, hit_y, 1.0, 1.0);
+                self.last_impact = Some((hit_x, hit_y));
 
                 self.phase = Phase::Settling;
                 self.settle_timer = 0.0;
             },
 
+            // Shield - protect the firing ball from the next hit and end the turn
+            Weapon::Shield => {
+                self.sim.balls[idx].shield = true;
+                self.phase = Phase::Settling;
+                self.settle_timer = 0.0;
+            },
+
             // Mine - place at ball position as a timed trap, then retreat
             Weapon::Mine => {
                 self.placed_explosives.push(PlacedExplosive {
                     x: ball.x,
-                    y: ball.y + BALL_RADIUS - 2.0,
+                    y: ball.y + ball.radius - 2.0,
                     fuse: 3.0,
                     alive: true,
                     radius: 30.0,
-                    damage: 45,
+                    damage: physics::scale_damage(45, damage_scale),
+                    spare_team,
                 });
                 self.phase = Phase::Retreat;
                 self.retreat_timer = 5.0;
-                if self.current_ball < self.balls.len() {
-                    self.balls[self.current_ball].reset_movement_budget();
-                }
+                self.refresh_dodge_budget(self.current_ball);
             },
 
             // Mortar - fire as projectile but enter Retreat immediately so player
             // can move while the shell (and its cluster bomblets) are in flight.
             Weapon::Mortar => {
-                let shooter_team = self.balls[idx].team;
-                let proj = Projectile::new(sx, sy, angle, power, weapon, shooter_team);
+                let shooter_team = self.sim.balls[idx].team;
+                let mut proj = Projectile::new(sx, sy, angle, power, weapon, shooter_team);
+                proj.spare_team = spare_team;
+                proj.pass_team = self.pass_team(idx);
+                proj.damage_scale = damage_scale;
                 self.proj = Some(proj);
                 self.phase = Phase::Retreat;
                 self.retreat_timer = 5.0;
-                if self.current_ball < self.balls.len() {
-                    self.balls[self.current_ball].reset_movement_budget();
-                }
+                self.refresh_dodge_budget(self.current_ball);
             },
 
             // All other weapons use regular projectile
             _ => {
-                let shooter_team = self.balls[idx].team;
-                let proj = Projectile::new(sx, sy, angle, power, weapon, shooter_team);
+                let shooter_team = self.sim.balls[idx].team;
+                let mut proj = Projectile::new(sx, sy, angle, power, weapon, shooter_team);
+                proj.spare_team = spare_team;
+                proj.pass_team = self.pass_team(idx);
+                proj.damage_scale = damage_scale;
                 self.proj = Some(proj);
                 self.phase = Phase::ProjectileFlying;
             }
         }
+
+        // Recoil: every client runs this from the same angle/power, so the kick lands alike
+        let kick = weapon.recoil() * power.clamp(0.0, 100.0) / 100.0;
+        if kick > 0.0 {
+            self.sim.balls[idx].apply_knockback(-angle.cos() * kick, -angle.sin() * kick);
+        }
+        Ok(())
     }
 
     fn end_turn(&mut self) {
@@ -1351,7 +2390,11 @@ impl Game {
             // so it is safe to call unconditionally and removes a class of race conditions.
             self.send_ball_state();
             self.send_terrain_damages();
-            self.net.send_message(r#"{"type":"end_turn"}"#);
+            if self.is_my_turn() {
+                self.send_terrain_hashes();
+            }
+            // The turn count lets the worker hand it back to a client that reconnects
+            self.net.send_message(&format!("{{\"type\":\"end_turn\",\"turn\":{}}}", self.turns_played));
         }
         self.phase = Phase::TurnEnd;
         self.turn_end_timer = TURN_END_DELAY;
@@ -1362,11 +2405,11 @@ impl Game {
     /// Send a snapshot of all ball positions/health to sync with other players
     fn send_ball_state(&self) {
         let mut ball_data = String::from("[");
-        for (i, w) in self.balls.iter().enumerate() {
+        for (i, w) in self.sim.balls.iter().enumerate() {
             if i > 0 { ball_data.push(','); }
             ball_data.push_str(&format!(
-                "{{\"x\":{},\"y\":{},\"vx\":{},\"vy\":{},\"hp\":{},\"alive\":{}}}",
-                w.x, w.y, w.vx, w.vy, w.health, w.alive
+                "{{\"x\":{},\"y\":{},\"vx\":{},\"vy\":{},\"hp\":{},\"alive\":{},\"shield\":{}}}",
+                w.x, w.y, w.vx, w.vy, w.health, w.alive, w.shield
             ));
         }
         ball_data.push(']');
@@ -1377,7 +2420,20 @@ impl Game {
     /// Carve a drill tunnel at the given ball origin and angle.
     /// Used by both do_fire (local) and the DrillFire network receive handler (remote)
     /// so all clients carve the exact same tunnel at the same world coordinates.
-    fn apply_drill_at(&mut self, bx: f32, by: f32, angle: f32) {
+    /// Terrain tools the per-turn terrain budget can switch off. Explosions still count
+    /// against the budget but are never blocked.
+    fn alters_terrain(weapon: Weapon) -> bool {
+        matches!(weapon, Weapon::Drill | Weapon::Foxhole | Weapon::BuildWall)
+    }
+
+    /// Cells the current turn may still carve or place, or None when unlimited.
+    fn terrain_budget_left(&self) -> Option<u32> {
+        let budget = self.settings.terrain_budget;
+        (budget > 0).then(|| budget.saturating_sub(self.sim.terrain.modified_cells))
+    }
+
+    /// Returns the number of solid cells carved away.
+    fn apply_drill_at(&mut self, bx: f32, by: f32, angle: f32) -> u32 {
         let cos_a = angle.cos();
         let sin_a = angle.sin();
         let perp_x = -sin_a;
@@ -1403,10 +2459,11 @@ impl Game {
         let bb_min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min).floor() as i32 - 1;
         let bb_max_y = corners.iter().map(|c| c.1).fold(f32::NEG_INFINITY, f32::max).ceil()  as i32 + 1;
 
-        let w = self.terrain.width as i32;
-        let h = self.terrain.height as i32;
+        let w = self.sim.terrain.width as i32;
+        let h = self.sim.terrain.height as i32;
         let mut min_x = i32::MAX; let mut max_x = i32::MIN;
         let mut min_y = i32::MAX; let mut max_y = i32::MIN;
+        let mut carved = 0;
 
         // Iterate over every pixel in the bounding box and test inclusion in the
         // rotated rectangle using dot-products. This guarantees no pixels are missed
@@ -1421,7 +2478,10 @@ impl Game {
                 if along >= -tunnel_back && along <= tunnel_fwd
                     && perp >= -half_w && perp <= half_w
                 {
-                    self.terrain.set(px, py, terrain::AIR);
+                    if self.sim.terrain.is_solid(px, py) {
+                        carved += 1;
+                    }
+                    self.sim.terrain.set(px, py, terrain::AIR);
                     if px < min_x { min_x = px; }
                     if px > max_x { max_x = px; }
                     if py < min_y { min_y = py; }
@@ -1431,43 +2491,300 @@ impl Game {
         }
         // Regrow grass on surfaces newly exposed around the tunnel edges
         if min_x <= max_x && min_y <= max_y {
-            self.terrain.refresh_grass_in_area(min_x, min_y, max_x, max_y);
+            self.sim.terrain.refresh_grass_in_area(min_x, min_y, max_x, max_y);
+            self.sim.terrain.settle_water_around(min_x, min_y, max_x, max_y);
         }
         self.terrain_dirty = true;
+        carved
+    }
+
+    /// Stamp a Build Wall plank centred on (ax, ay) along `angle`. Shared by local
+    /// placement, the remote BuildWallPlace input and reconnect replay so every client
+    /// places the same cells. Returns the number of cells that weren't solid before.
+    fn apply_wall_at(&mut self, ax: f32, ay: f32, angle: f32) -> u32 {
+        let cos_a = angle.cos();
+        let sin_a = angle.sin();
+        let half_len = 35i32;
+        let half_thick = 4i32;
+        let mut placed = 0;
+        for i in -half_len..=half_len {
+            for j in -half_thick..=half_thick {
+                let wx = (ax + i as f32 * cos_a - j as f32 * sin_a).round() as i32;
+                let wy = (ay + i as f32 * sin_a + j as f32 * cos_a).round() as i32;
+                if wx >= 0 && wx < self.sim.terrain.width as i32
+                    && wy >= 0 && wy < self.sim.terrain.height as i32 {
+                    if !self.sim.terrain.is_solid(wx, wy) {
+                        placed += 1;
+                    }
+                    self.sim.terrain.set(wx, wy, terrain::WOOD);
+                }
+            }
+        }
+        self.terrain_dirty = true;
+        placed
+    }
+
+    /// Dig a foxhole under a ball standing at (bx, by): a bowl a little wider than the
+    /// ball, reaching FOXHOLE_DEPTH below its feet. Fixed shape from integer maths, so
+    /// every client and every reconnect replay carves the same cells.
+    /// Returns the number of solid cells dug out.
+    fn apply_foxhole_at(&mut self, bx: i32, by: i32) -> u32 {
+        let foot = by + self.settings.ball_size.radius() as i32;
+        let (rx, ry) = (FOXHOLE_HALF_W, FOXHOLE_DEPTH);
+        let mut dug = 0;
+        // Start a little above the feet so the ball clears any lip it stood on
+        for dy in -4..=ry {
+            for dx in -rx..=rx {
+                // Inside the ellipse dx²/rx² + dy²/ry² <= 1, scaled to integers
+                if dx * dx * ry * ry + dy.max(0) * dy.max(0) * rx * rx <= rx * rx * ry * ry {
+                    if self.sim.terrain.is_solid(bx + dx, foot + dy) {
+                        dug += 1;
+                    }
+                    self.sim.terrain.set(bx + dx, foot + dy, terrain::AIR);
+                }
+            }
+        }
+        self.sim.terrain.refresh_grass_in_area(bx - rx, foot - 4, bx + rx, foot + ry);
+        self.sim.terrain.settle_water_around(bx - rx, foot - 4, bx + rx, foot + ry);
+        self.terrain_dirty = true;
+        dug
+    }
+
+    /// Crumble the loose sand a little wider than a ball around the foot position
+    /// (x, y), so whoever stood there drops through.
+    fn apply_crumble_at(&mut self, x: i32, y: i32) {
+        let radius = self.settings.ball_size.radius() as i32 + SAND_CRUMBLE_MARGIN;
+        self.sim.terrain.crumble_sand(x, y, radius);
+        self.terrain_dirty = true;
+    }
+
+    /// Hang a flare decoy above a ball at (bx, by), replacing any the team already has
+    /// burning so unused flares can't pile up over the turns.
+    fn drop_flare(&mut self, bx: i32, by: i32, team: u32) {
+        self.decoys.retain(|d| d.team != team);
+        self.decoys.push(Decoy {
+            x: bx as f32,
+            y: (by - FLARE_HEIGHT).max(0) as f32,
+            lifetime: special_weapons::FLARE_BURN_TIME,
+            team,
+        });
+    }
+
+    fn terrain_op_count(&self) -> usize {
+        self.sim.terrain.damage_log.len() + self.wall_log.len() + self.drill_log.len() + self.quake_log.len()
+            + self.foxhole_log.len() + self.grass_log.len() + self.crumble_log.len()
+    }
+
+    /// Record that an op of `kind` was just logged. Explosions are logged inside the
+    /// terrain, so any added since the last op are recorded first.
+    fn note_terrain_op(&mut self, kind: u8) {
+        let explosions = self.unnoted_explosions();
+        self.terrain_op_order.extend(std::iter::repeat_n(0, explosions));
+        self.terrain_op_order.push(kind);
+    }
+
+    fn unnoted_explosions(&self) -> usize {
+        let noted = self.terrain_op_order.iter().filter(|&&k| k == 0).count();
+        self.sim.terrain.damage_log.len().saturating_sub(noted)
+    }
+
+    /// Let sand come to rest between turns. Noted as a [9] op when the terrain changed
+    /// since the last one, so a resync settles sand at the same point between the ops.
+    fn settle_turn_sand(&mut self) {
+        self.settle_all_sand();
+        let changed = self.unnoted_explosions() > 0
+            || self.terrain_op_order.last().is_some_and(|&k| k != 9);
+        if changed {
+            self.note_terrain_op(9);
+        }
+    }
+
+    /// Sync-format entry for the `i`th logged op of `kind`, if there is one.
+    fn terrain_op_entry(&self, kind: u8, i: usize) -> Option<String> {
+        match kind {
+            0 => self.sim.terrain.damage_log.get(i).map(|&(cx, cy, r)| format!("[0,{},{},{}]", cx, cy, r)),
+            1 => self.drill_log.get(i).map(|&(bx, by, amrad)| format!("[1,{},{},{}]", bx, by, amrad)),
+            2 => self.wall_log.get(i).map(|&(ax, ay, amrad)| format!("[2,{},{},{}]", ax, ay, amrad)),
+            3 => self.quake_log.get(i).map(|&turn| format!("[3,{},0,0]", turn)),
+            4 => self.foxhole_log.get(i).map(|&(bx, by)| format!("[4,{},{},0]", bx, by)),
+            7 => self.grass_log.get(i).map(|&turn| format!("[7,{},0,0]", turn)),
+            8 => self.crumble_log.get(i).map(|&(x, y)| format!("[8,{},{},0]", x, y)),
+            9 => Some("[9,0,0,0]".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Fold the terrain ops logs into a single snapshot once they reach
+    /// TERRAIN_LOG_COMPACT_AT. Called at the start of every turn, where all clients have
+    /// simulated the same ops, so they compact at the same point and agree on the version.
+    fn compact_terrain_log(&mut self) {
+        if self.terrain_op_count() < TERRAIN_LOG_COMPACT_AT {
+            return;
+        }
+        self.terrain_snapshot = Some(self.sim.terrain.runs());
+        self.sim.terrain.damage_log.clear();
+        self.drill_log.clear();
+        self.wall_log.clear();
+        self.quake_log.clear();
+        self.foxhole_log.clear();
+        self.grass_log.clear();
+        self.crumble_log.clear();
+        self.terrain_op_order.clear();
+        self.terrain_log_version += 1;
     }
 
     /// Send the full terrain ops log to the server for persistence across reconnects.
-    /// Format: [[type,a,b,c],...] where type 0=explosion, 1=drill, 2=wall.
+    /// Format: [[type,a,b,c],...] where type 0=explosion, 1=drill, 2=wall, 3=earthquake,
+    /// 4=foxhole, 5=snapshot, 6=damaged barrel, 7=grass regrowth, 8=crumbled sand, 9=sand
+    /// settled at a turn start. A snapshot [5,cell,count,cell,count,...]
+    /// comes first when the log has been compacted. The ops follow in the order they
+    /// happened. Barrels are sent as their current [6,index,hp,0] rather than logged, so
+    /// compaction never drops them.
     fn send_terrain_damages(&self) {
-        let explosions = &self.terrain.damage_log;
-        let total = explosions.len() + self.wall_log.len() + self.drill_log.len();
-        if total == 0 {
-            return;
+        if let Some(msg) = self.terrain_damages_msg() {
+            self.net.send_message(&msg);
+        }
+    }
+
+    /// The `terrain_damages` message `send_terrain_damages` sends, if there's anything to log.
+    fn terrain_damages_msg(&self) -> Option<String> {
+        if self.terrain_op_count() == 0 && self.terrain_snapshot.is_none() {
+            return None;
         }
         let mut arr = String::from("[");
         let mut first = true;
-        for &(cx, cy, r) in explosions.iter() {
-            if !first { arr.push(','); }
-            arr.push_str(&format!("[0,{},{},{}]", cx, cy, r));
+        if let Some(runs) = &self.terrain_snapshot {
+            arr.push_str("[5");
+            for &(cell, count) in runs {
+                arr.push_str(&format!(",{},{}", cell, count));
+            }
+            arr.push(']');
             first = false;
         }
-        for &(bx, by, amrad) in self.drill_log.iter() {
+        // Ops in the order they were noted, then any not noted yet (explosions since the
+        // last other op, or logs restored from a save) kind by kind
+        let mut next = [0usize; 10];
+        let mut ops: Vec<String> = Vec::new();
+        for &kind in &self.terrain_op_order {
+            ops.extend(self.terrain_op_entry(kind, next[kind as usize]));
+            next[kind as usize] += 1;
+        }
+        for kind in [0u8, 1, 2, 3, 4, 7, 8] {
+            while let Some(entry) = self.terrain_op_entry(kind, next[kind as usize]) {
+                ops.push(entry);
+                next[kind as usize] += 1;
+            }
+        }
+        for entry in ops {
             if !first { arr.push(','); }
-            arr.push_str(&format!("[1,{},{},{}]", bx, by, amrad));
+            arr.push_str(&entry);
             first = false;
         }
-        for &(ax, ay, amrad) in self.wall_log.iter() {
+        for (i, barrel) in self.barrels.iter().enumerate() {
+            if barrel.alive && barrel.hp >= barrels::BARREL_HP {
+                continue;
+            }
             if !first { arr.push(','); }
-            arr.push_str(&format!("[2,{},{},{}]", ax, ay, amrad));
+            arr.push_str(&format!("[6,{},{},0]", i, if barrel.alive { barrel.hp } else { 0 }));
             first = false;
         }
         arr.push(']');
-        let msg = format!("{{\"type\":\"terrain_damages\",\"log\":{}}}", arr);
+        Some(format!("{{\"type\":\"terrain_damages\",\"version\":{},\"log\":{}}}", self.terrain_log_version, arr))
+    }
+
+    /// Broadcast a hash of every terrain block so the other clients can spot and heal any
+    /// blocks their simulation got wrong. Hashes go out as one string of 16-digit hex
+    /// values, since JSON numbers can't hold a u64 exactly.
+    fn send_terrain_hashes(&mut self) {
+        self.settle_all_sand();
+        let hashes = self.sim.terrain.block_hashes(TERRAIN_BLOCK_SIZE);
+        let hex: String = hashes.iter().map(|h| format!("{:016x}", h)).collect();
+        let msg = format!("{{\"type\":\"terrain_hashes\",\"turn\":{},\"hashes\":\"{}\"}}", self.turns_played, hex);
+        self.net.send_message(&msg);
+        self.sent_terrain_hashes = Some((self.turns_played, hashes));
+    }
+
+    /// Compare the active player's block hashes with our own terrain and ask for the
+    /// blocks that differ. Only called once our own effects have finished, so a shell
+    /// still in flight here isn't mistaken for a desync.
+    fn check_terrain_hashes(&mut self) {
+        let Some((turn, theirs)) = self.pending_terrain_hashes.take() else { return };
+        self.settle_all_sand();
+        let ours = self.sim.terrain.block_hashes(TERRAIN_BLOCK_SIZE);
+        self.terrain_heal_requests.clear();
+        if theirs.len() != ours.len() {
+            return;
+        }
+        for (i, (&a, &b)) in theirs.iter().zip(&ours).enumerate() {
+            if a != b && self.terrain_heal_requests.len() < MAX_HEAL_BLOCKS {
+                self.terrain_heal_requests.push((turn, i, b));
+            }
+        }
+        if self.terrain_heal_requests.is_empty() {
+            return;
+        }
+        let blocks: Vec<String> = self.terrain_heal_requests.iter().map(|&(_, i, _)| i.to_string()).collect();
+        let msg = format!("{{\"type\":\"terrain_heal_request\",\"turn\":{},\"blocks\":\"{}\"}}", turn, blocks.join(","));
+        self.net.send_message(&msg);
+        let event = format!("{{\"type\":\"terrain_desync\",\"turn\":{},\"blocks\":{}}}", turn, self.terrain_heal_requests.len());
+        self.net.send_game_event(&event);
+    }
+
+    /// Answer a heal request for the hashes we sent, with each asked-for block that
+    /// hasn't changed since as `index=cell:count,...`, blocks separated by `;`.
+    fn answer_terrain_heal_request(&self, msg: &str) {
+        let Some((sent_turn, sent)) = &self.sent_terrain_hashes else { return };
+        if parse_json_number(msg, "turn") != Some(*sent_turn as f64) {
+            return;
+        }
+        let Some(list) = parse_json_string(msg, "blocks") else { return };
+        let ours = self.sim.terrain.block_hashes(TERRAIN_BLOCK_SIZE);
+        let patches: Vec<String> = list
+            .split(',')
+            .filter_map(|i| i.trim().parse::<usize>().ok())
+            .take(MAX_HEAL_BLOCKS)
+            .filter(|&i| sent.get(i).is_some() && sent.get(i) == ours.get(i))
+            .filter_map(|i| Some(format!("{}={}", i, self.sim.terrain.encode_block(i, TERRAIN_BLOCK_SIZE)?)))
+            .collect();
+        if patches.is_empty() {
+            return;
+        }
+        let msg = format!("{{\"type\":\"terrain_block_patch\",\"turn\":{},\"blocks\":\"{}\"}}", sent_turn, patches.join(";"));
         self.net.send_message(&msg);
     }
 
+    /// Overwrite the blocks we asked for with the active player's copy. A block we've
+    /// changed since asking is skipped, as the patch would undo that change.
+    fn apply_terrain_block_patch(&mut self, msg: &str) {
+        let Some(turn) = parse_json_number(msg, "turn") else { return };
+        let Some(list) = parse_json_string(msg, "blocks") else { return };
+        let mut healed = 0;
+        for patch in list.split(';') {
+            let Some((index, runs)) = patch.split_once('=') else { continue };
+            let Ok(index) = index.trim().parse::<usize>() else { continue };
+            let Some(pos) = self.terrain_heal_requests.iter().position(|&(t, i, _)| t as f64 == turn && i == index) else {
+                continue;
+            };
+            let (_, _, hash) = self.terrain_heal_requests.swap_remove(pos);
+            if self.sim.terrain.block_hashes(TERRAIN_BLOCK_SIZE).get(index) != Some(&hash) {
+                continue;
+            }
+            if self.sim.terrain.decode_block(index, TERRAIN_BLOCK_SIZE, runs).is_some() {
+                healed += 1;
+            }
+        }
+        if healed > 0 {
+            self.terrain_dirty = true;
+        }
+    }
+
     /// Apply terrain ops log received from server on reconnect.
-    /// Handles [0,cx,cy,r] explosions, [1,bx,by,amrad] drills, [2,ax,ay,amrad] walls.
+    /// Handles [0,cx,cy,r] explosions, [1,bx,by,amrad] drills, [2,ax,ay,amrad] walls,
+    /// [3,turn,0,0] earthquake collapses, [4,bx,by,0] foxholes, [6,index,hp,0] barrel
+    /// damage, [7,turn,0,0] grass regrowth, [8,x,y,0] crumbled sand, [9,0,0,0] sand
+    /// settling at a turn start and a leading [5,cell,count,...] snapshot that replaces
+    /// the terrain before the rest. Ops are replayed in log order, since a collapse,
+    /// regrowth or sand fall depends on the craters before it.
     /// Also handles legacy 3-element [cx,cy,r] entries (old format = explosion).
     fn apply_terrain_sync(&mut self, msg: &str) {
         let key = "\"log\":[";
@@ -1489,8 +2806,10 @@ impl Game {
         }
         let content = &msg[start..end];
         if content.is_empty() { return; }
+        if let Some(version) = parse_json_number(msg, "version") {
+            self.terrain_log_version = self.terrain_log_version.max(version as u32);
+        }
 
-        let mut explosions: Vec<(i32, i32, i32)> = Vec::new();
         let mut pos = 0;
         while pos < content.len() {
             let sub_start = match content[pos..].find('[') {
@@ -1504,10 +2823,27 @@ impl Game {
             let entry = &content[sub_start..sub_end];
             let nums: Vec<i32> = entry.split(',').filter_map(|s| s.trim().parse().ok()).collect();
             match nums.as_slice() {
-                // Legacy 3-element = explosion
-                [cx, cy, r] => { explosions.push((*cx, *cy, *r)); }
-                // type 0 = explosion
-                [0, cx, cy, r] => { explosions.push((*cx, *cy, *r)); }
+                // type 5 = snapshot from a compacted log; the ops after it were logged since
+                [5, runs @ ..] => {
+                    let runs: Vec<(u8, usize)> = runs.chunks_exact(2).map(|r| (r[0] as u8, r[1] as usize)).collect();
+                    if self.sim.terrain.set_runs(&runs).is_some() {
+                        self.terrain_snapshot = Some(runs);
+                        self.sim.terrain.damage_log.clear();
+                        self.drill_log.clear();
+                        self.wall_log.clear();
+                        self.quake_log.clear();
+                        self.foxhole_log.clear();
+                        self.grass_log.clear();
+                        self.crumble_log.clear();
+                        self.terrain_op_order.clear();
+                        self.terrain_dirty = true;
+                    }
+                }
+                // Legacy 3-element = explosion; type 0 = explosion
+                [cx, cy, r] | [0, cx, cy, r] => {
+                    self.sim.terrain.replay_damage(&[(*cx, *cy, *r)]);
+                    self.terrain_dirty = true;
+                }
                 // type 1 = drill tunnel
                 [1, bx, by, amrad] => {
                     let bxf = *bx as f32; let byf = *by as f32;
@@ -1516,43 +2852,91 @@ impl Game {
                     // Track in log so this client can also upload it
                     if !self.drill_log.iter().any(|&(x,y,a)| x==*bx && y==*by && a==*amrad) {
                         self.drill_log.push((*bx, *by, *amrad));
+                        self.note_terrain_op(1);
                     }
                 }
                 // type 2 = build wall
                 [2, ax, ay, amrad] => {
                     let ax = *ax as f32; let ay = *ay as f32;
-                    let angle = *amrad as f32 / 1000.0;
-                    let cos_a = angle.cos(); let sin_a = angle.sin();
-                    let half_len = 35i32; let half_thick = 4i32;
-                    for i in -half_len..=half_len {
-                        for j in -half_thick..=half_thick {
-                            let wx = (ax + i as f32 * cos_a - j as f32 * sin_a).round() as i32;
-                            let wy = (ay + i as f32 * sin_a + j as f32 * cos_a).round() as i32;
-                            if wx >= 0 && wx < self.terrain.width as i32
-                                && wy >= 0 && wy < self.terrain.height as i32 {
-                                self.terrain.set(wx, wy, terrain::WOOD);
-                            }
-                        }
-                    }
-                    self.terrain_dirty = true;
+                    self.apply_wall_at(ax, ay, *amrad as f32 / 1000.0);
                     // Track in log so this client can also upload it
                     let aix = ax as i32; let aiy = ay as i32;
                     if !self.wall_log.iter().any(|&(x,y,a)| x==aix && y==aiy && a==*amrad) {
                         self.wall_log.push((aix, aiy, *amrad));
+                        self.note_terrain_op(2);
                     }
                 }
+                // type 4 = foxhole
+                [4, bx, by, _] => {
+                    self.apply_foxhole_at(*bx, *by);
+                    if !self.foxhole_log.contains(&(*bx, *by)) {
+                        self.foxhole_log.push((*bx, *by));
+                        self.note_terrain_op(4);
+                    }
+                }
+                // type 8 = sand crumbled under a ball
+                [8, x, y, _] => {
+                    self.apply_crumble_at(*x, *y);
+                    if !self.crumble_log.contains(&(*x, *y)) {
+                        self.crumble_log.push((*x, *y));
+                        self.note_terrain_op(8);
+                    }
+                }
+                // type 6 = barrel damage; hp 0 means it already went off, and its crater
+                // comes with the explosions
+                [6, index, hp, _] => {
+                    if let Some(barrel) = self.barrels.get_mut(*index as usize) {
+                        barrel.hp = barrel.hp.min(*hp);
+                        if barrel.hp <= 0 {
+                            barrel.alive = false;
+                        }
+                    }
+                }
+                // type 7 = grass regrowth pass
+                [7, turn, _, _] => {
+                    let turn = *turn as u32;
+                    if !self.grass_log.contains(&turn) {
+                        self.sim.terrain.grow_grass(grass_seed(self.map_seed, turn), GRASS_GROWTH_PER_TURN);
+                        self.grass_log.push(turn);
+                        self.note_terrain_op(7);
+                        self.terrain_dirty = true;
+                    }
+                }
+                // type 3 = earthquake collapse
+                [3, turn, _, _] => {
+                    let turn = *turn as u32;
+                    if !self.quake_log.contains(&turn) {
+                        self.quake_log.push(turn);
+                        self.note_terrain_op(3);
+                        if self.sim.terrain.collapse_floating() {
+                            self.terrain_dirty = true;
+                        }
+                        // Live, the turn start settles the sand a quake shook loose
+                        self.settle_all_sand();
+                    }
+                }
+                // type 9 = sand settled at a turn start
+                [9, _, _, _] => {
+                    self.settle_all_sand();
+                    self.note_terrain_op(9);
+                }
                 _ => {}
             }
             pos = sub_end + 1;
         }
 
-        if !explosions.is_empty() {
-            #[cfg(target_arch = "wasm32")]
-            {
-                let debug_msg = format!("[SYNC] Replaying {} terrain ops\0", explosions.len());
-                unsafe { console_log(debug_msg.as_ptr()); }
-            }
-            self.terrain.replay_damage(&explosions);
+        #[cfg(target_arch = "wasm32")]
+        {
+            let debug_msg = format!("[SYNC] Replayed {} explosions\0", self.sim.terrain.damage_log.len());
+            unsafe { console_log(debug_msg.as_ptr()); }
+        }
+        self.settle_all_sand();
+    }
+
+    /// Let every bit of loose sand finish falling. Done at turn start and before terrain
+    /// hashes are taken, where all clients must agree whatever their frame rate was.
+    fn settle_all_sand(&mut self) {
+        if self.sim.terrain.settle_all_sand() {
             self.terrain_dirty = true;
         }
     }
@@ -1560,7 +2944,7 @@ impl Game {
     /// Apply ball state snapshot from the active player to sync positions/health
     fn apply_ball_state(&mut self, msg: &str) {
         // Parse the balls array from the message
-        // Format: {"type":"ball_state","balls":[{"x":..,"y":..,"vx":..,"vy":..,"hp":..,"alive":..}, ...]}
+        // Format: {"type":"ball_state","balls":[{"x":..,"y":..,"vx":..,"vy":..,"hp":..,"alive":..,"shield":..}, ...]}
         let balls_key = "\"balls\":[";
         let start = match msg.find(balls_key) {
             Some(i) => i + balls_key.len(),
@@ -1576,7 +2960,7 @@ impl Game {
         // Split by "},{" to get individual ball objects
         let mut ball_idx = 0;
         let mut pos = 0;
-        while pos < array_content.len() && ball_idx < self.balls.len() {
+        while pos < array_content.len() && ball_idx < self.sim.balls.len() {
             // Find the next object boundaries
             let obj_start = match array_content[pos..].find('{') {
                 Some(i) => pos + i,
@@ -1590,25 +2974,30 @@ impl Game {
             
             // Parse fields
             if let Some(x) = parse_json_number(obj, "x") {
-                self.balls[ball_idx].x = x as f32;
+                self.sim.balls[ball_idx].x = x as f32;
             }
             if let Some(y) = parse_json_number(obj, "y") {
-                self.balls[ball_idx].y = y as f32;
+                self.sim.balls[ball_idx].y = y as f32;
             }
             if let Some(vx) = parse_json_number(obj, "vx") {
-                self.balls[ball_idx].vx = vx as f32;
+                self.sim.balls[ball_idx].vx = vx as f32;
             }
             if let Some(vy) = parse_json_number(obj, "vy") {
-                self.balls[ball_idx].vy = vy as f32;
+                self.sim.balls[ball_idx].vy = vy as f32;
             }
             if let Some(hp) = parse_json_number(obj, "hp") {
-                self.balls[ball_idx].health = hp as i32;
+                self.sim.balls[ball_idx].health = hp as i32;
             }
             // Parse alive (boolean)
             if obj.contains("\"alive\":true") {
-                self.balls[ball_idx].alive = true;
+                self.sim.balls[ball_idx].alive = true;
             } else if obj.contains("\"alive\":false") {
-                self.balls[ball_idx].alive = false;
+                self.sim.balls[ball_idx].alive = false;
+            }
+            if obj.contains("\"shield\":true") {
+                self.sim.balls[ball_idx].shield = true;
+            } else if obj.contains("\"shield\":false") {
+                self.sim.balls[ball_idx].shield = false;
             }
             
             ball_idx += 1;
@@ -1629,14 +3018,14 @@ impl Game {
             return;
         }
 
-        let n = self.balls.len();
+        let n = self.sim.balls.len();
         if n == 0 {
             return;
         }
         let start = self.current_ball;
         let mut next = (start + 1) % n;
         loop {
-            if self.balls[next].alive {
+            if self.sim.balls[next].alive {
                 break;
             }
             next = (next + 1) % n;
@@ -1644,124 +3033,625 @@ impl Game {
                 break;
             }
         }
-        self.current_ball = next;
-        // CRITICAL: keep current_turn_index in sync with the ball's team so that
-        // is_my_turn() remains accurate when advance_turn() is used as a fallback.
-        if next < self.balls.len() {
-            self.current_turn_index = self.balls[next].team as usize;
+        self.current_ball = next;
+        // CRITICAL: keep current_turn_index in sync with the ball's team so that
+        // is_my_turn() remains accurate when advance_turn() is used as a fallback.
+        if next < self.sim.balls.len() {
+            self.current_turn_index = self.sim.balls[next].team as usize;
+        }
+        self.turns_played += 1;
+        self.settle_turn_sand();
+        self.maybe_earthquake();
+        self.maybe_crate_rain();
+        self.shrink_safe_zone();
+        self.grow_grass();
+        self.reset_turn_state();
+    }
+
+    /// Sync to a player's turn from the worker. Finds the next alive ball belonging to
+    /// the given team (player_index) using round-robin so all balls get a turn.
+    fn sync_to_player_turn(&mut self, player_index: usize) {
+        if self.check_game_over() {
+            return;
+        }
+        let team = player_index as u32;
+        let n = self.sim.balls.len();
+        if n == 0 {
+            return;
+        }
+
+        // Ensure last_ball_per_team has enough entries
+        while self.last_ball_per_team.len() <= player_index {
+            self.last_ball_per_team.push(None);
+        }
+
+        // Collect indices of all alive balls on this team
+        let team_balls: Vec<usize> = (0..n)
+            .filter(|&i| self.sim.balls[i].alive && self.sim.balls[i].team == team)
+            .collect();
+
+        if team_balls.is_empty() {
+            // Fallback: just find any alive ball
+            for i in 0..n {
+                if self.sim.balls[i].alive {
+                    self.current_ball = i;
+                    self.reset_turn_state();
+                    return;
+                }
+            }
+            return;
+        }
+
+        // Pick the next ball in rotation after the last one used
+        let last = self.last_ball_per_team[player_index];
+        let chosen = match last {
+            Some(prev) => {
+                // Find the team ball that comes after prev in the global ball list
+                let mut pick = team_balls[0]; // default to first
+                for &wi in &team_balls {
+                    if wi > prev {
+                        pick = wi;
+                        break;
+                    }
+                }
+                // If none found after prev, wrap around to first
+                if pick <= prev {
+                    pick = team_balls[0];
+                }
+                pick
+            }
+            None => team_balls[0],
+        };
+
+        self.last_ball_per_team[player_index] = Some(chosen);
+        self.current_ball = chosen;
+        self.turns_played += 1;
+        self.settle_turn_sand();
+        self.maybe_earthquake();
+        self.maybe_crate_rain();
+        self.shrink_safe_zone();
+        self.grow_grass();
+        #[cfg(target_arch = "wasm32")]
+        {
+            let ball_name = if chosen < self.sim.balls.len() { self.sim.balls[chosen].name.as_str() } else { "?" };
+            let debug_msg = format!("[TURN] sync_to_player_turn({}): chose ball {} '{}', team_balls={:?}, last={:?}\0",
+                player_index, chosen, ball_name, team_balls, last);
+            unsafe { console_log(debug_msg.as_ptr()); }
+        }
+        self.reset_turn_state();
+    }
+
+    /// Turn-start grass regrowth over exposed dirt, seeded from the map seed and turn so
+    /// every client grows the same cells. Logged like quakes for reconnects.
+    fn grow_grass(&mut self) {
+        if self.sim.terrain.grow_grass(grass_seed(self.map_seed, self.turns_played), GRASS_GROWTH_PER_TURN) > 0 {
+            self.grass_log.push(self.turns_played);
+            self.note_terrain_op(7);
+            self.terrain_dirty = true;
+        }
+    }
+
+    /// Scheduled earthquake: every `earthquake_every` turns the terrain shakes loose,
+    /// floating chunks collapse and grounded balls get a seeded random shove.
+    /// Everything is derived from the map seed and turn count so all clients agree.
+    fn maybe_earthquake(&mut self) {
+        let every = self.settings.earthquake_every;
+        if every == 0 || self.turns_played == 0 || !self.turns_played.is_multiple_of(every) {
+            return;
+        }
+        if self.sim.terrain.collapse_floating() {
+            self.terrain_dirty = true;
+        }
+        self.quake_log.push(self.turns_played);
+        self.note_terrain_op(3);
+
+        let mut s = lcg(self.map_seed ^ self.turns_played.wrapping_mul(0x9E37_79B9));
+        for ball in self.sim.balls.iter_mut() {
+            s = lcg(s);
+            if !ball.alive || !ball.on_ground {
+                continue;
+            }
+            let dir = if (s >> 31) == 0 { -1.0 } else { 1.0 };
+            let push = 60.0 + ((s >> 16) & 0x7FFF) as f32 / 32768.0 * 120.0;
+            s = lcg(s);
+            let hop = 120.0 + ((s >> 16) & 0x7FFF) as f32 / 32768.0 * 100.0;
+            ball.apply_knockback(dir * push, -hop);
+        }
+
+        self.cam.shake(1.2);
+        let event = format!("{{\"type\":\"earthquake\",\"turn\":{}}}", self.turns_played);
+        self.net.send_game_event(&event);
+    }
+
+    /// Crate-rain finale: from turn `crate_rain_after` on, every turn start drops supply
+    /// crates at seeded positions, one more every CRATE_RAIN_ESCALATE_EVERY turns up to
+    /// CRATE_RAIN_MAX_PER_TURN. Derived from the map seed and turn count so all clients agree.
+    fn maybe_crate_rain(&mut self) {
+        let after = self.settings.crate_rain_after;
+        if after == 0 || self.turns_played < after {
+            return;
+        }
+        let count = (1 + (self.turns_played - after) / CRATE_RAIN_ESCALATE_EVERY).min(CRATE_RAIN_MAX_PER_TURN);
+        let margin = 40.0;
+        let span = self.sim.terrain.width as f32 - margin * 2.0;
+        let mut s = lcg(self.map_seed ^ self.turns_played.wrapping_mul(0x85EB_CA6B));
+        for _ in 0..count {
+            s = lcg(s);
+            let x = margin + ((s >> 16) & 0x7FFF) as f32 / 32768.0 * span;
+            s = lcg(s);
+            // A third of the drops are mystery weapons when teams have locked weapons to
+            // unlock; the drop's seed decides what's inside once it's opened
+            let kind = if self.settings.random_loadout && (s >> 16).is_multiple_of(3) {
+                s = lcg(s);
+                CrateKind::Mystery(s)
+            } else {
+                CrateKind::Health
+            };
+            if self.crates.len() >= crates::MAX_CRATES {
+                self.crates.remove(0);
+            }
+            self.crates.push(SupplyCrate::new(x, kind));
+        }
+    }
+
+    /// The safe zone for `turn`: the whole map until `shrink_zone_after`, then closing in
+    /// from the land edges by ZONE_SHRINK_STEP per side each turn, down to ZONE_MIN_WIDTH.
+    fn zone_for_turn(&self, turn: u32) -> (f32, f32) {
+        let after = self.settings.shrink_zone_after;
+        if after == 0 || turn < after {
+            return (0.0, self.sim.terrain.width as f32);
+        }
+        let center = self.sim.terrain.width as f32 / 2.0;
+        let scale = self.sim.terrain.scale();
+        let half = (self.sim.terrain.playable_land_width() / 2.0 - (turn - after + 1) as f32 * ZONE_SHRINK_STEP * scale)
+            .max(ZONE_MIN_WIDTH * scale / 2.0);
+        (center - half, center + half)
+    }
+
+    /// Tighten the safe zone for the turn that's starting and tell the UI when it moved.
+    fn shrink_safe_zone(&mut self) {
+        let zone = self.zone_for_turn(self.turns_played);
+        if zone == self.safe_zone {
+            return;
+        }
+        self.safe_zone = zone;
+        let event = format!("{{\"type\":\"zone_shrink\",\"left\":{},\"right\":{},\"width\":{}}}",
+            zone.0 as i32, zone.1 as i32, (zone.1 - zone.0) as i32);
+        self.net.send_game_event(&event);
+    }
+
+    /// Shade the map outside the safe zone and mark where it closes to next turn.
+    fn draw_safe_zone(&self) {
+        if self.settings.shrink_zone_after == 0 {
+            return;
+        }
+        let h = self.sim.terrain.height as f32 + 100.0;
+        let (left, right) = self.safe_zone;
+        let shade = Color::new(0.8, 0.1, 0.1, 0.22);
+        draw_rectangle(-200.0, -200.0, left + 200.0, h + 200.0, shade);
+        draw_rectangle(right, -200.0, self.sim.terrain.width as f32 - right + 200.0, h + 200.0, shade);
+        let edge = Color::new(1.0, 0.25, 0.2, 0.9);
+        draw_line(left, -200.0, left, h, 2.0, edge);
+        draw_line(right, -200.0, right, h, 2.0, edge);
+        let (next_left, next_right) = self.zone_for_turn(self.turns_played + 1);
+        if (next_left, next_right) != self.safe_zone {
+            let warn = Color::new(1.0, 0.6, 0.2, 0.5);
+            draw_line(next_left, -200.0, next_left, h, 1.0, warn);
+            draw_line(next_right, -200.0, next_right, h, 1.0, warn);
+        }
+    }
+
+    /// Hand crate `i` to ball `idx`: heal it, or unlock the weapon for its team.
+    fn pick_up_crate(&mut self, i: usize, idx: usize) {
+        let kind = self.crates[i].kind;
+        self.crates[i].alive = false;
+        let (cx, cy) = (self.crates[i].x, self.crates[i].y);
+        let ball = &mut self.sim.balls[idx];
+        let team = ball.team as usize;
+        let what = match kind {
+            CrateKind::Health => {
+                ball.health = (ball.health + crates::CRATE_HEAL).min(ball.max_health);
+                "health".to_string()
+            }
+            CrateKind::Mystery(seed) => {
+                let w = crates::mystery_weapon(seed);
+                if let Some(loadout) = self.loadouts.get_mut(team) {
+                    if !loadout.contains(&w) {
+                        loadout.push(w);
+                    }
+                }
+                self.crate_reveal = Some((cx, cy, w, CRATE_REVEAL_TIME));
+                w.name().to_string()
+            }
+        };
+        let event = format!(
+            "{{\"type\":\"crate\",\"name\":\"{}\",\"contents\":\"{}\",\"mystery\":{}}}",
+            sanitize_event_name(&self.sim.balls[idx].name),
+            what,
+            matches!(kind, CrateKind::Mystery(_)),
+        );
+        self.net.send_game_event(&event);
+    }
+
+    /// Keep the finished shell's whole flight as its team's ghost for the next turn.
+    fn keep_ghost(&mut self) {
+        let Some(proj) = &mut self.proj else { return };
+        let team = proj.shooter_team as usize;
+        if self.ghost_shots.len() <= team {
+            self.ghost_shots.resize(team + 1, Vec::new());
+        }
+        self.ghost_shots[team] = std::mem::take(&mut proj.path);
+    }
+
+    /// Whether nothing the last shot set off is still flying, burning down or primed.
+    fn effects_done(&self) -> bool {
+        self.proj.is_none()
+            && self.shotgun_pellets.is_empty()
+            && self.uzi_bullets.is_empty()
+            && self.airstrike_droplets.is_empty()
+            && self.placed_explosives.is_empty()
+            && self.cluster_bomblets.is_empty()
+            && !self.barrels.iter().any(|b| b.primed())
+            && self.gas_fuses.is_empty()
+    }
+
+    /// Play every in-flight effect out to the end in fixed RESOLVE_STEP steps, so a forced
+    /// turn end (watchdog, safety timeout, force_advance) still applies their craters and
+    /// damage instead of dropping them on one client only. Balls are held still meanwhile.
+    /// Anything left after RESOLVE_MAX_STEPS is dropped.
+    fn resolve_pending_effects(&mut self) {
+        let dt = RESOLVE_STEP;
+        for _ in 0..RESOLVE_MAX_STEPS {
+            if self.effects_done() {
+                break;
+            }
+            let mut explosions = Vec::new();
+            if let Some(proj) = &mut self.proj {
+                let (explosion, bomblets) = proj.tick(&mut self.sim.terrain, &mut self.sim.balls, &self.decoys, self.sim.wind, dt);
+                explosions.extend(explosion);
+                self.cluster_bomblets.extend(bomblets);
+                if !proj.alive {
+                    self.proj = None;
+                }
+            }
+            for pellet in self.shotgun_pellets.iter_mut().filter(|p| p.alive) {
+                pellet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt);
+            }
+            self.shotgun_pellets.retain(|p| p.alive);
+            for bullet in self.uzi_bullets.iter_mut().filter(|b| b.alive) {
+                bullet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt);
+            }
+            self.uzi_bullets.retain(|b| b.alive);
+            for droplet in self.airstrike_droplets.iter_mut().filter(|d| d.alive) {
+                let (explosion, fire) = droplet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt);
+                explosions.extend(explosion);
+                self.fire_pools.extend(fire);
+            }
+            self.airstrike_droplets.retain(|d| d.alive);
+            for bomblet in self.cluster_bomblets.iter_mut().filter(|b| b.alive) {
+                explosions.extend(bomblet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt));
+            }
+            self.cluster_bomblets.retain(|b| b.alive);
+            for exp in &explosions {
+                self.spawn_explosion_particles(exp);
+            }
+            self.tick_placed_explosives(dt);
+            self.tick_barrels(dt);
+            self.tick_gas(dt);
+        }
+        self.proj = None;
+        self.shotgun_pellets.clear();
+        self.uzi_bullets.clear();
+        self.airstrike_droplets.clear();
+        self.cluster_bomblets.clear();
+        self.placed_explosives.clear();
+        self.gas_fuses.clear();
+        self.terrain_dirty = true;
+    }
+
+    /// Count down placed explosives and detonate any whose fuse ran out. A blast sets
+    /// off every other placed explosive inside its radius on the next tick, so mines and
+    /// dynamite chain. Triggers go in list order so every client chains the same way.
+    fn tick_placed_explosives(&mut self, dt: f32) {
+        if self.placed_explosives.is_empty() {
+            return;
+        }
+        let mut explosions = Vec::new();
+        for explosive in &mut self.placed_explosives {
+            if explosive.tick(dt) {
+                let exp = explosive.explode(&mut self.sim.terrain, &mut self.sim.balls);
+                explosions.push(exp);
+                self.terrain_dirty = true;
+            }
+        }
+        self.placed_explosives.retain(|e| e.alive);
+        special_weapons::chain_placed_explosives(&explosions, &mut self.placed_explosives);
+
+        for exp in &explosions {
+            self.spawn_explosion_particles(exp);
+        }
+    }
+
+    /// Let barrels settle and detonate every barrel whose HP ran out. Each blast wears
+    /// down the barrels around it, which then go off on the next tick, so barrels chain
+    /// like placed explosives. Detonations go in list order, at most MAX_CHAIN_TRIGGERS
+    /// a tick, so every client chains the same way.
+    fn tick_barrels(&mut self, dt: f32) {
+        let mut explosions = Vec::new();
+        for barrel in &mut self.barrels {
+            barrel.tick(&self.sim.terrain, dt);
+            if barrel.primed() && explosions.len() < special_weapons::MAX_CHAIN_TRIGGERS {
+                explosions.push(barrel.explode(&mut self.sim.terrain, &mut self.sim.balls));
+                self.terrain_dirty = true;
+            }
+        }
+        for exp in &explosions {
+            self.spawn_explosion_particles(exp);
+        }
+    }
+
+    /// Light any cave gas within reach of a blast or fire at (x, y). Gas already burning
+    /// within a burst radius isn't lit twice.
+    fn ignite_gas(&mut self, x: f32, y: f32, radius: f32) {
+        let spacing = GAS_BURST_RADIUS as i32;
+        let points = self.sim.terrain.gas_ignition_points(x as i32, y as i32, radius as i32, spacing, GAS_IGNITIONS_PER_BLAST);
+        for (gx, gy) in points {
+            if self.gas_fuses.len() >= MAX_GAS_FUSES {
+                break;
+            }
+            let lit = self.gas_fuses.iter().any(|&(fx, fy, _)| (fx - gx).pow(2) + (fy - gy).pow(2) < spacing * spacing);
+            if !lit {
+                self.gas_fuses.push((gx, gy, GAS_FUSE));
+            }
+        }
+    }
+
+    /// Count down lit gas and burst whatever is due, in the order it caught. A burst
+    /// carves out the gas around it and, through its explosion, lights the gas just past
+    /// its edge, so a pocket burns through as a ripple. Always ends: every burst eats at
+    /// least the cell it sits on. Bursts are logged craters, so reconnects replay them.
+    fn tick_gas(&mut self, dt: f32) {
+        if self.gas_fuses.is_empty() {
+            return;
+        }
+        let mut due = Vec::new();
+        for fuse in &mut self.gas_fuses {
+            fuse.2 -= dt;
+            if fuse.2 <= 0.0 {
+                due.push((fuse.0, fuse.1));
+            }
+        }
+        self.gas_fuses.retain(|f| f.2 > 0.0);
+        for (x, y) in due {
+            let exp = self.burst_gas(x as f32, y as f32);
+            self.spawn_explosion_particles(&exp);
+            self.terrain_dirty = true;
+        }
+    }
+
+    fn burst_gas(&mut self, x: f32, y: f32) -> projectile::Explosion {
+        self.sim.terrain.apply_damage(x as i32, y as i32, GAS_BURST_RADIUS as i32);
+        let blast_radius = GAS_BURST_RADIUS * 1.8;
+        for w in self.sim.balls.iter_mut().filter(|w| w.alive) {
+            let (dx, dy) = (w.x - x, w.y - y);
+            let dist = (dx * dx + dy * dy).sqrt();
+            if dist >= blast_radius {
+                continue;
+            }
+            let dist = dist.max(1.0);
+            let factor = 1.0 - dist / blast_radius;
+            let dmg = (GAS_DAMAGE as f32 * factor) as i32;
+            if dmg > 0 {
+                w.take_weapon_damage(dmg, None);
+            }
+            let knock = 220.0 * factor;
+            w.apply_knockback(dx / dist * knock, dy / dist * knock - 120.0 * factor);
+        }
+        projectile::Explosion { x, y, radius: GAS_BURST_RADIUS, is_water: false }
+    }
+
+    /// The weapon name over an opened mystery crate: it flicks through the arsenal for
+    /// CRATE_REVEAL_SPIN, then settles on the contents and floats up as it fades.
+    fn draw_crate_reveal(&self) {
+        let Some((x, y, weapon, left)) = self.crate_reveal else { return };
+        let t = CRATE_REVEAL_TIME - left;
+        let name = if t < CRATE_REVEAL_SPIN {
+            let all = Weapon::all();
+            all[(t * 20.0) as usize % all.len()].name()
+        } else {
+            weapon.name()
+        };
+        let alpha = (left / 0.5).min(1.0);
+        let rise = (t - CRATE_REVEAL_SPIN).max(0.0) * 16.0;
+        let tw = measure_text(name, None, 14, 1.0).width;
+        draw_text(name, x - tw / 2.0, y - 16.0 - rise, 14.0, Color::new(1.0, 0.85, 0.25, alpha));
+    }
+
+    /// Thin line along the first solid cell of each column, so the skyline a lob has to
+    /// clear is easy to read. Sampled every SKYLINE_STRIDE px across the visible range only.
+    fn draw_skyline(&self) {
+        let half_w = self.cam.visible_width() / 2.0;
+        let x1 = ((self.cam.x - half_w) as i32).max(0);
+        let x2 = ((self.cam.x + half_w) as i32).min(self.sim.terrain.width as i32 - 1);
+        let color = Color::new(1.0, 1.0, 1.0, 0.55);
+        let mut prev: Option<(f32, f32)> = None;
+        for x in (x1..=x2).step_by(SKYLINE_STRIDE as usize) {
+            let Some(y) = self.sim.terrain.find_surface_y(x) else {
+                prev = None;
+                continue;
+            };
+            let point = (x as f32, y as f32);
+            if let Some((px, py)) = prev {
+                draw_line(px, py, point.0, point.1, 1.0, color);
+            }
+            prev = Some(point);
         }
-        self.reset_turn_state();
     }
 
-    /// Sync to a player's turn from the worker. Finds the next alive ball belonging to
-    /// the given team (player_index) using round-robin so all balls get a turn.
-    fn sync_to_player_turn(&mut self, player_index: usize) {
-        if self.check_game_over() {
-            return;
+    /// Mark every op in the terrain logs where it happened: explosions as translucent
+    /// discs that build up into a heat map where blasts overlap, drill tunnels, walls and
+    /// foxholes as outlines. Ops folded into the last snapshot are no longer logged, so
+    /// only what a reconnect would replay on top of it shows.
+    fn draw_terrain_ops(&self) {
+        for &(cx, cy, r) in &self.sim.terrain.damage_log {
+            let (x, y, r) = (cx as f32, cy as f32, r as f32);
+            draw_circle(x, y, r, Color::new(1.0, 0.2, 0.1, 0.18));
+            draw_circle_lines(x, y, r, 1.0, Color::new(1.0, 0.3, 0.1, 0.8));
         }
-        let team = player_index as u32;
-        let n = self.balls.len();
-        if n == 0 {
-            return;
+        for &(bx, by, amrad) in &self.drill_log {
+            let (x, y, a) = (bx as f32, by as f32, amrad as f32 / 1000.0);
+            // Same extent as apply_drill_at's tunnel
+            draw_line(x - a.cos() * 30.0, y - a.sin() * 30.0, x + a.cos() * 260.0, y + a.sin() * 260.0,
+                2.0, Color::new(1.0, 0.6, 0.1, 0.8));
+            draw_circle(x, y, 3.0, Color::new(1.0, 0.6, 0.1, 0.9));
         }
-
-        // Ensure last_ball_per_team has enough entries
-        while self.last_ball_per_team.len() <= player_index {
-            self.last_ball_per_team.push(None);
+        for &(ax, ay, amrad) in &self.wall_log {
+            let (x, y, a) = (ax as f32, ay as f32, amrad as f32 / 1000.0);
+            draw_line(x - a.cos() * 35.0, y - a.sin() * 35.0, x + a.cos() * 35.0, y + a.sin() * 35.0,
+                2.0, Color::new(0.3, 0.6, 1.0, 0.9));
         }
+        for &(bx, by) in &self.foxhole_log {
+            let foot = (by + self.settings.ball_size.radius() as i32) as f32;
+            draw_rectangle_lines(bx as f32 - FOXHOLE_HALF_W as f32, foot - 4.0,
+                FOXHOLE_HALF_W as f32 * 2.0, FOXHOLE_DEPTH as f32 + 4.0, 1.5, Color::new(1.0, 0.9, 0.2, 0.9));
+        }
+    }
 
-        // Collect indices of all alive balls on this team
-        let team_balls: Vec<usize> = (0..n)
-            .filter(|&i| self.balls[i].alive && self.balls[i].team == team)
-            .collect();
+    /// Whether the next turn start triggers a scheduled earthquake.
+    fn quake_next_turn(&self) -> bool {
+        let every = self.settings.earthquake_every;
+        every > 0 && (self.turns_played + 1).is_multiple_of(every)
+    }
 
-        if team_balls.is_empty() {
-            // Fallback: just find any alive ball
-            for i in 0..n {
-                if self.balls[i].alive {
-                    self.current_ball = i;
-                    self.reset_turn_state();
-                    return;
-                }
-            }
+    /// Outline terrain that has lost its support for a moment after it changes, and for
+    /// the whole turn before an earthquake drops it.
+    fn draw_unstable_terrain(&self) {
+        if self.unstable_outline.is_empty() || (self.unstable_flash <= 0.0 && !self.quake_next_turn()) {
             return;
         }
+        let pulse = (get_time() as f32 * 5.0).sin() * 0.25 + 0.65;
+        let color = Color::new(1.0, 0.35, 0.1, pulse);
+        for &(x, y) in &self.unstable_outline {
+            draw_rectangle(x as f32, y as f32, 1.0, 1.0, color);
+        }
+    }
 
-        // Pick the next ball in rotation after the last one used
-        let last = self.last_ball_per_team[player_index];
-        let chosen = match last {
-            Some(prev) => {
-                // Find the team ball that comes after prev in the global ball list
-                let mut pick = team_balls[0]; // default to first
-                for &wi in &team_balls {
-                    if wi > prev {
-                        pick = wi;
+    /// Predict the next few turns as `(team, ball_index)` pairs, starting with the
+    /// turn after the current one. Mirrors the worker's player round-robin plus
+    /// `last_ball_per_team` rotation when networked, and `advance_turn` offline.
+    fn turn_preview(&self) -> Vec<(u32, usize)> {
+        let n = self.sim.balls.len();
+        let mut preview = Vec::with_capacity(TURN_PREVIEW_LEN);
+        if n == 0 || self.phase == Phase::GameOver {
+            return preview;
+        }
+
+        if !self.net.connected {
+            let mut idx = self.current_ball;
+            for _ in 0..n {
+                idx = (idx + 1) % n;
+                if self.sim.balls[idx].alive {
+                    preview.push((self.sim.balls[idx].team, idx));
+                    if preview.len() >= TURN_PREVIEW_LEN {
                         break;
                     }
                 }
-                // If none found after prev, wrap around to first
-                if pick <= prev {
-                    pick = team_balls[0];
-                }
-                pick
             }
-            None => team_balls[0],
-        };
+            return preview;
+        }
 
-        self.last_ball_per_team[player_index] = Some(chosen);
-        self.current_ball = chosen;
-        #[cfg(target_arch = "wasm32")]
-        {
-            let ball_name = if chosen < self.balls.len() { self.balls[chosen].name.as_str() } else { "?" };
-            let debug_msg = format!("[TURN] sync_to_player_turn({}): chose ball {} '{}', team_balls={:?}, last={:?}\0",
-                player_index, chosen, ball_name, team_balls, last);
-            unsafe { console_log(debug_msg.as_ptr()); }
+        // Networked: teams take turns in player order; each team rotates its own balls.
+        let num_teams = self.num_teams.max(1);
+        let mut last: Vec<Option<usize>> = (0..num_teams)
+            .map(|t| self.last_ball_per_team.get(t).copied().flatten())
+            .collect();
+        let mut team = self.current_turn_index % num_teams;
+        for _ in 0..num_teams * TURN_PREVIEW_LEN {
+            team = (team + 1) % num_teams;
+            let team_balls: Vec<usize> = (0..n)
+                .filter(|&i| self.sim.balls[i].alive && self.sim.balls[i].team as usize == team)
+                .collect();
+            if team_balls.is_empty() {
+                continue;
+            }
+            let pick = match last[team] {
+                Some(prev) => team_balls.iter().copied().find(|&i| i > prev).unwrap_or(team_balls[0]),
+                None => team_balls[0],
+            };
+            last[team] = Some(pick);
+            preview.push((team as u32, pick));
+            if preview.len() >= TURN_PREVIEW_LEN {
+                break;
+            }
         }
-        self.reset_turn_state();
+        preview
     }
 
     fn reset_turn_state(&mut self) {
         // Emit turn_start event so the UI can show whose turn it is
-        if self.current_ball < self.balls.len() {
-            let ball = &self.balls[self.current_ball];
+        if self.current_ball < self.sim.balls.len() {
+            let ball = &self.sim.balls[self.current_ball];
             let player_name = self.net.player_names
                 .get(ball.team as usize)
                 .cloned()
                 .filter(|n| !n.is_empty())
                 .unwrap_or_else(|| ball.name.clone());
-            let event = format!("{{\"type\":\"turn_start\",\"name\":\"{}\",\"ball\":\"{}\"}}",
+            let event = format!("{{\"type\":\"turn_start\",\"name\":\"{}\",\"ball\":\"{}\",\"teams\":{}}}",
                 sanitize_event_name(&player_name),
-                sanitize_event_name(&ball.name));
+                sanitize_event_name(&ball.name),
+                state::team_summaries_json(&self.sim.balls));
             self.net.send_game_event(&event);
         }
         self.phase = Phase::Aiming;
         self.turn_timer = TURN_TIME;
+        self.kills_this_turn = 0;
         self.has_fired = false;
         self.firing_by_key = false;
         self.aim_locked = false;
         self.retreat_timer = 0.0;
         self.charging = false;
         self.charge_power = 0.0;
+        self.charge_target = None;
         self.teleport_mode = false;
         self.baseball_bat_mode = false;
         self.build_wall_mode = false;
         self.build_wall_anchor = None;
         self.airstrike_mode = None;
+        self.pending_target = None;
         self.bot_think_timer = 3.0;
         self.stuck_phase_timer = 0.0;
+        self.update_last_stands();
+        self.settle_all_sand();
+        self.compact_terrain_log();
         
-        // Reset movement budget for the current ball
-        if self.current_ball < self.balls.len() {
-            self.balls[self.current_ball].reset_movement_budget();
-            self.aim_angle = if self.balls[self.current_ball].facing > 0.0 {
-                -0.3
+        // Fall back to the Bazooka when the new team doesn't have the held weapon
+        if !self.current_loadout().contains(&self.selected_weapon) {
+            self.selected_weapon = Weapon::Bazooka;
+        }
+
+        // Reset (or partially regenerate) movement budget for the current ball
+        if self.current_ball < self.sim.balls.len() {
+            if self.settings.movement_regen >= 1.0 {
+                self.sim.balls[self.current_ball].reset_movement_budget();
             } else {
-                std::f32::consts::PI + 0.3
-            };
+                self.sim.balls[self.current_ball].regen_movement_budget(self.settings.movement_regen);
+            }
+            // Start roughly aimed at the nearest enemy; fall back to slightly up the way
+            // the ball faces when none are left
+            let angle = bot::aim_at_nearest_enemy(&self.sim.balls, self.current_ball).unwrap_or(
+                if self.sim.balls[self.current_ball].facing > 0.0 {
+                    -0.3
+                } else {
+                    std::f32::consts::PI + 0.3
+                },
+            );
+            self.aim_angle = self.selected_weapon.clamp_aim(angle);
+            self.turn_start_mouse = Some(mouse_position());
         }
+        self.sim.terrain.modified_cells = 0;
         
         self.rng_state = lcg(self.rng_state);
-        self.wind = ((self.rng_state >> 16) as f32 / 65536.0 - 0.5) * 6.0;
+        self.sim.wind = ((self.rng_state >> 16) as f32 / 65536.0 - 0.5) * 6.0;
 
         // Snap camera back to the new active ball after every turn change.
         // Clear free-look so auto_follow re-activates immediately, then start a
@@ -1770,18 +3660,29 @@ impl Game {
         self.cam_free_timer = 0.0;
         self.cam_return_timer = 2.0;
         self.cam_target_zoom = DEFAULT_ZOOM;
+        self.final_cam_active = false;
     }
 
-    fn check_game_over(&mut self) -> bool {
-        let mut alive_teams: Vec<u32> = Vec::new();
-        for w in &self.balls {
-            if w.alive && !alive_teams.contains(&w.team) {
-                alive_teams.push(w.team);
+    /// Stamp the current tick on every team that has just lost its last ball.
+    fn record_eliminations(&mut self) {
+        for team in 0..self.team_out_ticks.len() {
+            if self.team_out_ticks[team].is_none() && !self.sim.balls.iter().any(|w| w.alive && w.team as usize == team) {
+                self.team_out_ticks[team] = Some(self.sim.tick);
             }
         }
-        if alive_teams.len() <= 1 {
+    }
+
+    fn check_game_over(&mut self) -> bool {
+        self.record_eliminations();
+        if let Some(outcome) = state::match_outcome(&self.team_out_ticks) {
             self.phase = Phase::GameOver;
-            self.winning_team = alive_teams.first().copied();
+            if self.final_cam_focus.is_some() {
+                self.final_cam_hold = FINAL_CAM_HOLD;
+            }
+            self.winning_team = match outcome {
+                MatchOutcome::Winner(team) => Some(team),
+                MatchOutcome::Draw => None,
+            };
             // Emit game_over event for UI toast
             let winner_name = self.winning_team
                 .and_then(|t| self.net.player_names.get(t as usize).cloned())
@@ -1789,18 +3690,92 @@ impl Game {
                 .or_else(|| {
                     // Fall back to ball name
                     self.winning_team.and_then(|t| {
-                        self.balls.iter().find(|b| b.team == t).map(|b| b.name.clone())
+                        self.sim.balls.iter().find(|b| b.team == t).map(|b| b.name.clone())
                     })
                 })
                 .unwrap_or_else(|| String::from("Someone"));
-            let event = format!("{{\"type\":\"game_over\",\"winner\":\"{}\"}}",
-                sanitize_event_name(&winner_name));
+            let event = format!("{{\"type\":\"game_over\",\"winner\":\"{}\",\"draw\":{},\"teams\":{}}}",
+                sanitize_event_name(&winner_name),
+                outcome == MatchOutcome::Draw,
+                state::team_summaries_json(&self.sim.balls));
             self.net.send_game_event(&event);
             return true;
         }
         false
     }
 
+    /// Take `team` out of the match. Its balls are removed rather than killed, so the
+    /// forfeit gets its own toast instead of death toasts, hit-stop or a kill credit.
+    fn forfeit_team(&mut self, team: u32) {
+        if self.phase == Phase::GameOver || self.forfeited_teams.contains(&team) {
+            return;
+        }
+        self.forfeited_teams.push(team);
+        for w in self.sim.balls.iter_mut().filter(|w| w.team == team) {
+            w.alive = false;
+            w.health = 0;
+        }
+        if let Some(out) = self.team_out_ticks.get_mut(team as usize) {
+            *out = Some(0);
+        }
+        let name = self.team_display_name(team);
+        let event = format!("{{\"type\":\"forfeit\",\"name\":\"{}\"}}", sanitize_event_name(&name));
+        self.net.send_game_event(&event);
+
+        // A shot still in the air finishes first; the turn then ends as usual
+        if !matches!(self.phase, Phase::Aiming | Phase::Charging | Phase::TurnEnd) || self.check_game_over() {
+            return;
+        }
+        if self.sim.balls.get(self.current_ball).is_some_and(|w| w.team == team) && self.phase != Phase::TurnEnd {
+            // Connected clients then wait for the server's turn_advanced; the server has
+            // already moved on, so no end_turn is sent
+            self.phase = Phase::TurnEnd;
+            self.turn_end_timer = TURN_END_DELAY;
+            self.charging = false;
+            self.charge_power = 0.0;
+        }
+    }
+
+    /// Bring a brand-new team into the running match for a late joiner: spawn its balls
+    /// at safe spots and grow every per-team table to fit. Spots come from the map seed
+    /// and team number alone (not where anyone stands), so every client agrees.
+    fn add_team(&mut self, name: &str, is_bot: bool) {
+        let team = self.num_teams;
+        self.num_teams += 1;
+        let radius = self.settings.ball_size.radius();
+        let strip = self.sim.terrain.playable_land_width() / BALLS_PER_TEAM as f32;
+        let mut s = lcg(self.map_seed ^ (team as u32).wrapping_mul(0x9E37_79B9));
+        for wi in 0..BALLS_PER_TEAM {
+            s = lcg(s);
+            // One ball somewhere in each third of the map
+            let x = self.sim.terrain.land_start_x() + strip * wi as f32 + 20.0 + (s >> 16) as f32 / 65536.0 * (strip - 40.0);
+            let (spawn_x, y) = safe_spawn(&self.sim.terrain, x, radius);
+            let mut ball = Ball::new(spawn_x, y, team as u32, default_ball_name(team, wi));
+            ball.radius = radius;
+            ball.knockback_scale = self.settings.knockback_scale;
+            ball.weapon_damage_scale = self.settings.weapon_damage;
+            self.sim.balls.push(ball);
+        }
+
+        let balls = self.sim.balls.len();
+        self.ball_lerp_targets.resize(balls, None);
+        self.ball_event_cooldown.resize(balls, 0.0);
+        let teams = self.num_teams;
+        self.last_ball_per_team.resize(teams, None);
+        self.last_shot.resize(teams, None);
+        self.team_out_ticks.resize(teams, None);
+        self.kill_streaks.resize(teams, 0);
+        self.loadouts.push(starting_loadout(self.map_seed, team as u32, self.settings));
+        if self.net.player_names.len() <= team {
+            self.net.player_names.resize(team, String::new());
+            self.net.player_names.push(name.to_string());
+        }
+        if self.net.player_is_bot.len() <= team {
+            self.net.player_is_bot.resize(team, false);
+            self.net.player_is_bot.push(is_bot);
+        }
+    }
+
     fn apply_network_messages(&mut self) {
         for msg in self.net.poll_messages() {
             if msg.contains("\"type\":\"init\"") || msg.contains("\"type\": \"init\"") {
@@ -1843,9 +3818,16 @@ impl Game {
                 // Always flag reconnect so state/game_resync handlers force-sync
                 // unconditionally, even if turn index happens to already be 0.
                 self.just_reconnected = true;
-                if seed_u32 != self.rng_state || num_players != self.num_teams {
-                        // Regenerate terrain with proper seed and team count
-                        *self = Game::new_with_teams(seed_u32, num_players);
+                let settings = parse_match_settings(&msg);
+                let custom_map = parse_custom_map(&msg);
+                // The last `lateJoins` players joined mid-match; their teams are added on
+                // top of the starting ones, the way running clients added them
+                let late_joins = parse_json_number(&msg, "lateJoins").map_or(0, |n| n.max(0.0) as usize).min(num_players);
+                if seed_u32 != self.rng_state || num_players != self.num_teams || settings != self.settings
+                    || custom_map != self.custom_map
+                {
+                        // Regenerate terrain with proper seed, team count and match settings
+                        *self = Game::new_with_map(seed_u32, num_players - late_joins, settings, custom_map);
                         // Flag that we just reconnected — next `state` or `game_resync`
                         // must unconditionally sync the current turn/ball regardless of index.
                         self.just_reconnected = true;
@@ -1863,8 +3845,33 @@ impl Game {
                         if let Some(bots_str) = parse_json_string(&msg, "playerBots") {
                             self.net.player_is_bot = bots_str.split(',').map(|s| s == "1").collect();
                         }
+                        while self.num_teams < num_players {
+                            let team = self.num_teams;
+                            let name = self.net.player_names.get(team).cloned().unwrap_or_default();
+                            let is_bot = self.net.player_is_bot.get(team).copied().unwrap_or(false);
+                            self.add_team(&name, is_bot);
+                        }
                     }
                 }
+                if let Some(names_str) = parse_json_string(&msg, "ballNames") {
+                    self.assign_ball_names(&parse_ball_names(names_str));
+                }
+                // Campaign progression: comma-separated names of the weapons unlocked so far
+                if let Some(list) = parse_json_string(&msg, "unlockedWeapons") {
+                    let weapons: Vec<Weapon> = list.split(',').filter_map(|n| Weapon::from_name(n.trim())).collect();
+                    self.set_unlocked_weapons(&weapons);
+                }
+                // A viewing preference rather than a match setting, so clients may differ
+                if let Some(d) = parse_json_number(&msg, "cameraDeadZone") {
+                    self.cam.dead_zone = (d as f32).clamp(0.0, 100.0);
+                }
+                let low_spec = msg.contains("\"lowSpec\":true") || msg.contains("\\\"lowSpec\\\":true");
+                if low_spec != self.low_spec {
+                    self.low_spec = low_spec;
+                    self.terrain_dirty = true; // re-upload the texture at the new resolution
+                }
+                self.confirm_targets = msg.contains("\"confirmTargets\":true") || msg.contains("\\\"confirmTargets\\\":true");
+                self.final_cam = !(msg.contains("\"finalKillCam\":false") || msg.contains("\\\"finalKillCam\\\":false"));
                 continue;
             }
             if msg.contains("\"type\":\"force_advance\"") || msg.contains("\"type\": \"force_advance\"") {
@@ -1873,7 +3880,9 @@ impl Game {
                 // so the game visually unsticks even before those messages arrive.
                 match self.phase {
                     Phase::Aiming | Phase::Charging | Phase::TurnEnd | Phase::Retreat => {
-                        // Will be overwritten by the coming turn_advanced, but unstick now
+                        // Will be overwritten by the coming turn_advanced, but unstick now.
+                        // A retreat can still have a fuse burning; let it go off first.
+                        self.resolve_pending_effects();
                         self.phase = Phase::TurnEnd;
                         self.turn_end_timer = 0.1;
                     }
@@ -1907,6 +3916,42 @@ impl Game {
                 }
                 continue;
             }
+            if msg.contains("\"type\":\"join\"") || msg.contains("\"type\": \"join\"") {
+                // A player dropping in mid-match as a new team. Teams are numbered in join
+                // order, so anything but the next number is a repeat (or came with init).
+                if let Some(player) = parse_json_number(&msg, "player") {
+                    let name = parse_json_string(&msg, "name").unwrap_or("").to_string();
+                    let is_bot = msg.contains("\"bot\":true") || msg.contains("\\\"bot\\\":true");
+                    if player as usize == self.num_teams && self.phase != Phase::GameOver {
+                        self.add_team(&name, is_bot);
+                        // The new roster, for the front-end's scoreboard and a join toast
+                        let team = player as u32;
+                        let event = format!("{{\"type\":\"team_joined\",\"name\":\"{}\",\"team\":{},\"teams\":{}}}",
+                            sanitize_event_name(&self.team_display_name(team)), team,
+                            state::team_summaries_json(&self.sim.balls));
+                        self.net.send_game_event(&event);
+                    }
+                }
+                continue;
+            }
+            if msg.contains("\"type\":\"forfeit\"") || msg.contains("\"type\": \"forfeit\"") {
+                if let Some(player) = parse_json_number(&msg, "player") {
+                    self.forfeit_team(player as u32);
+                }
+                continue;
+            }
+            if msg.contains("\"type\":\"ping\"") || msg.contains("\"type\": \"ping\"") {
+                if let (Some(x), Some(y)) = (parse_json_number(&msg, "x"), parse_json_number(&msg, "y")) {
+                    let team = parse_json_number(&msg, "team").filter(|t| *t >= 0.0).map(|t| t as u32);
+                    self.add_ping(x as f32, y as f32, team);
+                }
+                continue;
+            }
+            if msg.contains("\"type\":\"ready\"") || msg.contains("\"type\": \"ready\"") {
+                // Server's list of players who want a rematch
+                self.net.ready_players = parse_ready_message(&msg);
+                continue;
+            }
             if msg.contains("\"type\":\"state\"") || msg.contains("\"type\": \"state\"") {
                 // Handle state message to sync with server's current turn
                 if let Some(current_turn_index) = parse_state_turn_index(&msg) {
@@ -1956,10 +4001,18 @@ impl Game {
                 for t in &mut self.ball_lerp_targets {
                     *t = None;
                 }
+                for ball in &mut self.sim.balls {
+                    ball.net_samples = [None; 2];
+                }
                 // Determine the authoritative turn to sync to
                 let turn_idx = parse_json_number(&msg, "currentTurnIndex")
                     .map(|v| v as usize)
                     .unwrap_or(self.current_turn_index);
+                // Turn count of the turn in progress; sync_to_player_turn counts it as started.
+                // Quakes, crate rain, the safe zone and grass growth are all keyed on it.
+                if let Some(turns) = parse_json_number(&msg, "turnsPlayed") {
+                    self.turns_played = (turns as u32).saturating_sub(1);
+                }
                 // sync_to_player_turn resets phase to Aiming and timer to TURN_TIME.
                 // We will override both immediately after.
                 self.current_turn_index = turn_idx;
@@ -2011,147 +4064,18 @@ impl Game {
                         self.find_ball_for_player(player_index)
                     };
                     if let Some(ball_idx) = ball_idx_opt {
-                        // Parse and apply different input types
-                        if let Some((angle_rad, power, weapon)) = parse_fire_input(&input_str) {
-                            self.do_fire(ball_idx, angle_rad, power, weapon);
-                            self.has_fired = true;
-                            // Reset budget on the firing ball so remote players also get
-                            // a fresh dodge window once their shot is in the air.
-                            if self.phase == Phase::ProjectileFlying && ball_idx < self.balls.len() {
-                                self.balls[ball_idx].reset_movement_budget();
-                            }
-                        } else if let Some(dir) = parse_walk_input(&input_str) {
-                            if ball_idx < self.balls.len() {
-                                physics::walk(&mut self.balls[ball_idx], &self.terrain, dir);
-                            }
-                        } else if input_str.contains("\"Jump\"") || input_str.contains("Jump") {
-                            if ball_idx < self.balls.len() {
-                                physics::jump(&mut self.balls[ball_idx]);
-                                self.balls[ball_idx].movement_used += 20.0;
-                            }
-                        } else if input_str.contains("\"Backflip\"") || input_str.contains("Backflip") {
-                            if ball_idx < self.balls.len() {
-                                physics::backflip(&mut self.balls[ball_idx]);
-                                self.balls[ball_idx].movement_used += 30.0;
-                            }
-                        } else if input_str.contains("AirstrikeTarget") {
-                            // Spawn airstrike/napalm droplets for the remote player's click
-                            if let Some(target_x) = parse_json_number(&input_str, "x").map(|v| v as f32) {
-                                let weapon_name = parse_json_string(&input_str, "weapon").unwrap_or("Airstrike");
-                                self.airstrike_droplets.clear();
-                                if weapon_name.contains("Napalm") {
-                                    let spacing = 60.0;
-                                    for i in 0..7 {
-                                        let x = target_x + (i as f32 - 3.0) * spacing;
-                                        self.airstrike_droplets.push(AirstrikeDroplet {
-                                            x, y: -50.0, vy: 0.0, alive: true,
-                                            weapon_type: AirstrikeType::Napalm,
-                                        });
-                                    }
-                                } else {
-                                    let spacing = 80.0;
-                                    for i in 0..5 {
-                                        let x = target_x + (i as f32 - 2.0) * spacing;
-                                        self.airstrike_droplets.push(AirstrikeDroplet {
-                                            x, y: -50.0, vy: 0.0, alive: true,
-                                            weapon_type: AirstrikeType::Explosive,
-                                        });
-                                    }
-                                }
-                                self.has_fired = true;
-                                self.phase = Phase::ProjectileFlying;
-                                if ball_idx < self.balls.len() {
-                                    self.balls[ball_idx].reset_movement_budget();
-                                }
-                            }
-                        } else if input_str.contains("BuildWallPlace") {
-                            // Stamp the wall onto terrain for the remote player's placement
-                            let ax = parse_json_number(&input_str, "ax").map(|v| v as f32);
-                            let ay = parse_json_number(&input_str, "ay").map(|v| v as f32);
-                            let angle = parse_json_number(&input_str, "angle").map(|v| v as f32);
-                            if let (Some(ax), Some(ay), Some(angle)) = (ax, ay, angle) {
-                                let cos_a = angle.cos();
-                                let sin_a = angle.sin();
-                                let half_len = 35i32;
-                                let half_thick = 4i32;
-                                for i in -half_len..=half_len {
-                                    for j in -half_thick..=half_thick {
-                                        let wx = (ax + i as f32 * cos_a - j as f32 * sin_a).round() as i32;
-                                        let wy = (ay + i as f32 * sin_a + j as f32 * cos_a).round() as i32;
-                                        if wx >= 0 && wx < self.terrain.width as i32
-                                            && wy >= 0 && wy < self.terrain.height as i32 {
-                                            self.terrain.set(wx, wy, terrain::WOOD);
-                                        }
-                                    }
-                                }
-                                self.terrain_dirty = true;
-                                self.has_fired = true;
-                                self.phase = Phase::Settling;
-                                self.settle_timer = 0.0;
-                                // Record for reconnect sync
-                                self.wall_log.push((ax as i32, ay as i32, (angle * 1000.0) as i32));
-                            }
-                        } else if input_str.contains("TeleportTo") {
-                            // Move the remote player's ball to target position
-                            let tx = parse_json_number(&input_str, "x").map(|v| v as f32);
-                            let ty = parse_json_number(&input_str, "y").map(|v| v as f32);
-                            if let (Some(tx), Some(ty)) = (tx, ty) {
-                                if ball_idx < self.balls.len() && self.balls[ball_idx].alive {
-                                    self.balls[ball_idx].x = tx.clamp(0.0, self.terrain.width as f32);
-                                    self.balls[ball_idx].y = ty.clamp(0.0, self.terrain.height as f32);
-                                    self.balls[ball_idx].vx = 0.0;
-                                    self.balls[ball_idx].vy = 0.0;
-                                }
-                                self.has_fired = true;
-                                self.phase = Phase::Settling;
-                                self.settle_timer = 0.0;
-                            }
-                        } else if input_str.contains("BatSwing") {
-                            // Apply baseball bat knockback for the remote player's swing
-                            if let Some(angle) = parse_json_number(&input_str, "angle").map(|v| v as f32) {
-                                if ball_idx < self.balls.len() && self.balls[ball_idx].alive {
-                                    let ball_x = self.balls[ball_idx].x;
-                                    let ball_y = self.balls[ball_idx].y;
-                                    let bat_range = 100.0;
-                                    let knock_x = angle.cos() * 850.0;
-                                    let knock_y = angle.sin() * 850.0 - 300.0;
-                                    for i in 0..self.balls.len() {
-                                        if i == ball_idx || !self.balls[i].alive { continue; }
-                                        let dx = self.balls[i].x - ball_x;
-                                        let dy = self.balls[i].y - ball_y;
-                                        if (dx*dx + dy*dy).sqrt() < bat_range {
-                                            self.balls[i].apply_knockback(knock_x, knock_y);
-                                            self.balls[i].health = self.balls[i].health.saturating_sub(20);
-                                            if self.balls[i].health == 0 {
-                                                self.balls[i].alive = false;
-                                            }
-                                        }
-                                    }
-                                }
-                                self.has_fired = true;
-                                self.phase = Phase::Settling;
-                                self.settle_timer = 0.0;
-                            }
-                        } else if input_str.contains("DrillFire") {
-                            // Carve drill tunnel using the exact origin the active player sent
-                            let bx = parse_json_number(&input_str, "bx").map(|v| v as f32);
-                            let by = parse_json_number(&input_str, "by").map(|v| v as f32);
-                            let angle = parse_json_number(&input_str, "angle").map(|v| v as f32);
-                            if let (Some(bx), Some(by), Some(angle)) = (bx, by, angle) {
-                                self.apply_drill_at(bx, by, angle);
-                                // Track for reconnect sync (dedup)
-                                let bxi = bx as i32; let byi = by as i32;
-                                let amrad = (angle * 1000.0) as i32;
-                                if !self.drill_log.iter().any(|&(x,y,a)| x==bxi && y==byi && a==amrad) {
-                                    self.drill_log.push((bxi, byi, amrad));
-                                }
-                                self.has_fired = true;
-                                self.phase = Phase::Settling;
-                                self.settle_timer = 0.0;
-                            }
+                        if let Some(input) = NetInput::from_json(&input_str) {
+                            self.apply_input(ball_idx, input);
                         }
                     }
-                }  
+                }
+                continue;
+            }
+            if msg.contains("\"type\":\"unlock_weapon\"") || msg.contains("\"type\": \"unlock_weapon\"") {
+                // Campaign reward handed out by the front-end mid-session
+                if let Some(w) = parse_json_string(&msg, "weapon").and_then(Weapon::from_name) {
+                    self.unlock_weapon(w);
+                }
                 continue;
             }
             if msg.contains("\"type\":\"ball_state\"") || msg.contains("\"type\": \"ball_state\"") {
@@ -2159,9 +4083,35 @@ impl Game {
                 for t in &mut self.ball_lerp_targets {
                     *t = None;
                 }
+                for ball in &mut self.sim.balls {
+                    ball.net_samples = [None; 2];
+                }
                 self.apply_ball_state(&msg);
                 continue;
             }
+            if msg.contains("\"type\":\"terrain_hashes\"") || msg.contains("\"type\": \"terrain_hashes\"") {
+                // End-of-turn block hashes from the active player; checked in update()
+                // once our own effects are done
+                if let (Some(turn), Some(hex)) = (parse_json_number(&msg, "turn"), parse_json_string(&msg, "hashes")) {
+                    let hashes: Option<Vec<u64>> = hex
+                        .as_bytes()
+                        .chunks(16)
+                        .map(|c| u64::from_str_radix(std::str::from_utf8(c).ok()?, 16).ok())
+                        .collect();
+                    if let Some(hashes) = hashes {
+                        self.pending_terrain_hashes = Some((turn as u32, hashes));
+                    }
+                }
+                continue;
+            }
+            if msg.contains("\"type\":\"terrain_heal_request\"") || msg.contains("\"type\": \"terrain_heal_request\"") {
+                self.answer_terrain_heal_request(&msg);
+                continue;
+            }
+            if msg.contains("\"type\":\"terrain_block_patch\"") || msg.contains("\"type\": \"terrain_block_patch\"") {
+                self.apply_terrain_block_patch(&msg);
+                continue;
+            }
             if msg.contains("\"type\":\"terrain_sync\"") || msg.contains("\"type\": \"terrain_sync\"") {
                 // Replay terrain damage events received from server on reconnect
                 self.apply_terrain_sync(&msg);
@@ -2176,6 +4126,25 @@ impl Game {
                         == Some(bi);
                     if !is_own_ball && bi < self.ball_lerp_targets.len() {
                         self.ball_lerp_targets[bi] = Some((x, y, vx, vy));
+                        if let Some(ball) = self.sim.balls.get_mut(bi) {
+                            ball.push_net_sample(get_time() as f32, x, y);
+                        }
+                    }
+                }
+                continue;
+            }
+            if msg.contains("\"type\":\"weapon_select\"") || msg.contains("\"type\": \"weapon_select\"") {
+                // Show the weapon a remote player has armed on their ball
+                if let Some((player_index, weapon)) = parse_weapon_select_message(&msg) {
+                    if self.net.my_player_index == Some(player_index) {
+                        continue;
+                    }
+                    if let Some(ball_idx) = self.find_ball_for_player(player_index) {
+                        if ball_idx == self.current_ball {
+                            self.selected_weapon = weapon;
+                        }
+                        let angle = self.team_aim(player_index).map_or(self.aim_angle, |(_, a, _)| a);
+                        self.set_team_aim(player_index, (self.current_ball, weapon.clamp_aim(angle), weapon));
                     }
                 }
                 continue;
@@ -2191,8 +4160,10 @@ impl Game {
                     // Find the ball for this player and update their local aim
                     if let Some(ball_idx) = self.find_ball_for_player(player_index) {
                         if ball_idx == self.current_ball {
-                            self.aim_angle = aim_angle;
+                            self.aim_angle = self.selected_weapon.clamp_aim(aim_angle);
                         }
+                        let weapon = self.team_aim(player_index).map_or(self.selected_weapon, |(_, _, w)| w);
+                        self.set_team_aim(player_index, (self.current_ball, weapon.clamp_aim(aim_angle), weapon));
                     }
                 }
                 continue;
@@ -2201,14 +4172,47 @@ impl Game {
     }
 
     fn update(&mut self, dt: f32) {
-        let dt = dt.min(1.0 / 30.0);
+        let real_dt = dt.min(1.0 / 30.0);
+
+        // Hit-stop: gameplay runs slowed for a moment after a big hit, then eases back.
+        // Camera, watchdog and event cooldowns below keep using real_dt.
+        if self.hit_stop_timer > 0.0 {
+            self.hit_stop_timer = (self.hit_stop_timer - real_dt).max(0.0);
+            self.time_scale = HIT_STOP_SCALE;
+        } else {
+            self.time_scale = (self.time_scale + real_dt * HIT_STOP_RECOVERY).min(1.0);
+        }
+        let dt = real_dt * self.time_scale;
+        self.sim.tick = self.sim.tick.wrapping_add(1);
+        self.weapon_flash_timer = (self.weapon_flash_timer - real_dt).max(0.0);
+        if let Some(reveal) = &mut self.crate_reveal {
+            reveal.3 -= real_dt;
+            if reveal.3 <= 0.0 {
+                self.crate_reveal = None;
+            }
+        }
+        self.forfeit_confirm_timer = (self.forfeit_confirm_timer - real_dt).max(0.0);
+        self.prod_cooldown = (self.prod_cooldown - real_dt).max(0.0);
+        if let Some((_, left)) = &mut self.pending_action {
+            *left -= real_dt;
+            if *left <= 0.0 {
+                self.pending_action = None;
+            }
+        }
+        for ping in &mut self.pings {
+            ping.2 -= real_dt;
+        }
+        self.pings.retain(|p| p.2 > 0.0);
 
         // Snapshot health/alive state before any updates so we can detect changes
-        let health_snapshot: Vec<(bool, i32)> = self.balls.iter()
+        let health_snapshot: Vec<(bool, i32)> = self.sim.balls.iter()
             .map(|b| (b.alive, b.health))
             .collect();
 
         self.apply_network_messages();
+        if self.pending_terrain_hashes.is_some() && self.effects_done() {
+            self.check_terrain_hashes();
+        }
 
         for p in &mut self.particles {
             p.vy += 200.0 * dt;
@@ -2217,16 +4221,20 @@ impl Game {
             p.life -= dt;
         }
         self.particles.retain(|p| p.life > 0.0);
+        for ball in &mut self.sim.balls {
+            ball.drain_display_health(real_dt);
+        }
 
         // Apply camera inertia coast (runs every frame; bled away by auto_follow when active)
-        self.cam.apply_momentum(dt);
+        self.cam.apply_momentum(real_dt);
+        self.cam.tick_shake(real_dt);
 
         // Smoothly return zoom toward the default level while the camera is gliding back
         // to the active ball after a turn change. Not triggered by mid-turn panning.
         if self.cam_return_timer > 0.0 {
             let diff = self.cam_target_zoom - self.cam.zoom;
             if diff.abs() > 0.005 {
-                let rate = (4.0 * dt).min(1.0);
+                let rate = (4.0 * real_dt).min(1.0);
                 self.cam.zoom += diff * rate;
             } else {
                 self.cam.zoom = self.cam_target_zoom;
@@ -2234,10 +4242,39 @@ impl Game {
         }
 
         // Tick napalm fire pools every frame (persist across turns)
+        let mut children = Vec::new();
         for fp in &mut self.fire_pools {
-            fp.tick(&mut self.balls, dt);
+            children.extend(fp.tick(&self.sim.terrain, &mut self.sim.balls, dt));
         }
         self.fire_pools.retain(|fp| fp.alive);
+        let room = special_weapons::MAX_FIRE_POOLS.saturating_sub(self.fire_pools.len());
+        self.fire_pools.extend(children.into_iter().take(room));
+        let flames: Vec<(f32, f32, f32)> = self.fire_pools.iter().map(|fp| (fp.x, fp.y, fp.radius)).collect();
+        for (x, y, radius) in flames {
+            self.ignite_gas(x, y, radius);
+        }
+
+        if !self.platforms.is_empty() {
+            self.platform_clock += dt;
+            while self.platform_clock >= platforms::PLATFORM_STEP {
+                self.platform_clock -= platforms::PLATFORM_STEP;
+                self.platform_ticks += 1;
+            }
+            for p in &mut self.platforms {
+                p.set_tick(self.platform_ticks);
+            }
+        }
+
+        // Supply crates drift down and get picked up by whichever ball touches them
+        for i in 0..self.crates.len() {
+            if let Some(ball) = self.crates[i].tick(&self.sim.terrain, &self.sim.balls, dt) {
+                self.pick_up_crate(i, ball);
+            }
+        }
+        self.crates.retain(|c| c.alive);
+
+        self.tick_barrels(dt);
+        self.tick_gas(dt);
 
         match self.phase {
             Phase::Aiming | Phase::Charging => {
@@ -2252,39 +4289,18 @@ impl Game {
                 if is_bot_turn && !self.has_fired {
                     self.bot_think_timer -= dt;
                     if self.bot_think_timer <= 0.0 {
-                        if let Some(bot_ball_idx) = self.find_ball_for_player(bot_team) {
-                            let bx = self.balls[bot_ball_idx].x;
-                            let by = self.balls[bot_ball_idx].y;
-                            // Find nearest living enemy ball
-                            let mut best_dist = f32::MAX;
-                            let mut best_angle = -0.5f32; // default: slightly upward
-                            let mut found_enemy = false;
-                            for w in &self.balls {
-                                if !w.alive || w.team == bot_team as u32 { continue; }
-                                let dx = w.x - bx;
-                                let dy = w.y - by;
-                                let dist = (dx * dx + dy * dy).sqrt();
-                                if dist < best_dist {
-                                    best_dist = dist;
-                                    // Aim slightly above the target so the missile arcs in
-                                    best_angle = dy.atan2(dx) - 0.25;
-                                    found_enemy = true;
-                                }
-                            }
-                            if found_enemy {
-                                self.current_ball = bot_ball_idx;
-                                self.aim_angle = best_angle;
-                                self.selected_weapon = Weapon::HomingMissile;
-                                let shooter_team = self.balls[bot_ball_idx].team;
-                                let offset = BALL_RADIUS + 4.0;
-                                let sx = bx + best_angle.cos() * offset;
-                                let sy = by + best_angle.sin() * offset;
-                                let proj = Projectile::new(sx, sy, best_angle, 80.0, Weapon::HomingMissile, shooter_team);
-                                self.proj = Some(proj);
-                                self.has_fired = true;
-                                self.phase = Phase::ProjectileFlying;
-                            } else {
-                                self.end_turn();
+                        let bot_ball = self.find_ball_for_player(bot_team);
+                        if let (Some(bot_ball_idx), Some(NetInput::Fire { weapon: Some(weapon), angle_deg, power_percent })) =
+                            (bot_ball, self.bot_fire_input(bot_team))
+                        {
+                            // The same path a remote player's shot takes, so the bot is held to
+                            // its loadout, locked weapons and aim limits like everyone else
+                            self.current_ball = bot_ball_idx;
+                            self.selected_weapon = weapon;
+                            self.aim_angle = weapon.clamp_aim(angle_deg.to_radians());
+                            match self.do_fire(bot_ball_idx, angle_deg.to_radians(), power_percent, weapon) {
+                                Ok(()) => self.has_fired = true,
+                                Err(_) => self.end_turn(),
                             }
                         } else {
                             self.end_turn();
@@ -2294,51 +4310,37 @@ impl Game {
                 // ────────────────────────────────────────────────────────────────
                 // Skip physics for remote balls that are network-driven (pos_update stream);
                 // running local physics on them fights the lerp and causes visible teleporting.
-                let my_ball_phys = self.net.my_player_index
-                    .and_then(|pi| self.find_ball_for_player(pi));
-                for (bi, w) in self.balls.iter_mut().enumerate() {
-                    if self.net.connected
-                        && Some(bi) != my_ball_phys
-                        && self.ball_lerp_targets.get(bi).copied().flatten().is_some()
-                    {
-                        continue; // position driven by network; no local physics needed
-                    }
-                    w.tick(&self.terrain, dt);
-                }
+                let skip = self.network_driven_balls();
+                self.sim.step_balls(&self.platforms, self.safe_zone, dt, |bi| skip.contains(&bi));
                 // If the current ball died (walked into water/lava), end turn immediately
-                if self.current_ball < self.balls.len() && !self.balls[self.current_ball].alive {
+                if self.current_ball < self.sim.balls.len() && !self.sim.balls[self.current_ball].alive {
                     self.end_turn();
                 }
-                if self.current_ball < self.balls.len() {
+                if self.current_ball < self.sim.balls.len() {
                     let (wx, wy) = {
-                        let w = &self.balls[self.current_ball];
+                        let w = &self.sim.balls[self.current_ball];
                         (w.x, w.y)
                     };
-                    let alive = self.balls[self.current_ball].alive;
+                    let alive = self.sim.balls[self.current_ball].alive;
                     if alive {
-                        self.auto_follow(wx, wy - 30.0, 4.0, dt);
+                        self.auto_follow(wx, wy - 30.0, 4.0, real_dt);
                     }
                 }
             }
             Phase::ProjectileFlying => {
-                let my_ball_phys2 = self.net.my_player_index
-                    .and_then(|pi| self.find_ball_for_player(pi));
-                for (bi, w) in self.balls.iter_mut().enumerate() {
-                    if self.net.connected
-                        && Some(bi) != my_ball_phys2
-                        && self.ball_lerp_targets.get(bi).copied().flatten().is_some()
-                    {
-                        continue;
-                    }
-                    w.tick(&self.terrain, dt);
-                }
+                let skip = self.network_driven_balls();
+                self.sim.step_balls(&self.platforms, self.safe_zone, dt, |bi| skip.contains(&bi));
                 let mut explosion_opt = None;
                 let mut proj_died = false;
                 
                 // Handle regular projectile
                 let mut proj_follow: Option<(f32, f32)> = None;
                 if let Some(ref mut proj) = self.proj {
-                    let (explosion, bomblets) = proj.tick(&mut self.terrain, &mut self.balls, self.wind, dt);
+                    let (mut explosion, mut bomblets) = proj.tick(&mut self.sim.terrain, &mut self.sim.balls, &self.decoys, self.sim.wind, dt);
+                    if let Some(barrel) = self.barrels.iter_mut().find(|b| proj.alive && b.struck_by(proj.x, proj.y)) {
+                        barrel.hp = 0;
+                        (explosion, bomblets) = proj.detonate(&mut self.sim.terrain, &mut self.sim.balls);
+                    }
                     proj_follow = Some((proj.x, proj.y));
                     explosion_opt = explosion;
                     proj_died = !proj.alive;
@@ -2346,8 +4348,15 @@ impl Game {
                         self.cluster_bomblets.extend(bomblets);
                     }
                 }
+                // Flares only burn down while a homing missile is out hunting
+                if self.proj.as_ref().is_some_and(|p| p.weapon == Weapon::HomingMissile) {
+                    for d in &mut self.decoys {
+                        d.lifetime -= dt;
+                    }
+                    self.decoys.retain(|d| d.lifetime > 0.0);
+                }
                 if let Some((px, py)) = proj_follow {
-                    self.auto_follow(px, py, 8.0, dt);
+                    self.follow_shell(px, py, 8.0, real_dt);
                 }
 
                 // Handle shotgun pellets
@@ -2356,7 +4365,7 @@ impl Game {
                     let mut pellet_follow: Option<(f32, f32)> = None;
                     for pellet in &mut self.shotgun_pellets {
                         if pellet.alive {
-                            let hit = pellet.tick(&mut self.terrain, &mut self.balls, dt);
+                            let hit = pellet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt);
                             if hit {
                                 self.terrain_dirty = true;
                             }
@@ -2367,7 +4376,7 @@ impl Game {
                         }
                     }
                     if let Some((px, py)) = pellet_follow {
-                        self.auto_follow(px, py, 6.0, dt);
+                        self.auto_follow(px, py, 6.0, real_dt);
                     }
                     if !any_active {
                         self.shotgun_pellets.clear();
@@ -2380,7 +4389,7 @@ impl Game {
                     let mut bullet_follow: Option<(f32, f32)> = None;
                     for bullet in &mut self.uzi_bullets {
                         if bullet.alive {
-                            let hit = bullet.tick(&mut self.terrain, &mut self.balls, dt);
+                            let hit = bullet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt);
                             if hit {
                                 self.terrain_dirty = true;
                             }
@@ -2391,7 +4400,7 @@ impl Game {
                         }
                     }
                     if let Some((bx, by)) = bullet_follow {
-                        self.auto_follow(bx, by, 5.0, dt);
+                        self.auto_follow(bx, by, 5.0, real_dt);
                     }
                     if !any_active {
                         self.uzi_bullets.clear();
@@ -2406,7 +4415,7 @@ impl Game {
                     let mut new_fires: Vec<FirePool> = Vec::new();
                     for droplet in &mut self.airstrike_droplets {
                         if droplet.alive {
-                            let (exp_opt, fire_opt) = droplet.tick(&mut self.terrain, &mut self.balls, dt);
+                            let (exp_opt, fire_opt) = droplet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt);
                             if let Some(exp) = exp_opt {
                                 explosions.push(exp);
                                 self.terrain_dirty = true;
@@ -2421,7 +4430,7 @@ impl Game {
                         }
                     }
                     if let Some((dx, dy)) = droplet_follow {
-                        self.auto_follow(dx, dy, 7.0, dt);
+                        self.auto_follow(dx, dy, 7.0, real_dt);
                     }
                     for exp in explosions {
                         self.spawn_explosion_particles(&exp);
@@ -2433,21 +4442,7 @@ impl Game {
                 }
                 
                 // Handle placed explosives
-                if !self.placed_explosives.is_empty() {
-                    let mut explosions = Vec::new();
-                    for explosive in &mut self.placed_explosives {
-                        if explosive.tick(dt) {
-                            let exp = explosive.explode(&mut self.terrain, &mut self.balls);
-                            explosions.push(exp);
-                            self.terrain_dirty = true;
-                        }
-                    }
-                    self.placed_explosives.retain(|e| e.alive);
-                    
-                    for exp in &explosions {
-                        self.spawn_explosion_particles(exp);
-                    }
-                }
+                self.tick_placed_explosives(dt);
                 
                 // Handle cluster bomblets
                 if !self.cluster_bomblets.is_empty() {
@@ -2456,7 +4451,7 @@ impl Game {
                     let mut bomblet_follow: Option<(f32, f32)> = None;
                     for bomblet in &mut self.cluster_bomblets {
                         if bomblet.alive {
-                            if let Some(exp) = bomblet.tick(&mut self.terrain, &mut self.balls, dt) {
+                            if let Some(exp) = bomblet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt) {
                                 explosions.push(exp);
                                 self.terrain_dirty = true;
                             }
@@ -2467,7 +4462,7 @@ impl Game {
                         }
                     }
                     if let Some((bx, by)) = bomblet_follow {
-                        self.auto_follow(bx, by, 6.0, dt);
+                        self.auto_follow(bx, by, 6.0, real_dt);
                     }
                     for exp in &explosions {
                         self.spawn_explosion_particles(exp);
@@ -2478,30 +4473,22 @@ impl Game {
                 }
                 
                 // Check if all projectiles/effects are done
-                let all_done = self.proj.is_none() 
-                    && self.shotgun_pellets.is_empty() 
-                    && self.uzi_bullets.is_empty()
-                    && self.airstrike_droplets.is_empty()
-                    && self.placed_explosives.is_empty()
-                    && self.cluster_bomblets.is_empty();
+                let all_done = self.effects_done();
                 
                 if proj_died || explosion_opt.is_some() {
+                    self.keep_ghost();
                     self.proj = None;
                 }
 
                 // Stuck-phase watchdog: if projectile flying goes on too long, force-end
-                self.stuck_phase_timer += dt;
+                self.stuck_phase_timer += real_dt;
                 if self.stuck_phase_timer > 30.0 {
                     #[cfg(target_arch = "wasm32")]
                     {
                         let s = "[WATCHDOG] ProjectileFlying stuck >30s, force-ending turn\0";
                         unsafe { console_log(s.as_ptr()); }
                     }
-                    self.proj = None;
-                    self.shotgun_pellets.clear();
-                    self.uzi_bullets.clear();
-                    self.airstrike_droplets.clear();
-                    self.cluster_bomblets.clear();
+                    self.resolve_pending_effects();
                     self.end_turn();
                 }
                 
@@ -2528,10 +4515,11 @@ impl Game {
             }
             Phase::Settling => {
                 self.settle_timer += dt;
-                for w in &mut self.balls {
-                    w.tick(&self.terrain, dt);
+                if self.sim.terrain.settle_sand(SAND_MOVES_PER_TICK) > 0 {
+                    self.terrain_dirty = true;
                 }
-                let all_settled = self.balls.iter().all(|w| w.is_settled());
+                self.sim.step_balls(&self.platforms, self.safe_zone, dt, |_| false);
+                let all_settled = self.sim.balls.iter().all(|w| w.is_settled());
                 if all_settled || self.settle_timer > SETTLE_TIMEOUT {
                     #[cfg(target_arch = "wasm32")]
                     {
@@ -2552,9 +4540,7 @@ impl Game {
                         self.phase = Phase::Retreat;
                         self.retreat_timer = 5.0;
                         // Reset movement budget for retreat
-                        if self.current_ball < self.balls.len() {
-                            self.balls[self.current_ball].reset_movement_budget();
-                        }
+                        self.refresh_dodge_budget(self.current_ball);
                     } else if !self.net.connected {
                         self.end_turn();
                     } else if self.is_my_turn() {
@@ -2565,25 +4551,16 @@ impl Game {
                         self.turn_end_timer = TURN_END_DELAY;
                     }
                 }
-                if self.current_ball < self.balls.len() {
-                    let w = &self.balls[self.current_ball];
-                    self.cam.follow(w.x, w.y - 30.0, 3.0, dt);
+                if self.current_ball < self.sim.balls.len() && !self.director.enabled && self.final_cam_focus.is_none() {
+                    let w = &self.sim.balls[self.current_ball];
+                    self.cam.follow(w.x, w.y - 30.0, 3.0, real_dt);
                 }
             }
             Phase::Retreat => {
                 self.retreat_timer -= dt;
                 // Tick ball physics — skip remote network-driven balls to avoid teleporting
-                let my_ball_phys3 = self.net.my_player_index
-                    .and_then(|pi| self.find_ball_for_player(pi));
-                for (bi, w) in self.balls.iter_mut().enumerate() {
-                    if self.net.connected
-                        && Some(bi) != my_ball_phys3
-                        && self.ball_lerp_targets.get(bi).copied().flatten().is_some()
-                    {
-                        continue;
-                    }
-                    w.tick(&self.terrain, dt);
-                }
+                let skip = self.network_driven_balls();
+                self.sim.step_balls(&self.platforms, self.safe_zone, dt, |bi| skip.contains(&bi));
 
                 // Tick in-flight projectile (Mortar fires then enters Retreat so player
                 // can move while the shell is travelling)
@@ -2591,7 +4568,11 @@ impl Game {
                 let mut retreat_proj_died = false;
                 let mut retreat_proj_explosion = None;
                 if let Some(ref mut proj) = self.proj {
-                    let (explosion, bomblets) = proj.tick(&mut self.terrain, &mut self.balls, self.wind, dt);
+                    let (mut explosion, mut bomblets) = proj.tick(&mut self.sim.terrain, &mut self.sim.balls, &self.decoys, self.sim.wind, dt);
+                    if let Some(barrel) = self.barrels.iter_mut().find(|b| proj.alive && b.struck_by(proj.x, proj.y)) {
+                        barrel.hp = 0;
+                        (explosion, bomblets) = proj.detonate(&mut self.sim.terrain, &mut self.sim.balls);
+                    }
                     retreat_proj_follow = Some((proj.x, proj.y));
                     retreat_proj_explosion = explosion;
                     retreat_proj_died = !proj.alive;
@@ -2599,7 +4580,10 @@ impl Game {
                         self.cluster_bomblets.extend(bomblets);
                     }
                 }
-                if retreat_proj_died { self.proj = None; }
+                if retreat_proj_died {
+                    self.keep_ghost();
+                    self.proj = None;
+                }
                 if let Some(ref exp) = retreat_proj_explosion {
                     self.spawn_explosion_particles(exp);
                     self.terrain_dirty = true;
@@ -2610,7 +4594,7 @@ impl Game {
                     let mut explosions = Vec::new();
                     for bomblet in &mut self.cluster_bomblets {
                         if bomblet.alive {
-                            if let Some(exp) = bomblet.tick(&mut self.terrain, &mut self.balls, dt) {
+                            if let Some(exp) = bomblet.tick(&mut self.sim.terrain, &mut self.sim.balls, dt) {
                                 explosions.push(exp);
                                 self.terrain_dirty = true;
                             }
@@ -2623,42 +4607,30 @@ impl Game {
                 }
 
                 // Tick fused placed explosives (Dynamite / Mine countdown)
-                if !self.placed_explosives.is_empty() {
-                    let mut explosions = Vec::new();
-                    for explosive in &mut self.placed_explosives {
-                        if explosive.tick(dt) {
-                            let exp = explosive.explode(&mut self.terrain, &mut self.balls);
-                            explosions.push(exp);
-                            self.terrain_dirty = true;
-                        }
-                    }
-                    self.placed_explosives.retain(|e| e.alive);
-                    for exp in &explosions {
-                        self.spawn_explosion_particles(exp);
-                    }
-                }
+                self.tick_placed_explosives(dt);
 
                 // If current ball died during retreat (fell in water/lava), end turn now
-                if self.current_ball < self.balls.len() && !self.balls[self.current_ball].alive {
+                if self.current_ball < self.sim.balls.len() && !self.sim.balls[self.current_ball].alive {
                     self.retreat_timer = 0.0; // Force turn end
                 }
                 // Follow projectile while in flight, otherwise follow current ball
                 if let Some((px, py)) = retreat_proj_follow {
                     let rpx = px; let rpy = py;
-                    self.auto_follow(rpx, rpy, 7.0, dt);
-                } else if self.current_ball < self.balls.len() {
+                    self.follow_shell(rpx, rpy, 7.0, real_dt);
+                } else if self.current_ball < self.sim.balls.len() {
                     let (wx, wy) = {
-                        let w = &self.balls[self.current_ball];
+                        let w = &self.sim.balls[self.current_ball];
                         (w.x, w.y)
                     };
-                    if self.balls[self.current_ball].alive {
-                        self.cam.follow(wx, wy - 30.0, 4.0, dt);
+                    if self.sim.balls[self.current_ball].alive && !self.director.enabled && self.final_cam_focus.is_none() {
+                        self.cam.follow(wx, wy - 30.0, 4.0, real_dt);
                     }
                 }
                 // When retreat time expires AND all in-flight effects are resolved, end turn
                 let retreat_all_done = self.proj.is_none()
                     && self.cluster_bomblets.is_empty()
-                    && self.placed_explosives.is_empty();
+                    && self.placed_explosives.is_empty()
+                    && self.gas_fuses.is_empty();
                 if self.retreat_timer <= 0.0 && retreat_all_done {
                     if let Some(player_idx) = self.pending_turn_sync.take() {
                         self.sync_to_player_turn(player_idx);
@@ -2669,9 +4641,7 @@ impl Game {
             }
             Phase::TurnEnd => {
                 self.turn_end_timer -= dt;
-                for w in &mut self.balls {
-                    w.tick(&self.terrain, dt);
-                }
+                self.sim.step_balls(&self.platforms, self.safe_zone, dt, |_| false);
                 if self.turn_end_timer <= 0.0 {
                     if let Some(player_idx) = self.pending_turn_sync.take() {
                         self.sync_to_player_turn(player_idx);
@@ -2687,16 +4657,31 @@ impl Game {
                                 let s = "[TURN] Safety timeout: force-advancing because server never sent turn_advanced\0";
                                 unsafe { console_log(s.as_ptr()); }
                             }
+                            self.resolve_pending_effects();
                             self.advance_turn();
                         }
                     }
                 }
             }
-            Phase::GameOver => {}
+            Phase::GameOver => {
+                if self.final_cam_hold > 0.0 {
+                    self.final_cam_hold = (self.final_cam_hold - real_dt).max(0.0);
+                }
+            }
+        }
+        if let Some((fx, fy)) = self.final_cam_focus {
+            self.cam.recenter();
+            self.cam.follow(fx, fy, 4.0, real_dt);
+        }
+        self.tick_rope();
+
+        // Pop out balls the collision push-out left buried in terrain
+        for w in &mut self.sim.balls {
+            w.rescue_if_embedded(&self.sim.terrain, dt);
         }
 
-        self.cam
-            .clamp_to_world(terrain::PLAYABLE_LAND_WIDTH, self.terrain.height as f32);
+        let land_x = (self.sim.terrain.land_start_x(), self.sim.terrain.land_end_x());
+        self.cam.clamp_to_world(land_x, self.sim.terrain.height as f32);
 
         // ── Position streaming ──────────────────────────────────────────────
         // The active player streams their ball position at ~30 Hz.  Remote
@@ -2717,8 +4702,8 @@ impl Game {
                     }
                 });
                 if let Some(bi) = my_ball_opt {
-                    if bi < self.balls.len() && self.balls[bi].alive {
-                        let b = &self.balls[bi];
+                    if bi < self.sim.balls.len() && self.sim.balls[bi].alive {
+                        let b = &self.sim.balls[bi];
                         // Only transmit when something actually changed (rounds to 1dp precision)
                         let changed = match self.last_pos_sent {
                             None => true,
@@ -2749,18 +4734,19 @@ impl Game {
         if self.net.connected {
             let my_ball = self.net.my_player_index
                 .and_then(|pi| self.find_ball_for_player(pi));
-            let n = self.balls.len().min(self.ball_lerp_targets.len());
+            let n = self.sim.balls.len().min(self.ball_lerp_targets.len());
             for bi in 0..n {
                 // Never lerp our own ball
                 if my_ball == Some(bi) {
                     continue;
                 }
                 if let Some((tx, ty, tvx, tvy)) = self.ball_lerp_targets[bi] {
-                    let ball = &mut self.balls[bi];
+                    let ball = &mut self.sim.balls[bi];
                     if !ball.alive { continue; }
-                    // Physics is skipped for network-driven balls, so snap directly to the
-                    // authoritative position received from the active client (~60 Hz).
-                    // With no local physics fighting the target, this produces smooth movement.
+                    // Physics is skipped for network-driven balls, so the simulation snaps
+                    // to the authoritative position received from the active client (~60 Hz).
+                    // Drawing blends between the last two samples (`Ball::draw_pos`), so
+                    // jittery arrival doesn't show as stutter.
                     ball.x = tx;
                     ball.y = ty;
                     ball.vx = tvx;
@@ -2770,25 +4756,81 @@ impl Game {
         }
 
         if self.terrain_dirty {
-            self.terrain_image = self.terrain.bake_image();
+            // Only re-colour the cells that changed; a full bake is 1400x800 cells
+            for r in self.drain_terrain_dirty_rects().chunks_exact(4) {
+                self.sim.terrain.bake_rect(&mut self.terrain_image, r[0], r[1], r[2], r[3]);
+            }
             // Recreate texture entirely instead of updating in-place to avoid WebGL state issues
-            // The old texture will be automatically dropped and cleaned up by Rust
-            self.terrain_texture = Texture2D::from_image(&self.terrain_image);
-            self.terrain_texture.set_filter(FilterMode::Nearest);
+            // The old texture is dropped first so its memory is free for the new one
+            self.terrain_texture = None;
+            let min_scale = if self.low_spec { LOW_SPEC_TERRAIN_SCALE } else { 1 };
+            let uploaded = terrain_texture::upload(&self.terrain_image, min_scale);
+            self.terrain_texture_scale = uploaded.as_ref().map_or(0, |(_, scale)| *scale);
+            self.terrain_texture = uploaded.map(|(tex, _)| tex);
             self.terrain_dirty = false;
+            self.stability_stale = true;
+        }
+
+        // Only earthquakes collapse terrain, so only look for unsupported chunks when they're on
+        self.stability_recheck = (self.stability_recheck - real_dt).max(0.0);
+        self.unstable_flash = (self.unstable_flash - real_dt).max(0.0);
+        if self.stability_stale && self.stability_recheck <= 0.0 && self.settings.earthquake_every > 0 {
+            self.unstable_outline = self.sim.terrain.floating_outline();
+            self.stability_stale = false;
+            self.stability_recheck = STABILITY_RECHECK;
+            if !self.unstable_outline.is_empty() {
+                self.unstable_flash = UNSTABLE_FLASH_TIME;
+            }
         }
 
         // Detect damage/death and emit game events for UI toasts.
         // Resize cooldown vec in case balls were re-created (new game).
-        if self.ball_event_cooldown.len() < self.balls.len() {
-            self.ball_event_cooldown.resize(self.balls.len(), 0.0);
+        if self.ball_event_cooldown.len() < self.sim.balls.len() {
+            self.ball_event_cooldown.resize(self.sim.balls.len(), 0.0);
         }
         for cd in &mut self.ball_event_cooldown {
-            if *cd > 0.0 { *cd -= dt; }
+            if *cd > 0.0 { *cd -= real_dt; }
+        }
+        // Sand that gave way under a ball this tick
+        let crumbled: Vec<(i32, i32)> = self.sim.balls.iter_mut().filter_map(|b| b.crumbled.take()).collect();
+        for (x, y) in crumbled {
+            self.apply_crumble_at(x, y);
+            self.crumble_log.push((x, y));
+            self.note_terrain_op(8);
+        }
+        // Hard landings, tagged with the material for the front-end's landing sounds
+        for ball in &mut self.sim.balls {
+            let Some(material) = ball.landed_on.take() else { continue };
+            if !ball.alive {
+                continue;
+            }
+            let surface = match material {
+                terrain::GRASS => "grass",
+                terrain::DIRT => "dirt",
+                terrain::STONE => "stone",
+                terrain::WOOD => "wood",
+                terrain::ICE => "ice",
+                terrain::SAND => "sand",
+                _ => "platform",
+            };
+            let event = format!("{{\"type\":\"land\",\"name\":\"{}\",\"surface\":\"{}\"}}", sanitize_event_name(&ball.name), surface);
+            self.net.send_game_event(&event);
         }
-        for (i, (&(was_alive, prev_hp), ball)) in health_snapshot.iter().zip(self.balls.iter()).enumerate() {
-            if !was_alive { continue; }
+
+        let mut hurt = None;
+        let mut killed = false;
+        let mut victims = Vec::new();
+        for (i, (&(was_alive, prev_hp), ball)) in health_snapshot.iter().zip(self.sim.balls.iter()).enumerate() {
+            // Forfeited balls didn't die, so they get no death toast or hit-stop
+            if !was_alive || self.forfeited_teams.contains(&ball.team) { continue; }
+            if ball.alive && ball.health < prev_hp && hurt.is_none() {
+                hurt = Some(i);
+            }
             let cooldown = self.ball_event_cooldown.get(i).copied().unwrap_or(0.0);
+            if !ball.alive {
+                killed = true;
+                victims.push((ball.team, ball.name.clone()));
+            }
             if !ball.alive && cooldown <= 0.0 {
                 // Ball died this frame
                 let name = sanitize_event_name(&ball.name);
@@ -2809,9 +4851,120 @@ impl Game {
                 }
             }
         }
+
+        if killed {
+            self.start_hit_stop();
+            self.announce_kills(&victims);
+            // The winning blow: hold the camera where the last enemy went down
+            if self.final_cam_active && self.final_cam_focus.is_none() && self.enemies_left() == 0 {
+                let team = self.sim.balls.get(self.current_ball).map(|b| b.team);
+                self.final_cam_focus = health_snapshot.iter().zip(&self.sim.balls)
+                    .find(|((was_alive, _), b)| *was_alive && !b.alive && Some(b.team) != team)
+                    .map(|(_, b)| (b.x, b.y));
+            }
+        }
+        if self.final_cam_active && self.final_cam_focus.is_none()
+            && self.effects_done() && self.sim.balls.iter().all(|b| b.is_settled())
+        {
+            self.final_cam_active = false; // everything came to rest and the last enemy survived
+        }
+        self.record_eliminations();
+
+        self.update_director(real_dt, hurt);
+    }
+
+    /// Credit this tick's deaths to the active team and emit the announcer events:
+    /// `first_blood` for the match's first enemy kill, `multi_kill` for a second or
+    /// later kill in one turn, and `streak` once a team has KILL_STREAK_MIN enemy kills
+    /// without losing a ball. Own-team deaths are never credited but still end the
+    /// victim team's streak.
+    fn announce_kills(&mut self, victims: &[(u32, String)]) {
+        let Some(killer) = self.sim.balls.get(self.current_ball).map(|b| b.team) else {
+            return;
+        };
+        for (victim_team, victim) in victims {
+            let most = (*victim_team).max(killer) as usize;
+            if self.kill_streaks.len() <= most {
+                self.kill_streaks.resize(most + 1, 0);
+            }
+            self.kill_streaks[*victim_team as usize] = 0;
+            if *victim_team == killer {
+                continue;
+            }
+            let name = sanitize_event_name(&self.team_display_name(killer));
+            if self.total_kills == 0 {
+                let event = format!("{{\"type\":\"first_blood\",\"name\":\"{}\",\"victim\":\"{}\"}}",
+                    name, sanitize_event_name(victim));
+                self.net.send_game_event(&event);
+            }
+            self.total_kills += 1;
+            self.kills_this_turn += 1;
+            self.kill_streaks[killer as usize] += 1;
+            if self.kills_this_turn >= 2 {
+                let event = format!("{{\"type\":\"multi_kill\",\"name\":\"{}\",\"count\":{}}}", name, self.kills_this_turn);
+                self.net.send_game_event(&event);
+            }
+            let streak = self.kill_streaks[killer as usize];
+            if streak >= KILL_STREAK_MIN {
+                let event = format!("{{\"type\":\"streak\",\"name\":\"{}\",\"count\":{}}}", name, streak);
+                self.net.send_game_event(&event);
+            }
+        }
+    }
+
+    /// The player's name for a team in network games, else "Team N".
+    fn team_display_name(&self, team: u32) -> String {
+        self.net.player_names.get(team as usize)
+            .filter(|n| !n.is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("Team {}", team + 1))
+    }
+
+    /// Shove the shell and any cluster bomblets still in flight away from a blast, with
+    /// the same 1.5x radius falloff as ball knockback (`blast_push` setting). Runs in list
+    /// order on every client, so the deflected paths stay in sync.
+    fn push_projectiles(&mut self, exp: &projectile::Explosion) {
+        if self.settings.blast_push <= 0.0 || exp.is_water || exp.radius <= 0.0 {
+            return;
+        }
+        let reach = exp.radius * 1.5;
+        let strength = BLAST_PUSH_SPEED * self.settings.blast_push;
+        let push = |x: f32, y: f32| -> Option<(f32, f32)> {
+            let (dx, dy) = (x - exp.x, y - exp.y);
+            let dist = (dx * dx + dy * dy).sqrt();
+            if dist >= reach {
+                return None;
+            }
+            let dist = dist.max(1.0);
+            let impulse = strength * (1.0 - dist / reach);
+            Some((dx / dist * impulse, dy / dist * impulse))
+        };
+        if let Some(proj) = self.proj.as_mut().filter(|p| p.alive) {
+            if let Some((ix, iy)) = push(proj.x, proj.y) {
+                proj.vx += ix;
+                proj.vy += iy;
+            }
+        }
+        for bomblet in self.cluster_bomblets.iter_mut().filter(|b| b.alive) {
+            if let Some((ix, iy)) = push(bomblet.x, bomblet.y) {
+                bomblet.vx += ix;
+                bomblet.vy += iy;
+            }
+        }
     }
 
     fn spawn_explosion_particles(&mut self, exp: &projectile::Explosion) {
+        for barrel in &mut self.barrels {
+            barrel.take_blast(exp);
+        }
+        self.push_projectiles(exp);
+        if !exp.is_water {
+            self.ignite_gas(exp.x, exp.y, exp.radius);
+        }
+        self.last_impact = Some((exp.x, exp.y));
+        if exp.radius >= HIT_STOP_RADIUS {
+            self.start_hit_stop();
+        }
         // Scale particle count, speed, size and lifetime based on explosion radius
         let scale = (exp.radius / 25.0).max(1.0); // 25px = baseline
         let count = if exp.is_water {
@@ -2851,15 +5004,19 @@ impl Game {
             } else {
                 (0.0, 0.0)
             };
-            self.particles.push(Particle {
-                x: exp.x + spawn_offset.0,
-                y: exp.y + spawn_offset.1,
-                vx: angle.cos() * speed,
-                vy: angle.sin() * speed - 80.0 * speed_mult,
-                life: (0.5 + (self.rng_state >> 16) as f32 / 65536.0 * 0.8) * life_mult,
-                color,
-                size: (2.0 + (self.rng_state >> 24) as f32 / 256.0 * 3.0) * size_mult,
-            });
+            // Low-spec drops particles only after drawing the same random numbers, since
+            // rng_state also feeds the wind and must stay in step with other clients
+            if !self.low_spec || i % LOW_SPEC_PARTICLE_STRIDE == 0 {
+                self.particles.push(Particle {
+                    x: exp.x + spawn_offset.0,
+                    y: exp.y + spawn_offset.1,
+                    vx: angle.cos() * speed,
+                    vy: angle.sin() * speed - 80.0 * speed_mult,
+                    life: (0.5 + (self.rng_state >> 16) as f32 / 65536.0 * 0.8) * life_mult,
+                    color,
+                    size: (2.0 + (self.rng_state >> 24) as f32 / 256.0 * 3.0) * size_mult,
+                });
+            }
             self.rng_state = lcg(self.rng_state);
         }
     }
@@ -2869,7 +5026,7 @@ impl Game {
         if !self.net.connected {
             return String::new();
         }
-        let team = self.balls.get(self.current_ball).map(|w| w.team as usize).unwrap_or(0);
+        let team = self.sim.balls.get(self.current_ball).map(|w| w.team as usize).unwrap_or(0);
         
         // Safely get player name, handling out-of-bounds and empty cases
         let name = if team < self.net.player_names.len() {
@@ -2899,31 +5056,63 @@ impl Game {
 
         self.draw_sky();
 
-        draw_texture_ex(
-            &self.terrain_texture,
-            0.0,
-            0.0,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(
-                    self.terrain.width as f32,
-                    self.terrain.height as f32,
-                )),
-                ..Default::default()
-            },
-        );
+        if let Some(tex) = &self.terrain_texture {
+            draw_texture_ex(
+                tex,
+                0.0,
+                0.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(
+                        self.sim.terrain.width as f32,
+                        self.sim.terrain.height as f32,
+                    )),
+                    ..Default::default()
+                },
+            );
+        } else {
+            let vw = self.cam.visible_width();
+            let vh = self.cam.visible_height();
+            self.sim.terrain.draw_blocks(
+                self.cam.x - vw / 2.0,
+                self.cam.y - vh / 2.0,
+                self.cam.x + vw / 2.0,
+                self.cam.y + vh / 2.0,
+                CPU_TERRAIN_BLOCK,
+            );
+        }
 
         self.draw_water();
+        self.draw_safe_zone();
+        self.draw_unstable_terrain();
+        if self.skyline_overlay {
+            self.draw_skyline();
+        }
+        if self.terrain_ops_overlay {
+            self.draw_terrain_ops();
+        }
 
-        hud::draw_ball_world(&self.balls, self.current_ball);
+        let cast_aims: Vec<(usize, f32, Weapon)> = if self.cast_aims_overlay {
+            // The ball on turn shows its live aim; everyone else their team's last broadcast
+            self.team_aims.iter().flatten().copied()
+                .filter(|&(i, _, _)| i != self.current_ball)
+                .chain(std::iter::once((self.current_ball, self.aim_angle, self.selected_weapon)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        hud::draw_ball_world(&self.sim.balls, self.current_ball, &cast_aims);
 
         if let Some(ref proj) = self.proj {
-            for (i, &(tx, ty)) in proj.trail.iter().enumerate() {
-                let alpha = i as f32 / proj.trail.len().max(1) as f32 * 0.6;
+            let skip = if self.low_spec { proj.trail.len().saturating_sub(LOW_SPEC_TRAIL_LEN) } else { 0 };
+            let trail = &proj.trail[skip..];
+            for (i, &(tx, ty)) in trail.iter().enumerate() {
+                let alpha = i as f32 / trail.len().max(1) as f32 * 0.6;
                 draw_circle(tx, ty, 2.0, Color::new(1.0, 0.6, 0.2, alpha));
             }
-            draw_circle(proj.x, proj.y, 4.0, Color::new(1.0, 0.3, 0.1, 1.0));
-            draw_circle(proj.x, proj.y, 2.5, Color::new(1.0, 0.8, 0.3, 1.0));
+            let (px, py) = proj.draw_pos();
+            draw_circle(px, py, 4.0, Color::new(1.0, 0.3, 0.1, 1.0));
+            draw_circle(px, py, 2.5, Color::new(1.0, 0.8, 0.3, 1.0));
         }
         
         // Draw cluster bomblets
@@ -2962,6 +5151,43 @@ impl Game {
             }
         }
 
+        for c in &self.crates {
+            c.draw();
+        }
+        self.draw_crate_reveal();
+        for p in &self.platforms {
+            p.draw();
+        }
+        for b in self.barrels.iter().filter(|b| b.alive) {
+            b.draw();
+        }
+
+        // Map pings: a pin with rings rippling out, fading over its last second
+        for &(x, y, timer, team) in &self.pings {
+            let (r, g, b) = team.map_or((1.0, 1.0, 1.0), |t| physics::TEAM_COLORS[t as usize % physics::TEAM_COLORS.len()]);
+            let alpha = timer.min(1.0);
+            let ripple = (PING_TIME - timer) % 1.0;
+            draw_circle_lines(x, y, 6.0 + ripple * 24.0, 2.0, Color::new(r, g, b, alpha * (1.0 - ripple)));
+            draw_line(x, y, x, y - 18.0, 2.0, Color::new(r, g, b, alpha));
+            draw_circle(x, y - 18.0, 4.0, Color::new(r, g, b, alpha));
+        }
+
+        // Draw the ninja rope
+        if let Some(rope) = &self.rope {
+            if let Some(ball) = self.sim.balls.get(rope.ball) {
+                let (bx, by) = ball.draw_pos(get_time() as f32);
+                draw_line(rope.anchor_x, rope.anchor_y, bx, by, 1.5, Color::new(0.85, 0.75, 0.55, 1.0));
+                draw_circle(rope.anchor_x, rope.anchor_y, 2.5, Color::new(0.6, 0.6, 0.65, 1.0));
+            }
+        }
+
+        // Draw flare decoys
+        for d in &self.decoys {
+            let flicker = ((get_time() as f32 * 20.0 + d.x).sin() * 0.2 + 0.8).max(0.0);
+            draw_circle(d.x, d.y, 9.0 * flicker, Color::new(1.0, 0.45, 0.1, 0.35));
+            draw_circle(d.x, d.y, 3.0, Color::new(1.0, 0.95, 0.7, 1.0));
+        }
+
         // Draw airstrike droplets
         for droplet in &self.airstrike_droplets {
             if droplet.alive {
@@ -3014,8 +5240,7 @@ impl Game {
 
         // Build Wall placement preview
         if self.build_wall_mode && self.is_my_turn() {
-            let (mx, my) = mouse_position();
-            let world_pos = self.cam.to_macroquad().screen_to_world(vec2(mx, my));
+            let world_pos = self.target_preview_pos();
 
             let (cx, cy, angle) = match self.build_wall_anchor {
                 None => {
@@ -3061,9 +5286,8 @@ impl Game {
 
         // Teleport preview: ghost circle + crosshair at cursor
         if self.teleport_mode && self.is_my_turn() {
-            let (mx, my) = mouse_position();
-            let world_pos = self.cam.to_macroquad().screen_to_world(vec2(mx, my));
-            let r = BALL_RADIUS;
+            let world_pos = self.target_preview_pos();
+            let r = self.sim.balls.get(self.current_ball).map_or(BALL_RADIUS, |b| b.radius);
             draw_circle(world_pos.x, world_pos.y, r, Color::new(0.4, 0.85, 1.0, 0.35));
             draw_circle_lines(world_pos.x, world_pos.y, r, 2.0, Color::new(0.4, 0.9, 1.0, 0.9));
             let gap = r * 0.5;
@@ -3081,9 +5305,9 @@ impl Game {
             && !self.has_fired && self.is_my_turn()
         {
             let idx = self.current_ball;
-            if idx < self.balls.len() && self.balls[idx].alive {
-                let bx = self.balls[idx].x;
-                let by = self.balls[idx].y;
+            if idx < self.sim.balls.len() && self.sim.balls[idx].alive {
+                let bx = self.sim.balls[idx].x;
+                let by = self.sim.balls[idx].y;
                 let angle = self.aim_angle;
                 let cos_a = angle.cos();
                 let sin_a = angle.sin();
@@ -3113,8 +5337,7 @@ impl Game {
         // Airstrike / NapalmStrike preview: vertical drop lines at each target X
         if let Some(airstrike_weapon) = self.airstrike_mode {
             if self.is_my_turn() {
-                let (mx, my) = mouse_position();
-                let world_pos = self.cam.to_macroquad().screen_to_world(vec2(mx, my));
+                let world_pos = self.target_preview_pos();
                 let top_y = self.cam.y - self.cam.visible_height() / 2.0 - 50.0;
                 let bot_y = self.cam.y + self.cam.visible_height() / 2.0;
                 let (count, spacing, color) = match airstrike_weapon {
@@ -3154,6 +5377,8 @@ impl Game {
         if self.build_wall_mode && self.is_my_turn() {
             let hint = if self.build_wall_anchor.is_none() {
                 "[ BUILD WALL ]  Click to set position"
+            } else if self.pending_target.is_some() {
+                "[ BUILD WALL ]  Tap the wall again to build, elsewhere to turn it"
             } else {
                 "[ BUILD WALL ]  Click to set rotation"
             };
@@ -3164,12 +5389,24 @@ impl Game {
 
         // Teleport hint
         if self.teleport_mode && self.is_my_turn() {
-            let hint = "[ TELEPORT ]  Click destination";
+            let hint = if self.pending_target.is_some() {
+                "[ TELEPORT ]  Tap the target again to confirm"
+            } else {
+                "[ TELEPORT ]  Click destination"
+            };
             let sw = screen_width();
             let tw = measure_text(hint, None, 22, 1.0).width;
             draw_text(hint, sw / 2.0 - tw / 2.0, 58.0, 22.0, Color::new(0.4, 0.9, 1.0, 1.0));
         }
 
+        // Ninja rope hint
+        if self.rope.is_some() && self.is_my_turn() {
+            let hint = "[ ROPE ]  A/D swing   W/S reel   F let go";
+            let sw = screen_width();
+            let tw = measure_text(hint, None, 22, 1.0).width;
+            draw_text(hint, sw / 2.0 - tw / 2.0, 58.0, 22.0, Color::new(0.9, 0.8, 0.55, 1.0));
+        }
+
         // Baseball Bat hint
         if self.selected_weapon == Weapon::BaseballBat
             && (self.phase == Phase::Aiming || self.phase == Phase::Charging)
@@ -3184,9 +5421,11 @@ impl Game {
         // Airstrike / NapalmStrike targeting hint
         if let Some(airstrike_weapon) = self.airstrike_mode {
             if self.is_my_turn() {
-                let hint = match airstrike_weapon {
-                    Weapon::NapalmStrike => "[ NAPALM STRIKE ]  Click to set target",
-                    _ =>                   "[ AIRSTRIKE ]  Click to set target",
+                let hint = match (airstrike_weapon, self.pending_target.is_some()) {
+                    (Weapon::NapalmStrike, false) => "[ NAPALM STRIKE ]  Click to set target",
+                    (Weapon::NapalmStrike, true) =>  "[ NAPALM STRIKE ]  Tap the target again to confirm",
+                    (_, false) =>                    "[ AIRSTRIKE ]  Click to set target",
+                    (_, true) =>                     "[ AIRSTRIKE ]  Tap the target again to confirm",
                 };
                 let sw = screen_width();
                 let tw = measure_text(hint, None, 22, 1.0).width;
@@ -3194,22 +5433,122 @@ impl Game {
             }
         }
 
+        // Blocked-shot warning: the predicted impact is right at the muzzle
+        if self.is_my_turn() && self.phase.allows_input() && !self.has_fired && self.aim_blocked() {
+            let hint = "[ BLOCKED ]  This shot will hit the wall in front of you";
+            let sw = screen_width();
+            let tw = measure_text(hint, None, 22, 1.0).width;
+            draw_text(hint, sw / 2.0 - tw / 2.0, 84.0, 22.0, Color::new(1.0, 0.25, 0.2, 1.0));
+        } else if self.is_my_turn() && self.phase.allows_input() && !self.has_fired && Self::alters_terrain(self.selected_weapon)
+            && self.terrain_budget_left() == Some(0)
+        {
+            let hint = "[ NO TERRAIN BUDGET ]  Drill, foxhole and wall are used up this turn";
+            let sw = screen_width();
+            let tw = measure_text(hint, None, 20, 1.0).width;
+            draw_text(hint, sw / 2.0 - tw / 2.0, 84.0, 20.0, Color::new(1.0, 0.6, 0.2, 1.0));
+        } else if self.is_my_turn() && self.phase.allows_input() && !self.has_fired {
+            let danger = self.shot_self_danger(self.current_ball, self.aim_angle, self.preview_power(), self.selected_weapon);
+            if danger >= 1.0 {
+                let hint = format!("[ CAREFUL ]  This shot would hurt you for ~{:.0}", danger);
+                let sw = screen_width();
+                let tw = measure_text(&hint, None, 20, 1.0).width;
+                draw_text(&hint, sw / 2.0 - tw / 2.0, 84.0, 20.0, Color::new(1.0, 0.6, 0.2, 1.0));
+            }
+        }
+
+        // Remaining terrain budget when the match limits it
+        if let Some(left) = self.terrain_budget_left().filter(|_| self.phase.allows_input()) {
+            let text = format!("Terrain budget: {} px", left);
+            let sw = screen_width();
+            let tw = measure_text(&text, None, 16, 1.0).width;
+            let color = if left == 0 { Color::new(1.0, 0.4, 0.3, 0.9) } else { Color::new(0.8, 0.85, 0.9, 0.9) };
+            draw_text(&text, sw / 2.0 - tw / 2.0, 106.0, 16.0, color);
+        }
+
+        if self.is_spectator() {
+            let hint = if self.director.enabled { "DIRECTOR CAM  [V] off" } else { "[V] director cam" };
+            let sw = screen_width();
+            let tw = measure_text(hint, None, 16, 1.0).width;
+            draw_text(hint, sw - tw - 12.0, 62.0, 16.0, Color::new(1.0, 1.0, 1.0, 0.7));
+            let hint = if self.cast_aims_overlay { "ALL AIMS  [C] off" } else { "[C] all aims" };
+            let tw = measure_text(hint, None, 16, 1.0).width;
+            draw_text(hint, sw - tw - 12.0, 82.0, 16.0, Color::new(1.0, 1.0, 1.0, 0.7));
+        } else if self.is_my_turn() && matches!(self.phase, Phase::Aiming | Phase::Charging) {
+            let has_ghost = self.sim.balls.get(self.current_ball)
+                .and_then(|b| self.ghost_shots.get(b.team as usize))
+                .is_some_and(|p| !p.is_empty());
+            if has_ghost {
+                let hint = if self.ghost_overlay { "LAST SHOT  [G] off" } else { "[G] last shot" };
+                let sw = screen_width();
+                let tw = measure_text(hint, None, 16, 1.0).width;
+                draw_text(hint, sw - tw - 12.0, 62.0, 16.0, Color::new(1.0, 1.0, 1.0, 0.7));
+            }
+        }
+
+        if self.terrain_ops_overlay {
+            let note = format!(
+                "Terrain ops v{}{}: {} blasts, {} drills, {} walls, {} foxholes, {} quakes, {} regrowths",
+                self.terrain_log_version,
+                if self.terrain_snapshot.is_some() { " +snapshot" } else { "" },
+                self.sim.terrain.damage_log.len(), self.drill_log.len(), self.wall_log.len(),
+                self.foxhole_log.len(), self.quake_log.len(), self.grass_log.len(),
+            );
+            draw_text(&note, 12.0, screen_height() - 30.0, 14.0, Color::new(1.0, 0.6, 0.3, 0.9));
+        }
+
+        // Low-memory diagnostic: say so instead of leaving the player guessing at blurry terrain
+        if self.terrain_texture_scale != 1 && !(self.low_spec && self.terrain_texture_scale == LOW_SPEC_TERRAIN_SCALE) {
+            let note = if self.terrain_texture.is_some() {
+                format!("Low memory: terrain at 1/{} resolution", self.terrain_texture_scale)
+            } else {
+                "Low memory: terrain drawn without a texture".to_string()
+            };
+            let sh = screen_height();
+            draw_text(&note, 12.0, sh - 12.0, 14.0, Color::new(1.0, 0.8, 0.3, 0.8));
+        }
+
+        if self.final_cam_showing() {
+            // Cinematic letterbox in place of the HUD
+            let (sw, sh) = (screen_width(), screen_height());
+            let bar = sh * 0.1;
+            draw_rectangle(0.0, 0.0, sw, bar, BLACK);
+            draw_rectangle(0.0, sh - bar, sw, bar, BLACK);
+            return;
+        }
+
         let is_my_turn = self.is_my_turn();
         let turn_owner = self.turn_owner_label();
-        hud::draw_hud(
-            &self.balls,
-            self.current_ball,
-            self.phase,
-            self.selected_weapon,
-            self.charge_power,
-            if self.phase == Phase::Retreat { self.retreat_timer } else { self.turn_timer },
-            self.wind,
-            self.winning_team,
+        let turn_preview = self.turn_preview();
+        let loadout = self.current_loadout();
+        hud::draw_hud(hud::HudState {
+            balls: &self.sim.balls,
+            current_ball: self.current_ball,
+            phase: self.phase,
+            selected_weapon: self.selected_weapon,
+            charge_power: self.charge_power,
+            turn_timer: if self.phase == Phase::Retreat { self.retreat_timer } else { self.turn_timer },
+            wind: self.sim.wind,
+            winning_team: self.winning_team,
             is_my_turn,
-            &turn_owner,
-            self.weapon_menu_open,
-            self.weapon_menu_scroll,
-        );
+            turn_owner_name: &turn_owner,
+            turn_preview: &turn_preview,
+            weapon_menu_open: self.weapon_menu_open,
+            weapon_menu_scroll: self.weapon_menu_scroll,
+            loadout: &loadout,
+            compare_weapon: self.compare_weapon,
+            menu_filter: &self.menu_filter,
+            weapon_flash: self.weapon_flash_timer,
+            rematch: self.net.connected.then(|| {
+                let (ready, needed) = self.net.rematch_progress();
+                (ready, needed, self.net.is_ready())
+            }),
+        });
+
+        if self.forfeit_confirm_timer > 0.0 {
+            let text = "Press End again to forfeit";
+            let tw = measure_text(text, None, 24, 1.0).width;
+            draw_text(text, (screen_width() - tw) / 2.0, screen_height() * 0.3, 24.0, Color::new(1.0, 0.4, 0.3, 1.0));
+        }
     }
 
     fn draw_sky(&self) {
@@ -3217,7 +5556,7 @@ impl Game {
         let vh = self.cam.visible_height();
         let left = self.cam.x - vw / 2.0;
         let top = self.cam.y - vh / 2.0;
-        let steps = 8;
+        let steps = if self.low_spec { 1 } else { 8 };
         let step_h = vh / steps as f32;
         for i in 0..steps {
             let t = i as f32 / steps as f32;
@@ -3229,16 +5568,16 @@ impl Game {
     }
 
     fn draw_water(&self) {
-        let water_y = terrain::WATER_LEVEL;
+        let water_y = self.sim.terrain.water_level();
         let t = get_time() as f32;
-        let level_w = self.terrain.width as f32;
+        let level_w = self.sim.terrain.width as f32;
 
         // Draw water bounded to the level width (not viewport width)
         draw_rectangle(
             0.0,
             water_y,
             level_w,
-            self.terrain.height as f32 - water_y + 100.0,
+            self.sim.terrain.height as f32 - water_y + 100.0,
             Color::new(0.08, 0.25, 0.55, 0.85),
         );
 
@@ -3253,12 +5592,53 @@ impl Game {
         }
     }
 
+    /// Damage ball `idx` would likely take from its own `weapon` shot: simulates the
+    /// trajectory and applies the blast falloff at the predicted impact. Bounces, bomblets
+    /// and knockback aren't modelled, so it's an estimate; 0 means safe. Changes nothing.
+    fn shot_self_danger(&self, idx: usize, angle: f32, power: f32, weapon: Weapon) -> f32 {
+        if weapon.weapon_type() != weapons::WeaponType::Projectile || self.spare_team(idx).is_some() {
+            return 0.0;
+        }
+        let ball = match self.sim.balls.get(idx) {
+            Some(b) if b.alive => b,
+            _ => return 0.0,
+        };
+        let max_damage = physics::scale_damage(weapon.base_damage(), self.damage_scale(idx));
+        projectile::self_blast_damage(ball, angle, power, weapon, self.sim.wind, &self.sim.terrain, max_damage) as f32
+    }
+
+    /// Whether the current aim would detonate a projectile weapon in the shooter's face.
+    fn aim_blocked(&self) -> bool {
+        match self.selected_weapon {
+            Weapon::BaseballBat | Weapon::SniperRifle | Weapon::Uzi | Weapon::Shotgun
+            | Weapon::Mine | Weapon::Dynamite | Weapon::Drill | Weapon::Teleport
+            | Weapon::BuildWall | Weapon::Airstrike | Weapon::NapalmStrike
+            | Weapon::Jetpack | Weapon::Parachute | Weapon::Rope | Weapon::Shield
+            | Weapon::Foxhole | Weapon::Flares => return false,
+            _ => {}
+        }
+        let ball = match self.sim.balls.get(self.current_ball) {
+            Some(b) if b.alive => b,
+            _ => return false,
+        };
+        let power = if self.charging { self.charge_power } else { 50.0 };
+        let muzzle_x = ball.x + self.aim_angle.cos() * (ball.radius + 4.0);
+        let muzzle_y = ball.y + self.aim_angle.sin() * (ball.radius + 4.0);
+        let traj = projectile::simulate_trajectory(
+            muzzle_x, muzzle_y, self.aim_angle, power, self.selected_weapon, self.sim.wind, &self.sim.terrain,
+        );
+        projectile::is_trajectory_blocked(muzzle_x, muzzle_y, &traj, &self.sim.terrain)
+    }
+
     fn draw_aim(&self) {
         let idx = self.current_ball;
-        if idx >= self.balls.len() || !self.balls[idx].alive {
+        if idx >= self.sim.balls.len() || !self.sim.balls[idx].alive {
             return;
         }
-        let ball = &self.balls[idx];
+        let ball = &self.sim.balls[idx];
+        if self.ghost_overlay {
+            self.draw_ghost_shot(ball.team);
+        }
         let bx = ball.x;
         let by = ball.y;
         let angle = self.aim_angle;
@@ -3266,6 +5646,23 @@ impl Game {
         let sin_a = angle.sin();
         let pi = std::f32::consts::PI;
 
+        // Allowed aim arc on the side the ball is aiming at
+        if let Some((lo, hi)) = self.selected_weapon.aim_limits() {
+            let side = if cos_a >= 0.0 { 1.0 } else { -1.0 };
+            let (r, steps) = (ball.radius + 26.0, 16);
+            let point = |e: f32| (bx + side * e.cos() * r, by - e.sin() * r);
+            let color = Color::new(1.0, 1.0, 1.0, 0.35);
+            for i in 0..steps {
+                let (x1, y1) = point(lo + (hi - lo) * i as f32 / steps as f32);
+                let (x2, y2) = point(lo + (hi - lo) * (i + 1) as f32 / steps as f32);
+                draw_line(x1, y1, x2, y2, 2.0, color);
+            }
+            for e in [lo, hi] {
+                let (x, y) = point(e);
+                draw_line(bx + side * e.cos() * (r - 6.0), by - e.sin() * (r - 6.0), x, y, 1.5, color);
+            }
+        }
+
         match self.selected_weapon {
             // ── Baseball Bat ─────────────────────────────────────────────────
             Weapon::BaseballBat => {
@@ -3285,17 +5682,17 @@ impl Game {
                 draw_line(bx, by, tip_x, tip_y, 3.0, Color::new(0.9, 0.7, 0.3, 0.85));
                 draw_circle(tip_x, tip_y, 5.0, Color::new(0.95, 0.8, 0.4, 0.9));
                 draw_circle_lines(tip_x, tip_y, 6.5, 1.5, Color::new(1.0, 0.9, 0.5, 0.8));
-                for (i, w) in self.balls.iter().enumerate() {
+                for (i, w) in self.sim.balls.iter().enumerate() {
                     if i == idx || !w.alive || w.team == ball.team { continue; }
                     let dx = w.x - bx;
                     let dy = w.y - by;
                     let dist = (dx * dx + dy * dy).sqrt();
                     if dist < bat_range {
                         let pulse = (get_time() as f32 * 4.0).sin() * 0.25 + 0.75;
-                        draw_circle_lines(w.x, w.y, BALL_RADIUS + 4.0, 2.0,
+                        draw_circle_lines(w.x, w.y, w.radius + 4.0, 2.0,
                             Color::new(1.0, 0.15, 0.15, pulse));
                         let label = format!("{:.0}", dist);
-                        draw_text(&label, w.x - 8.0, w.y - BALL_RADIUS - 10.0, 14.0,
+                        draw_text(&label, w.x - 8.0, w.y - w.radius - 10.0, 14.0,
                             Color::new(1.0, 0.7, 0.3, 0.9));
                     }
                 }
@@ -3306,11 +5703,11 @@ impl Game {
                 let max_range = 1600.0f32;
                 let mut hit_x = bx + cos_a * max_range;
                 let mut hit_y = by + sin_a * max_range;
-                let mut t = BALL_RADIUS + 4.0;
+                let mut t = ball.radius + 4.0;
                 while t < max_range {
                     let rx = bx + cos_a * t;
                     let ry = by + sin_a * t;
-                    if self.terrain.is_solid(rx as i32, ry as i32) {
+                    if self.sim.terrain.is_solid(rx as i32, ry as i32) {
                         hit_x = rx; hit_y = ry; break;
                     }
                     t += 2.0;
@@ -3341,11 +5738,11 @@ impl Game {
                     let sa = ray_angle.sin();
                     let mut ex = bx + ca * range;
                     let mut ey = by + sa * range;
-                    let mut tr = BALL_RADIUS + 4.0;
+                    let mut tr = ball.radius + 4.0;
                     while tr < range {
                         let rx = bx + ca * tr;
                         let ry = by + sa * tr;
-                        if self.terrain.is_solid(rx as i32, ry as i32) {
+                        if self.sim.terrain.is_solid(rx as i32, ry as i32) {
                             ex = rx; ey = ry; break;
                         }
                         ex = rx; ey = ry;
@@ -3371,11 +5768,11 @@ impl Game {
                     let sa = ray_angle.sin();
                     let mut ex = bx + ca * range;
                     let mut ey = by + sa * range;
-                    let mut tr = BALL_RADIUS + 4.0;
+                    let mut tr = ball.radius + 4.0;
                     while tr < range {
                         let rx = bx + ca * tr;
                         let ry = by + sa * tr;
-                        if self.terrain.is_solid(rx as i32, ry as i32) {
+                        if self.sim.terrain.is_solid(rx as i32, ry as i32) {
                             ex = rx; ey = ry; break;
                         }
                         ex = rx; ey = ry;
@@ -3397,10 +5794,10 @@ impl Game {
             // ── Homing Missile ────────────────────────────────────────────────
             Weapon::HomingMissile => {
                 // Show trajectory arc
-                let power_for_preview = if self.charging { self.charge_power } else { 50.0 };
+                let power_for_preview = self.preview_power();
                 let traj = projectile::simulate_trajectory(
-                    bx + cos_a * (BALL_RADIUS + 4.0), by + sin_a * (BALL_RADIUS + 4.0),
-                    angle, power_for_preview, Weapon::HomingMissile, self.wind, &self.terrain,
+                    bx + cos_a * (ball.radius + 4.0), by + sin_a * (ball.radius + 4.0),
+                    angle, power_for_preview, Weapon::HomingMissile, self.sim.wind, &self.sim.terrain,
                 );
                 for (i, &(tx, ty)) in traj.iter().enumerate() {
                     if i % 2 == 0 {
@@ -3408,18 +5805,21 @@ impl Game {
                         draw_circle(tx, ty, 1.5, Color::new(1.0, 0.5, 0.2, alpha * 0.6));
                     }
                 }
+                if let Some(&(ix, iy)) = traj.last() {
+                    Self::draw_impact_preview(ix, iy, Weapon::HomingMissile.explosion_radius(), power_for_preview);
+                }
                 // Lock-on reticle for nearest enemy
-                let mut closest: Option<(f32, f32)> = None;
+                let mut closest: Option<(f32, f32, f32)> = None;
                 let mut best_dist = f32::MAX;
-                for (i, w) in self.balls.iter().enumerate() {
+                for (i, w) in self.sim.balls.iter().enumerate() {
                     if i == idx || !w.alive || w.team == ball.team { continue; }
                     let dx = w.x - bx; let dy = w.y - by;
                     let dist = (dx * dx + dy * dy).sqrt();
-                    if dist < best_dist { best_dist = dist; closest = Some((w.x, w.y)); }
+                    if dist < best_dist { best_dist = dist; closest = Some((w.x, w.y, w.radius)); }
                 }
-                if let Some((tx, ty)) = closest {
+                if let Some((tx, ty, radius)) = closest {
                     let pulse = (get_time() as f32 * 4.0).sin() * 0.25 + 0.75;
-                    let r = BALL_RADIUS + 8.0;
+                    let r = radius + 8.0;
                     draw_circle_lines(tx, ty, r, 2.0, Color::new(1.0, 0.2, 0.2, pulse));
                     let arm = r * 0.5;
                     let gap = r * 0.65;
@@ -3437,7 +5837,7 @@ impl Game {
                 let radius = self.selected_weapon.explosion_radius();
                 let pulse = (get_time() as f32 * 2.5).sin() * 0.15 + 0.55;
                 let foot_x = bx;
-                let foot_y = by + BALL_RADIUS + 2.0;
+                let foot_y = by + ball.radius + 2.0;
                 draw_circle(foot_x, foot_y, radius, Color::new(1.0, 0.3, 0.1, 0.06));
                 draw_circle_lines(foot_x, foot_y, radius, 1.5, Color::new(1.0, 0.3, 0.1, pulse));
                 draw_circle(foot_x, foot_y, 4.0, Color::new(1.0, 0.3, 0.1, pulse));
@@ -3452,22 +5852,31 @@ impl Game {
 
             // ── All other projectile weapons ──────────────────────────────────
             _ => {
+                let power_for_preview = self.preview_power();
+                let muzzle_x = bx + cos_a * (ball.radius + 4.0);
+                let muzzle_y = by + sin_a * (ball.radius + 4.0);
+                let traj = projectile::simulate_trajectory(
+                    muzzle_x,
+                    muzzle_y,
+                    angle, power_for_preview,
+                    self.selected_weapon,
+                    self.sim.wind,
+                    &self.sim.terrain,
+                );
+                let blocked = projectile::is_trajectory_blocked(muzzle_x, muzzle_y, &traj, &self.sim.terrain);
+
                 let line_len = 50.0 + self.charge_power * 0.5;
                 let ex = bx + cos_a * line_len;
                 let ey = by + sin_a * line_len;
-                draw_line(bx, by, ex, ey, 2.0, Color::new(1.0, 1.0, 0.4, 0.8));
+                let line_color = if blocked {
+                    Color::new(1.0, 0.15, 0.1, 0.9)
+                } else {
+                    Color::new(1.0, 1.0, 0.4, 0.8)
+                };
+                draw_line(bx, by, ex, ey, 2.0, line_color);
                 draw_circle(ex, ey, 4.0, Color::new(1.0, 0.2, 0.2, 0.8));
                 draw_circle_lines(ex, ey, 6.0, 1.5, WHITE);
-
-                let power_for_preview = if self.charging { self.charge_power } else { 50.0 };
-                let traj = projectile::simulate_trajectory(
-                    bx + cos_a * (BALL_RADIUS + 4.0),
-                    by + sin_a * (BALL_RADIUS + 4.0),
-                    angle, power_for_preview,
-                    self.selected_weapon,
-                    self.wind,
-                    &self.terrain,
-                );
+                self.draw_wind_band(muzzle_x, muzzle_y, angle, power_for_preview);
                 let impact = traj.last().copied();
                 for (i, &(tx, ty)) in traj.iter().enumerate() {
                     if i % 2 == 0 {
@@ -3475,25 +5884,115 @@ impl Game {
                         draw_circle(tx, ty, 1.5, Color::new(1.0, 1.0, 0.6, alpha * 0.6));
                     }
                 }
-                // Explosion radius circle at predicted impact point
-                let radius = self.selected_weapon.explosion_radius();
-                if radius > 0.0 {
-                    if let Some((ix, iy)) = impact {
-                        draw_circle(ix, iy, radius, Color::new(1.0, 0.45, 0.1, 0.08));
-                        draw_circle_lines(ix, iy, radius, 1.5, Color::new(1.0, 0.55, 0.2, 0.65));
-                        // Tick marks on explosion circle
-                        for i in 0..8 {
-                            let a = i as f32 * pi * 0.25;
-                            draw_line(
-                                ix + a.cos() * (radius - 4.0), iy + a.sin() * (radius - 4.0),
-                                ix + a.cos() * (radius + 4.0), iy + a.sin() * (radius + 4.0),
-                                1.5, Color::new(1.0, 0.55, 0.2, 0.8),
-                            );
-                        }
-                    }
+                if let Some((ix, iy)) = impact {
+                    Self::draw_impact_preview(ix, iy, self.selected_weapon.explosion_radius(), power_for_preview);
                 }
             }
         }
+
+        if is_key_down(KeyCode::X) {
+            self.draw_compare_arc(ball);
+        }
+    }
+
+    /// The team's previous shell flight as faint dots ending in a cross where it came down,
+    /// to line the new aim up against.
+    fn draw_ghost_shot(&self, team: u32) {
+        let Some(path) = self.ghost_shots.get(team as usize).filter(|p| !p.is_empty()) else { return };
+        let color = Color::new(1.0, 1.0, 1.0, 0.3);
+        for &(x, y) in path.iter().step_by(3) {
+            draw_circle(x, y, 1.2, color);
+        }
+        let (ex, ey) = path[path.len() - 1];
+        draw_line(ex - 4.0, ey - 4.0, ex + 4.0, ey + 4.0, 1.5, color);
+        draw_line(ex - 4.0, ey + 4.0, ex + 4.0, ey - 4.0, 1.5, color);
+    }
+
+    /// The compare weapon's arc for the same aim and power, in cyan, so two weapons can
+    /// be weighed against each other for one shot. Only thrown and fired projectiles
+    /// have an arc to compare.
+    fn draw_compare_arc(&self, ball: &Ball) {
+        let Some(weapon) = self.compare_weapon.filter(|&w| w != self.selected_weapon) else { return };
+        if weapon.weapon_type() != weapons::WeaponType::Projectile || matches!(weapon, Weapon::Mine | Weapon::Dynamite) {
+            return;
+        }
+        let angle = weapon.clamp_aim(self.aim_angle);
+        let power = self.preview_power();
+        let muzzle_x = ball.x + angle.cos() * (ball.radius + 4.0);
+        let muzzle_y = ball.y + angle.sin() * (ball.radius + 4.0);
+        let traj = projectile::simulate_trajectory(muzzle_x, muzzle_y, angle, power, weapon, self.sim.wind, &self.sim.terrain);
+        for (i, &(tx, ty)) in traj.iter().enumerate() {
+            if i % 2 == 0 {
+                let alpha = 1.0 - i as f32 / traj.len().max(1) as f32;
+                draw_circle(tx, ty, 1.5, Color::new(0.3, 0.9, 1.0, alpha * 0.7));
+            }
+        }
+        if let Some(&(ix, iy)) = traj.last() {
+            let radius = weapon.explosion_radius();
+            if radius > 0.0 {
+                draw_circle_lines(ix, iy, radius, 1.5, Color::new(0.3, 0.9, 1.0, 0.7));
+            }
+            let tw = measure_text(weapon.name(), None, 11, 1.0).width;
+            draw_text(weapon.name(), ix - tw / 2.0, iy - radius - 6.0, 11.0, Color::new(0.3, 0.9, 1.0, 0.9));
+        }
+    }
+
+    /// Faint envelope between the arcs the shot would fly at wind ± WIND_BAND_DELTA, so a
+    /// wide band warns that the shot is sensitive to the wind.
+    fn draw_wind_band(&self, muzzle_x: f32, muzzle_y: f32, angle: f32, power: f32) {
+        let arc = |wind: f32| projectile::simulate_trajectory(
+            muzzle_x, muzzle_y, angle, power, self.selected_weapon, wind, &self.sim.terrain,
+        );
+        let low = arc(self.sim.wind - WIND_BAND_DELTA);
+        let high = arc(self.sim.wind + WIND_BAND_DELTA);
+        let n = low.len().min(high.len());
+        for i in 1..n {
+            let alpha = (1.0 - i as f32 / n as f32) * 0.12;
+            let fill = Color::new(0.6, 0.85, 1.0, alpha);
+            let (a, b) = (Vec2::from(low[i - 1]), Vec2::from(high[i - 1]));
+            let (c, d) = (Vec2::from(low[i]), Vec2::from(high[i]));
+            draw_triangle(a, b, c, fill);
+            draw_triangle(b, d, c, fill);
+        }
+        for edge in [&low, &high] {
+            for w in edge.windows(2).step_by(2) {
+                draw_line(w[0].0, w[0].1, w[1].0, w[1].1, 1.0, Color::new(0.6, 0.85, 1.0, 0.3));
+            }
+        }
+    }
+
+    /// Power the trajectory preview uses: the live charge while charging, a recalled
+    /// shot's target power when one is queued, otherwise a mid-range default.
+    fn preview_power(&self) -> f32 {
+        if self.charging {
+            self.charge_power
+        } else {
+            self.charge_target.unwrap_or(50.0)
+        }
+    }
+
+    /// Explosion radius circle at the predicted impact point, plus a pulse that grows and
+    /// quickens with `power` so charging visibly pushes the shot further.
+    fn draw_impact_preview(ix: f32, iy: f32, radius: f32, power: f32) {
+        let pi = std::f32::consts::PI;
+        if radius > 0.0 {
+            draw_circle(ix, iy, radius, Color::new(1.0, 0.45, 0.1, 0.08));
+            draw_circle_lines(ix, iy, radius, 1.5, Color::new(1.0, 0.55, 0.2, 0.65));
+            // Tick marks on explosion circle
+            for i in 0..8 {
+                let a = i as f32 * pi * 0.25;
+                draw_line(
+                    ix + a.cos() * (radius - 4.0), iy + a.sin() * (radius - 4.0),
+                    ix + a.cos() * (radius + 4.0), iy + a.sin() * (radius + 4.0),
+                    1.5, Color::new(1.0, 0.55, 0.2, 0.8),
+                );
+            }
+        }
+        let p = (power / 100.0).clamp(0.0, 1.0);
+        let phase = (get_time() as f32 * (1.0 + p * 2.0)).fract();
+        let max_r = 6.0 + radius.max(10.0) * (0.3 + 0.7 * p);
+        draw_circle_lines(ix, iy, max_r * phase, 2.0, Color::new(1.0, 0.8, 0.3, (1.0 - phase) * 0.8));
+        draw_circle(ix, iy, 2.5 + p * 2.5, Color::new(1.0, 0.7, 0.2, 0.9));
     }
 }
 
@@ -3501,6 +6000,11 @@ fn lcg(s: u32) -> u32 {
     s.wrapping_mul(1103515245).wrapping_add(12345)
 }
 
+/// Seed of the grass regrowth pass at the start of `turn`
+fn grass_seed(map_seed: u32, turn: u32) -> u32 {
+    map_seed ^ turn.wrapping_mul(0x27D4_EB2F)
+}
+
 /// Escape a player/ball name for safe embedding in a JSON string value.
 fn sanitize_event_name(name: &str) -> String {
     name.chars()
@@ -3527,6 +6031,15 @@ fn parse_state_turn_index(msg: &str) -> Option<usize> {
     num_slice[..num_end].trim().parse().ok()
 }
 
+/// Player indices from a `{"type":"ready","ready":[0,2]}` broadcast.
+fn parse_ready_message(msg: &str) -> Vec<usize> {
+    let Some(start) = msg.find("\"ready\":[").map(|i| i + 9) else {
+        return Vec::new();
+    };
+    let end = msg[start..].find(']').map_or(msg.len(), |i| start + i);
+    msg[start..end].split(',').filter_map(|n| n.trim().parse().ok()).collect()
+}
+
 fn parse_turn_index_from_message(msg: &str) -> Option<usize> {
     let turn_pos = msg.find("\"turnIndex\":")?;
     let after_turn = msg.get(turn_pos..)?;
@@ -3561,26 +6074,7 @@ fn parse_input_message(msg: &str) -> Option<(usize, String)> {
     }
     let raw = msg.get(input_start..input_end)?;
     // Unescape JSON string escapes so inner parsers see clean JSON
-    let unescaped = raw.replace("\\\"", "\"").replace("\\\\", "\\");
-    Some((turn_index, unescaped))
-}
-
-fn parse_fire_input(input: &str) -> Option<(f32, f32, Weapon)> {
-    if !input.contains("Fire") {
-        return None;
-    }
-    let angle_deg = parse_json_number(input, "angle_deg")? as f32;
-    let power_percent = parse_json_number(input, "power_percent")? as f32;
-    let weapon_name = parse_json_string(input, "weapon")?;
-    let weapon = Weapon::from_name(weapon_name)?;
-    Some((angle_deg.to_radians(), power_percent, weapon))
-}
-
-fn parse_walk_input(input: &str) -> Option<f32> {
-    if !input.contains("Walk") {
-        return None;
-    }
-    parse_json_number(input, "dir").map(|v| v as f32)
+    Some((turn_index, net_input::unescape_json(raw)))
 }
 
 fn parse_aim_message(msg: &str) -> Option<(usize, f32)> {
@@ -3598,6 +6092,217 @@ fn parse_aim_message(msg: &str) -> Option<(usize, f32)> {
     Some((turn_index, aim_angle))
 }
 
+/// Parse a weapon_select message: {"type":"weapon_select","weapon":"Bazooka","turnIndex":N}
+fn parse_weapon_select_message(msg: &str) -> Option<(usize, Weapon)> {
+    let turn_index = parse_turn_index_from_message(msg)?;
+    let weapon = Weapon::from_name(parse_json_string(msg, "weapon")?)?;
+    Some((turn_index, weapon))
+}
+
+/// Longest custom ball name kept; longer names are cut so the HUD labels stay readable.
+const MAX_BALL_NAME_LEN: usize = 16;
+
+/// Split the init message's `ballNames` value: teams separated by `;`, names by `,`
+/// (e.g. `"Ann,Bo,Cy;Dee,,Fay"`, where the empty entry keeps the generated name).
+fn parse_ball_names(raw: &str) -> Vec<Vec<String>> {
+    net_input::unescape_json(raw)
+        .split(';')
+        .map(|team| {
+            team.split(',')
+                .map(|n| n.trim().chars().take(MAX_BALL_NAME_LEN).collect())
+                .collect()
+        })
+        .collect()
+}
+
+const BALLS_PER_TEAM: usize = 3;
+const TEAM_BALL_NAMES: [[&str; BALLS_PER_TEAM]; 4] = [
+    ["Spike", "Tank", "Blaze"],
+    ["Frost", "Storm", "Shadow"],
+    ["Viper", "Ghost", "Flash"],
+    ["Rex", "Duke", "Scout"],
+];
+
+/// Name of ball `wi` of team `ti` until the front-end sends its own. Teams past the table
+/// reuse its names with the team number so nobody shares a name.
+fn default_ball_name(ti: usize, wi: usize) -> String {
+    let base = TEAM_BALL_NAMES[ti % TEAM_BALL_NAMES.len()][wi % BALLS_PER_TEAM];
+    if ti < TEAM_BALL_NAMES.len() { base.to_string() } else { format!("{} {}", base, ti + 1) }
+}
+
+/// Weapons `team` may use from the start: a seeded subset under random loadouts, else all.
+fn starting_loadout(seed: u32, team: u32, settings: MatchSettings) -> Vec<Weapon> {
+    if settings.random_loadout {
+        weapons::random_loadout(seed, team)
+    } else {
+        Weapon::all().to_vec()
+    }
+}
+
+/// Spawn point for a ball of `radius` near column `x`: on the surface there, or the
+/// nearest column within 50px that isn't by lava or at the bottom of a water pocket.
+fn safe_spawn(t: &Terrain, x: f32, radius: f32) -> (f32, f32) {
+    // Find safe spawn position (avoid lava)
+    let mut spawn_y = None;
+    let mut search_x = x as i32;
+    
+    // Try original position first
+    if let Some(surface_y) = t.find_surface_y(search_x) {
+        // Check if there's lava at or near where the ball would spawn
+        let ball_y = surface_y - (radius as i32) - 2;
+        
+        // Don't spawn at the bottom of a water pocket
+        let mut is_safe = t.get(search_x, surface_y - 1) != terrain::WATER;
+
+        // Check area around spawn position for lava
+        for dy in -2..3 {
+            for dx in -2..3 {
+                let check_x = search_x + dx;
+                let check_y = ball_y + dy;
+                if t.get(check_x, check_y) == terrain::LAVA {
+                    is_safe = false;
+                    break;
+                }
+            }
+            if !is_safe { break; }
+        }
+        
+        if is_safe {
+            spawn_y = Some(surface_y as f32 - radius - 2.0);
+        }
+    }
+    
+    // If original position has lava, search nearby for safe spot
+    if spawn_y.is_none() {
+        for offset in 1..50 {
+            for dir in [-1, 1] {
+                let test_x = (x as i32 + offset * dir).max(t.land_start_x() as i32).min(t.land_end_x() as i32);
+                if let Some(surface_y) = t.find_surface_y(test_x) {
+                    let ball_y = surface_y - (radius as i32) - 2;
+                    let mut is_safe = t.get(test_x, surface_y - 1) != terrain::WATER;
+                    
+                    // Check area around spawn position for lava
+                    for dy in -2..3 {
+                        for dx in -2..3 {
+                            let check_x = test_x + dx;
+                            let check_y = ball_y + dy;
+                            if t.get(check_x, check_y) == terrain::LAVA {
+                                is_safe = false;
+                                break;
+                            }
+                        }
+                        if !is_safe { break; }
+                    }
+                    
+                    if is_safe {
+                        spawn_y = Some(surface_y as f32 - radius - 2.0);
+                        search_x = test_x;
+                        break;
+                    }
+                }
+            }
+            if spawn_y.is_some() {
+                break;
+            }
+        }
+    }
+    
+    (search_x as f32, spawn_y.unwrap_or(400.0))
+}
+
+/// Read a hand-made map from an init message: `customTerrain` holds the cells as
+/// `cell:count` runs (the save format), `customSpawns` optional comma-separated spawn x
+/// positions in ball slot order. None when absent or when the cells don't validate.
+fn parse_custom_map(msg: &str) -> Option<CustomMap> {
+    let mut t = Terrain::new(terrain::WIDTH, terrain::HEIGHT);
+    t.decode_cells(parse_json_string(msg, "customTerrain")?)?;
+    let spawns = parse_json_string(msg, "customSpawns")
+        .map(|raw| raw.split(',').filter_map(|x| x.trim().parse().ok()).collect())
+        .unwrap_or_default();
+    CustomMap::new(t.cells, spawns)
+}
+
+/// Read optional match settings from an init message; missing keys keep their defaults.
+fn parse_match_settings(msg: &str) -> MatchSettings {
+    let mut settings = MatchSettings::default();
+    if let Some(d) = parse_json_number(msg, "terrainDensity") {
+        settings.terrain_density = (d as f32).clamp(0.0, 1.0);
+    }
+    if let Some(r) = parse_json_number(msg, "movementRegen") {
+        settings.movement_regen = (r as f32).clamp(0.0, 1.0);
+    }
+    if let Some(p) = parse_json_number(msg, "craterPush") {
+        settings.crater_push = (p as f32).clamp(0.0, 1.0);
+    }
+    if let Some(p) = parse_json_number(msg, "blastPush") {
+        settings.blast_push = (p as f32).clamp(0.0, 1.0);
+    }
+    if let Some(k) = parse_json_number(msg, "knockbackScale") {
+        settings.knockback_scale = (k as f32).clamp(0.25, 4.0);
+    }
+    if let Some(d) = parse_json_number(msg, "weaponDamage") {
+        settings.weapon_damage = (d as f32).clamp(0.0, 1.0);
+    }
+    if let Some(n) = parse_json_number(msg, "earthquakeEvery") {
+        settings.earthquake_every = n.max(0.0) as u32;
+    }
+    if let Some(n) = parse_json_number(msg, "crateRainAfter") {
+        settings.crate_rain_after = n.max(0.0) as u32;
+    }
+    if let Some(n) = parse_json_number(msg, "shrinkZoneAfter") {
+        settings.shrink_zone_after = n.max(0.0) as u32;
+    }
+    if let Some(n) = parse_json_number(msg, "terrainBudget") {
+        settings.terrain_budget = n.max(0.0) as u32;
+    }
+    if let Some(b) = parse_json_bool(msg, "friendlyFire") {
+        settings.friendly_fire = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "randomLoadout") {
+        settings.random_loadout = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "ownTeamPassThrough") {
+        settings.own_team_pass_through = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "lastStand") {
+        settings.last_stand = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "fixedPhysics") {
+        settings.fixed_physics = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "mirrorMap") {
+        settings.mirror_map = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "movingPlatforms") {
+        settings.moving_platforms = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "gasPockets") {
+        settings.gas_pockets = b;
+    }
+    if let Some(b) = parse_json_bool(msg, "halfResTerrain") {
+        settings.half_res_terrain = b;
+    }
+    if let Some(mode) = parse_json_string(msg, "spawnMode").and_then(SpawnMode::from_name) {
+        settings.spawn_mode = mode;
+    }
+    if let Some(size) = parse_json_string(msg, "ballSize").and_then(BallSize::from_name) {
+        settings.ball_size = size;
+    }
+    match parse_json_string(msg, "terrainTheme") {
+        Some("random") => settings.terrain_theme = None,
+        Some(name) => {
+            if let Some(theme) = TerrainTheme::from_name(name) {
+                settings.terrain_theme = Some(theme);
+            }
+        }
+        None => {}
+    }
+    if let Some(b) = parse_json_bool(msg, "randomFirstTurn") {
+        settings.random_first_turn = b;
+    }
+    settings
+}
+
 fn parse_json_number(s: &str, key: &str) -> Option<f64> {
     let key_plain = format!("\"{}\":", key);
     let key_escaped = format!("\\\"{}\\\":", key);
@@ -3606,6 +6311,22 @@ fn parse_json_number(s: &str, key: &str) -> Option<f64> {
     s[start..end].trim().parse().ok()
 }
 
+fn parse_json_bool(s: &str, key: &str) -> Option<bool> {
+    for prefix in &[format!("\"{}\":", key), format!("\\\"{}\\\":", key)] {
+        if let Some(i) = s.find(prefix) {
+            let rest = s[i + prefix.len()..].trim_start();
+            return if rest.starts_with("true") {
+                Some(true)
+            } else if rest.starts_with("false") {
+                Some(false)
+            } else {
+                None
+            };
+        }
+    }
+    None
+}
+
 fn parse_json_string<'a>(s: &'a str, key: &str) -> Option<&'a str> {
     for prefix in &[format!("\"{}\":\"", key), format!("\\\"{}\\\":\\\"", key)] {
         if let Some(i) = s.find(prefix) {
@@ -3628,6 +6349,35 @@ fn parse_json_string<'a>(s: &'a str, key: &str) -> Option<&'a str> {
     None
 }
 
+/// Buffer-worthy action pressed this frame, if any.
+fn buffered_input_pressed() -> Option<BufferedInput> {
+    if is_key_pressed(KeyCode::F) {
+        Some(BufferedInput::Fire)
+    } else if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Space) {
+        Some(BufferedInput::Jump)
+    } else if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+        Some(BufferedInput::Backflip)
+    } else {
+        None
+    }
+}
+
+/// Consume `pending` if it holds `action`. Callers check it last, once everything else
+/// about applying the action has passed.
+fn take_buffered(pending: &mut Option<(BufferedInput, f32)>, action: BufferedInput) -> bool {
+    if pending.is_some_and(|(a, _)| a == action) {
+        *pending = None;
+        true
+    } else {
+        false
+    }
+}
+
+/// Alt turns a tap on the map into a ping for players; spectators ping with a plain tap.
+fn ping_modifier_down() -> bool {
+    is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt)
+}
+
 /// Parse a pos_update message: {"type":"pos_update","bi":N,"x":..,"y":..,"vx":..,"vy":..}
 /// Returns (ball_index, x, y, vx, vy)
 fn parse_pos_update_message(msg: &str) -> Option<(usize, f32, f32, f32, f32)> {
@@ -3659,7 +6409,169 @@ async fn main() {
         let dt = get_frame_time();
         game.handle_input();
         game.update(dt);
+        network::publish_projectile(game.projectile_json());
+        network::publish_selected_weapon(game.selected_weapon.name());
         game.draw();
         next_frame().await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_settings_read_booleans_with_or_without_spaces() {
+        assert!(!parse_match_settings(r#"{"friendlyFire":false}"#).friendly_fire);
+        assert!(!parse_match_settings(r#"{"friendlyFire": false}"#).friendly_fire);
+        assert!(!parse_match_settings(r#"{\"friendlyFire\": false}"#).friendly_fire);
+        assert!(parse_match_settings(r#"{"friendlyFire": true}"#).friendly_fire);
+        assert!(parse_match_settings(r#"{"mirrorMap": true, "gasPockets":true}"#).mirror_map);
+    }
+
+    #[test]
+    fn match_settings_read_from_the_lobby_init() {
+        // As ws_plugin.js forwards the host's lobby choices
+        let init = r#"{"gameId":"g","rngSeed":7,"playerNames":"a,b","settings":{"friendlyFire":false,"terrainTheme":"random","ballSize":"large","earthquakeEvery":3,"knockbackScale":9}}"#;
+        let s = parse_match_settings(init);
+        assert!(!s.friendly_fire);
+        assert_eq!(s.terrain_theme, None);
+        assert_eq!(s.ball_size, BallSize::Large);
+        assert_eq!(s.earthquake_every, 3);
+        assert_eq!(s.knockback_scale, 4.0);
+        assert_eq!(parse_match_settings(r#"{"gameId":"g","settings":{}}"#), MatchSettings::default());
+    }
+
+    #[test]
+    fn a_line_of_mines_chains_one_hop_a_tick_and_stops_at_the_end() {
+        let mut game = Game::new_with_teams(5, 2, MatchSettings::default());
+        // Five mines 15 px apart (inside each other's 20 px blast) high above the
+        // ground, and a sixth well out of reach at the end of the line
+        let mine = |x: f32| PlacedExplosive { x, y: 60.0, fuse: 30.0, alive: true, radius: 20.0, damage: 30, spare_team: None };
+        game.placed_explosives = [200.0, 215.0, 230.0, 245.0, 260.0, 400.0].map(mine).into();
+        game.placed_explosives[0].fuse = 0.001;
+        let mut left = Vec::new();
+        for _ in 0..8 {
+            game.tick_placed_explosives(1.0 / 60.0);
+            left.push(game.placed_explosives.len());
+        }
+        assert_eq!(left, vec![5, 4, 3, 2, 1, 1, 1, 1]);
+        assert_eq!(game.placed_explosives[0].x, 400.0);
+        assert!(game.placed_explosives[0].fuse > 29.0, "the far mine was never set off");
+    }
+
+    #[test]
+    fn rope_hooks_swings_and_lets_go_without_using_the_shot() {
+        let mut game = Game::new_with_teams(9, 2, MatchSettings::default());
+        // An open cave under a flat roof at y = 200
+        for y in 100..560 {
+            for x in 400..1000 {
+                game.sim.terrain.set(x, y, if y < 200 { terrain::DIRT } else { terrain::AIR });
+            }
+        }
+        let idx = game.current_ball;
+        for (i, ball) in game.sim.balls.iter_mut().enumerate().filter(|(i, _)| *i != idx) {
+            ball.x = 100.0 + i as f32 * 20.0;
+        }
+        let ball = &mut game.sim.balls[idx];
+        (ball.x, ball.y, ball.vx, ball.vy, ball.on_ground) = (600.0, 320.0, 0.0, 0.0, false);
+        game.phase = Phase::Aiming;
+        game.selected_weapon = Weapon::Rope;
+        // Up and to the right, so the ball starts its swing off to one side
+        game.aim_angle = -1.1;
+
+        assert!(matches!(game.fire(), Ok(ActionOutcome::Moved)));
+        assert!(!game.has_fired, "the rope must leave the turn's shot");
+        let rope = game.rope.as_ref().expect("rope should hook the roof");
+        assert!((rope.anchor_y - 200.0).abs() < 2.0);
+        let (ax, ay, length) = (rope.anchor_x, rope.anchor_y, rope.length);
+
+        let dist = |b: &Ball| ((b.x - ax).powi(2) + (b.y - ay).powi(2)).sqrt();
+        let mut min_x = f32::MAX;
+        let mut max_x = f32::MIN;
+        for _ in 0..90 {
+            game.update(1.0 / 60.0);
+            let ball = &game.sim.balls[idx];
+            assert!(dist(ball) <= length + 0.5, "ball left the rope: {} > {length}", dist(ball));
+            min_x = min_x.min(ball.x);
+            max_x = max_x.max(ball.x);
+        }
+        assert!(game.rope.is_some());
+        // Swung from the left of the anchor through to the other side
+        assert!(min_x < ax - 20.0 && max_x > ax, "no swing: {min_x}..{max_x} around {ax}");
+
+        // Letting go (F on the rope) drops it and the ball falls free
+        game.rope = None;
+        for _ in 0..30 {
+            game.update(1.0 / 60.0);
+        }
+        assert!(dist(&game.sim.balls[idx]) > length + 5.0);
+    }
+
+    #[test]
+    fn terrain_sync_replays_grass_between_the_craters_it_grew_around() {
+        let settings = MatchSettings { terrain_theme: Some(TerrainTheme::Grassland), ..MatchSettings::default() };
+        // A lone block of bare dirt, so every blade of grass grows where the blasts land
+        let block_map = |seed| {
+            let mut game = Game::new_with_teams(seed, 2, settings);
+            for y in 0..terrain::HEIGHT as i32 {
+                for x in 0..terrain::WIDTH as i32 {
+                    let dirt = (650..750).contains(&x) && y >= 500;
+                    game.sim.terrain.set(x, y, if dirt { terrain::DIRT } else { terrain::AIR });
+                }
+            }
+            game
+        };
+        let mut live = block_map(21);
+        live.sim.terrain.apply_damage(700, 500, 30);
+        live.turns_played = 1;
+        live.grow_grass();
+        // Through the first crater and the grass that grew in it
+        live.sim.terrain.apply_damage(690, 525, 30);
+        live.turns_played = 2;
+        live.grow_grass();
+        assert_eq!(live.grass_log, vec![1, 2]);
+        live.settle_all_sand();
+
+        let msg = live.terrain_damages_msg().unwrap();
+        let mut rejoined = block_map(21);
+        rejoined.apply_terrain_sync(&msg);
+        assert!(live.sim.terrain.runs() == rejoined.sim.terrain.runs(), "replayed terrain differs from the live game");
+        assert_eq!(rejoined.terrain_damages_msg(), Some(msg.clone()));
+
+        // Regrowing only after every crater leaves different grass
+        let late = msg.replace("[7,1,0,0],", "").replace("[7,2,0,0]", "[7,1,0,0],[7,2,0,0]");
+        let mut reordered = block_map(21);
+        reordered.apply_terrain_sync(&late);
+        assert!(live.sim.terrain.runs() != reordered.sim.terrain.runs());
+    }
+
+    #[test]
+    fn a_half_resolution_match_spawns_fires_and_saves() {
+        let settings = MatchSettings { half_res_terrain: true, ..MatchSettings::default() };
+        let mut game = Game::new_with_teams(7, 2, settings);
+        let t = &game.sim.terrain;
+        assert_eq!((t.width, t.height), terrain::map_size(true));
+        for w in &game.sim.balls {
+            assert!(w.x > t.land_start_x() && w.x < t.land_end_x() && w.y < t.water_level(), "{} at {},{}", w.name, w.x, w.y);
+        }
+        let before = game.sim.terrain.cells.clone();
+        let mut loaded = Game::load_from_string(&game.save_to_string()).expect("half-resolution saves load");
+        assert_eq!(loaded.settings, game.settings);
+        assert_eq!(loaded.sim.terrain.cells, before);
+
+        // A bazooka shot into the ground at the shooter's feet, in both copies
+        let ball = game.current_ball;
+        let shot = NetInput::Fire { weapon: Some(Weapon::Bazooka), angle_deg: 80.0, power_percent: 30.0 };
+        for g in [&mut game, &mut loaded] {
+            g.apply_input(ball, shot);
+            for _ in 0..120 {
+                g.update(1.0 / 60.0);
+            }
+            assert!(g.has_fired);
+        }
+        assert_ne!(game.sim.terrain.cells, before, "the shot left a crater");
+        assert_eq!(loaded.sim.terrain.cells, game.sim.terrain.cells);
+        assert!(game.sim.balls.iter().filter(|w| w.alive).all(|w| w.y < game.sim.terrain.water_level()));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::weapons::Weapon;
+use crate::{parse_json_number, parse_json_string};
+
+/// A player action relayed to the other clients through the worker.
+///
+/// On the wire each input is a small JSON object keyed by its variant name
+/// (`{"Fire":{"weapon":"Bazooka","angle_deg":-30,"power_percent":60}}`), carried as a
+/// string inside the `{"type":"input","input":"..."}` envelope. That is the format the
+/// worker logs and its bot emits, so it stays unchanged; this type just owns the
+/// encoding in one place instead of formatting and escaping it at every call site.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NetInput {
+    /// A Fire without a weapon shoots whatever the shooter has armed
+    Fire { weapon: Option<Weapon>, angle_deg: f32, power_percent: f32 },
+    /// Drill shot with the exact ball origin so every client carves the same tunnel
+    DrillFire { bx: i32, by: i32, angle: f32 },
+    /// Foxhole dug at the exact ball position, for the same reason
+    FoxholeDig { bx: i32, by: i32 },
+    /// Flares dropped from the exact ball position so the decoy matches everywhere
+    FlaresDrop { bx: i32, by: i32 },
+    BuildWallPlace { ax: f32, ay: f32, angle: f32 },
+    /// `weapon` is Airstrike or NapalmStrike
+    AirstrikeTarget { weapon: Weapon, x: f32 },
+    TeleportTo { x: f32, y: f32 },
+    /// Ninja rope hooked at this point; the owner streams the swing itself
+    RopeAttach { x: f32, y: f32 },
+    RopeDetach,
+    BatSwing { angle: f32 },
+    /// Free shove of an adjacent enemy; the target is sent so remotes don't re-pick it
+    Prod { target: usize, dir: f32 },
+    Jump,
+    Backflip,
+    Walk { dir: f32 },
+}
+
+impl NetInput {
+    /// Inner JSON object, e.g. `{"Jump":{}}`.
+    pub fn to_json(self) -> String {
+        match self {
+            NetInput::Fire { weapon: Some(weapon), angle_deg, power_percent } => format!(
+                r#"{{"Fire":{{"weapon":"{}","angle_deg":{},"power_percent":{}}}}}"#,
+                weapon.name(), angle_deg, power_percent
+            ),
+            NetInput::Fire { weapon: None, angle_deg, power_percent } => format!(
+                r#"{{"Fire":{{"angle_deg":{},"power_percent":{}}}}}"#,
+                angle_deg, power_percent
+            ),
+            NetInput::DrillFire { bx, by, angle } => format!(
+                r#"{{"DrillFire":{{"bx":{},"by":{},"angle":{}}}}}"#,
+                bx, by, angle
+            ),
+            NetInput::FoxholeDig { bx, by } => format!(r#"{{"FoxholeDig":{{"bx":{},"by":{}}}}}"#, bx, by),
+            NetInput::FlaresDrop { bx, by } => format!(r#"{{"FlaresDrop":{{"bx":{},"by":{}}}}}"#, bx, by),
+            NetInput::BuildWallPlace { ax, ay, angle } => format!(
+                r#"{{"BuildWallPlace":{{"ax":{},"ay":{},"angle":{}}}}}"#,
+                ax, ay, angle
+            ),
+            NetInput::AirstrikeTarget { weapon, x } => {
+                let name = match weapon {
+                    Weapon::NapalmStrike => "NapalmStrike",
+                    _ => "Airstrike",
+                };
+                format!(r#"{{"AirstrikeTarget":{{"weapon":"{}","x":{}}}}}"#, name, x)
+            }
+            NetInput::TeleportTo { x, y } => format!(r#"{{"TeleportTo":{{"x":{},"y":{}}}}}"#, x, y),
+            NetInput::RopeAttach { x, y } => format!(r#"{{"RopeAttach":{{"x":{},"y":{}}}}}"#, x, y),
+            NetInput::RopeDetach => r#"{"RopeDetach":{}}"#.to_string(),
+            NetInput::BatSwing { angle } => format!(r#"{{"BatSwing":{{"angle":{}}}}}"#, angle),
+            NetInput::Prod { target, dir } => format!(r#"{{"Prod":{{"target":{},"dir":{}}}}}"#, target, dir),
+            NetInput::Jump => r#"{"Jump":{}}"#.to_string(),
+            NetInput::Backflip => r#"{"Backflip":{}}"#.to_string(),
+            NetInput::Walk { dir } => format!(r#"{{"Walk":{{"dir":{}}}}}"#, dir),
+        }
+    }
+
+    /// Full `{"type":"input"}` envelope with the inner JSON escaped exactly once.
+    pub fn to_message(self) -> String {
+        format!(r#"{{"type":"input","input":"{}"}}"#, escape_json(&self.to_json()))
+    }
+
+    /// Parse the (already unescaped) inner JSON object. Returns None for unknown
+    /// variants or missing fields.
+    pub fn from_json(s: &str) -> Option<NetInput> {
+        let body = s.trim_start().strip_prefix('{')?.trim_start();
+        let body = body.strip_prefix('"')?;
+        let tag = &body[..body.find('"')?];
+        let num = |key: &str| parse_json_number(s, key).map(|v| v as f32);
+        match tag {
+            "Fire" => Some(NetInput::Fire {
+                weapon: match parse_json_string(s, "weapon") {
+                    Some(name) => Some(Weapon::from_name(name)?),
+                    None => None,
+                },
+                angle_deg: num("angle_deg")?,
+                power_percent: num("power_percent")?,
+            }),
+            "DrillFire" => Some(NetInput::DrillFire {
+                bx: num("bx")? as i32,
+                by: num("by")? as i32,
+                angle: num("angle")?,
+            }),
+            "FoxholeDig" => Some(NetInput::FoxholeDig { bx: num("bx")? as i32, by: num("by")? as i32 }),
+            "FlaresDrop" => Some(NetInput::FlaresDrop { bx: num("bx")? as i32, by: num("by")? as i32 }),
+            "BuildWallPlace" => Some(NetInput::BuildWallPlace {
+                ax: num("ax")?,
+                ay: num("ay")?,
+                angle: num("angle")?,
+            }),
+            "AirstrikeTarget" => {
+                let name = parse_json_string(s, "weapon").unwrap_or("Airstrike");
+                let weapon = if name.contains("Napalm") { Weapon::NapalmStrike } else { Weapon::Airstrike };
+                Some(NetInput::AirstrikeTarget { weapon, x: num("x")? })
+            }
+            "TeleportTo" => Some(NetInput::TeleportTo { x: num("x")?, y: num("y")? }),
+            "RopeAttach" => Some(NetInput::RopeAttach { x: num("x")?, y: num("y")? }),
+            "RopeDetach" => Some(NetInput::RopeDetach),
+            "BatSwing" => Some(NetInput::BatSwing { angle: num("angle")? }),
+            "Prod" => Some(NetInput::Prod { target: num("target")? as usize, dir: num("dir")? }),
+            "Jump" => Some(NetInput::Jump),
+            "Backflip" => Some(NetInput::Backflip),
+            "Walk" => Some(NetInput::Walk { dir: num("dir")? }),
+            _ => None,
+        }
+    }
+}
+
+/// Escape a string for embedding inside a JSON string literal.
+pub fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Reverse of `escape_json`, processing escapes left to right so `\\"` decodes correctly.
+pub fn unescape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_variant() -> Vec<NetInput> {
+        vec![
+            NetInput::Fire { weapon: Some(Weapon::Bazooka), angle_deg: -30.5, power_percent: 62.25 },
+            NetInput::Fire { weapon: None, angle_deg: 15.0, power_percent: 100.0 },
+            NetInput::DrillFire { bx: 412, by: -3, angle: 1.25 },
+            NetInput::FoxholeDig { bx: 90, by: 610 },
+            NetInput::FlaresDrop { bx: 1300, by: 5 },
+            NetInput::BuildWallPlace { ax: 700.5, ay: 320.0, angle: -0.75 },
+            NetInput::AirstrikeTarget { weapon: Weapon::Airstrike, x: 455.0 },
+            NetInput::AirstrikeTarget { weapon: Weapon::NapalmStrike, x: 12.5 },
+            NetInput::TeleportTo { x: 88.0, y: 199.5 },
+            NetInput::RopeAttach { x: 640.25, y: 40.0 },
+            NetInput::RopeDetach,
+            NetInput::BatSwing { angle: 3.0 },
+            NetInput::Prod { target: 7, dir: -1.0 },
+            NetInput::Jump,
+            NetInput::Backflip,
+            NetInput::Walk { dir: 1.0 },
+        ]
+    }
+
+    #[test]
+    fn every_input_round_trips_through_json() {
+        for input in every_variant() {
+            assert_eq!(NetInput::from_json(&input.to_json()), Some(input), "{}", input.to_json());
+        }
+    }
+
+    #[test]
+    fn every_input_round_trips_through_the_envelope() {
+        for input in every_variant() {
+            let msg = input.to_message();
+            let raw = crate::parse_json_string(&msg, "input").expect("envelope carries the input");
+            assert_eq!(NetInput::from_json(&unescape_json(raw)), Some(input), "{}", msg);
+        }
+    }
+
+    #[test]
+    fn unknown_or_incomplete_inputs_are_rejected() {
+        assert_eq!(NetInput::from_json(r#"{"Moonwalk":{}}"#), None);
+        assert_eq!(NetInput::from_json(r#"{"Fire":{"weapon":"Bazooka","angle_deg":10}}"#), None);
+        assert_eq!(NetInput::from_json(r#"{"Fire":{"weapon":"Slingshot","angle_deg":10,"power_percent":50}}"#), None);
+        assert_eq!(NetInput::from_json("not json"), None);
+    }
+
+    #[test]
+    fn escaping_is_undone_exactly() {
+        let s = "a \"quoted\" \\ path\nnext";
+        assert_eq!(unescape_json(&escape_json(s)), s);
+    }
+}
@@ -1,7 +1,34 @@
+use crate::net_input::NetInput;
+use crate::weapons::Weapon;
 use std::cell::RefCell;
 
 thread_local! {
     static INCOMING: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    /// The shell in flight as JSON, or "null", for JS renderers to read each frame
+    static PROJECTILE: RefCell<String> = RefCell::new(String::from("null"));
+    /// Weapon the JS arsenal asked to arm, picked up on the next frame
+    static WEAPON_REQUEST: RefCell<Option<Weapon>> = const { RefCell::new(None) };
+    /// Name of the armed weapon, for the JS arsenal to highlight
+    static SELECTED_WEAPON: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Replace the projectile JSON `get_projectile_json` hands to JS.
+pub fn publish_projectile(json: String) {
+    PROJECTILE.with(|p| *p.borrow_mut() = json);
+}
+
+/// Replace the weapon name `get_selected_weapon_name` hands to JS.
+pub fn publish_selected_weapon(name: &str) {
+    SELECTED_WEAPON.with(|w| {
+        if *w.borrow() != name {
+            *w.borrow_mut() = name.to_string();
+        }
+    });
+}
+
+/// The weapon `set_weapon` last asked for, if the game hasn't taken it yet.
+pub fn take_weapon_request() -> Option<Weapon> {
+    WEAPON_REQUEST.with(|w| w.borrow_mut().take())
 }
 
 pub struct NetworkState {
@@ -16,6 +43,8 @@ pub struct NetworkState {
     pub player_names: Vec<String>,
     /// Which players are bots
     pub player_is_bot: Vec<bool>,
+    /// Players who asked for a rematch after game over, from the server's `ready` broadcasts
+    pub ready_players: Vec<usize>,
 }
 
 impl NetworkState {
@@ -27,6 +56,7 @@ impl NetworkState {
             my_player_index: None,
             player_names: Vec::new(),
             player_is_bot: Vec::new(),
+            ready_players: Vec::new(),
         }
     }
 
@@ -34,6 +64,20 @@ impl NetworkState {
         self.my_player_index.map(|i| i as u32)
     }
 
+    /// Rematch progress as (ready, needed). Bots never have to confirm.
+    pub fn rematch_progress(&self) -> (usize, usize) {
+        let humans: Vec<usize> = (0..self.player_names.len().max(self.player_is_bot.len()))
+            .filter(|&i| !self.player_is_bot.get(i).copied().unwrap_or(false))
+            .collect();
+        let ready = humans.iter().filter(|i| self.ready_players.contains(i)).count();
+        (ready, humans.len())
+    }
+
+    /// Whether this client has already asked for a rematch.
+    pub fn is_ready(&self) -> bool {
+        self.my_player_index.is_some_and(|i| self.ready_players.contains(&i))
+    }
+
     pub fn poll_messages(&self) -> Vec<String> {
         INCOMING.with(|q| {
             let mut q = q.borrow_mut();
@@ -55,7 +99,12 @@ impl NetworkState {
         }
     }
 
-    /// Dispatch a UI event (hit, died, turn_start, game_over) to the JS layer.
+    /// Send a player input wrapped in the `{"type":"input"}` envelope.
+    pub fn send_input(&self, input: NetInput) {
+        self.send_message(&input.to_message());
+    }
+
+    /// Dispatch a UI event (hit, died, turn_start, game_over, earthquake) to the JS layer.
     /// The JS side listens and converts this into React toast notifications.
     pub fn send_game_event(&self, event_json: &str) {
         #[cfg(target_arch = "wasm32")]
@@ -94,6 +143,41 @@ pub extern "C" fn on_ws_message(ptr: *const u8, len: u32) {
     }
 }
 
+/// Pointer to the latest projectile JSON, `get_projectile_json_len` bytes long. Valid
+/// until the next frame publishes a new one.
+#[no_mangle]
+pub extern "C" fn get_projectile_json() -> *const u8 {
+    PROJECTILE.with(|p| p.borrow().as_ptr())
+}
+
+#[no_mangle]
+pub extern "C" fn get_projectile_json_len() -> u32 {
+    PROJECTILE.with(|p| p.borrow().len() as u32)
+}
+
+/// Arm the weapon named by the UTF-8 string at `ptr` (as `Weapon::from_name` reads it)
+/// on the next frame, if it's in the current team's loadout. False for an unknown name.
+#[no_mangle]
+pub extern "C" fn set_weapon(ptr: *const u8, len: u32) -> bool {
+    let slice = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
+    let Some(weapon) = std::str::from_utf8(slice).ok().and_then(Weapon::from_name) else {
+        return false;
+    };
+    WEAPON_REQUEST.with(|w| *w.borrow_mut() = Some(weapon));
+    true
+}
+
+/// Pointer to the armed weapon's name, `get_selected_weapon_name_len` bytes long.
+#[no_mangle]
+pub extern "C" fn get_selected_weapon_name() -> *const u8 {
+    SELECTED_WEAPON.with(|w| w.borrow().as_ptr())
+}
+
+#[no_mangle]
+pub extern "C" fn get_selected_weapon_name_len() -> u32 {
+    SELECTED_WEAPON.with(|w| w.borrow().len() as u32)
+}
+
 #[no_mangle]
 pub extern "C" fn on_game_init(ptr: *const u8, len: u32) {
     let slice = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
@@ -103,3 +187,18 @@ pub extern "C" fn on_game_init(ptr: *const u8, len: u32) {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_weapon_queues_known_names_only() {
+        let ask = |name: &str| set_weapon(name.as_ptr(), name.len() as u32);
+        assert!(!ask("Slingshot"));
+        assert_eq!(take_weapon_request(), None);
+        assert!(ask("Holy Hand Grenade"));
+        assert_eq!(take_weapon_request(), Some(Weapon::HolyHandGrenade));
+        assert_eq!(take_weapon_request(), None);
+    }
+}
@@ -1,22 +1,52 @@
-use crate::terrain::Terrain;
+use crate::physics_backend::PhysicsBackend;
+use crate::platforms::MovingPlatform;
+use crate::terrain::{self, Terrain};
 
 pub const BALL_RADIUS: f32 = 8.0;
 const GRAVITY: f32 = 480.0;
+const MAX_FALL_SPEED: f32 = 600.0;
 const WALK_SPEED: f32 = 115.0;         // Slightly snappier
 const JUMP_VEL: f32 = -320.0;          // More air — bigger, floatier jump
 const JUMP_HORIZONTAL_BOOST: f32 = 75.0; // Extra run on jump
 const MAX_CLIMB: i32 = 12;             // Can hop up over small terrain bumps
 const GROUND_FRICTION: f32 = 0.80;
+/// Ground friction by the material underfoot (velocity kept per tick, so lower grips
+/// harder): grass and dirt grip a touch more than GROUND_FRICTION, stone a touch less.
+pub const SOFT_GROUND_FRICTION: f32 = 0.78;
+pub const STONE_FRICTION: f32 = 0.84;
+/// Ice barely grips: a ball keeps most of its speed and slides a long way
+pub const ICE_FRICTION: f32 = 0.96;
+/// Landing at least this fast reports the material landed on (`Ball::landed_on`)
+const LANDING_EVENT_SPEED: f32 = 150.0;
 const AIR_FRICTION: f32 = 0.985;       // Slightly less air drag
 const AIR_CONTROL_ACCEL: f32 = 420.0; // Horizontal acceleration applied per-frame while airborne
 const MAX_AIR_SPEED: f32 = 105.0;     // Max horizontal speed from air control
 const FALL_DAMAGE_THRESHOLD: f32 = 120.0;
 const FALL_DAMAGE_FACTOR: f32 = 0.25;
+/// Landing faster than this hurts however short the fall, e.g. when a blast slams a ball
+/// down. Below MAX_FALL_SPEED so it can be reached.
+const IMPACT_DAMAGE_SPEED: f32 = 420.0;
+const IMPACT_DAMAGE_FACTOR: f32 = 0.2;  // damage per unit of downward speed past the threshold
+const MAX_IMPACT_DAMAGE: i32 = 35;
 const WALL_IMPACT_THRESHOLD: f32 = 250.0; // min speed to take wall-impact damage
 const WALL_IMPACT_FACTOR: f32 = 0.04;    // damage per unit of excess speed
 const MOVEMENT_BUDGET: f32 = 170.0;   // Slightly more movement per turn
 const COYOTE_TIME: f32 = 0.15;        // Grace window after walking off edge
 const JUMP_BUFFER_TIME: f32 = 0.12;   // Jump pressed just before landing
+const SLOPE_SAMPLE_RADIUS: i32 = 5;   // Neighbourhood used for the surface normal under the feet
+const SLIDE_MIN_STEEPNESS: f32 = 0.75; // |normal.x| above this (~49°) is too steep to stand on
+const MAX_SLIDE_SPEED: f32 = 90.0;
+const MAX_SLIDE_TIME: f32 = 2.0;      // Give up sliding after this long so settling can't hang
+const EMBED_RESCUE_TIME: f32 = 0.3;   // Buried this long and the ball is popped free
+const HEALTH_DRAIN_TIME: f32 = 0.3;   // HP bar's lag ghost catches up over about this long
+const SAND_CRUMBLE_TIME: f32 = 1.0;   // Standing on loose sand this long makes it give way
+const NET_SAMPLE_MIN_GAP: f32 = 1.0 / 120.0; // Streamed samples closer than this still blend over it
+const NET_SAMPLE_MAX_GAP: f32 = 0.1;  // A gap after a pause blends over this, not the whole pause
+const CONTACT_DAMPING: f32 = 0.2;     // Share of their closing speed two touching balls keep
+/// Last stand (optional rule): a team's final living ball deals this much more damage
+/// and gets this much more movement per turn.
+pub const LAST_STAND_DAMAGE: f32 = 1.2;
+const LAST_STAND_MOVEMENT: f32 = 1.5;
 
 pub const TEAM_COLORS: [(f32, f32, f32); 4] = [
     (0.85, 0.25, 0.25),
@@ -46,6 +76,35 @@ pub struct Ball {
     pub coyote_timer: f32,
     /// Queued jump — executes on next landing if within window
     pub jump_buffer: f32,
+    /// Absorbs the next damage taken, then breaks
+    pub shield: bool,
+    /// On a slope too steep to stand on and sliding downhill
+    pub sliding: bool,
+    /// How long the current slide has lasted — sliding stops once it hits MAX_SLIDE_TIME
+    pub slide_timer: f32,
+    /// Last living ball of its team under the last-stand rule: boosted damage and movement
+    pub last_stand: bool,
+    /// Collision and drawing radius, from the match's ball size
+    pub radius: f32,
+    /// How long the ball has been buried in solid terrain without a break
+    pub embedded_time: f32,
+    /// Match-wide multipliers on knockback taken and weapon damage taken
+    pub knockback_scale: f32,
+    pub weapon_damage_scale: f32,
+    /// Material under the ball at its last hard landing (AIR for a platform), for the
+    /// front-end's landing sounds. Cosmetic; the game takes it each tick.
+    pub landed_on: Option<u8>,
+    /// HP shown by the health bar's lag ghost: eases down to `health` after a hit so the
+    /// chunk just lost stays visible for a moment. Cosmetic.
+    pub display_health: f32,
+    /// How long the ball has stood on loose sand without a break
+    pub sand_time: f32,
+    /// Foot position where the sand under the ball just gave way; the game takes it,
+    /// crumbles the sand and logs it
+    pub crumbled: Option<(i32, i32)>,
+    /// Last two positions streamed for a remote ball as (receive time, x, y), oldest
+    /// first, so it's drawn gliding between them instead of jumping. Cosmetic.
+    pub net_samples: [Option<(f32, f32, f32)>; 2],
 }
 
 impl Ball {
@@ -69,12 +128,66 @@ impl Ball {
             movement_used: 0.0,
             coyote_timer: 0.0,
             jump_buffer: 0.0,
+            shield: false,
+            sliding: false,
+            slide_timer: 0.0,
+            last_stand: false,
+            radius: BALL_RADIUS,
+            embedded_time: 0.0,
+            knockback_scale: 1.0,
+            weapon_damage_scale: 1.0,
+            landed_on: None,
+            display_health: 100.0,
+            sand_time: 0.0,
+            crumbled: None,
+            net_samples: [None; 2],
         }
     }
 
+    /// Squared distance from the centre inside which a projectile counts as touching
+    /// this ball, `margin` px beyond its radius.
+    pub fn hit_radius_sq(&self, margin: f32) -> f32 {
+        let r = self.radius + margin;
+        r * r
+    }
+
+    /// Full per-turn movement budget, raised while on a last stand.
+    fn full_movement_budget(&self) -> f32 {
+        if self.last_stand { MOVEMENT_BUDGET * LAST_STAND_MOVEMENT } else { MOVEMENT_BUDGET }
+    }
+
     pub fn reset_movement_budget(&mut self) {
         self.movement_used = 0.0;
-        self.movement_budget = MOVEMENT_BUDGET;
+        self.movement_budget = self.full_movement_budget();
+    }
+
+    /// Start a turn by regenerating only `regen` (fraction of the full budget) on top of
+    /// whatever is left over, so over-walking one turn limits the next. The budget stays
+    /// at full size and the shortfall is kept in `movement_used`, so the HUD bar shows it.
+    pub fn regen_movement_budget(&mut self, regen: f32) {
+        let full = self.full_movement_budget();
+        let available = (self.movement_remaining() + full * regen).min(full);
+        self.movement_budget = full;
+        self.movement_used = full - available;
+    }
+
+    /// Come to rest on the ground at the current position, taking fall damage if this
+    /// ends a long enough drop.
+    fn land(&mut self, was_on_ground: bool, material: u8) {
+        self.on_ground = true;
+        if !was_on_ground && self.vy >= LANDING_EVENT_SPEED {
+            self.landed_on = Some(material);
+        }
+        if !was_on_ground && self.vy > 0.0 {
+            let fall_dist = self.y - self.fall_start_y;
+            let fall_dmg = ((fall_dist - FALL_DAMAGE_THRESHOLD) * FALL_DAMAGE_FACTOR).max(0.0) as i32;
+            // Whichever is worse, so a long fall isn't counted twice
+            let dmg = fall_dmg.max(impact_damage(self.vy));
+            if dmg > 0 {
+                self.take_damage(dmg);
+            }
+        }
+        self.vy = 0.0;
     }
 
     pub fn can_move(&self) -> bool {
@@ -85,33 +198,33 @@ impl Ball {
         (self.movement_budget - self.movement_used).max(0.0)
     }
 
-    pub fn tick(&mut self, terrain: &Terrain, dt: f32) {
+    /// Advance one step. `backend` does the gravity/velocity integration; see
+    /// `physics_backend` for the float and fixed-point variants. A ball falling onto one
+    /// of `platforms` lands on it and is carried along while it stands there.
+    /// `safe_zone` is the (left, right) x-range balls may be in; outside it they die.
+    pub fn tick(
+        &mut self,
+        terrain: &Terrain,
+        platforms: &[MovingPlatform],
+        safe_zone: (f32, f32),
+        dt: f32,
+        backend: &dyn PhysicsBackend,
+    ) {
         if !self.alive {
             return;
         }
 
         let was_on_ground = self.on_ground;
 
-        self.vy += GRAVITY * dt;
-        if self.vy > 600.0 {
-            self.vy = 600.0;
-        }
-
-        self.x += self.vx * dt;
-        self.y += self.vy * dt;
-
-        let friction = if self.on_ground {
-            GROUND_FRICTION
+        let friction = if self.on_ground && !self.sliding {
+            ground_friction(terrain.get(self.x as i32, (self.y + self.radius) as i32))
         } else {
             AIR_FRICTION
         };
-        self.vx *= friction;
-        if self.vx.abs() < 0.5 {
-            self.vx = 0.0;
-        }
+        backend.integrate(self, dt, GRAVITY, MAX_FALL_SPEED, friction);
 
         self.on_ground = false;
-        let r = BALL_RADIUS;
+        let r = self.radius;
         for &offset in &[-r * 0.4, 0.0, r * 0.4] {
             let cx = (self.x + offset) as i32;
             let foot_y = (self.y + r) as i32;
@@ -123,27 +236,56 @@ impl Ball {
                 let new_y = (sy + 1) as f32 - r;
                 if new_y < self.y + 2.0 {
                     self.y = new_y;
-                    self.on_ground = true;
-
-                    if !was_on_ground && self.vy > 0.0 {
-                        let fall_dist = self.y - self.fall_start_y;
-                        if fall_dist > FALL_DAMAGE_THRESHOLD {
-                            let dmg = ((fall_dist - FALL_DAMAGE_THRESHOLD) * FALL_DAMAGE_FACTOR) as i32;
-                            if dmg > 0 {
-                                self.take_damage(dmg);
-                            }
-                        }
-                    }
-                    self.vy = 0.0;
+                    self.land(was_on_ground, terrain.get(cx, sy + 1));
                     break;
                 }
             }
         }
+        if !self.on_ground && self.vy >= 0.0 {
+            if let Some(p) = platforms.iter().find(|p| p.supports(self.x, self.y + r, dt)) {
+                self.y = p.top() - r;
+                self.x += p.vx * dt;
+                self.land(was_on_ground, terrain::AIR);
+            }
+        }
 
         if !self.on_ground && was_on_ground && self.vy >= 0.0 {
             self.fall_start_y = self.y;
         }
 
+        // Slope sliding: on near-vertical ground, slide downhill at a capped speed
+        // instead of sticking. Airborne frames between ground contacts keep the slide going.
+        if self.on_ground {
+            let steep_dir = terrain
+                .surface_normal(self.x as i32, (self.y + r) as i32, SLOPE_SAMPLE_RADIUS)
+                .filter(|&(nx, _)| nx.abs() > SLIDE_MIN_STEEPNESS)
+                .map(|(nx, _)| nx.signum());
+            match steep_dir {
+                Some(dir) if self.slide_timer < MAX_SLIDE_TIME => {
+                    self.sliding = true;
+                    self.slide_timer += dt;
+                    self.vx = (self.vx + dir * GRAVITY * dt).clamp(-MAX_SLIDE_SPEED, MAX_SLIDE_SPEED);
+                }
+                Some(_) => self.sliding = false,
+                None => {
+                    self.sliding = false;
+                    self.slide_timer = 0.0;
+                }
+            }
+        }
+
+        // Loose sand gives way under a ball that stays on it too long
+        let foot = (self.x as i32, (self.y + r) as i32);
+        if self.on_ground && terrain.get(foot.0, foot.1) == terrain::SAND {
+            self.sand_time += dt;
+            if self.sand_time >= SAND_CRUMBLE_TIME {
+                self.sand_time = 0.0;
+                self.crumbled = Some(foot);
+            }
+        } else {
+            self.sand_time = 0.0;
+        }
+
         // Coyote time: grant a grace window to jump after walking off an edge
         if self.on_ground {
             self.coyote_timer = 0.0;
@@ -198,28 +340,29 @@ impl Ball {
 
         self.x = self.x.clamp(r, terrain.width as f32 - r);
 
-        // Check for lava — instant death!
-        let mut touching_lava = false;
+        // Check for lava or a water pocket — instant death!
+        let mut touching_hazard = false;
         for &offset_x in &[-r * 0.5, 0.0, r * 0.5] {
             for &offset_y in &[-r * 0.5, 0.0, r * 0.5] {
                 let check_x = (self.x + offset_x) as i32;
                 let check_y = (self.y + offset_y) as i32;
-                if terrain.get(check_x, check_y) == crate::terrain::LAVA {
-                    touching_lava = true;
+                let cell = terrain.get(check_x, check_y);
+                if cell == crate::terrain::LAVA || cell == crate::terrain::WATER {
+                    touching_hazard = true;
                     break;
                 }
             }
-            if touching_lava {
+            if touching_hazard {
                 break;
             }
         }
-        if touching_lava {
+        if touching_hazard {
             self.alive = false;
             self.health = 0;
         }
 
-        // Drowning — instant death when touching water!
-        if self.y + r > crate::terrain::WATER_LEVEL {
+        // Drowning — instant death when touching water, or when caught outside the zone
+        if self.y + r > terrain.water_level() || self.x < safe_zone.0 || self.x > safe_zone.1 {
             self.alive = false;
             self.health = 0;
         }
@@ -234,7 +377,85 @@ impl Ball {
         }
     }
 
+    /// Centre and the points halfway to the edge in all four directions are solid:
+    /// the ball is inside terrain rather than resting against it.
+    pub fn is_embedded(&self, terrain: &Terrain) -> bool {
+        let h = self.radius * 0.5;
+        [(0.0, 0.0), (-h, 0.0), (h, 0.0), (0.0, -h), (0.0, h)]
+            .iter()
+            .all(|&(dx, dy)| terrain.is_solid((self.x + dx) as i32, (self.y + dy) as i32))
+    }
+
+    /// Whether the ball could sit centred at height `y` in its column without overlapping
+    /// terrain at the top, sides or bottom.
+    fn fits_at(&self, terrain: &Terrain, y: f32) -> bool {
+        let r = self.radius;
+        [(0.0, -r), (-r, 0.0), (0.0, 0.0), (r, 0.0), (0.0, r - 1.0)]
+            .iter()
+            .all(|&(dx, dy)| !terrain.is_solid((self.x + dx) as i32, (y + dy) as i32))
+    }
+
+    /// Free a ball that `Ball::tick`'s push-out couldn't (e.g. a wall built on top of it):
+    /// after EMBED_RESCUE_TIME buried, move it up to the nearest gap it fits in, or onto
+    /// the column's top surface if there's none. Only reads the terrain, so every client
+    /// frees it to the same spot.
+    pub fn rescue_if_embedded(&mut self, terrain: &Terrain, dt: f32) {
+        if !self.alive || !self.is_embedded(terrain) {
+            self.embedded_time = 0.0;
+            return;
+        }
+        self.embedded_time += dt;
+        if self.embedded_time < EMBED_RESCUE_TIME {
+            return;
+        }
+        let gap = (0..self.y as i32).rev().map(|y| y as f32).find(|&y| self.fits_at(terrain, y));
+        self.y = gap.unwrap_or_else(|| {
+            terrain.find_surface_y(self.x as i32).map_or(0.0, |s| s as f32) - self.radius - 1.0
+        });
+        self.vx = 0.0;
+        self.vy = 0.0;
+        self.fall_start_y = self.y;
+        self.embedded_time = 0.0;
+    }
+
+    /// Ease `display_health` toward `health` over about HEALTH_DRAIN_TIME. Healing isn't
+    /// drawn as a ghost, so it catches up at once.
+    pub fn drain_display_health(&mut self, dt: f32) {
+        let target = self.health.max(0) as f32;
+        if self.display_health <= target + 0.5 {
+            self.display_health = target;
+            return;
+        }
+        self.display_health += (target - self.display_health) * (dt * 3.0 / HEALTH_DRAIN_TIME).min(1.0);
+    }
+
+    /// Record a position streamed by the ball's owner, received at `time`.
+    pub fn push_net_sample(&mut self, time: f32, x: f32, y: f32) {
+        self.net_samples = [self.net_samples[1], Some((time, x, y))];
+    }
+
+    /// Where to draw the ball at render `time`. A remote ball is drawn one sample gap
+    /// behind, blending from the older streamed position to the newer one, so it always
+    /// has somewhere to head for until the next sample lands. Anything else, or a ball
+    /// moved since its last sample, is drawn where it is.
+    pub fn draw_pos(&self, time: f32) -> (f32, f32) {
+        let [Some((t0, x0, y0)), Some((t1, x1, y1))] = self.net_samples else {
+            return (self.x, self.y);
+        };
+        if (x1, y1) != (self.x, self.y) {
+            return (self.x, self.y);
+        }
+        let gap = (t1 - t0).clamp(NET_SAMPLE_MIN_GAP, NET_SAMPLE_MAX_GAP);
+        let f = ((time - t1) / gap).clamp(0.0, 1.0);
+        (x0 + (x1 - x0) * f, y0 + (y1 - y0) * f)
+    }
+
     pub fn take_damage(&mut self, amount: i32) {
+        // A shield soaks up the whole hit and breaks
+        if self.shield && amount > 0 {
+            self.shield = false;
+            return;
+        }
         self.health = (self.health - amount).max(0);
         self.last_damage = amount;
         self.damage_timer = 2.0;
@@ -243,17 +464,100 @@ impl Ball {
         }
     }
 
+    /// Multiplier on damage this ball's shots deal.
+    pub fn damage_scale(&self) -> f32 {
+        if self.last_stand { LAST_STAND_DAMAGE } else { 1.0 }
+    }
+
+    /// Weapon damage that respects the friendly-fire rule: `spare_team` is the
+    /// shooter's team when friendly fire is off, so its balls take no damage.
+    pub fn take_weapon_damage(&mut self, amount: i32, spare_team: Option<u32>) {
+        if spare_team == Some(self.team) {
+            return;
+        }
+        let amount = scale_damage(amount, self.weapon_damage_scale);
+        if amount <= 0 {
+            return;
+        }
+        self.take_damage(amount);
+    }
+
     pub fn apply_knockback(&mut self, dx: f32, dy: f32) {
-        self.vx += dx;
-        self.vy += dy;
+        self.vx += dx * self.knockback_scale;
+        self.vy += dy * self.knockback_scale;
         self.on_ground = false;
+        self.slide_timer = 0.0;
     }
 
     pub fn is_settled(&self) -> bool {
-        !self.alive || (self.on_ground && self.vx.abs() < 2.0 && self.vy.abs() < 2.0)
+        !self.alive || (self.on_ground && !self.sliding && self.vx.abs() < 2.0 && self.vy.abs() < 2.0)
+    }
+}
+
+/// Push apart every pair of alive balls that overlap, after they have all ticked. Each
+/// ball takes half the overlap along the line between their centres, except that a
+/// ball is never pushed down into the ground it stands on or into solid terrain; the
+/// other takes the whole overlap then. Pairs go in slice order so every client
+/// resolves the same way. Balls at the exact same spot split sideways, lower index to
+/// the left, so one landing on top of another (a teleport, say) rolls off.
+pub fn separate_balls(balls: &mut [Ball], terrain: &Terrain) {
+    for i in 0..balls.len() {
+        for j in i + 1..balls.len() {
+            let (head, tail) = balls.split_at_mut(j);
+            let (a, b) = (&mut head[i], &mut tail[0]);
+            if !a.alive || !b.alive {
+                continue;
+            }
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let dist = (dx * dx + dy * dy).sqrt();
+            let overlap = a.radius + b.radius - dist;
+            if overlap <= 0.0 {
+                continue;
+            }
+            // With the centres (nearly) level, lean the axis sideways so the top ball slides off
+            let (nx, ny) = if dx.abs() < 0.5 {
+                let lean = (dy / dist.max(0.001)).clamp(-1.0, 1.0);
+                let nx = (1.0 - lean * lean).sqrt().max(0.2);
+                let len = (nx * nx + lean * lean).sqrt();
+                (nx / len, lean / len)
+            } else {
+                (dx / dist, dy / dist)
+            };
+            let blocked = |ball: &Ball, sx: f32, sy: f32| {
+                (ball.on_ground && sy > 0.0) || terrain.is_solid((ball.x + sx) as i32, (ball.y + sy) as i32)
+            };
+            let a_blocked = blocked(a, -nx * overlap * 0.5, -ny * overlap * 0.5);
+            let b_blocked = blocked(b, nx * overlap * 0.5, ny * overlap * 0.5);
+            let a_share = match (a_blocked, b_blocked) {
+                (true, false) => 0.0,
+                (false, true) => 1.0,
+                _ => 0.5,
+            };
+            a.x -= nx * overlap * a_share;
+            a.y -= ny * overlap * a_share;
+            b.x += nx * overlap * (1.0 - a_share);
+            b.y += ny * overlap * (1.0 - a_share);
+
+            // Damp the speed at which they close in, so they come to rest touching
+            // rather than bouncing apart
+            let closing = (b.vx - a.vx) * nx + (b.vy - a.vy) * ny;
+            if closing < 0.0 {
+                let cancel = closing * (1.0 - CONTACT_DAMPING);
+                a.vx += nx * cancel * a_share;
+                a.vy += ny * cancel * a_share;
+                b.vx -= nx * cancel * (1.0 - a_share);
+                b.vy -= ny * cancel * (1.0 - a_share);
+            }
+        }
     }
 }
 
+/// Damage for hitting the ground at downward speed `vy`: nothing up to
+/// IMPACT_DAMAGE_SPEED, then IMPACT_DAMAGE_FACTOR per unit past it, capped at MAX_IMPACT_DAMAGE.
+fn impact_damage(vy: f32) -> i32 {
+    (((vy - IMPACT_DAMAGE_SPEED) * IMPACT_DAMAGE_FACTOR).max(0.0) as i32).min(MAX_IMPACT_DAMAGE)
+}
+
 pub fn walk(ball: &mut Ball, terrain: &Terrain, dir: f32) {
     if !ball.alive {
         return;
@@ -300,7 +604,7 @@ pub fn walk(ball: &mut Ball, terrain: &Terrain, dir: f32) {
     
     let old_x = ball.x;
     let new_x = ball.x + step;
-    let r = BALL_RADIUS;
+    let r = ball.radius;
     let nx = new_x as i32;
     let foot_y = (ball.y + r) as i32;
 
@@ -374,3 +678,103 @@ pub fn backflip(ball: &mut Ball) {
         ball.fall_start_y = ball.y;
     }
 }
+
+/// Horizontal friction for a ball standing on `material`; platforms and anything
+/// without its own value use GROUND_FRICTION.
+pub fn ground_friction(material: u8) -> f32 {
+    match material {
+        terrain::GRASS | terrain::DIRT | terrain::SAND => SOFT_GROUND_FRICTION,
+        terrain::STONE => STONE_FRICTION,
+        terrain::ICE => ICE_FRICTION,
+        _ => GROUND_FRICTION,
+    }
+}
+
+/// Apply a shooter's damage multiplier to a base damage value.
+pub fn scale_damage(damage: i32, scale: f32) -> i32 {
+    (damage as f32 * scale).round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_backend;
+
+    const FLOOR_Y: i32 = 600;
+
+    fn floor() -> Terrain {
+        let mut terrain = Terrain::new(terrain::WIDTH, terrain::HEIGHT);
+        for y in FLOOR_Y..terrain::HEIGHT as i32 {
+            for x in 0..terrain::WIDTH as i32 {
+                terrain.set(x, y, terrain::DIRT);
+            }
+        }
+        terrain
+    }
+
+    /// Health a ball loses dropped `height` px onto a flat dirt floor, starting at `vy`.
+    fn drop_damage(height: f32, vy: f32) -> i32 {
+        let terrain = floor();
+        let mut ball = Ball::new(700.0, FLOOR_Y as f32 - BALL_RADIUS - height, 0, "drop".into());
+        ball.on_ground = false;
+        ball.fall_start_y = ball.y;
+        ball.vy = vy;
+        let backend = physics_backend::backend(false);
+        for _ in 0..600 {
+            ball.tick(&terrain, &[], (0.0, terrain::WIDTH as f32), 1.0 / 60.0, backend);
+            if ball.on_ground {
+                break;
+            }
+        }
+        assert!(ball.on_ground, "ball never landed");
+        100 - ball.health
+    }
+
+    #[test]
+    fn impact_damage_starts_past_the_threshold_and_caps() {
+        assert_eq!(impact_damage(IMPACT_DAMAGE_SPEED), 0);
+        assert_eq!(impact_damage(IMPACT_DAMAGE_SPEED + 50.0), 10);
+        assert_eq!(impact_damage(IMPACT_DAMAGE_SPEED + 10_000.0), MAX_IMPACT_DAMAGE);
+    }
+
+    #[test]
+    fn short_drops_are_free_and_long_drops_hurt() {
+        assert_eq!(drop_damage(60.0, 0.0), 0);
+        let medium = drop_damage(250.0, 0.0);
+        let long = drop_damage(400.0, 0.0);
+        assert!(medium > 0);
+        assert!(long > medium);
+    }
+
+    #[test]
+    fn slam_into_the_ground_hurts_without_a_long_fall() {
+        // Knocked down at full speed from just above the floor: no fall distance to speak of
+        assert_eq!(drop_damage(20.0, MAX_FALL_SPEED), impact_damage(MAX_FALL_SPEED));
+        assert!(impact_damage(MAX_FALL_SPEED) > 0);
+    }
+
+    #[test]
+    fn teleporting_onto_another_ball_settles_apart() {
+        let terrain = floor();
+        let rest_y = FLOOR_Y as f32 - BALL_RADIUS;
+        let mut balls = vec![Ball::new(700.0, rest_y, 0, "a".into()), Ball::new(900.0, rest_y, 1, "b".into())];
+        // Teleport drops b exactly onto a's centre, already standing
+        balls[1].x = balls[0].x;
+        balls[1].y = balls[0].y;
+        let backend = physics_backend::backend(false);
+        let mut settled = Vec::new();
+        for frame in 0..240 {
+            for ball in balls.iter_mut() {
+                ball.tick(&terrain, &[], (0.0, terrain::WIDTH as f32), 1.0 / 60.0, backend);
+            }
+            separate_balls(&mut balls, &terrain);
+            if frame >= 180 {
+                settled.push((balls[0].x, balls[0].y, balls[1].x, balls[1].y));
+            }
+        }
+        let (dx, dy) = (balls[1].x - balls[0].x, balls[1].y - balls[0].y);
+        assert!((dx * dx + dy * dy).sqrt() >= 2.0 * BALL_RADIUS - 0.5, "still overlapping: {dx}, {dy}");
+        // At rest, not jittering against each other
+        assert!(settled.windows(2).all(|w| w[0] == w[1]), "{settled:?}");
+    }
+}
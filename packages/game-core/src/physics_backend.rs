@@ -0,0 +1,154 @@
+use crate::physics::Ball;
+
+/// Fractional bits of the fixed-point format. Positions and velocities are stored as
+/// `i32` in 1/256 px (Q24.8) and time as `i32` in 1/65536 s (Q16.16). A Q24.8 value
+/// below 65536 px fits exactly in an `f32` mantissa, so writing the result back into
+/// the ball's `f32` fields loses nothing.
+const POS_FRAC_BITS: u32 = 8;
+const TIME_FRAC_BITS: u32 = 16;
+/// Friction factors are applied as Q16 multipliers
+const FRICTION_FRAC_BITS: u32 = 16;
+
+/// The integration step of `Ball::tick`: gravity, the fall-speed cap, moving the ball
+/// by its velocity and horizontal friction. Everything after it (terrain collision,
+/// sliding, hazards) only snaps to whole pixels and is shared by both backends.
+pub trait PhysicsBackend {
+    fn integrate(&self, ball: &mut Ball, dt: f32, gravity: f32, max_fall: f32, friction: f32);
+}
+
+/// Plain `f32` maths, the classic behaviour.
+pub struct FloatPhysics;
+
+/// Integer maths on quantized values, so every platform computes the same bits for
+/// the same inputs regardless of how the compiler schedules float operations.
+pub struct FixedPhysics;
+
+impl PhysicsBackend for FloatPhysics {
+    fn integrate(&self, ball: &mut Ball, dt: f32, gravity: f32, max_fall: f32, friction: f32) {
+        ball.vy += gravity * dt;
+        if ball.vy > max_fall {
+            ball.vy = max_fall;
+        }
+
+        ball.x += ball.vx * dt;
+        ball.y += ball.vy * dt;
+
+        ball.vx *= friction;
+        if ball.vx.abs() < 0.5 {
+            ball.vx = 0.0;
+        }
+    }
+}
+
+impl PhysicsBackend for FixedPhysics {
+    fn integrate(&self, ball: &mut Ball, dt: f32, gravity: f32, max_fall: f32, friction: f32) {
+        let dt = to_fixed(dt, TIME_FRAC_BITS) as i64;
+        let gravity = to_fixed(gravity, POS_FRAC_BITS) as i64;
+        let max_fall = to_fixed(max_fall, POS_FRAC_BITS);
+        let friction = to_fixed(friction, FRICTION_FRAC_BITS) as i64;
+        let mut x = to_fixed(ball.x, POS_FRAC_BITS) as i64;
+        let mut y = to_fixed(ball.y, POS_FRAC_BITS) as i64;
+        let mut vx = to_fixed(ball.vx, POS_FRAC_BITS) as i64;
+        let mut vy = to_fixed(ball.vy, POS_FRAC_BITS) as i64;
+
+        vy = (vy + ((gravity * dt) >> TIME_FRAC_BITS)).min(max_fall as i64);
+
+        x += (vx * dt) >> TIME_FRAC_BITS;
+        y += (vy * dt) >> TIME_FRAC_BITS;
+
+        vx = (vx * friction) >> FRICTION_FRAC_BITS;
+        if vx.abs() < 1 << (POS_FRAC_BITS - 1) {
+            vx = 0;
+        }
+
+        ball.x = from_fixed(x, POS_FRAC_BITS);
+        ball.y = from_fixed(y, POS_FRAC_BITS);
+        ball.vx = from_fixed(vx, POS_FRAC_BITS);
+        ball.vy = from_fixed(vy, POS_FRAC_BITS);
+    }
+}
+
+/// Backend chosen by the match settings.
+pub fn backend(fixed: bool) -> &'static dyn PhysicsBackend {
+    if fixed { &FixedPhysics } else { &FloatPhysics }
+}
+
+fn to_fixed(v: f32, frac_bits: u32) -> i32 {
+    (v * (1u32 << frac_bits) as f32).round() as i32
+}
+
+fn from_fixed(v: i64, frac_bits: u32) -> f32 {
+    v as f32 / (1u32 << frac_bits) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ball() -> Ball {
+        let mut b = Ball::new(400.0, 100.0, 0, String::new());
+        b.vx = 180.0;
+        b.vy = -250.0;
+        b
+    }
+
+    #[test]
+    fn fixed_tracks_float_over_a_long_flight() {
+        let (mut float, mut fixed) = (ball(), ball());
+        for _ in 0..120 {
+            FloatPhysics.integrate(&mut float, 1.0 / 60.0, 400.0, 600.0, 0.995);
+            FixedPhysics.integrate(&mut fixed, 1.0 / 60.0, 400.0, 600.0, 0.995);
+        }
+        assert!((float.x - fixed.x).abs() < 1.0, "x {} vs {}", float.x, fixed.x);
+        assert!((float.y - fixed.y).abs() < 1.0, "y {} vs {}", float.y, fixed.y);
+        assert!((float.vy - fixed.vy).abs() < 1.0, "vy {} vs {}", float.vy, fixed.vy);
+    }
+
+    #[test]
+    fn fixed_results_stay_on_the_grid() {
+        let mut b = ball();
+        b.x = 400.123;
+        for _ in 0..30 {
+            FixedPhysics.integrate(&mut b, 1.0 / 60.0, 400.0, 600.0, 0.99);
+            for v in [b.x, b.y, b.vx, b.vy] {
+                assert_eq!((v * 256.0).fract(), 0.0, "{} is off the 1/256 grid", v);
+            }
+        }
+    }
+
+    #[test]
+    fn both_cap_fall_speed_and_stop_slow_drift() {
+        for fixed in [false, true] {
+            let mut b = ball();
+            b.vx = 0.4;
+            b.vy = 590.0;
+            backend(fixed).integrate(&mut b, 0.5, 400.0, 600.0, 1.0);
+            assert_eq!(b.vy, 600.0, "fixed={}", fixed);
+            assert_eq!(b.vx, 0.0, "fixed={}", fixed);
+        }
+    }
+
+    /// A fixed input sequence: varying frame times, a wall bounce and friction changes.
+    fn run_sequence() -> Ball {
+        let mut b = ball();
+        for i in 0..80 {
+            let dt = [1.0 / 60.0, 1.0 / 30.0, 1.0 / 144.0][i % 3];
+            let friction = if i < 40 { 0.995 } else { 0.98 };
+            if i == 30 {
+                b.vx = -b.vx * 0.6;
+            }
+            FixedPhysics.integrate(&mut b, dt, 400.0, 600.0, friction);
+        }
+        b
+    }
+
+    #[test]
+    fn fixed_backend_reproduces_golden_bits() {
+        let b = run_sequence();
+        // x 430.91016, y 193.40234, vx -39.41797, vy 362.10938: all whole 1/256ths
+        let bits = [b.x.to_bits(), b.y.to_bits(), b.vx.to_bits(), b.vy.to_bits()];
+        assert_eq!(bits, [1138193536, 1128359680, 3256724480, 1135939072], "{:?}", (b.x, b.y, b.vx, b.vy));
+        let again = run_sequence();
+        assert_eq!([again.x, again.y, again.vx, again.vy].map(f32::to_bits), bits);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::terrain::Terrain;
+use macroquad::prelude::*;
+
+/// Length of one platform tick. Platform motion is a pure function of the tick count,
+/// so clients that have simulated the same game time agree on where every platform is.
+pub const PLATFORM_STEP: f32 = 1.0 / 60.0;
+
+const PLATFORM_COUNT: usize = 3;
+const PLATFORM_HALF_W: f32 = 28.0;
+const PLATFORM_HALF_H: f32 = 4.0;
+
+/// A small solid slab swinging back and forth along a straight path for gimmick maps.
+/// Kept apart from the terrain cells: only balls collide with it, landing on top like a
+/// one-way ledge, and a ball standing on one rides along with it.
+pub struct MovingPlatform {
+    /// Middle of the path
+    pub cx: f32,
+    pub cy: f32,
+    /// Half the travel along each axis
+    pub ax: f32,
+    pub ay: f32,
+    /// Seconds for a full back-and-forth
+    pub period: f32,
+    /// Where in the cycle the platform starts, 0..1
+    pub phase: f32,
+    /// Current position and velocity, set by `set_tick`
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+}
+
+impl MovingPlatform {
+    /// Move to where the platform is `tick` steps into the match.
+    pub fn set_tick(&mut self, tick: u32) {
+        let t = tick as f32 * PLATFORM_STEP;
+        let theta = std::f32::consts::TAU * (t / self.period + self.phase);
+        let rate = std::f32::consts::TAU / self.period;
+        self.x = self.cx + self.ax * theta.sin();
+        self.y = self.cy + self.ay * theta.sin();
+        self.vx = self.ax * rate * theta.cos();
+        self.vy = self.ay * rate * theta.cos();
+    }
+
+    pub fn top(&self) -> f32 {
+        self.y - PLATFORM_HALF_H
+    }
+
+    /// Whether a ball whose feet are at (`x`, `foot_y`) is standing on the platform this
+    /// step. Allows for the platform dropping away by up to one step's travel.
+    pub fn supports(&self, x: f32, foot_y: f32, dt: f32) -> bool {
+        (x - self.x).abs() <= PLATFORM_HALF_W
+            && foot_y >= self.top() - 2.0 - self.vy.max(0.0) * dt
+            && foot_y <= self.y + PLATFORM_HALF_H
+    }
+
+    pub fn draw(&self) {
+        let (x, y) = (self.x - PLATFORM_HALF_W, self.top());
+        let (w, h) = (PLATFORM_HALF_W * 2.0, PLATFORM_HALF_H * 2.0);
+        draw_rectangle(x, y, w, h, Color::new(0.45, 0.47, 0.52, 1.0));
+        draw_rectangle(x, y, w, 2.0, Color::new(0.7, 0.72, 0.78, 1.0));
+        draw_rectangle_lines(x, y, w, h, 1.0, Color::new(0.2, 0.22, 0.26, 1.0));
+    }
+}
+
+/// Seeded platforms hovering over the land, spread evenly across it. Each swings either
+/// sideways or up and down with its own period and starting phase.
+pub fn generate(seed: u32, terrain: &Terrain) -> Vec<MovingPlatform> {
+    let land_w = terrain.land_end_x() - terrain.land_start_x();
+    let mut s = crate::lcg(seed ^ 0x5EED_F1A7);
+    let mut rand = || {
+        s = crate::lcg(s);
+        ((s >> 16) & 0x7FFF) as f32 / 32768.0
+    };
+    (0..PLATFORM_COUNT)
+        .map(|i| {
+            let cx = terrain.land_start_x() + land_w * (i as f32 + 0.3 + rand() * 0.4) / PLATFORM_COUNT as f32;
+            let surface = terrain
+                .find_surface_y(cx as i32)
+                .map_or(terrain.water_level(), |y| (y as f32).min(terrain.water_level()));
+            let (ax, ay) = if rand() < 0.5 { (50.0 + rand() * 40.0, 0.0) } else { (0.0, 30.0 + rand() * 20.0) };
+            let cy = (surface - 70.0 - ay - rand() * 40.0).max(60.0 + ay);
+            let mut p = MovingPlatform {
+                cx,
+                cy,
+                ax,
+                ay,
+                period: 4.0 + rand() * 3.0,
+                phase: rand(),
+                x: cx,
+                y: cy,
+                vx: 0.0,
+                vy: 0.0,
+            };
+            p.set_tick(0);
+            p
+        })
+        .collect()
+}
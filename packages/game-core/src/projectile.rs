@@ -1,6 +1,7 @@
-use crate::physics::Ball;
+use crate::physics::{scale_damage, Ball};
+use crate::special_weapons::Decoy;
 use crate::terrain::Terrain;
-use crate::weapons::Weapon;
+use crate::weapons::{Falloff, Weapon};
 
 pub struct Projectile {
     pub x: f32,
@@ -12,8 +13,78 @@ pub struct Projectile {
     pub bounces: i32,
     pub alive: bool,
     pub trail: Vec<(f32, f32)>,
+    /// Every stepped position since launch, up to MAX_PATH_POINTS; kept as the ghost of
+    /// the team's last shot once the flight ends
+    pub path: Vec<(f32, f32)>,
     /// Team that fired this projectile — used to avoid friendly-fire targeting
     pub shooter_team: u32,
+    /// Shooter's team when friendly fire is off — its balls take no damage (knockback still applies)
+    pub spare_team: Option<u32>,
+    /// Shooter's team when own-team pass-through is on — the projectile flies through its
+    /// balls instead of hitting them directly (splash from the explosion still reaches them)
+    pub pass_team: Option<u32>,
+    /// Shooter's damage multiplier (last stand), applied to every hit this shot deals
+    pub damage_scale: f32,
+    /// Frame time not yet simulated; the projectile advances in whole SHELL_STEPs
+    clock: f32,
+}
+
+/// Fixed simulation step of a projectile. Flight is stepped at this rate whatever the
+/// frame rate, so the aim preview (which uses the same step) traces the real path.
+pub const SHELL_STEP: f32 = 1.0 / 60.0;
+/// Longest recorded flight path, in steps (15 s)
+const MAX_PATH_POINTS: usize = 900;
+const GRAVITY: f32 = 480.0;
+/// Recent positions kept in `trail` for the fading streak behind a shell
+pub const TRAIL_LEN: usize = 30;
+
+/// Position and velocity of a shell in ballistic flight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShellState {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+}
+
+/// What a shell ran into at the end of a `step_projectile`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Contact {
+    /// Still flying (also while above the top of the map, where nothing is checked)
+    Clear,
+    /// Off the sides or far below the map
+    OutOfBounds,
+    Water,
+    Terrain,
+}
+
+/// One step of ballistic flight (wind, drag, gravity, then move) and what the shell is
+/// touching afterwards. Pure: the live projectile and the aim preview both advance with
+/// this, so the preview can't drift from the real shot. Balls are the caller's concern.
+pub fn step_projectile(state: ShellState, weapon: Weapon, terrain: &Terrain, wind: f32, dt: f32) -> (ShellState, Contact) {
+    let air_resistance = if weapon == Weapon::Bazooka { 0.99 } else { 0.98 };
+    let mut s = state;
+    s.vx += wind * 15.0 * weapon.wind_factor() * dt;
+    s.vx *= air_resistance;
+    s.vy += GRAVITY * dt;
+    s.x += s.vx * dt;
+    s.y += s.vy * dt;
+
+    let px = s.x as i32;
+    let py = s.y as i32;
+    let contact = if px < -100 || px >= terrain.width as i32 + 100 || py >= terrain.height as i32 + 100 {
+        Contact::OutOfBounds
+    } else if py < 0 {
+        // Allowed above the map, with nothing there to hit
+        Contact::Clear
+    } else if py >= terrain.water_level() as i32 {
+        Contact::Water
+    } else if terrain.is_solid(px, py) {
+        Contact::Terrain
+    } else {
+        Contact::Clear
+    };
+    (s, contact)
 }
 
 pub struct ShotgunPellet {
@@ -23,6 +94,8 @@ pub struct ShotgunPellet {
     pub vy: f32,
     pub alive: bool,
     pub damage: i32,
+    /// Shooter's team when friendly fire is off — its balls take no damage (knockback still applies)
+    pub spare_team: Option<u32>,
 }
 
 pub struct Explosion {
@@ -41,6 +114,8 @@ pub struct ClusterBomblet {
     pub alive: bool,
     pub radius: f32,
     pub damage: i32,
+    /// Shooter's team when friendly fire is off — its balls take no damage (knockback still applies)
+    pub spare_team: Option<u32>,
 }
 
 impl ShotgunPellet {
@@ -68,7 +143,7 @@ impl ShotgunPellet {
         }
 
         // Check water
-        if self.y >= crate::terrain::WATER_LEVEL {
+        if self.y >= terrain.water_level() {
             self.alive = false;
             return false;
         }
@@ -89,8 +164,8 @@ impl ShotgunPellet {
             let dx = w.x - self.x;
             let dy = w.y - self.y;
             let dist_sq = dx * dx + dy * dy;
-            if dist_sq < 100.0 { // ~10 pixel radius
-                w.take_damage(self.damage);
+            if dist_sq < w.hit_radius_sq(2.0) {
+                w.take_weapon_damage(self.damage, self.spare_team);
                 let dist = dist_sq.sqrt().max(1.0);
                 let knock = 80.0;
                 w.apply_knockback((dx / dist) * knock, (dy / dist) * knock - 50.0);
@@ -130,7 +205,7 @@ impl ClusterBomblet {
             return self.explode(terrain, balls);
         }
 
-        if self.y > crate::terrain::WATER_LEVEL {
+        if self.y > terrain.water_level() {
             self.alive = false;
             return Some(Explosion {
                 x: self.x,
@@ -162,7 +237,7 @@ impl ClusterBomblet {
                 let factor = 1.0 - (dist / blast_radius).min(1.0);
                 let dmg = (self.damage as f32 * factor) as i32;
                 if dmg > 0 {
-                    w.take_damage(dmg.max(1));
+                    w.take_weapon_damage(dmg.max(1), self.spare_team);
                 }
                 let knock = 200.0 * factor;
                 let nx = dx / dist * knock;
@@ -182,7 +257,7 @@ impl ClusterBomblet {
 
 impl Projectile {
     pub fn new(x: f32, y: f32, angle: f32, power: f32, weapon: Weapon, shooter_team: u32) -> Self {
-        let speed = power * 12.0;
+        let speed = weapon.launch_speed(power);
         let vx = angle.cos() * speed;
         let vy = angle.sin() * speed;
         
@@ -201,21 +276,48 @@ impl Projectile {
             bounces: 0,
             alive: true,
             trail: Vec::new(),
+            path: Vec::new(),
             shooter_team,
+            spare_team: None,
+            pass_team: None,
+            damage_scale: 1.0,
+            clock: 0.0,
         }
     }
 
-    pub fn tick(&mut self, terrain: &mut Terrain, balls: &mut [Ball], wind: f32, dt: f32) -> (Option<Explosion>, Vec<ClusterBomblet>) {
-        if !self.alive {
-            return (None, Vec::new());
+    /// Advance by `dt` of frame time in whole SHELL_STEPs, carrying the remainder over.
+    /// Stops at the step that ends the flight.
+    pub fn tick(&mut self, terrain: &mut Terrain, balls: &mut [Ball], decoys: &[Decoy], wind: f32, dt: f32) -> (Option<Explosion>, Vec<ClusterBomblet>) {
+        self.clock += dt;
+        while self.alive && self.clock >= SHELL_STEP {
+            self.clock -= SHELL_STEP;
+            let result = self.step(terrain, balls, decoys, wind, SHELL_STEP);
+            if !self.alive {
+                return result;
+            }
+        }
+        (None, Vec::new())
+    }
+
+    /// Where to draw the projectile: its last stepped position carried forward by the
+    /// frame time not yet simulated, so it moves smoothly between steps. Sheep walk rather
+    /// than fly, so they're drawn where they are.
+    pub fn draw_pos(&self) -> (f32, f32) {
+        if matches!(self.weapon, Weapon::Sheep | Weapon::SuperSheep) {
+            return (self.x, self.y);
         }
+        (self.x + self.vx * self.clock, self.y + self.vy * self.clock)
+    }
 
+    fn step(&mut self, terrain: &mut Terrain, balls: &mut [Ball], decoys: &[Decoy], wind: f32, dt: f32) -> (Option<Explosion>, Vec<ClusterBomblet>) {
         self.trail.push((self.x, self.y));
-        if self.trail.len() > 30 {
+        if self.trail.len() > TRAIL_LEN {
             self.trail.remove(0);
         }
+        if self.path.len() < MAX_PATH_POINTS {
+            self.path.push((self.x, self.y));
+        }
 
-        const GRAVITY: f32 = 480.0;
         let air_resistance = if self.weapon == Weapon::Bazooka { 0.99 } else { 0.98 };
 
         // ── Sheep / SuperSheep: walk along the terrain surface ──────────────────
@@ -260,13 +362,12 @@ impl Projectile {
             // Check if sheep touched any player ball → explode immediately
             // Skip the shooter's own ball so the sheep doesn't blow up in their hands
             {
-                let hit_radius_sq = 18.0f32 * 18.0f32;
                 let sheep_hit = balls.iter().any(|w| {
                     if !w.alive { return false; }
                     if w.team == self.shooter_team { return false; }
                     let dx = w.x - self.x;
                     let dy = w.y - self.y;
-                    dx * dx + dy * dy < hit_radius_sq
+                    dx * dx + dy * dy < w.hit_radius_sq(10.0)
                 });
                 if sheep_hit {
                     self.alive = false;
@@ -290,7 +391,7 @@ impl Projectile {
                 self.alive = false;
                 return (None, Vec::new());
             }
-            if py >= crate::terrain::WATER_LEVEL as i32 {
+            if py >= terrain.water_level() as i32 {
                 self.alive = false;
                 return (Some(Explosion { x: self.x, y: self.y, radius: 0.0, is_water: true }), Vec::new());
             }
@@ -299,14 +400,29 @@ impl Projectile {
         }
         // ────────────────────────────────────────────────────────────────────────
 
-        // Homing Missile behavior - track nearest ball
+        // Homing Missile behavior - track the nearest enemy flare, else the nearest ball
         if self.weapon == Weapon::HomingMissile {
             let mut closest_dist = f32::MAX;
             let mut target_x = 0.0;
             let mut target_y = 0.0;
+
+            for d in decoys.iter() {
+                if d.team == self.shooter_team {
+                    continue;
+                }
+                let dx = d.x - self.x;
+                let dy = d.y - self.y;
+                let dist = (dx * dx + dy * dy).sqrt();
+                if dist < closest_dist && dist < 1200.0 {
+                    closest_dist = dist;
+                    target_x = d.x;
+                    target_y = d.y;
+                }
+            }
+            let lured = closest_dist < f32::MAX;
             
             for w in balls.iter() {
-                if !w.alive {
+                if lured || !w.alive {
                     continue;
                 }
                 // Only home on enemies, not teammates
@@ -341,7 +457,7 @@ impl Projectile {
             }
 
             // Homing missile self-propels — skip gravity so it isn't dragged down
-            self.vx += wind * 5.0 * dt;
+            self.vx += wind * 5.0 * self.weapon.wind_factor() * dt;
             self.vx *= air_resistance;
             self.x += self.vx * dt;
             self.y += self.vy * dt;
@@ -363,13 +479,12 @@ impl Projectile {
             if py < 0 { return (None, Vec::new()); }
 
             // Ball hit check
-            let hit_radius_sq = 14.0f32 * 14.0f32;
             let hit_idx = balls.iter().enumerate()
                 .find(|(_, w)| {
-                    if !w.alive { return false; }
+                    if !w.alive || self.pass_team == Some(w.team) { return false; }
                     let dx = w.x - self.x;
                     let dy = w.y - self.y;
-                    dx * dx + dy * dy < hit_radius_sq
+                    dx * dx + dy * dy < w.hit_radius_sq(6.0)
                 })
                 .map(|(i, _)| i);
             if hit_idx.is_some() {
@@ -384,12 +499,9 @@ impl Projectile {
             return (None, Vec::new());
         }
 
-        self.vx += wind * 15.0 * dt;
-        self.vx *= air_resistance;
-        self.vy += GRAVITY * dt;
-        
-        self.x += self.vx * dt;
-        self.y += self.vy * dt;
+        let state = ShellState { x: self.x, y: self.y, vx: self.vx, vy: self.vy };
+        let (state, contact) = step_projectile(state, self.weapon, terrain, wind, dt);
+        (self.x, self.y, self.vx, self.vy) = (state.x, state.y, state.vx, state.vy);
 
         if self.fuse > 0.0 {
             self.fuse -= dt;
@@ -399,11 +511,10 @@ impl Projectile {
             }
         }
 
-        let px = self.x as i32;
         let py = self.y as i32;
 
         // Allow projectiles above map (py < 0), die off sides/far below
-        if px < -100 || px >= terrain.width as i32 + 100 || py >= terrain.height as i32 + 100 {
+        if contact == Contact::OutOfBounds {
             self.alive = false;
             return (None, Vec::new());
         }
@@ -413,8 +524,7 @@ impl Projectile {
             return (None, Vec::new());
         }
 
-        let is_water = py >= crate::terrain::WATER_LEVEL as i32;
-        if is_water {
+        if contact == Contact::Water {
             self.alive = false;
             return (Some(Explosion {
                 x: self.x,
@@ -425,13 +535,12 @@ impl Projectile {
         }
 
         // Check direct ball collision — scan first (immutable), then act (mutable)
-        let hit_radius_sq = 14.0f32 * 14.0f32;
         let hit_idx = balls.iter().enumerate()
             .find(|(_, w)| {
-                if !w.alive { return false; }
+                if !w.alive || self.pass_team == Some(w.team) { return false; }
                 let dx = w.x - self.x;
                 let dy = w.y - self.y;
-                dx * dx + dy * dy < hit_radius_sq
+                dx * dx + dy * dy < w.hit_radius_sq(6.0)
             })
             .map(|(i, _)| i);
 
@@ -442,10 +551,10 @@ impl Projectile {
                 return self.create_explosion(terrain, balls);
             } else {
                 // Non-explosive (SniperRifle, etc.): direct damage + directional knockback
-                let damage = self.weapon.base_damage();
+                let damage = scale_damage(self.weapon.base_damage(), self.damage_scale);
                 let speed = (self.vx * self.vx + self.vy * self.vy).sqrt().max(1.0);
                 let knock_scale = 200.0_f32.max(damage as f32 * 0.2);
-                balls[bi].take_damage(damage);
+                balls[bi].take_weapon_damage(damage, self.spare_team);
                 balls[bi].apply_knockback(
                     (self.vx / speed) * knock_scale,
                     (self.vy / speed) * knock_scale - 80.0,
@@ -454,7 +563,7 @@ impl Projectile {
             }
         }
 
-        if terrain.is_solid(px, py) {
+        if contact == Contact::Terrain {
             // Handle bouncing for specific weapons
             let max_bounces = self.weapon.max_bounces();
             if max_bounces > 0 && self.bounces < max_bounces {
@@ -481,6 +590,13 @@ impl Projectile {
         (None, Vec::new())
     }
 
+    /// Go off where the projectile is now, e.g. on striking something outside the
+    /// terrain and balls such as a barrel.
+    pub fn detonate(&mut self, terrain: &mut Terrain, balls: &mut [Ball]) -> (Option<Explosion>, Vec<ClusterBomblet>) {
+        self.alive = false;
+        self.create_explosion(terrain, balls)
+    }
+
     fn create_explosion(&self, terrain: &mut Terrain, balls: &mut [Ball]) -> (Option<Explosion>, Vec<ClusterBomblet>) {
         let explosion_radius = self.weapon.explosion_radius() as i32;
 
@@ -489,7 +605,7 @@ impl Projectile {
 
         terrain.apply_damage(px, py, explosion_radius);
 
-        let max_damage = self.weapon.base_damage();
+        let max_damage = scale_damage(self.weapon.base_damage(), self.damage_scale);
 
         for w in balls.iter_mut() {
             if !w.alive {
@@ -502,9 +618,9 @@ impl Projectile {
             
             if dist < r {
                 let damage_factor = (1.0 - dist / r).max(0.0);
-                let damage = (max_damage as f32 * damage_factor) as i32;
+                let damage = blast_damage(max_damage, explosion_radius as f32, dist, self.weapon.falloff_kind());
                 if damage > 0 {
-                    w.take_damage(damage);
+                    w.take_weapon_damage(damage, self.spare_team);
                     let knockback_force = 250.0 * damage_factor;
                     let knockback_x = (dx / dist.max(1.0)) * knockback_force;
                     let knockback_y = (dy / dist.max(1.0)) * knockback_force - 100.0;
@@ -518,30 +634,21 @@ impl Projectile {
         let mut bomblets = Vec::new();
         if cluster_count > 0 {
             use std::f32::consts::PI;
+            let (base_speed, spread) = self.weapon.cluster_spread();
+            let damage = scale_damage(self.weapon.cluster_damage(), self.damage_scale);
             for i in 0..cluster_count {
                 let angle = (i as f32 / cluster_count as f32) * 2.0 * PI;
-                let speed = 100.0 + (i as f32 * 20.0) % 80.0;
+                let speed = base_speed + (i as f32 * 20.0) % spread;
                 bomblets.push(ClusterBomblet {
                     x: self.x,
                     y: self.y,
                     vx: angle.cos() * speed,
                     vy: angle.sin() * speed - 50.0,
-                    fuse: 1.0 + (i as f32 * 0.1),
+                    fuse: self.weapon.cluster_fuse() + (i as f32 * 0.1),
                     alive: true,
-                    radius: match self.weapon {
-                        Weapon::ClusterBomb => 15.0,
-                        Weapon::BananaBomb => 18.0,
-                        Weapon::BananaBonanza => 20.0,
-                        Weapon::Mortar => 12.0,
-                        _ => 10.0,
-                    },
-                    damage: match self.weapon {
-                        Weapon::ClusterBomb => 20,
-                        Weapon::BananaBomb => 25,
-                        Weapon::BananaBonanza => 18,
-                        Weapon::Mortar => 15,
-                        _ => 10,
-                    },
+                    radius: self.weapon.cluster_radius(),
+                    damage,
+                    spare_team: self.spare_team,
                 });
             }
         }
@@ -555,6 +662,47 @@ impl Projectile {
     }
 }
 
+/// Damage a projectile's blast deals `dist` px from its centre. Reaches out to 1.5x the
+/// crater radius and drops off from `max_damage` at the centre following `falloff`.
+pub fn blast_damage(max_damage: i32, explosion_radius: f32, dist: f32, falloff: Falloff) -> i32 {
+    let r = explosion_radius * 1.5;
+    (max_damage as f32 * falloff.factor(dist / r)) as i32
+}
+
+/// Damage `shooter` would take from its own shot: simulates the flight from the muzzle
+/// and applies the blast falloff at the predicted terrain impact. `max_damage` is the
+/// shot's centre damage. 0 when the shell leaves the map without hitting anything.
+pub fn self_blast_damage(shooter: &Ball, angle: f32, power: f32, weapon: Weapon, wind: f32, terrain: &Terrain, max_damage: i32) -> i32 {
+    let muzzle_x = shooter.x + angle.cos() * (shooter.radius + 4.0);
+    let muzzle_y = shooter.y + angle.sin() * (shooter.radius + 4.0);
+    let traj = simulate_trajectory(muzzle_x, muzzle_y, angle, power, weapon, wind, terrain);
+    let (ix, iy) = match traj.last() {
+        Some(&p) if terrain.is_solid(p.0 as i32, p.1 as i32) => p,
+        _ => return 0,
+    };
+    let dist = ((ix - shooter.x).powi(2) + (iy - shooter.y).powi(2)).sqrt();
+    let damage = blast_damage(max_damage, weapon.explosion_radius(), dist, weapon.falloff_kind());
+    scale_damage(damage, shooter.weapon_damage_scale)
+}
+
+/// A predicted terrain impact closer than this to the muzzle counts as a blocked shot.
+pub const BLOCKED_SHOT_DISTANCE: f32 = 24.0;
+
+/// True when a simulated trajectory ends in solid terrain right next to the muzzle,
+/// i.e. the shooter is aiming into an adjacent wall and would catch the blast.
+pub fn is_trajectory_blocked(start_x: f32, start_y: f32, traj: &[(f32, f32)], terrain: &Terrain) -> bool {
+    let (ix, iy) = match traj.last() {
+        Some(&p) => p,
+        None => return false,
+    };
+    if !terrain.is_solid(ix as i32, iy as i32) {
+        return false;
+    }
+    let dx = ix - start_x;
+    let dy = iy - start_y;
+    dx * dx + dy * dy < BLOCKED_SHOT_DISTANCE * BLOCKED_SHOT_DISTANCE
+}
+
 pub fn simulate_trajectory(
     start_x: f32,
     start_y: f32,
@@ -565,38 +713,164 @@ pub fn simulate_trajectory(
     terrain: &Terrain,
 ) -> Vec<(f32, f32)> {
     let mut points = Vec::new();
-    let speed = power * 12.0;
-    let mut x = start_x;
-    let mut y = start_y;
-    let mut vx = angle.cos() * speed;
-    let mut vy = angle.sin() * speed;
-    
-    const GRAVITY: f32 = 480.0;
-    const DT: f32 = 1.0 / 60.0;
-    let air_resistance = if weapon == Weapon::Bazooka { 0.99 } else { 0.98 };
+    // Built the way the live shot is, so both start from identical state
+    let shell = Projectile::new(start_x, start_y, angle, power, weapon, 0);
+    let mut state = ShellState { x: shell.x, y: shell.y, vx: shell.vx, vy: shell.vy };
     let max_steps = 180;
 
     for _ in 0..max_steps {
-        vx += wind * 15.0 * DT;
-        vx *= air_resistance;
-        vy += GRAVITY * DT;
-        x += vx * DT;
-        y += vy * DT;
+        let (next, contact) = step_projectile(state, weapon, terrain, wind, SHELL_STEP);
+        state = next;
+        points.push((state.x, state.y));
+        if contact != Contact::Clear {
+            break;
+        }
+    }
 
-        points.push((x, y));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_weapons_split_into_their_own_bomblets() {
+        for weapon in [Weapon::ClusterBomb, Weapon::BananaBomb, Weapon::BananaBonanza, Weapon::Mortar] {
+            let mut terrain = Terrain::new(1400, 800);
+            let mut shell = Projectile::new(700.0, 400.0, 0.0, 50.0, weapon, 0);
+            shell.damage_scale = 0.5;
+            let (_, bomblets) = shell.create_explosion(&mut terrain, &mut []);
+            assert_eq!(bomblets.len(), weapon.cluster_count(), "{:?}", weapon);
+            let (base, spread) = weapon.cluster_spread();
+            for (i, b) in bomblets.iter().enumerate() {
+                assert_eq!(b.radius, weapon.cluster_radius());
+                assert_eq!(b.damage, scale_damage(weapon.cluster_damage(), 0.5));
+                assert_eq!(b.fuse, weapon.cluster_fuse() + i as f32 * 0.1);
+                let speed = (b.vx * b.vx + (b.vy + 50.0) * (b.vy + 50.0)).sqrt();
+                assert!(speed >= base - 0.01 && speed < base + spread, "{:?} bomblet {} at {}", weapon, i, speed);
+            }
+        }
+    }
 
-        let px = x as i32;
-        let py = y as i32;
+    #[test]
+    fn plain_shells_leave_no_bomblets() {
+        let mut terrain = Terrain::new(1400, 800);
+        let shell = Projectile::new(700.0, 400.0, 0.0, 50.0, Weapon::Bazooka, 0);
+        assert_eq!(Weapon::Bazooka.cluster_count(), 0);
+        assert!(shell.create_explosion(&mut terrain, &mut []).1.is_empty());
+    }
 
-        if px < -100 || px >= terrain.width as i32 + 100 || py >= terrain.height as i32 + 100 {
-            break;
+    /// A ball on a flat floor at y=600 with a stone wall from x=`wall_x` rightwards.
+    fn ball_by_wall(wall_x: i32) -> (Ball, Terrain) {
+        let mut terrain = Terrain::new(1400, 800);
+        for x in 0..1400 {
+            for y in 600..800 {
+                terrain.set(x, y, crate::terrain::STONE);
+            }
         }
+        for x in wall_x..wall_x + 40 {
+            for y in 300..600 {
+                terrain.set(x, y, crate::terrain::STONE);
+            }
+        }
+        let mut ball = Ball::new(500.0, 590.0, 0, String::new());
+        ball.radius = 10.0;
+        (ball, terrain)
+    }
 
-        // Only check terrain/water when on-screen vertically
-        if py >= 0 && (py >= crate::terrain::WATER_LEVEL as i32 || terrain.is_solid(px, py)) {
-            break;
+    #[test]
+    fn shooting_into_a_nearby_wall_hurts_the_shooter() {
+        let (ball, terrain) = ball_by_wall(514);
+        let max = Weapon::Bazooka.base_damage();
+        let danger = self_blast_damage(&ball, 0.0, 60.0, Weapon::Bazooka, 0.0, &terrain, max);
+        assert!(danger > 0 && danger < max, "{}", danger);
+        // Halving the shot's damage halves the estimate (give or take rounding)
+        let half = self_blast_damage(&ball, 0.0, 60.0, Weapon::Bazooka, 0.0, &terrain, max / 2);
+        assert!((half - danger / 2).abs() <= 1, "{} vs {}", half, danger);
+    }
+
+    #[test]
+    fn distant_or_missing_impacts_are_safe() {
+        let (ball, terrain) = ball_by_wall(1100);
+        let max = Weapon::Bazooka.base_damage();
+        assert_eq!(self_blast_damage(&ball, 0.0, 60.0, Weapon::Bazooka, 0.0, &terrain, max), 0);
+        // Straight up and out of the top of the map
+        let open = Terrain::new(1400, 800);
+        assert_eq!(self_blast_damage(&ball, -std::f32::consts::FRAC_PI_2, 100.0, Weapon::Bazooka, 0.0, &open, max), 0);
+    }
+
+    #[test]
+    fn blast_damage_follows_the_weapons_curve_out_to_its_reach() {
+        // Reach is 1.5x the crater radius: 60 px for a 40 px crater
+        assert_eq!(blast_damage(40, 40.0, 0.0, Falloff::Linear), 40);
+        assert_eq!(blast_damage(40, 40.0, 30.0, Falloff::Linear), 20);
+        assert_eq!(blast_damage(40, 40.0, 30.0, Falloff::Steep), 10);
+        assert_eq!(blast_damage(40, 40.0, 59.0, Falloff::Constant), 40);
+        for falloff in [Falloff::Linear, Falloff::Constant, Falloff::Steep] {
+            assert_eq!(blast_damage(40, 40.0, 60.0, falloff), 0);
         }
     }
 
-    points
+    #[test]
+    fn aim_preview_traces_the_live_shot() {
+        let mut floor = Terrain::new(1400, 800);
+        for x in 0..1400 {
+            for y in 600..800 {
+                floor.set(x, y, crate::terrain::DIRT);
+            }
+        }
+        for (weapon, wind) in [(Weapon::Bazooka, 0.0), (Weapon::Bazooka, -3.5), (Weapon::Mortar, 2.0)] {
+            let angle = -0.8;
+            let preview = simulate_trajectory(300.0, 580.0, angle, 70.0, weapon, wind, &floor);
+            let mut terrain = Terrain::new(1400, 800);
+            terrain.cells.clone_from(&floor.cells);
+            let mut shell = Projectile::new(300.0, 580.0, angle, 70.0, weapon, 0);
+            let mut blast = None;
+            while shell.alive && shell.path.len() <= preview.len() {
+                blast = shell.tick(&mut terrain, &mut [], &[], wind, SHELL_STEP).0.or(blast);
+            }
+            assert!(!shell.alive, "{:?} still flying after the preview landed", weapon);
+            // The live path records where each step started; the preview where each ended
+            assert_eq!(shell.path[1..], preview[..preview.len() - 1], "{:?} in wind {}", weapon, wind);
+            let blast = blast.expect("the shell hits the floor");
+            assert_eq!((blast.x, blast.y), *preview.last().unwrap());
+        }
+    }
+
+    #[test]
+    fn step_projectile_reports_what_the_shell_touches() {
+        let mut terrain = Terrain::new(1400, 800);
+        terrain.set(500, 300, crate::terrain::DIRT);
+        let at = |x: f32, y: f32| ShellState { x, y, vx: 0.0, vy: 0.0 };
+        let step = |s| step_projectile(s, Weapon::Bazooka, &terrain, 0.0, 0.0).1;
+        assert_eq!(step(at(500.5, 300.5)), Contact::Terrain);
+        assert_eq!(step(at(400.0, 300.0)), Contact::Clear);
+        assert_eq!(step(at(400.0, -50.0)), Contact::Clear);
+        assert_eq!(step(at(-150.0, 300.0)), Contact::OutOfBounds);
+        assert_eq!(step(at(400.0, terrain.water_level() + 1.0)), Contact::Water);
+    }
+
+    #[test]
+    fn heavier_shells_land_shorter_at_the_same_charge() {
+        // Same muzzle scale and air drag, different mass
+        assert_eq!(Weapon::Mortar.muzzle_speed_scale(), Weapon::HomingMissile.muzzle_speed_scale());
+        assert!(Weapon::Mortar.mass() > Weapon::HomingMissile.mass());
+        let mut floor = Terrain::new(1400, 800);
+        for x in 0..1400 {
+            for y in 600..800 {
+                floor.set(x, y, crate::terrain::DIRT);
+            }
+        }
+        let landing_x = |weapon: Weapon| {
+            let path = simulate_trajectory(100.0, 590.0, -0.8, 50.0, weapon, 0.0, &floor);
+            let &(x, y) = path.last().unwrap();
+            assert!(floor.is_solid(x as i32, y as i32), "{:?} never landed", weapon);
+            x
+        };
+        let (light, heavy) = (landing_x(Weapon::HomingMissile), landing_x(Weapon::Mortar));
+        assert!(heavy < light, "mortar {} vs missile {}", heavy, light);
+        // Full charge flies the same either way
+        assert_eq!(Weapon::Mortar.launch_speed(100.0), Weapon::HomingMissile.launch_speed(100.0));
+    }
 }
@@ -0,0 +1,254 @@
+//! Recorded matches played back tick by tick, with seeking for analysis. A replay is a
+//! snapshot of the match before its first tick plus every input played since, so any
+//! tick can be rebuilt by re-simulating. To keep seeks cheap the player stores keyframe
+//! snapshots (the save format) as it goes and restarts from the nearest one.
+//!
+//! Nothing in the game records or plays replays yet; this is the API a viewer builds on.
+#![allow(dead_code)]
+
+use crate::net_input::NetInput;
+use crate::Game;
+
+/// Ticks between keyframes. A keyframe is only taken once the match can be snapshotted
+/// (between shots), so the gap may run a little longer.
+pub const KEYFRAME_EVERY: u32 = 300;
+/// Time one replay tick simulates
+pub const REPLAY_DT: f32 = 1.0 / 60.0;
+
+/// A match a replay can re-run.
+pub trait Replayable: Sized {
+    /// Play `inputs` (each on a ball index), then advance one tick.
+    fn step(&mut self, inputs: &[(usize, NetInput)]);
+    /// The full state, or None at a point that can't be captured.
+    fn snapshot(&self) -> Option<String>;
+    fn restore(snapshot: &str) -> Option<Self>;
+}
+
+impl Replayable for Game {
+    fn step(&mut self, inputs: &[(usize, NetInput)]) {
+        for &(ball, input) in inputs {
+            self.apply_input(ball, input);
+        }
+        self.update(REPLAY_DT);
+    }
+
+    fn snapshot(&self) -> Option<String> {
+        self.can_save().then(|| self.save_to_string())
+    }
+
+    fn restore(snapshot: &str) -> Option<Game> {
+        Game::load_from_string(snapshot)
+    }
+}
+
+/// A recording: where the match started and what was played on which tick.
+#[derive(Clone)]
+pub struct Replay {
+    start: String,
+    /// (tick, ball, input), in the order they were played
+    inputs: Vec<(u32, usize, NetInput)>,
+    ticks: u32,
+}
+
+impl Replay {
+    /// Start recording from `start`, a snapshot of the match before its first tick.
+    pub fn new(start: String) -> Self {
+        Replay { start, inputs: Vec::new(), ticks: 0 }
+    }
+
+    /// Note `input` played on `ball` during the tick being recorded.
+    pub fn record_input(&mut self, ball: usize, input: NetInput) {
+        self.inputs.push((self.ticks, ball, input));
+    }
+
+    /// Close off the tick being recorded.
+    pub fn end_tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    /// Ticks recorded.
+    pub fn len(&self) -> u32 {
+        self.ticks
+    }
+
+    fn inputs_at(&self, tick: u32) -> Vec<(usize, NetInput)> {
+        self.inputs.iter().filter(|(t, _, _)| *t == tick).map(|&(_, ball, input)| (ball, input)).collect()
+    }
+}
+
+/// Plays a replay back, positioned between ticks: at tick n the first n recorded ticks
+/// have been simulated.
+pub struct ReplayPlayer<G: Replayable> {
+    replay: Replay,
+    game: G,
+    tick: u32,
+    /// (tick, snapshot) in tick order, starting with the replay's start
+    keyframes: Vec<(u32, String)>,
+}
+
+impl<G: Replayable> ReplayPlayer<G> {
+    /// None when the replay's start snapshot doesn't load.
+    pub fn new(replay: Replay) -> Option<Self> {
+        let game = G::restore(&replay.start)?;
+        let keyframes = vec![(0, replay.start.clone())];
+        Some(ReplayPlayer { replay, game, tick: 0, keyframes })
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    /// Simulate the next tick. False at the end of the replay.
+    pub fn step_forward(&mut self) -> bool {
+        if self.tick >= self.replay.len() {
+            return false;
+        }
+        self.game.step(&self.replay.inputs_at(self.tick));
+        self.tick += 1;
+        let last = self.keyframes.last().map_or(0, |(t, _)| *t);
+        if self.tick >= last + KEYFRAME_EVERY {
+            if let Some(snapshot) = self.game.snapshot() {
+                self.keyframes.push((self.tick, snapshot));
+            }
+        }
+        true
+    }
+
+    /// Go back one tick. False at the start.
+    pub fn step_back(&mut self) -> bool {
+        if self.tick == 0 {
+            return false;
+        }
+        self.seek(self.tick - 1);
+        true
+    }
+
+    /// Move to `target` (clamped to the replay's length). Forward seeks simulate on from
+    /// here unless a later keyframe is closer; backward ones restart from the last
+    /// keyframe at or before the target.
+    pub fn seek(&mut self, target: u32) {
+        let target = target.min(self.replay.len());
+        let (key_tick, snapshot) = self.keyframes.iter().rev().find(|(t, _)| *t <= target).expect("the start is a keyframe");
+        if target < self.tick || *key_tick > self.tick {
+            // Keyframes come from snapshots that loaded before, so they load again
+            self.game = G::restore(snapshot).expect("keyframe snapshot loads");
+            self.tick = *key_tick;
+        }
+        while self.tick < target {
+            self.step_forward();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::MatchSettings;
+    use crate::weapons::Weapon;
+
+    /// A stand-in match: a running total of the inputs' walk directions, which can only
+    /// be snapshotted on even totals.
+    struct Counter {
+        total: i64,
+        steps: u32,
+    }
+
+    impl Replayable for Counter {
+        fn step(&mut self, inputs: &[(usize, NetInput)]) {
+            for (_, input) in inputs {
+                if let NetInput::Walk { dir } = input {
+                    self.total += *dir as i64;
+                }
+            }
+            self.total += 2;
+            self.steps += 1;
+        }
+
+        fn snapshot(&self) -> Option<String> {
+            (self.total % 2 == 0).then(|| self.total.to_string())
+        }
+
+        fn restore(snapshot: &str) -> Option<Counter> {
+            Some(Counter { total: snapshot.parse().ok()?, steps: 0 })
+        }
+    }
+
+    fn counter_replay(ticks: u32) -> Replay {
+        let mut replay = Replay::new("0".into());
+        for tick in 0..ticks {
+            // An odd step every 100 ticks, undone on the next, so some ticks can't be keyframes
+            if tick % 100 == 99 {
+                replay.record_input(0, NetInput::Walk { dir: 1.0 });
+            } else if tick.is_multiple_of(100) && tick > 0 {
+                replay.record_input(0, NetInput::Walk { dir: -1.0 });
+            }
+            replay.end_tick();
+        }
+        replay
+    }
+
+    /// Total after `tick` ticks of `counter_replay`
+    fn expected(tick: u32) -> i64 {
+        let total = 2 * tick as i64;
+        if tick.is_multiple_of(100) && tick > 0 { total + 1 } else { total }
+    }
+
+    #[test]
+    fn seeking_matches_playing_straight_through() {
+        let mut player: ReplayPlayer<Counter> = ReplayPlayer::new(counter_replay(1000)).unwrap();
+        for target in [650, 120, 999, 0, 300, 301, 5000] {
+            player.seek(target);
+            let tick = target.min(1000);
+            assert_eq!(player.tick(), tick);
+            assert_eq!(player.game().total, expected(tick), "seek to {}", target);
+        }
+        assert!(!player.step_forward());
+        assert!(player.step_back());
+        assert_eq!((player.tick(), player.game().total), (999, expected(999)));
+        player.seek(0);
+        assert!(!player.step_back());
+    }
+
+    #[test]
+    fn keyframes_keep_backward_seeks_short() {
+        let mut player: ReplayPlayer<Counter> = ReplayPlayer::new(counter_replay(1000)).unwrap();
+        player.seek(1000);
+        // Tick 300 lands on an odd total, so that keyframe waits a tick
+        let ticks: Vec<u32> = player.keyframes.iter().map(|(t, _)| *t).collect();
+        assert_eq!(ticks, vec![0, 301, 601, 901]);
+        // Stepping back restarts from the keyframe at 901, not from the start
+        player.step_back();
+        assert_eq!(player.game().steps, 999 - 901);
+        player.seek(650);
+        assert_eq!(player.game().steps, 650 - 601);
+    }
+
+    #[test]
+    fn a_recorded_shot_replays_the_same_from_any_seek() {
+        let mut game = Game::new_with_teams(11, 2, MatchSettings::default());
+        let mut replay = Replay::new(game.save_to_string());
+        let ball = game.current_ball;
+        let shot = NetInput::Fire { weapon: Some(Weapon::Grenade), angle_deg: -50.0, power_percent: 55.0 };
+        let mut positions = Vec::new();
+        for tick in 0..150 {
+            if tick == 20 {
+                replay.record_input(ball, shot);
+                game.apply_input(ball, shot);
+            }
+            game.update(REPLAY_DT);
+            replay.end_tick();
+            positions.push(game.sim.balls.iter().map(|b| (b.x.to_bits(), b.y.to_bits())).collect::<Vec<_>>());
+        }
+        assert!(game.has_fired);
+        let mut player: ReplayPlayer<Game> = ReplayPlayer::new(replay).unwrap();
+        for target in [150, 40, 41, 90, 21] {
+            player.seek(target);
+            let now: Vec<_> = player.game().sim.balls.iter().map(|b| (b.x.to_bits(), b.y.to_bits())).collect();
+            assert_eq!(now, positions[target as usize - 1], "tick {}", target);
+        }
+    }
+}
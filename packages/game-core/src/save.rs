@@ -0,0 +1,489 @@
+//! Snapshot of an offline match so it can be quit and resumed. Unlike a replay this
+//! stores the state directly: one `key value...` line per item, floats as their raw
+//! bits in hex so a loaded game continues bit-identically. Saves are taken between
+//! shots, so in-flight projectiles are not part of the format.
+
+use crate::barrels::Barrel;
+use crate::physics::Ball;
+use crate::settings::{BallSize, MatchSettings, SpawnMode};
+use crate::crates::{CrateKind, SupplyCrate};
+use crate::special_weapons::{Decoy, FirePool};
+use crate::state::Phase;
+use crate::terrain::TerrainTheme;
+use crate::weapons::{random_loadout, Weapon};
+use crate::Game;
+
+const HEADER: &str = "balls-save 2";
+
+/// Where F5/F9 write and read the save, relative to the working directory
+pub const SAVE_FILE: &str = "balls_save.txt";
+
+fn f(v: f32) -> String {
+    format!("{:08x}", v.to_bits())
+}
+
+fn b(v: bool) -> &'static str {
+    if v { "1" } else { "0" }
+}
+
+/// Reads fields of one line in order.
+struct Fields<'a>(std::str::SplitWhitespace<'a>);
+
+impl<'a> Fields<'a> {
+    fn str(&mut self) -> Option<&'a str> {
+        self.0.next()
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        u32::from_str_radix(self.str()?, 16).ok().map(f32::from_bits)
+    }
+
+    fn num<T: std::str::FromStr>(&mut self) -> Option<T> {
+        self.str()?.parse().ok()
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.str()? {
+            "1" => Some(true),
+            "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Everything left on the line, for names that may contain spaces.
+    fn rest(self) -> String {
+        self.0.collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn settings_line(s: &MatchSettings) -> String {
+    format!(
+        "settings {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+        f(s.terrain_density), f(s.movement_regen), s.earthquake_every, s.crate_rain_after,
+        b(s.friendly_fire),
+        b(s.own_team_pass_through), b(s.random_loadout), b(s.last_stand), b(s.fixed_physics),
+        f(s.crater_push), b(s.random_first_turn), s.ball_size.name(), s.spawn_mode.name(),
+        b(s.mirror_map), b(s.moving_platforms), s.terrain_budget, s.shrink_zone_after,
+        f(s.blast_push), f(s.knockback_scale), f(s.weapon_damage),
+        s.terrain_theme.as_ref().map_or("random", |t| t.name()), b(s.gas_pockets),
+        b(s.half_res_terrain),
+    )
+}
+
+fn read_settings(mut st: Fields) -> Option<MatchSettings> {
+    Some(MatchSettings {
+        terrain_density: st.f32()?,
+        movement_regen: st.f32()?,
+        earthquake_every: st.num()?,
+        crate_rain_after: st.num()?,
+        friendly_fire: st.bool()?,
+        own_team_pass_through: st.bool()?,
+        random_loadout: st.bool()?,
+        last_stand: st.bool()?,
+        fixed_physics: st.bool()?,
+        crater_push: st.f32()?,
+        random_first_turn: st.bool()?,
+        ball_size: BallSize::from_name(st.str()?)?,
+        spawn_mode: SpawnMode::from_name(st.str()?)?,
+        mirror_map: st.bool()?,
+        moving_platforms: st.bool()?,
+        terrain_budget: st.num()?,
+        shrink_zone_after: st.num()?,
+        blast_push: st.f32()?,
+        knockback_scale: st.f32()?,
+        weapon_damage: st.f32()?,
+        terrain_theme: match st.str()? {
+            "random" => None,
+            name => Some(TerrainTheme::from_name(name)?),
+        },
+        gas_pockets: st.bool()?,
+        half_res_terrain: st.bool()?,
+    })
+}
+
+fn ball_line(w: &Ball) -> String {
+    format!(
+        "ball {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+        f(w.x), f(w.y), f(w.vx), f(w.vy), w.health, w.max_health, f(w.facing), w.team,
+        b(w.on_ground), b(w.alive), f(w.fall_start_y), w.last_damage, f(w.damage_timer),
+        f(w.movement_budget), f(w.movement_used), f(w.coyote_timer), f(w.jump_buffer),
+        b(w.shield), b(w.sliding), f(w.slide_timer), b(w.last_stand), w.name,
+    )
+}
+
+/// A saved ball; size and damage scaling aren't stored but come from the settings.
+fn read_ball(mut v: Fields, settings: &MatchSettings) -> Option<Ball> {
+    let (x, y) = (v.f32()?, v.f32()?);
+    let mut w = Ball::new(x, y, 0, String::new());
+    w.radius = settings.ball_size.radius();
+    w.knockback_scale = settings.knockback_scale;
+    w.weapon_damage_scale = settings.weapon_damage;
+    w.vx = v.f32()?;
+    w.vy = v.f32()?;
+    w.health = v.num()?;
+    w.max_health = v.num()?;
+    w.display_health = w.health as f32;
+    w.facing = v.f32()?;
+    w.team = v.num()?;
+    w.on_ground = v.bool()?;
+    w.alive = v.bool()?;
+    w.fall_start_y = v.f32()?;
+    w.last_damage = v.num()?;
+    w.damage_timer = v.f32()?;
+    w.movement_budget = v.f32()?;
+    w.movement_used = v.f32()?;
+    w.coyote_timer = v.f32()?;
+    w.jump_buffer = v.f32()?;
+    w.shield = v.bool()?;
+    w.sliding = v.bool()?;
+    w.slide_timer = v.f32()?;
+    w.last_stand = v.bool()?;
+    w.name = v.rest();
+    Some(w)
+}
+
+impl Game {
+    /// Whether the match is at a point `save_to_string` can capture: offline, between
+    /// shots, with nothing in flight.
+    pub fn can_save(&self) -> bool {
+        !self.net.connected
+            && self.phase == Phase::Aiming
+            && !self.has_fired
+            && self.proj.is_none()
+            && self.placed_explosives.is_empty()
+            && self.gas_fuses.is_empty()
+    }
+
+    pub fn save_to_string(&self) -> String {
+        let s = &self.settings;
+        let mut out = vec![
+            HEADER.to_string(),
+            format!("match {} {}", self.map_seed, self.num_teams),
+            settings_line(s),
+            format!("rng {} {}", self.rng_state, f(self.sim.wind)),
+            format!("platforms {} {}", self.platform_ticks, f(self.platform_clock)),
+            format!(
+                "turn {} {} {} {} {} {}",
+                self.current_ball, self.current_turn_index, self.turns_played,
+                f(self.turn_timer), f(self.aim_angle), self.selected_weapon.name(),
+            ),
+            format!("terrain {}", self.sim.terrain.encode_cells()),
+        ];
+        for w in &self.sim.balls {
+            out.push(ball_line(w));
+        }
+        for p in &self.fire_pools {
+            out.push(format!(
+                "fire {} {} {} {} {} {} {} {} {}",
+                f(p.x), f(p.y), f(p.radius), f(p.lifetime), f(p.damage_timer), b(p.alive),
+                f(p.flow_timer), p.spawns_left, p.spare_team.map_or("-".to_string(), |t| t.to_string()),
+            ));
+        }
+        for d in &self.decoys {
+            out.push(format!("decoy {} {} {} {}", f(d.x), f(d.y), f(d.lifetime), d.team));
+        }
+        for c in &self.crates {
+            let kind = match &c.kind {
+                CrateKind::Health => "health".to_string(),
+                CrateKind::Mystery(seed) => format!("mystery {}", seed),
+            };
+            out.push(format!("crate {} {} {} {}", f(c.x), f(c.y), f(c.vy), kind));
+        }
+        // Barrels that already went off are left out
+        for r in self.barrels.iter().filter(|r| r.alive) {
+            out.push(format!("barrel {} {} {} {}", f(r.x), f(r.y), f(r.vy), r.hp));
+        }
+        if s.random_loadout {
+            // Weapons won from crates on top of the seeded loadout
+            for (team, loadout) in self.loadouts.iter().enumerate() {
+                let base = random_loadout(self.map_seed, team as u32);
+                for w in loadout.iter().filter(|w| !base.contains(w)) {
+                    out.push(format!("unlock {} {}", team, w.name()));
+                }
+            }
+        }
+        // Campaign progression, as the weapons still locked
+        if self.unlocked_weapons.is_some() {
+            out.push("campaign".to_string());
+            for w in self.locked_weapons() {
+                out.push(format!("campaign_lock {}", w.name()));
+            }
+        }
+        for (team, last) in self.last_ball_per_team.iter().enumerate() {
+            if let Some(idx) = last {
+                out.push(format!("last_ball {} {}", team, idx));
+            }
+        }
+        for (team, shot) in self.last_shot.iter().enumerate() {
+            if let Some((weapon, angle, power)) = shot {
+                out.push(format!("last_shot {} {} {} {}", team, f(*angle), f(*power), weapon.name()));
+            }
+        }
+        for turn in &self.quake_log {
+            out.push(format!("quake {}", turn));
+        }
+        for turn in &self.grass_log {
+            out.push(format!("grass {}", turn));
+        }
+        out.join("\n")
+    }
+
+    /// Rebuild a match from `save_to_string` output. None if the text isn't a valid save.
+    pub fn load_from_string(s: &str) -> Option<Game> {
+        let mut lines = s.lines();
+        if lines.next()? != HEADER {
+            return None;
+        }
+        let lines: Vec<(&str, Fields)> = lines
+            .filter_map(|l| {
+                let mut it = l.split_whitespace();
+                Some((it.next()?, Fields(it)))
+            })
+            .collect();
+        let line = |key: &str| {
+            lines.iter().find(|(k, _)| *k == key).map(|(_, f)| Fields(f.0.clone()))
+        };
+
+        let mut m = line("match")?;
+        let seed: u32 = m.num()?;
+        let num_teams: usize = m.num()?;
+        let settings = read_settings(line("settings")?)?;
+
+        let mut game = Game::new_with_teams(seed, num_teams, settings);
+
+        let mut r = line("rng")?;
+        game.rng_state = r.num()?;
+        game.sim.wind = r.f32()?;
+
+        let mut p = line("platforms")?;
+        game.platform_ticks = p.num()?;
+        game.platform_clock = p.f32()?;
+        for platform in &mut game.platforms {
+            platform.set_tick(game.platform_ticks);
+        }
+
+        let mut t = line("turn")?;
+        game.current_ball = t.num()?;
+        game.current_turn_index = t.num()?;
+        game.turns_played = t.num()?;
+        game.turn_timer = t.f32()?;
+        game.aim_angle = t.f32()?;
+        // Weapon names can have spaces, so they always go last on a line
+        game.selected_weapon = Weapon::from_name(&t.rest())?;
+        game.safe_zone = game.zone_for_turn(game.turns_played);
+
+        game.sim.terrain.decode_cells(line("terrain")?.str()?)?;
+        game.terrain_dirty = true;
+
+        game.sim.balls.clear();
+        game.fire_pools.clear();
+        game.decoys.clear();
+        game.crates.clear();
+        game.barrels.clear();
+        for (key, fields) in &lines {
+            let mut v = Fields(fields.0.clone());
+            match *key {
+                "ball" => game.sim.balls.push(read_ball(v, &settings)?),
+                "fire" => game.fire_pools.push(FirePool {
+                    x: v.f32()?,
+                    y: v.f32()?,
+                    radius: v.f32()?,
+                    lifetime: v.f32()?,
+                    damage_timer: v.f32()?,
+                    alive: v.bool()?,
+                    flow_timer: v.f32()?,
+                    spawns_left: v.num()?,
+                    // "-" when the pool burns every team
+                    spare_team: v.str().and_then(|t| t.parse().ok()),
+                }),
+                "decoy" => game.decoys.push(Decoy {
+                    x: v.f32()?,
+                    y: v.f32()?,
+                    lifetime: v.f32()?,
+                    team: v.num()?,
+                }),
+                "crate" => {
+                    let (x, y, vy) = (v.f32()?, v.f32()?, v.f32()?);
+                    let kind = match v.rest().as_str() {
+                        "health" => CrateKind::Health,
+                        kind => CrateKind::Mystery(kind.strip_prefix("mystery ")?.parse().ok()?),
+                    };
+                    game.crates.push(SupplyCrate { x, y, vy, kind, alive: true });
+                }
+                "barrel" => game.barrels.push(Barrel {
+                    x: v.f32()?,
+                    y: v.f32()?,
+                    vy: v.f32()?,
+                    hp: v.num()?,
+                    alive: true,
+                }),
+                "unlock" => {
+                    let team: usize = v.num()?;
+                    let w = Weapon::from_name(&v.rest())?;
+                    game.loadouts.get_mut(team)?.push(w);
+                }
+                "last_ball" => {
+                    let team: usize = v.num()?;
+                    let idx: usize = v.num()?;
+                    if team >= game.last_ball_per_team.len() {
+                        game.last_ball_per_team.resize(team + 1, None);
+                    }
+                    game.last_ball_per_team[team] = Some(idx);
+                }
+                "last_shot" => {
+                    let team: usize = v.num()?;
+                    let (angle, power) = (v.f32()?, v.f32()?);
+                    let shot = (Weapon::from_name(&v.rest())?, angle, power);
+                    *game.last_shot.get_mut(team)? = Some(shot);
+                }
+                "quake" => game.quake_log.push(v.num()?),
+                "grass" => game.grass_log.push(v.num()?),
+                _ => {}
+            }
+        }
+        if line("campaign").is_some() {
+            let locked: Vec<Weapon> = lines.iter()
+                .filter(|(k, _)| *k == "campaign_lock")
+                .map(|(_, f)| Weapon::from_name(&Fields(f.0.clone()).rest()))
+                .collect::<Option<_>>()?;
+            let unlocked: Vec<Weapon> = Weapon::all().iter().copied().filter(|w| !locked.contains(w)).collect();
+            game.set_unlocked_weapons(&unlocked);
+        }
+        if game.current_ball >= game.sim.balls.len() {
+            return None;
+        }
+        game.ball_lerp_targets = vec![None; game.sim.balls.len()];
+        game.ball_event_cooldown = vec![0.0; game.sim.balls.len()];
+        Some(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::{Terrain, AIR, DIRT, STONE};
+
+    /// The fields after a line's key, as load_from_string hands them out
+    fn fields(line: &str) -> Fields<'_> {
+        let mut it = line.split_whitespace();
+        it.next();
+        Fields(it)
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let settings = MatchSettings {
+            terrain_density: 0.3,
+            earthquake_every: 4,
+            friendly_fire: false,
+            crater_push: 0.7,
+            ball_size: BallSize::Large,
+            spawn_mode: SpawnMode::Zones,
+            mirror_map: true,
+            terrain_budget: 2500,
+            knockback_scale: 1.75,
+            terrain_theme: None,
+            gas_pockets: true,
+            half_res_terrain: true,
+            ..MatchSettings::default()
+        };
+        assert_eq!(read_settings(fields(&settings_line(&settings))), Some(settings));
+        assert_eq!(read_settings(fields(&settings_line(&MatchSettings::default()))), Some(MatchSettings::default()));
+    }
+
+    #[test]
+    fn ball_round_trips_bit_for_bit() {
+        let settings = MatchSettings::default();
+        let mut w = Ball::new(123.456, 78.9, 2, "Sir Bounce A Lot".to_string());
+        w.vx = -0.1;
+        w.vy = 1e-7;
+        w.health = 37;
+        w.facing = -1.0;
+        w.on_ground = false;
+        w.movement_used = 12.345;
+        w.shield = true;
+        let back = read_ball(fields(&ball_line(&w)), &settings).unwrap();
+        assert_eq!(ball_line(&back), ball_line(&w));
+        assert_eq!((back.x.to_bits(), back.vy.to_bits()), (w.x.to_bits(), w.vy.to_bits()));
+        assert_eq!(back.name, "Sir Bounce A Lot");
+        assert_eq!(back.radius, settings.ball_size.radius());
+    }
+
+    #[test]
+    fn terrain_cells_round_trip() {
+        let mut terrain = Terrain::new(1400, 800);
+        for x in 100..900 {
+            terrain.set(x, 600, DIRT);
+            terrain.set(x, 601 + x % 7, STONE);
+        }
+        let encoded = terrain.encode_cells();
+        let mut back = Terrain::new(1400, 800);
+        assert_eq!(back.decode_cells(&encoded), Some(()));
+        assert_eq!(back.encode_cells(), encoded);
+        assert_eq!((back.get(300, 600), back.get(300, 601 + 300 % 7), back.get(300, 599)), (DIRT, STONE, AIR));
+    }
+
+    #[test]
+    fn truncated_saves_are_rejected() {
+        let mut terrain = Terrain::new(1400, 800);
+        assert_eq!(terrain.decode_cells("1:100"), None);
+        assert_eq!(terrain.decode_cells("1:99999999"), None);
+        assert!(read_settings(fields("settings 3e99999a 3f800000")).is_none());
+        assert!(Game::load_from_string("balls-save 0\nmatch 1 2").is_none());
+    }
+
+    /// Positions, velocities and health of every ball, as raw bits.
+    fn ball_bits(game: &Game) -> Vec<[u32; 5]> {
+        game.sim.balls.iter()
+            .map(|w| [w.x.to_bits(), w.y.to_bits(), w.vx.to_bits(), w.vy.to_bits(), w.health as u32])
+            .collect()
+    }
+
+    #[test]
+    fn loaded_game_keeps_simulating_identically() {
+        let mut game = Game::new_with_teams(7, 2, MatchSettings::default());
+        // Let the balls drop onto the map, then pick a weapon whose name has a space
+        for _ in 0..120 {
+            game.update(1.0 / 60.0);
+        }
+        game.selected_weapon = Weapon::ClusterBomb;
+        game.last_shot[0] = Some((Weapon::HomingMissile, -0.5, 70.0));
+        assert!(game.can_save());
+        let saved = game.save_to_string();
+        let mut loaded = Game::load_from_string(&saved).expect("the save loads");
+        assert_eq!(loaded.save_to_string(), saved);
+
+        for g in [&mut game, &mut loaded] {
+            g.aim_angle = -0.9;
+            g.charge_power = 65.0;
+            g.fire().expect("the shot goes off");
+        }
+        // Through the flight, the blast and the settling, up to the next turn (which
+        // reads the mouse, and there's no window here)
+        let mut tick = 0;
+        while game.phase != Phase::TurnEnd {
+            assert!(tick < 1800, "the shot never finished");
+            tick += 1;
+            game.update(1.0 / 60.0);
+            loaded.update(1.0 / 60.0);
+            assert_eq!(ball_bits(&loaded), ball_bits(&game), "tick {}", tick);
+        }
+        assert!(tick > 60);
+        assert_eq!(loaded.sim.terrain.cells, game.sim.terrain.cells);
+        assert_eq!(loaded.phase, game.phase);
+    }
+
+    #[test]
+    fn weapons_with_spaces_or_renames_survive_a_save() {
+        let mut game = Game::new_with_teams(3, 2, MatchSettings::default());
+        // Every weapon's name maps back to it (see weapons.rs); these are the ones the
+        // save format used to trip over
+        for w in [Weapon::Drill, Weapon::NapalmStrike, Weapon::HolyHandGrenade] {
+            game.selected_weapon = w;
+            game.last_shot[1] = Some((w, 0.25, 40.0));
+            let back = Game::load_from_string(&game.save_to_string()).unwrap_or_else(|| panic!("{} breaks the save", w.name()));
+            assert!(back.selected_weapon == w);
+            assert!(back.last_shot[1].is_some_and(|(lw, _, _)| lw == w));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::physics::BALL_RADIUS;
+use crate::terrain::TerrainTheme;
+
+/// Ball size preset. Bigger balls are easier to hit but also block more shots.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BallSize {
+    Small,
+    Normal,
+    Large,
+}
+
+impl BallSize {
+    pub fn radius(&self) -> f32 {
+        match self {
+            BallSize::Small => 6.0,
+            BallSize::Normal => BALL_RADIUS,
+            BallSize::Large => 11.0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            BallSize::Small => "small",
+            BallSize::Normal => "normal",
+            BallSize::Large => "large",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<BallSize> {
+        match s {
+            "small" => Some(BallSize::Small),
+            "normal" => Some(BallSize::Normal),
+            "large" => Some(BallSize::Large),
+            _ => None,
+        }
+    }
+}
+
+/// Where balls start. Spread interleaves every team evenly across the map; Zones gives
+/// each team its own strip (left/right halves for two teams) for base-style matches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpawnMode {
+    Spread,
+    Zones,
+}
+
+impl SpawnMode {
+    pub fn name(&self) -> &str {
+        match self {
+            SpawnMode::Spread => "spread",
+            SpawnMode::Zones => "zones",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<SpawnMode> {
+        match s {
+            "spread" => Some(SpawnMode::Spread),
+            "zones" => Some(SpawnMode::Zones),
+            _ => None,
+        }
+    }
+}
+
+/// Per-match options agreed before the game starts. Every client must use the
+/// same values because they feed terrain generation and the simulation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MatchSettings {
+    /// Solid terrain vs. open space, 0.0 (sparse) to 1.0 (dense). 0.5 is the classic map.
+    pub terrain_density: f32,
+    /// Fraction of the movement budget restored at the start of a ball's turn.
+    /// 1.0 is a full reset every turn; lower values carry over-walking into the next turn.
+    pub movement_regen: f32,
+    /// Trigger an earthquake every N turns; 0 disables the event.
+    pub earthquake_every: u32,
+    /// From this turn on, supply crates rain onto the map every turn to force the
+    /// endgame; 0 disables it.
+    pub crate_rain_after: u32,
+    /// When false, weapons don't damage the shooter's own team (knockback still applies).
+    pub friendly_fire: bool,
+    /// When true, projectiles fly through the shooter's own balls instead of hitting them
+    /// directly. Splash damage is unaffected (that's `friendly_fire`).
+    pub own_team_pass_through: bool,
+    /// Each team starts with a seeded random subset of the arsenal; the rest is locked.
+    pub random_loadout: bool,
+    /// A team's last living ball gets a last-stand buff: more damage dealt and movement.
+    pub last_stand: bool,
+    /// Integrate ball movement in fixed-point (see `physics_backend`) so clients on
+    /// different CPUs can't drift apart.
+    pub fixed_physics: bool,
+    /// Fraction of the material blasted out near a crater's edge that piles up on its
+    /// rim, 0.0 (plain holes) to 1.0. Raised rims make craters usable as cover.
+    pub crater_push: f32,
+    /// Pick the team that moves first from the map seed instead of always team 0.
+    /// Networked games follow the server's turn order, so this only matters offline.
+    pub random_first_turn: bool,
+    /// Collision and drawing radius of every ball.
+    pub ball_size: BallSize,
+    /// How balls are laid out at the start of the match.
+    pub spawn_mode: SpawnMode,
+    /// Generate a left/right symmetric map and spawn the teams as mirror images.
+    pub mirror_map: bool,
+    /// Gimmick map with a few moving platforms hovering over the land.
+    pub moving_platforms: bool,
+    /// Terrain cells a turn may carve or place before the drill, foxhole and wall stop
+    /// working for the rest of that turn; 0 means unlimited.
+    pub terrain_budget: u32,
+    /// From this turn on, the safe zone closes in from both map edges every turn and
+    /// balls caught outside it die; 0 disables it.
+    pub shrink_zone_after: u32,
+    /// How hard explosions shove shells and bomblets still in flight, 0.0 (off) to 1.0.
+    /// Bullets (shotgun pellets, Uzi rounds) are too fast to deflect and ignore it.
+    pub blast_push: f32,
+    /// Multiplier on every shove a ball takes, from blasts, bullets, the bat and the like.
+    /// High values with low `weapon_damage` make a sumo match of pushing balls into the water.
+    pub knockback_scale: f32,
+    /// Fraction of weapon damage balls actually take, 0.0 (none) to 1.0. Falls, fire and
+    /// water still hurt as usual.
+    pub weapon_damage: f32,
+    /// Palette and material mix of the map; None picks one from the map seed.
+    pub terrain_theme: Option<TerrainTheme>,
+    /// Fill cave alcoves with gas that bursts when a blast or fire reaches it, chaining
+    /// through the rest of the pocket. Digging near caves becomes a gamble.
+    pub gas_pockets: bool,
+    /// Generate and simulate the map at half resolution (700x400 cells) for weak devices.
+    /// Balls and blasts keep their size, so the map plays smaller.
+    pub half_res_terrain: bool,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            terrain_density: 0.5,
+            movement_regen: 1.0,
+            earthquake_every: 0,
+            crate_rain_after: 0,
+            friendly_fire: true,
+            own_team_pass_through: false,
+            random_loadout: false,
+            last_stand: false,
+            fixed_physics: false,
+            crater_push: 0.0,
+            random_first_turn: false,
+            ball_size: BallSize::Normal,
+            spawn_mode: SpawnMode::Spread,
+            mirror_map: false,
+            moving_platforms: false,
+            terrain_budget: 0,
+            shrink_zone_after: 0,
+            blast_push: 0.0,
+            knockback_scale: 1.0,
+            weapon_damage: 1.0,
+            terrain_theme: Some(TerrainTheme::Grassland),
+            gas_pockets: false,
+            half_res_terrain: false,
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! The part of a match every client must simulate identically: terrain, balls, wind and
+//! the tick count, plus the steps that only touch them (ball physics and movement
+//! inputs). Nothing here draws or reads devices, so it runs headless — in tests, replays
+//! or a server checking a match. `Game` keeps its world in a `GameSim` and layers the
+//! phases, shots, effects, camera and networking on top.
+
+use crate::net_input::NetInput;
+use crate::physics::{self, Ball};
+use crate::physics_backend;
+use crate::platforms::MovingPlatform;
+use crate::terrain::Terrain;
+
+pub struct GameSim {
+    pub terrain: Terrain,
+    pub balls: Vec<Ball>,
+    pub wind: f32,
+    /// Ticks run so far; stamps when each team was eliminated
+    pub tick: u32,
+    /// Step balls with the fixed-point backend (the match's `fixed_physics` setting)
+    pub fixed_physics: bool,
+}
+
+impl GameSim {
+    pub fn new(terrain: Terrain, balls: Vec<Ball>, wind: f32, fixed_physics: bool) -> Self {
+        GameSim { terrain, balls, wind, tick: 0, fixed_physics }
+    }
+
+    /// Move each ball one step, then push overlapping ones apart. Balls `skip` picks out
+    /// are left where they are (remote balls whose position the network streams).
+    pub fn step_balls(
+        &mut self,
+        platforms: &[MovingPlatform],
+        safe_zone: (f32, f32),
+        dt: f32,
+        skip: impl Fn(usize) -> bool,
+    ) {
+        let backend = physics_backend::backend(self.fixed_physics);
+        for (bi, w) in self.balls.iter_mut().enumerate() {
+            if !skip(bi) {
+                w.tick(&self.terrain, platforms, safe_zone, dt, backend);
+            }
+        }
+        physics::separate_balls(&mut self.balls, &self.terrain);
+    }
+
+    /// Play a movement input (walk, jump, backflip) on `ball`. False for inputs that need
+    /// the rest of the game (shots and tools) and for balls that don't exist.
+    pub fn apply_input(&mut self, ball: usize, input: NetInput) -> bool {
+        let Some(w) = self.balls.get_mut(ball) else {
+            return false;
+        };
+        match input {
+            NetInput::Walk { dir } => physics::walk(w, &self.terrain, dir),
+            NetInput::Jump => {
+                physics::jump(w);
+                w.movement_used += 20.0;
+            }
+            NetInput::Backflip => {
+                physics::backflip(w);
+                w.movement_used += 30.0;
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+// The headless entry points. `Game` runs its own phases around `step_balls`, so only
+// tests and tools drive a `GameSim` through these for now.
+#[allow(dead_code)]
+impl GameSim {
+    /// One tick of the world on its own: no platforms, and the whole map is safe.
+    pub fn tick(&mut self, dt: f32) {
+        self.tick = self.tick.wrapping_add(1);
+        let safe_zone = (0.0, self.terrain.width as f32);
+        self.step_balls(&[], safe_zone, dt, |_| false);
+    }
+
+    /// The tick, wind and every ball as JSON.
+    pub fn state(&self) -> String {
+        let balls: Vec<String> = self
+            .balls
+            .iter()
+            .map(|w| {
+                format!(
+                    r#"{{"x":{},"y":{},"vx":{},"vy":{},"health":{},"team":{},"alive":{}}}"#,
+                    w.x, w.y, w.vx, w.vy, w.health, w.team, w.alive
+                )
+            })
+            .collect();
+        format!(r#"{{"tick":{},"wind":{},"balls":[{}]}}"#, self.tick, self.wind, balls.join(","))
+    }
+
+    /// Hash of the terrain, balls and wind. Clients that agree on it hold the same world.
+    pub fn state_hash(&self) -> u64 {
+        // FNV-1a, as `Terrain::block_hashes` uses
+        let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut mix = |bytes: &[u8]| {
+            for &b in bytes {
+                h ^= b as u64;
+                h = h.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        mix(&self.terrain.cells);
+        mix(&self.wind.to_bits().to_le_bytes());
+        for w in &self.balls {
+            for v in [w.x, w.y, w.vx, w.vy] {
+                mix(&v.to_bits().to_le_bytes());
+            }
+            mix(&w.health.to_le_bytes());
+            mix(&[w.alive as u8]);
+        }
+        h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::{self, TerrainTheme};
+
+    fn headless(seed: u32) -> GameSim {
+        let terrain = terrain::generate(seed, 0.5, false, TerrainTheme::from_seed(seed), false);
+        let balls = (0..4).map(|i| Ball::new(300.0 + 250.0 * i as f32, 40.0, i % 2, format!("b{}", i))).collect();
+        GameSim::new(terrain, balls, 0.0, true)
+    }
+
+    fn run(sim: &mut GameSim, inputs: &[(u32, usize, NetInput)]) {
+        for tick in 0..240 {
+            for &(_, ball, input) in inputs.iter().filter(|(t, _, _)| *t == tick) {
+                sim.apply_input(ball, input);
+            }
+            sim.tick(1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn runs_without_a_graphics_context() {
+        let mut inputs = vec![(120, 0, NetInput::Jump)];
+        inputs.extend((150..180).map(|t| (t, 1, NetInput::Walk { dir: 1.0 })));
+        let (mut a, mut b) = (headless(5), headless(5));
+        run(&mut a, &inputs);
+        run(&mut b, &inputs);
+        assert_eq!(a.tick, 240);
+        assert!(a.balls.iter().all(|w| !w.alive || w.y > 40.0), "balls land on the map");
+        assert_eq!(a.state_hash(), b.state_hash());
+        assert_eq!(a.state(), b.state());
+
+        // Same match without the walk
+        let mut c = headless(5);
+        run(&mut c, &inputs[..1]);
+        assert_ne!(a.state_hash(), c.state_hash());
+        // Shots need the whole game
+        assert!(!c.apply_input(0, NetInput::BatSwing { angle: 0.0 }));
+    }
+}
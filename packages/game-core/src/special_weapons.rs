@@ -9,6 +9,10 @@ pub struct AirstrikeDroplet {
     pub vy: f32,
     pub alive: bool,
     pub weapon_type: AirstrikeType,
+    /// Shooter's team when friendly fire is off — its balls take no damage (knockback still applies)
+    pub spare_team: Option<u32>,
+    /// Shooter's damage multiplier (last stand)
+    pub damage_scale: f32,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -38,7 +42,7 @@ impl AirstrikeDroplet {
         }
 
         // Check collision with terrain or water
-        let is_water = self.y >= crate::terrain::WATER_LEVEL;
+        let is_water = self.y >= terrain.water_level();
         if is_water || terrain.is_solid(self.x as i32, self.y as i32) {
             return self.explode(terrain, balls);
         }
@@ -69,9 +73,9 @@ impl AirstrikeDroplet {
             if dist_sq < r2 {
                 let dist = dist_sq.sqrt().max(1.0);
                 let factor = 1.0 - (dist / blast_radius).min(1.0);
-                let dmg = (damage as f32 * factor) as i32;
+                let dmg = (damage as f32 * self.damage_scale * factor) as i32;
                 if dmg > 0 {
-                    w.take_damage(dmg.max(1));
+                    w.take_weapon_damage(dmg.max(1), self.spare_team);
                 }
                 let knock = 180.0 * factor;
                 let nx = dx / dist * knock;
@@ -96,6 +100,9 @@ impl AirstrikeDroplet {
                 lifetime: 5.0,
                 damage_timer: 0.0,
                 alive: true,
+                flow_timer: FIRE_FLOW_INTERVAL,
+                spawns_left: FIRE_CHILD_SPAWNS,
+                spare_team: self.spare_team,
             }),
             AirstrikeType::Explosive => None,
         };
@@ -106,6 +113,18 @@ impl AirstrikeDroplet {
 
 // ── Fire pool left by napalm ─────────────────────────────────────────────────
 
+/// Seconds between a fire pool's steps downhill
+const FIRE_FLOW_INTERVAL: f32 = 0.25;
+/// Horizontal distance of one step, and the smallest drop worth flowing down
+const FIRE_FLOW_STEP: i32 = 6;
+const FIRE_FLOW_MIN_DROP: i32 = 2;
+/// How far above its own level a pool looks for ground, so it can't flow through walls
+const FIRE_FLOW_CLIMB: i32 = 4;
+/// Child pools a napalm pool leaves along its path; children leave none
+const FIRE_CHILD_SPAWNS: u32 = 3;
+/// Most fire pools burning at once; flowing pools stop leaving children beyond this
+pub const MAX_FIRE_POOLS: usize = 40;
+
 pub struct FirePool {
     pub x: f32,
     pub y: f32,
@@ -115,24 +134,41 @@ pub struct FirePool {
     /// Countdown to the next damage tick (fires every 0.5 s)
     pub damage_timer: f32,
     pub alive: bool,
+    /// Countdown to the next step downhill
+    pub flow_timer: f32,
+    /// Smaller pools this one may still drop behind it as it flows
+    pub spawns_left: u32,
+    /// Team the burn leaves alone when friendly fire is off
+    pub spare_team: Option<u32>,
+}
+
+/// First solid cell at or below `from_y` in column `x`, looking no further than a
+/// pool could fall in one step. None when `from_y` is already inside terrain.
+fn ground_below(terrain: &Terrain, x: i32, from_y: i32) -> Option<i32> {
+    if terrain.is_solid(x, from_y) {
+        return None;
+    }
+    (from_y..from_y + FIRE_FLOW_STEP * 8).find(|&y| terrain.is_solid(x, y))
 }
 
 impl FirePool {
-    pub fn tick(&mut self, balls: &mut [Ball], dt: f32) {
+    /// Burn, hurt balls in range and flow downhill. Returns a child pool left behind
+    /// on the slope, if this step spawned one.
+    pub fn tick(&mut self, terrain: &Terrain, balls: &mut [Ball], dt: f32) -> Option<FirePool> {
         if !self.alive {
-            return;
+            return None;
         }
         self.lifetime -= dt;
         if self.lifetime <= 0.0 {
             self.alive = false;
-            return;
+            return None;
         }
         self.damage_timer -= dt;
         if self.damage_timer <= 0.0 {
             self.damage_timer = 0.5; // deal damage every 0.5 s
             let r2 = self.radius * self.radius;
             for w in balls.iter_mut() {
-                if !w.alive {
+                if !w.alive || self.spare_team == Some(w.team) {
                     continue;
                 }
                 let dx = w.x - self.x;
@@ -142,6 +178,156 @@ impl FirePool {
                 }
             }
         }
+        self.flow_timer -= dt;
+        if self.flow_timer > 0.0 {
+            return None;
+        }
+        self.flow_timer = FIRE_FLOW_INTERVAL;
+        self.flow(terrain)
+    }
+
+    /// Step one FIRE_FLOW_STEP toward whichever neighbouring column has lower ground
+    /// (left on a tie), leaving a child pool where it was while it has spawns left.
+    /// Whole-pixel terrain samples only, so every client moves the fire identically.
+    fn flow(&mut self, terrain: &Terrain) -> Option<FirePool> {
+        let (x, y) = (self.x as i32, self.y as i32);
+        // Napalm lands a little way into the ground; measure from the top of what it hit
+        let here = if terrain.is_solid(x, y) {
+            (y - FIRE_FLOW_STEP * 2..y).rev().take_while(|&sy| terrain.is_solid(x, sy)).last().unwrap_or(y)
+        } else {
+            ground_below(terrain, x, y)?
+        };
+        let from_y = here - FIRE_FLOW_CLIMB;
+        let (nx, ground) = [x - FIRE_FLOW_STEP, x + FIRE_FLOW_STEP]
+            .into_iter()
+            .filter_map(|nx| ground_below(terrain, nx, from_y).map(|g| (nx, g)))
+            .filter(|&(_, g)| g >= here + FIRE_FLOW_MIN_DROP)
+            .fold(None, |best: Option<(i32, i32)>, c| match best {
+                Some(b) if b.1 >= c.1 => Some(b),
+                _ => Some(c),
+            })?;
+        let child = (self.spawns_left > 0).then(|| {
+            self.spawns_left -= 1;
+            FirePool {
+                x: self.x,
+                y: self.y,
+                radius: self.radius * 0.6,
+                lifetime: self.lifetime * 0.5,
+                damage_timer: self.damage_timer,
+                alive: true,
+                flow_timer: FIRE_FLOW_INTERVAL,
+                spawns_left: 0,
+                spare_team: self.spare_team,
+            }
+        });
+        self.x = nx as f32;
+        self.y = ground as f32;
+        child
+    }
+}
+
+// ── Flare decoy ──────────────────────────────────────────────────────────────
+
+/// Seconds a flare keeps luring homing missiles once one is chasing it
+pub const FLARE_BURN_TIME: f32 = 3.0;
+
+/// Decoy left by the Flares utility. Enemy homing missiles steer for it ahead of any
+/// ball; it only burns down while a homing missile is in the air.
+pub struct Decoy {
+    pub x: f32,
+    pub y: f32,
+    /// Seconds of lure left
+    pub lifetime: f32,
+    /// Team that dropped it; that team's own missiles ignore it
+    pub team: u32,
+}
+
+// ── Ninja rope ───────────────────────────────────────────────────────────────
+
+/// Furthest the rope reaches, and the longest it can be paid out to
+pub const ROPE_RANGE: f32 = 320.0;
+/// Shortest the rope can be reeled in to, kept clear of the terrain it hooks
+pub const ROPE_MIN_LENGTH: f32 = 20.0;
+/// Rope paid out or reeled in per second while S/W is held
+pub const ROPE_REEL_SPEED: f32 = 120.0;
+/// Sideways acceleration A/D add to a swing, along the arc
+pub const ROPE_SWING_ACCEL: f32 = 380.0;
+
+/// Ninja rope hooked into terrain. The ball hangs from the anchor and can't get further
+/// from it than `length`, so gravity swings it like a pendulum.
+pub struct NinjaRope {
+    pub anchor_x: f32,
+    pub anchor_y: f32,
+    pub length: f32,
+    /// Index of the ball on the rope
+    pub ball: usize,
+}
+
+impl NinjaRope {
+    /// Cast the rope from (x, y) along `angle`; it hooks into the first solid cell
+    /// within ROPE_RANGE and takes the distance to it as its length.
+    pub fn cast(terrain: &Terrain, ball: usize, x: f32, y: f32, angle: f32) -> Option<NinjaRope> {
+        let (cos_a, sin_a) = (angle.cos(), angle.sin());
+        let mut dist = ROPE_MIN_LENGTH * 0.5;
+        while dist <= ROPE_RANGE {
+            let (ax, ay) = (x + cos_a * dist, y + sin_a * dist);
+            if ax < 0.0 || ay < 0.0 || ax >= terrain.width as f32 || ay >= terrain.height as f32 {
+                return None;
+            }
+            if terrain.is_solid(ax as i32, ay as i32) {
+                return Some(NinjaRope { anchor_x: ax, anchor_y: ay, length: dist.max(ROPE_MIN_LENGTH), ball });
+            }
+            dist += 2.0;
+        }
+        None
+    }
+
+    /// Pump the swing (`swing` -1 left, 1 right) and reel the rope in (`reel` < 0) or out.
+    pub fn steer(&mut self, ball: &mut Ball, swing: f32, reel: f32, dt: f32) {
+        self.length = (self.length + reel * ROPE_REEL_SPEED * dt).clamp(ROPE_MIN_LENGTH, ROPE_RANGE);
+        let (dx, dy) = (ball.x - self.anchor_x, ball.y - self.anchor_y);
+        let dist = (dx * dx + dy * dy).sqrt();
+        if swing == 0.0 || dist < 0.001 {
+            return;
+        }
+        // Along the arc, whichever way round heads towards `swing`
+        let (mut tx, mut ty) = (-dy / dist, dx / dist);
+        if tx * swing < 0.0 {
+            (tx, ty) = (-tx, -ty);
+        }
+        ball.vx += tx * ROPE_SWING_ACCEL * dt;
+        ball.vy += ty * ROPE_SWING_ACCEL * dt;
+        ball.facing = swing.signum();
+    }
+
+    /// Pull a ball that ended its tick past the rope's length back onto the arc, dropping
+    /// the part of its velocity heading away from the anchor. A taut rope restarts the
+    /// fall height, so letting go only counts the drop after release.
+    pub fn constrain(&self, ball: &mut Ball, terrain: &Terrain) {
+        let (dx, dy) = (ball.x - self.anchor_x, ball.y - self.anchor_y);
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist > self.length && dist >= 0.001 {
+            let (nx, ny) = (dx / dist, dy / dist);
+            ball.x = self.anchor_x + nx * self.length;
+            ball.y = self.anchor_y + ny * self.length;
+            let outward = ball.vx * nx + ball.vy * ny;
+            if outward > 0.0 {
+                ball.vx -= nx * outward;
+                ball.vy -= ny * outward;
+            }
+            ball.fall_start_y = ball.y;
+        }
+        // Ball physics has no ceiling, and a swing easily rises into the overhang the
+        // rope hangs from, so stop it under the roof rather than let it push through
+        let r = ball.radius;
+        let mut pushed = 0.0;
+        while pushed < r * 2.0 && terrain.is_solid(ball.x as i32, (ball.y - r) as i32) {
+            ball.y += 1.0;
+            pushed += 1.0;
+        }
+        if pushed > 0.0 {
+            ball.vy = ball.vy.max(0.0);
+        }
     }
 }
 
@@ -151,6 +337,9 @@ pub struct UziBullet {
     pub vx: f32,
     pub vy: f32,
     pub alive: bool,
+    pub damage: i32,
+    /// Shooter's team when friendly fire is off — its balls take no damage (knockback still applies)
+    pub spare_team: Option<u32>,
 }
 
 impl UziBullet {
@@ -178,7 +367,7 @@ impl UziBullet {
         }
 
         // Check water
-        if self.y >= crate::terrain::WATER_LEVEL {
+        if self.y >= terrain.water_level() {
             self.alive = false;
             return false;
         }
@@ -198,8 +387,8 @@ impl UziBullet {
             let dx = w.x - self.x;
             let dy = w.y - self.y;
             let dist_sq = dx * dx + dy * dy;
-            if dist_sq < 80.0 { // ~9 pixel radius
-                w.take_damage(5);
+            if dist_sq < w.hit_radius_sq(1.0) {
+                w.take_weapon_damage(self.damage, self.spare_team);
                 let dist = dist_sq.sqrt().max(1.0);
                 let knock = 40.0;
                 w.apply_knockback((dx / dist) * knock, (dy / dist) * knock - 30.0);
@@ -212,6 +401,9 @@ impl UziBullet {
     }
 }
 
+/// Safety cap on how many placed explosives one tick's blasts can set off
+pub const MAX_CHAIN_TRIGGERS: usize = 8;
+
 pub struct PlacedExplosive {
     pub x: f32,
     pub y: f32,
@@ -219,6 +411,8 @@ pub struct PlacedExplosive {
     pub alive: bool,
     pub radius: f32,
     pub damage: i32,
+    /// Shooter's team when friendly fire is off — its balls take no damage (knockback still applies)
+    pub spare_team: Option<u32>,
 }
 
 impl PlacedExplosive {
@@ -254,7 +448,7 @@ impl PlacedExplosive {
                 let factor = 1.0 - (dist / blast_radius).min(1.0);
                 let dmg = (self.damage as f32 * factor) as i32;
                 if dmg > 0 {
-                    w.take_damage(dmg.max(1));
+                    w.take_weapon_damage(dmg.max(1), self.spare_team);
                 }
                 let knock = 280.0 * factor;
                 let nx = dx / dist * knock;
@@ -271,3 +465,75 @@ impl PlacedExplosive {
         }
     }
 }
+
+/// Set off every placed explosive inside one of `explosions` by zeroing its fuse, so it
+/// goes off next tick. Goes in list order and stops at a cap, so every client chains the
+/// same way. Returns how many were set off.
+pub fn chain_placed_explosives(explosions: &[Explosion], placed: &mut [PlacedExplosive]) -> usize {
+    let mut triggered = 0;
+    for exp in explosions {
+        for other in placed.iter_mut() {
+            if triggered >= MAX_CHAIN_TRIGGERS {
+                return triggered;
+            }
+            let (dx, dy) = (other.x - exp.x, other.y - exp.y);
+            if other.fuse > 0.0 && dx * dx + dy * dy <= exp.radius * exp.radius {
+                other.fuse = 0.0;
+                triggered += 1;
+            }
+        }
+    }
+    triggered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fire_pool_spares_the_shooters_team() {
+        let terrain = Terrain::new(1400, 800);
+        let mut balls = vec![Ball::new(100.0, 100.0, 0, "a".into()), Ball::new(104.0, 100.0, 1, "b".into())];
+        let mut pool = FirePool {
+            x: 102.0,
+            y: 100.0,
+            radius: 30.0,
+            lifetime: 5.0,
+            damage_timer: 0.0,
+            alive: true,
+            flow_timer: FIRE_FLOW_INTERVAL,
+            spawns_left: 0,
+            spare_team: Some(0),
+        };
+        let before = (balls[0].health, balls[1].health);
+        pool.tick(&terrain, &mut balls, 0.1);
+        assert_eq!(balls[0].health, before.0);
+        assert!(balls[1].health < before.1);
+    }
+
+    fn mine(x: f32) -> PlacedExplosive {
+        PlacedExplosive { x, y: 100.0, fuse: 3.0, alive: true, radius: 20.0, damage: 40, spare_team: None }
+    }
+
+    #[test]
+    fn blast_sets_off_explosives_inside_its_radius() {
+        let blast = [Explosion { x: 100.0, y: 100.0, radius: 50.0, is_water: false }];
+        let mut placed = vec![mine(140.0), mine(151.0), mine(60.0), mine(100.0)];
+        // Already about to go off: not counted again
+        placed[3].fuse = 0.0;
+        assert_eq!(chain_placed_explosives(&blast, &mut placed), 2);
+        let fuses: Vec<f32> = placed.iter().map(|e| e.fuse).collect();
+        assert_eq!(fuses, vec![0.0, 3.0, 0.0, 0.0]);
+        // The chained mine pops on the next tick
+        assert!(placed[0].tick(1.0 / 60.0));
+    }
+
+    #[test]
+    fn chains_stop_at_the_cap_in_list_order() {
+        let blast = [Explosion { x: 100.0, y: 100.0, radius: 50.0, is_water: false }];
+        let mut placed: Vec<PlacedExplosive> = (0..12).map(|i| mine(90.0 + i as f32)).collect();
+        assert_eq!(chain_placed_explosives(&blast, &mut placed), MAX_CHAIN_TRIGGERS);
+        assert!(placed[..MAX_CHAIN_TRIGGERS].iter().all(|e| e.fuse == 0.0));
+        assert!(placed[MAX_CHAIN_TRIGGERS..].iter().all(|e| e.fuse > 0.0));
+    }
+}
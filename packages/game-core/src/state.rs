@@ -1,3 +1,6 @@
+use crate::physics::Ball;
+use crate::projectile::{Projectile, TRAIL_LEN};
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Phase {
     Aiming,
@@ -32,3 +35,306 @@ impl Phase {
         matches!(self, Phase::Aiming | Phase::Charging | Phase::ProjectileFlying | Phase::Retreat)
     }
 }
+
+/// An action pressed a moment before the phase allows it, held for a short window
+/// instead of being dropped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BufferedInput {
+    /// F: start charging (or use an instant weapon)
+    Fire,
+    Jump,
+    Backflip,
+}
+
+impl BufferedInput {
+    /// Whether `phase` takes this action.
+    pub fn allowed_in(&self, phase: Phase) -> bool {
+        match self {
+            BufferedInput::Fire => phase.allows_input(),
+            BufferedInput::Jump | BufferedInput::Backflip => phase.allows_movement(),
+        }
+    }
+}
+
+/// Why a turn action was refused, so the front-end can say why nothing happened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameError {
+    /// It's another player's turn, or this client only spectates
+    NotYourTurn,
+    /// The turn's shot has already been taken
+    AlreadyFired,
+    /// The acting ball doesn't exist or is dead
+    InvalidBall,
+    /// The weapon isn't in the team's loadout or unlocks
+    WeaponLocked,
+    /// The weapon changes terrain and the turn's terrain budget is used up
+    OutOfTerrainBudget,
+    /// The current phase doesn't take this action
+    WrongPhase,
+}
+
+impl GameError {
+    pub fn name(&self) -> &str {
+        match self {
+            GameError::NotYourTurn => "not_your_turn",
+            GameError::AlreadyFired => "already_fired",
+            GameError::InvalidBall => "invalid_ball",
+            GameError::WeaponLocked => "weapon_locked",
+            GameError::OutOfTerrainBudget => "out_of_terrain_budget",
+            GameError::WrongPhase => "wrong_phase",
+        }
+    }
+}
+
+/// What the game knows about a local fire press, checked before the shot goes off.
+#[derive(Clone, Copy, Debug)]
+pub struct FireCheck {
+    pub my_turn: bool,
+    pub phase: Phase,
+    pub has_fired: bool,
+    /// The current ball exists and is alive
+    pub ball_alive: bool,
+    /// The selected weapon is in the team's loadout and unlocked
+    pub weapon_allowed: bool,
+    /// The weapon changes terrain and the turn's terrain budget is used up
+    pub budget_spent: bool,
+}
+
+impl FireCheck {
+    /// The first reason the shot can't go off, in the order a player would fix them.
+    pub fn check(&self) -> Result<(), GameError> {
+        if !self.my_turn {
+            Err(GameError::NotYourTurn)
+        } else if !self.phase.allows_input() {
+            Err(GameError::WrongPhase)
+        } else if self.has_fired {
+            Err(GameError::AlreadyFired)
+        } else if !self.ball_alive {
+            Err(GameError::InvalidBall)
+        } else if !self.weapon_allowed {
+            Err(GameError::WeaponLocked)
+        } else if self.budget_spent {
+            Err(GameError::OutOfTerrainBudget)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// What an accepted fire action did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionOutcome {
+    /// The shot went off and used up the turn's attack
+    Fired,
+    /// The weapon is armed and waits for a target click (bat, teleport, wall, strikes)
+    AwaitingTarget,
+    /// The item only moved the ball (rope), so the turn's attack is still to come
+    Moved,
+}
+
+/// Per-team standings for scoreboards and match-progress UI.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TeamSummary {
+    pub team: u32,
+    /// Balls still alive on this team
+    pub living: usize,
+    /// Sum of the living balls' health
+    pub total_hp: i32,
+    pub eliminated: bool,
+}
+
+impl TeamSummary {
+    pub fn to_json(self) -> String {
+        format!(
+            "{{\"team\":{},\"living\":{},\"hp\":{},\"eliminated\":{}}}",
+            self.team, self.living, self.total_hp, self.eliminated
+        )
+    }
+}
+
+/// Summarise teams 0 up to the highest team any ball belongs to, ordered by team index.
+/// A team number with no balls at all still gets an entry, marked eliminated.
+pub fn team_summaries(balls: &[Ball]) -> Vec<TeamSummary> {
+    let num_teams = balls.iter().map(|b| b.team as usize + 1).max().unwrap_or(0);
+    let mut out: Vec<TeamSummary> = (0..num_teams)
+        .map(|t| TeamSummary { team: t as u32, living: 0, total_hp: 0, eliminated: true })
+        .collect();
+    for b in balls {
+        if b.alive {
+            let s = &mut out[b.team as usize];
+            s.living += 1;
+            s.total_hp += b.health.max(0);
+            s.eliminated = false;
+        }
+    }
+    out
+}
+
+/// JSON array of team summaries, e.g. for game events consumed by the web UI.
+pub fn team_summaries_json(balls: &[Ball]) -> String {
+    let items: Vec<String> = team_summaries(balls).iter().map(|s| s.to_json()).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// A shell in flight as a JS renderer draws it: where it is, where it's heading, what it
+/// is and the trail behind it, oldest point first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectileView {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub weapon: String,
+    pub trail: Vec<(f32, f32)>,
+}
+
+impl ProjectileView {
+    pub fn new(proj: &Projectile) -> Self {
+        let skip = proj.trail.len().saturating_sub(TRAIL_LEN);
+        ProjectileView {
+            x: proj.x,
+            y: proj.y,
+            vx: proj.vx,
+            vy: proj.vy,
+            weapon: proj.weapon.name().to_string(),
+            trail: proj.trail[skip..].to_vec(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let trail: Vec<String> = self.trail.iter().map(|(x, y)| format!("[{},{}]", x, y)).collect();
+        format!(
+            "{{\"x\":{},\"y\":{},\"vx\":{},\"vy\":{},\"weapon\":\"{}\",\"trail\":[{}]}}",
+            self.x, self.y, self.vx, self.vy, self.weapon, trail.join(",")
+        )
+    }
+}
+
+/// How a finished match ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchOutcome {
+    Winner(u32),
+    Draw,
+}
+
+/// Decide the match from when each team lost its last ball: `out_ticks[t]` is the
+/// update tick team t was eliminated on, None while it still has a ball. With one team
+/// left it wins; with none left the team that lasted longest wins, and teams wiped out
+/// on the same tick draw. Works from whole ticks, so the order balls died in within a
+/// tick never matters. None while two or more teams are still in.
+pub fn match_outcome(out_ticks: &[Option<u32>]) -> Option<MatchOutcome> {
+    let living: Vec<usize> = (0..out_ticks.len()).filter(|&t| out_ticks[t].is_none()).collect();
+    match living.as_slice() {
+        [team] => return Some(MatchOutcome::Winner(*team as u32)),
+        [] => {}
+        _ => return None,
+    }
+    let last = out_ticks.iter().flatten().max();
+    let survivors: Vec<usize> = (0..out_ticks.len()).filter(|&t| out_ticks[t].as_ref() == last).collect();
+    match survivors.as_slice() {
+        [team] => Some(MatchOutcome::Winner(*team as u32)),
+        _ => Some(MatchOutcome::Draw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready() -> FireCheck {
+        FireCheck {
+            my_turn: true,
+            phase: Phase::Aiming,
+            has_fired: false,
+            ball_alive: true,
+            weapon_allowed: true,
+            budget_spent: false,
+        }
+    }
+
+    #[test]
+    fn fire_goes_off_when_every_condition_holds() {
+        assert_eq!(ready().check(), Ok(()));
+        assert_eq!(FireCheck { phase: Phase::Charging, ..ready() }.check(), Ok(()));
+    }
+
+    #[test]
+    fn each_refusal_names_its_reason() {
+        let cases = [
+            (FireCheck { my_turn: false, ..ready() }, GameError::NotYourTurn, "not_your_turn"),
+            (FireCheck { phase: Phase::ProjectileFlying, ..ready() }, GameError::WrongPhase, "wrong_phase"),
+            (FireCheck { phase: Phase::GameOver, ..ready() }, GameError::WrongPhase, "wrong_phase"),
+            (FireCheck { has_fired: true, ..ready() }, GameError::AlreadyFired, "already_fired"),
+            (FireCheck { ball_alive: false, ..ready() }, GameError::InvalidBall, "invalid_ball"),
+            (FireCheck { weapon_allowed: false, ..ready() }, GameError::WeaponLocked, "weapon_locked"),
+            (FireCheck { budget_spent: true, ..ready() }, GameError::OutOfTerrainBudget, "out_of_terrain_budget"),
+        ];
+        for (check, err, name) in cases {
+            assert_eq!(check.check(), Err(err));
+            assert_eq!(err.name(), name);
+        }
+    }
+
+    fn ball(team: u32, health: i32, alive: bool) -> Ball {
+        let mut b = Ball::new(0.0, 0.0, team, String::new());
+        b.health = health;
+        b.alive = alive;
+        b
+    }
+
+    #[test]
+    fn team_summaries_count_only_living_balls() {
+        let balls = [ball(0, 100, true), ball(1, 40, true), ball(0, 0, false), ball(1, 25, true), ball(2, 0, false)];
+        let summaries = team_summaries(&balls);
+        assert_eq!(summaries, vec![
+            TeamSummary { team: 0, living: 1, total_hp: 100, eliminated: false },
+            TeamSummary { team: 1, living: 2, total_hp: 65, eliminated: false },
+            TeamSummary { team: 2, living: 0, total_hp: 0, eliminated: true },
+        ]);
+        assert_eq!(
+            team_summaries_json(&balls[4..]),
+            "[{\"team\":0,\"living\":0,\"hp\":0,\"eliminated\":true},{\"team\":1,\"living\":0,\"hp\":0,\"eliminated\":true},{\"team\":2,\"living\":0,\"hp\":0,\"eliminated\":true}]"
+        );
+        assert!(team_summaries(&[]).is_empty());
+    }
+
+    #[test]
+    fn another_players_turn_outranks_other_reasons() {
+        let check = FireCheck { my_turn: false, phase: Phase::TurnEnd, has_fired: true, ball_alive: false, ..ready() };
+        assert_eq!(check.check(), Err(GameError::NotYourTurn));
+    }
+
+    #[test]
+    fn last_team_standing_wins() {
+        assert_eq!(match_outcome(&[None, Some(40), Some(12)]), Some(MatchOutcome::Winner(0)));
+        assert_eq!(match_outcome(&[Some(5), None]), Some(MatchOutcome::Winner(1)));
+        assert_eq!(match_outcome(&[None, None, Some(3)]), None);
+    }
+
+    #[test]
+    fn teams_wiped_out_on_the_same_tick_draw() {
+        assert_eq!(match_outcome(&[Some(90), Some(90)]), Some(MatchOutcome::Draw));
+        // Team 1 went out earlier, so it can't share the draw or win
+        assert_eq!(match_outcome(&[Some(90), Some(10), Some(90)]), Some(MatchOutcome::Draw));
+    }
+
+    #[test]
+    fn when_everyone_is_out_the_team_that_lasted_longest_wins() {
+        assert_eq!(match_outcome(&[Some(30), Some(31), Some(29)]), Some(MatchOutcome::Winner(1)));
+        // Team order doesn't matter, only the ticks
+        assert_eq!(match_outcome(&[Some(31), Some(30)]), Some(MatchOutcome::Winner(0)));
+    }
+
+    #[test]
+    fn projectile_view_keeps_the_capped_trail() {
+        let mut proj = Projectile::new(100.0, 200.0, 0.0, 50.0, crate::weapons::Weapon::HolyHandGrenade, 0);
+        proj.trail = (0..TRAIL_LEN + 5).map(|i| (i as f32, 1.5)).collect();
+        let view = ProjectileView::new(&proj);
+        assert_eq!(view.trail.len(), TRAIL_LEN);
+        assert_eq!(view.trail[0], (5.0, 1.5));
+        let json = view.to_json();
+        assert!(json.starts_with("{\"x\":100,\"y\":200,\"vx\":"), "{}", json);
+        assert!(json.contains("\"weapon\":\"Holy Hand Grenade\",\"trail\":[[5,1.5],[6,1.5],"), "{}", json);
+        assert!(json.ends_with("[34,1.5]]}"), "{}", json);
+    }
+}
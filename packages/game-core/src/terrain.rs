@@ -1,11 +1,14 @@
 use macroquad::prelude::*;
 
+/// Full-resolution map size. Maps are generated at this size; `map_size` gives the
+/// size a match actually plays at.
 pub const WIDTH: u32 = 1400;
 pub const HEIGHT: u32 = 800;
-pub const WATER_LEVEL: f32 = 740.0;
-pub const PLAYABLE_LAND_WIDTH: f32 = 1360.0; // Land is centered, minimal water margins
-pub const LAND_START_X: f32 = 20.0; // Land starts 20px from left edge (minimal water)
-pub const LAND_END_X: f32 = 1380.0; // Land ends 20px from right edge (minimal water)
+// Layout of a full-resolution map. Other sizes scale it: see `Terrain::water_level` and co.
+const WATER_LEVEL: f32 = 740.0;
+const PLAYABLE_LAND_WIDTH: f32 = 1360.0; // Land is centered, minimal water margins
+const LAND_START_X: f32 = 20.0; // Land starts 20px from left edge (minimal water)
+const LAND_END_X: f32 = 1380.0; // Land ends 20px from right edge (minimal water)
 
 pub const AIR: u8 = 0;
 pub const DIRT: u8 = 1;
@@ -13,6 +16,140 @@ pub const GRASS: u8 = 2;
 pub const STONE: u8 = 3;
 pub const LAVA: u8 = 4;
 pub const WOOD: u8 = 5;
+/// Standing water in an enclosed pocket: not solid, deadly like the sea, and drains when breached
+pub const WATER: u8 = 6;
+/// Slick frozen ground from the snow theme: solid but with almost no grip, and shatters
+/// in any blast
+pub const ICE: u8 = 7;
+/// Flammable cave gas: not solid, but any blast or fire that reaches it sets it off
+pub const GAS: u8 = 8;
+/// Loose desert sand: solid, but crumbles away under a ball that stands on it too long
+pub const SAND: u8 = 9;
+/// Highest material id; cells above it are rejected when importing a map
+pub const MAX_MATERIAL: u8 = SAND;
+
+/// Give up on a pocket whose flood fill grows past this many cells (it isn't enclosed)
+const MAX_POCKET_CELLS: usize = 1800;
+const MIN_POCKET_CELLS: usize = 60;
+const MAX_POCKET_DEPTH: i32 = 24;
+/// How far along a row water looks for a drop before it stops spreading sideways
+const WATER_SPREAD: i32 = 40;
+/// Safety cap on flow steps when settling water after a breach
+const MAX_WATER_STEPS: usize = 4000;
+/// A changed cell this close to the last dirty rect grows it instead of starting a new one
+const DIRTY_MERGE_DIST: i32 = 16;
+/// Past this many pending rects they collapse into one bounding rect
+const MAX_DIRTY_RECTS: usize = 32;
+/// Explosions smaller than this never push dirt (bullets, drill bites)
+const MIN_PUSH_RADIUS: i32 = 8;
+/// Only cells carved from this outer fraction of the crater are pushed to the rim
+const PUSH_BAND: f32 = 0.4;
+/// How far past the crater edge pushed dirt may land before it is lost
+const PUSH_REACH: i32 = 3;
+/// How far past the edge of a blast or fire gas still catches
+const GAS_CATCH: i32 = 4;
+/// Depth of a generated sand patch at its middle
+const SAND_PATCH_DEPTH: i32 = 10;
+
+fn is_solid_cell(c: u8) -> bool {
+    c != AIR && c != WATER && c != GAS
+}
+
+/// Blast energy a cell needs before an explosion carves it (energy is 1.0 at the
+/// centre, 0.0 at the radius). Dirt and grass always go; stone only gives way in the
+/// inner 60% of a blast, so it survives the outer part of the crater.
+pub fn material_resistance(cell: u8) -> f32 {
+    match cell {
+        STONE => 0.4,
+        WOOD => 0.1,
+        _ => 0.0, // ice included: it shatters
+    }
+}
+
+/// Look and material mix of a match's terrain, on top of the generated shape. Grassland
+/// is the classic map; the others recolour it and swap materials after generation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TerrainTheme {
+    Grassland,
+    /// Sand instead of grass and topsoil, thick soft ground over sandstone, no water,
+    /// and drifts of loose sand that crumble under a ball left standing on them
+    Desert,
+    /// Snow-capped, with ice sheets on some slopes and frozen pools
+    Snow,
+    /// Ash over basalt, with lava seams deep in the rock and lava in the pools
+    Volcanic,
+}
+
+impl TerrainTheme {
+    pub fn name(&self) -> &str {
+        match self {
+            TerrainTheme::Grassland => "grassland",
+            TerrainTheme::Desert => "desert",
+            TerrainTheme::Snow => "snow",
+            TerrainTheme::Volcanic => "volcanic",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<TerrainTheme> {
+        match s {
+            "grassland" => Some(TerrainTheme::Grassland),
+            "desert" => Some(TerrainTheme::Desert),
+            "snow" => Some(TerrainTheme::Snow),
+            "volcanic" => Some(TerrainTheme::Volcanic),
+            _ => None,
+        }
+    }
+
+    /// Theme picked from the map seed, for matches that leave it to chance
+    pub fn from_seed(seed: u32) -> TerrainTheme {
+        match (lcg(seed ^ 0x7E3A_91C5) >> 16) % 4 {
+            0 => TerrainTheme::Grassland,
+            1 => TerrainTheme::Desert,
+            2 => TerrainTheme::Snow,
+            _ => TerrainTheme::Volcanic,
+        }
+    }
+
+    /// Material that caps exposed ground: grass (snow, in the snow theme) or bare dirt
+    /// where nothing grows.
+    fn topsoil(&self) -> u8 {
+        match self {
+            TerrainTheme::Grassland | TerrainTheme::Snow => GRASS,
+            TerrainTheme::Desert | TerrainTheme::Volcanic => DIRT,
+        }
+    }
+}
+
+/// A hand-made map played instead of a generated one: its cells and, optionally, a
+/// spawn x for each ball slot. Only built from cells that pass `from_material_buffer`.
+#[derive(Clone, PartialEq)]
+pub struct CustomMap {
+    cells: Vec<u8>,
+    pub spawns: Vec<f32>,
+}
+
+impl CustomMap {
+    pub fn new(cells: Vec<u8>, spawns: Vec<f32>) -> Option<CustomMap> {
+        let t = Terrain::from_material_buffer(WIDTH, HEIGHT, cells)?;
+        Some(CustomMap { cells: t.cells, spawns })
+    }
+
+    pub fn terrain(&self) -> Terrain {
+        let mut t = Terrain::new(WIDTH, HEIGHT);
+        t.cells = self.cells.clone();
+        t
+    }
+}
+
+/// Size in cells a match's map plays at: WIDTH x HEIGHT, or half that each way with the
+/// `half_res_terrain` setting.
+pub fn map_size(half_res: bool) -> (u32, u32) {
+    if half_res {
+        (WIDTH / 2, HEIGHT / 2)
+    } else {
+        (WIDTH, HEIGHT)
+    }
+}
 
 pub struct Terrain {
     pub width: u32,
@@ -20,6 +157,19 @@ pub struct Terrain {
     pub cells: Vec<u8>,
     /// Log of all (cx, cy, radius) damage events for replay on reconnect
     pub damage_log: Vec<(i32, i32, i32)>,
+    /// Fraction of the material carved near a crater's edge that piles up on its rim
+    /// instead of vanishing (0.0 = plain holes). A match setting, so replays agree.
+    pub push_ratio: f32,
+    /// Cells carved or placed by logged terrain ops since the game last reset it; feeds
+    /// the per-turn terrain budget. Replays don't count.
+    pub modified_cells: u32,
+    /// Palette and regrowth material. Set by `generate`; hand-made maps take the match's.
+    pub theme: TerrainTheme,
+    /// Inclusive (x1, y1, x2, y2) regions whose cells changed since the last `take_dirty_rects`
+    dirty_rects: Vec<(i32, i32, i32, i32)>,
+    /// Inclusive (x1, x2) span of columns changed since sand last came to rest. Sand only
+    /// falls straight down, so columns outside it have none left to settle.
+    unsettled_columns: Option<(i32, i32)>,
 }
 
 impl Terrain {
@@ -29,9 +179,216 @@ impl Terrain {
             height: h,
             cells: vec![AIR; (w * h) as usize],
             damage_log: Vec::new(),
+            push_ratio: 0.0,
+            modified_cells: 0,
+            theme: TerrainTheme::Grassland,
+            // A fresh terrain is entirely new to whoever is drawing it
+            dirty_rects: vec![(0, 0, w as i32 - 1, h as i32 - 1)],
+            unsettled_columns: Some((0, w as i32 - 1)),
         }
     }
 
+    /// Terrain from externally supplied cells, e.g. a hand-drawn map decoded from an
+    /// image into material ids, row by row. Returns None unless the size is the standard
+    /// WIDTH x HEIGHT that the spawn and water layout assume, there is exactly one cell
+    /// per position and every cell is a known material.
+    pub fn from_material_buffer(width: u32, height: u32, cells: Vec<u8>) -> Option<Terrain> {
+        if width != WIDTH || height != HEIGHT || cells.len() != (width * height) as usize {
+            return None;
+        }
+        if cells.iter().any(|&c| c > MAX_MATERIAL) {
+            return None;
+        }
+        let mut t = Terrain::new(width, height);
+        t.cells = cells;
+        Some(t)
+    }
+
+    /// This map's size relative to a full-resolution one (0.5 at half resolution)
+    pub fn scale(&self) -> f32 {
+        self.width as f32 / WIDTH as f32
+    }
+
+    /// Surface of the sea: anything below it drowns
+    pub fn water_level(&self) -> f32 {
+        WATER_LEVEL * self.height as f32 / HEIGHT as f32
+    }
+
+    /// Left edge of the land; the water margin is outside it
+    pub fn land_start_x(&self) -> f32 {
+        LAND_START_X * self.scale()
+    }
+
+    /// Right edge of the land
+    pub fn land_end_x(&self) -> f32 {
+        LAND_END_X * self.scale()
+    }
+
+    pub fn playable_land_width(&self) -> f32 {
+        PLAYABLE_LAND_WIDTH * self.scale()
+    }
+
+    /// The same map at half the size each way, for the `half_res_terrain` setting. Each
+    /// 2x2 block becomes its most common solid material when at least half of it is
+    /// solid, and otherwise whatever fills it (air, water or gas).
+    pub fn half_resolution(&self) -> Terrain {
+        let (w, h) = (self.width / 2, self.height / 2);
+        let mut t = Terrain::new(w, h);
+        t.theme = self.theme;
+        t.push_ratio = self.push_ratio;
+        for y in 0..h as i32 {
+            for x in 0..w as i32 {
+                let block = [
+                    self.get(2 * x, 2 * y),
+                    self.get(2 * x + 1, 2 * y),
+                    self.get(2 * x, 2 * y + 1),
+                    self.get(2 * x + 1, 2 * y + 1),
+                ];
+                let count = |c: u8| block.iter().filter(|&&b| b == c).count();
+                let solid = block.iter().filter(|&&c| is_solid_cell(c)).count();
+                // Ties go to the first of the block in reading order
+                let (mut cell, mut best) = (AIR, 0);
+                for &c in &block {
+                    if is_solid_cell(c) == (solid >= 2) && count(c) > best {
+                        (cell, best) = (c, count(c));
+                    }
+                }
+                t.cells[(y as u32 * w + x as u32) as usize] = cell;
+            }
+        }
+        t
+    }
+
+    /// Run-length encode the cells, row by row, as (cell, count) pairs.
+    pub fn runs(&self) -> Vec<(u8, usize)> {
+        let mut runs = Vec::new();
+        let mut i = 0;
+        while i < self.cells.len() {
+            let cell = self.cells[i];
+            let start = i;
+            while i < self.cells.len() && self.cells[i] == cell {
+                i += 1;
+            }
+            runs.push((cell, i - start));
+        }
+        runs
+    }
+
+    /// Replace every cell from `runs` output and mark the whole map for re-baking.
+    /// Returns None, leaving the terrain untouched, unless it covers exactly width x height cells.
+    pub fn set_runs(&mut self, runs: &[(u8, usize)]) -> Option<()> {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for &(cell, count) in runs {
+            if cells.len() + count > self.cells.len() {
+                return None;
+            }
+            cells.extend(std::iter::repeat_n(cell, count));
+        }
+        if cells.len() != self.cells.len() {
+            return None;
+        }
+        self.cells = cells;
+        self.dirty_rects = vec![(0, 0, self.width as i32 - 1, self.height as i32 - 1)];
+        self.unsettled_columns = Some((0, self.width as i32 - 1));
+        Some(())
+    }
+
+    /// `runs` as comma-separated `cell:count` pairs (for saved games).
+    pub fn encode_cells(&self) -> String {
+        let runs: Vec<String> = self.runs().iter().map(|(cell, count)| format!("{}:{}", cell, count)).collect();
+        runs.join(",")
+    }
+
+    /// Inverse of `encode_cells`, with the same all-or-nothing rule as `set_runs`.
+    pub fn decode_cells(&mut self, s: &str) -> Option<()> {
+        let runs = s
+            .split(',')
+            .map(|run| {
+                let (cell, count) = run.split_once(':')?;
+                Some((cell.parse().ok()?, count.parse().ok()?))
+            })
+            .collect::<Option<Vec<(u8, usize)>>>()?;
+        self.set_runs(&runs)
+    }
+
+    /// Blocks per row and per column when the map is cut into `block_size` squares.
+    /// Blocks on the right and bottom edges are clipped to the map.
+    pub fn block_grid(&self, block_size: u32) -> (usize, usize) {
+        let b = block_size.max(1);
+        (self.width.div_ceil(b) as usize, self.height.div_ceil(b) as usize)
+    }
+
+    /// Cell bounds of block `index` (row by row of blocks) as (x0, y0, x1, y1), end exclusive.
+    fn block_bounds(&self, index: usize, block_size: u32) -> Option<(usize, usize, usize, usize)> {
+        let (cols, rows) = self.block_grid(block_size);
+        if index >= cols * rows {
+            return None;
+        }
+        let b = block_size.max(1) as usize;
+        let (x0, y0) = ((index % cols) * b, (index / cols) * b);
+        Some((x0, y0, (x0 + b).min(self.width as usize), (y0 + b).min(self.height as usize)))
+    }
+
+    fn block_cells(&self, index: usize, block_size: u32) -> Option<Vec<u8>> {
+        let (x0, y0, x1, y1) = self.block_bounds(index, block_size)?;
+        let w = self.width as usize;
+        Some((y0..y1).flat_map(|y| self.cells[y * w + x0..y * w + x1].iter().copied()).collect())
+    }
+
+    /// FNV-1a hash of every block's cells, for spotting where two clients' terrain differs
+    /// without sending the whole map.
+    pub fn block_hashes(&self, block_size: u32) -> Vec<u64> {
+        let (cols, rows) = self.block_grid(block_size);
+        (0..cols * rows)
+            .filter_map(|i| self.block_cells(i, block_size))
+            .map(|cells| {
+                cells.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, &c| (h ^ c as u64).wrapping_mul(0x0100_0000_01b3))
+            })
+            .collect()
+    }
+
+    /// One block's cells as `cell:count` runs, in the same form as `encode_cells`.
+    pub fn encode_block(&self, index: usize, block_size: u32) -> Option<String> {
+        let cells = self.block_cells(index, block_size)?;
+        let mut runs: Vec<String> = Vec::new();
+        for chunk in cells.chunk_by(|a, b| a == b) {
+            runs.push(format!("{}:{}", chunk[0], chunk.len()));
+        }
+        Some(runs.join(","))
+    }
+
+    /// Overwrite one block from `encode_block` output and mark it for re-baking. All or
+    /// nothing: returns None, leaving the terrain untouched, unless the runs cover the
+    /// block exactly with known materials.
+    pub fn decode_block(&mut self, index: usize, block_size: u32, s: &str) -> Option<()> {
+        let (x0, y0, x1, y1) = self.block_bounds(index, block_size)?;
+        let size = (x1 - x0) * (y1 - y0);
+        let mut cells = Vec::with_capacity(size);
+        for run in s.split(',') {
+            let (cell, count) = run.split_once(':')?;
+            let (cell, count): (u8, usize) = (cell.parse().ok()?, count.parse().ok()?);
+            if cell > MAX_MATERIAL || cells.len() + count > size {
+                return None;
+            }
+            cells.extend(std::iter::repeat_n(cell, count));
+        }
+        if cells.len() != size {
+            return None;
+        }
+        let w = self.width as usize;
+        let mut src = cells.into_iter();
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let v = src.next()?;
+                if self.cells[y * w + x] != v {
+                    self.cells[y * w + x] = v;
+                    self.mark_dirty(x as i32, y as i32);
+                }
+            }
+        }
+        Some(())
+    }
+
     fn idx(&self, x: i32, y: i32) -> Option<usize> {
         if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
             return None;
@@ -47,12 +404,97 @@ impl Terrain {
 
     pub fn set(&mut self, x: i32, y: i32, v: u8) {
         if let Some(i) = self.idx(x, y) {
-            self.cells[i] = v;
+            if self.cells[i] != v {
+                self.cells[i] = v;
+                self.mark_dirty(x, y);
+            }
         }
     }
 
+    /// Record that the cell at (x, y) changed, growing the most recent dirty rect when
+    /// the cell is close to it so an explosion ends up as one rect, not hundreds.
+    fn mark_dirty(&mut self, x: i32, y: i32) {
+        self.unsettled_columns = Some(match self.unsettled_columns {
+            Some((x1, x2)) => (x1.min(x), x2.max(x)),
+            None => (x, x),
+        });
+        if let Some(r) = self.dirty_rects.last_mut() {
+            if x >= r.0 - DIRTY_MERGE_DIST && x <= r.2 + DIRTY_MERGE_DIST
+                && y >= r.1 - DIRTY_MERGE_DIST && y <= r.3 + DIRTY_MERGE_DIST
+            {
+                *r = (r.0.min(x), r.1.min(y), r.2.max(x), r.3.max(y));
+                return;
+            }
+        }
+        if self.dirty_rects.len() >= MAX_DIRTY_RECTS {
+            let all = self.dirty_rects.drain(..).fold((x, y, x, y), |a, r| {
+                (a.0.min(r.0), a.1.min(r.1), a.2.max(r.2), a.3.max(r.3))
+            });
+            self.dirty_rects.push(all);
+            return;
+        }
+        self.dirty_rects.push((x, y, x, y));
+    }
+
+    fn mark_dirty_idx(&mut self, i: usize) {
+        let w = self.width as usize;
+        self.mark_dirty((i % w) as i32, (i / w) as i32);
+    }
+
+    /// Regions changed since the last call, as (x, y, w, h) in cells.
+    pub fn take_dirty_rects(&mut self) -> Vec<(i32, i32, i32, i32)> {
+        self.dirty_rects
+            .drain(..)
+            .map(|(x1, y1, x2, y2)| (x1, y1, x2 - x1 + 1, y2 - y1 + 1))
+            .collect()
+    }
+
     pub fn is_solid(&self, x: i32, y: i32) -> bool {
-        self.get(x, y) != AIR
+        is_solid_cell(self.get(x, y))
+    }
+
+    /// Gas cells a blast or fire of `radius` around (cx, cy) sets alight, GAS_CATCH cells
+    /// past its edge included: scanned row by row, keeping only cells at least `spacing`
+    /// from the ones already picked, at most `max`. Fixed scan order, so every client
+    /// lights the same cells.
+    pub fn gas_ignition_points(&self, cx: i32, cy: i32, radius: i32, spacing: i32, max: usize) -> Vec<(i32, i32)> {
+        let reach = radius + GAS_CATCH;
+        let mut points: Vec<(i32, i32)> = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if points.len() >= max {
+                    return points;
+                }
+                let (x, y) = (cx + dx, cy + dy);
+                if dx * dx + dy * dy <= reach * reach
+                    && self.get(x, y) == GAS
+                    && points.iter().all(|&(px, py)| (px - x).pow(2) + (py - y).pow(2) >= spacing * spacing)
+                {
+                    points.push((x, y));
+                }
+            }
+        }
+        points
+    }
+
+    /// Approximate outward surface normal at (x, y), pointing from solid into air.
+    /// Sums the offsets of solid cells within `radius` and flips the result, so it is
+    /// integer-exact and deterministic. Returns None when the area is all air or all solid.
+    pub fn surface_normal(&self, x: i32, y: i32, radius: i32) -> Option<(f32, f32)> {
+        let (mut sx, mut sy) = (0i32, 0i32);
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius && self.is_solid(x + dx, y + dy) {
+                    sx += dx;
+                    sy += dy;
+                }
+            }
+        }
+        if sx == 0 && sy == 0 {
+            return None;
+        }
+        let len = ((sx * sx + sy * sy) as f32).sqrt();
+        Some((-sx as f32 / len, -sy as f32 / len))
     }
 
     pub fn find_surface_y(&self, x: i32) -> Option<i32> {
@@ -66,20 +508,134 @@ impl Terrain {
 
     pub fn apply_damage(&mut self, cx: i32, cy: i32, radius: i32) {
         self.damage_log.push((cx, cy, radius));
-        self.apply_damage_no_log(cx, cy, radius);
+        self.modified_cells += self.apply_damage_no_log(cx, cy, radius);
     }
 
-    /// Apply damage without recording to the log (used for replay on reconnect)
-    fn apply_damage_no_log(&mut self, cx: i32, cy: i32, radius: i32) {
+    /// Apply damage without recording to the log (used for replay on reconnect).
+    /// Returns the number of solid cells carved away.
+    fn apply_damage_no_log(&mut self, cx: i32, cy: i32, radius: i32) -> u32 {
         let r2 = radius * radius;
+        let push = radius >= MIN_PUSH_RADIUS && self.push_ratio > 0.0;
+        let band = radius as f32 * (1.0 - PUSH_BAND);
+        // Accumulates push_ratio per carved rim cell; every whole unit pushes one cell.
+        // Scan order is fixed, so every client deposits the same cells.
+        let mut carry = 0.0;
+        let mut carved = 0;
+        let mut pushed: Vec<(i32, i32)> = Vec::new();
         for dy in -radius..=radius {
             for dx in -radius..=radius {
                 if dx * dx + dy * dy <= r2 {
-                    self.set(cx + dx, cy + dy, AIR);
+                    let (x, y) = (cx + dx, cy + dy);
+                    let cell = self.get(x, y);
+                    let dist = ((dx * dx + dy * dy) as f32).sqrt();
+                    // Blast energy falls from 1.0 at the centre to 0.0 at the edge
+                    let energy = 1.0 - dist / radius.max(1) as f32;
+                    if energy < material_resistance(cell) {
+                        continue;
+                    }
+                    if push && is_solid_cell(cell) && dist > band {
+                        carry += self.push_ratio;
+                        if carry >= 1.0 {
+                            carry -= 1.0;
+                            pushed.push((dx, dy));
+                        }
+                    }
+                    if is_solid_cell(cell) {
+                        carved += 1;
+                    }
+                    self.set(x, y, AIR);
                 }
             }
         }
+        for (dx, dy) in pushed {
+            self.push_to_rim(cx, cy, radius, dx, dy);
+        }
         self.regrow_grass_near(cx, cy, radius);
+        self.settle_water_around(cx - radius, cy - radius, cx + radius, cy + radius);
+        carved
+    }
+
+    /// Crumble the sand within `radius` of (cx, cy) into air, leaving every other
+    /// material alone. Used when a ball has stood on sand too long; logged by the game.
+    pub fn crumble_sand(&mut self, cx: i32, cy: i32, radius: i32) {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius && self.get(cx + dx, cy + dy) == SAND {
+                    self.set(cx + dx, cy + dy, AIR);
+                }
+            }
+        }
+        self.settle_water_around(cx - radius, cy - radius, cx + radius, cy + radius);
+    }
+
+    /// Let loose sand fall: each SAND cell with AIR right below it drops one cell, bottom
+    /// row first, so a column of sand comes down together. Stops after `max_moves` so a
+    /// big collapse spreads over several calls. Returns how many cells moved.
+    pub fn settle_sand(&mut self, max_moves: usize) -> usize {
+        let Some((x1, x2)) = self.unsettled_columns else { return 0 };
+        let w = self.width as usize;
+        let mut moved = 0;
+        for y in (0..self.height as usize - 1).rev() {
+            for x in x1 as usize..=x2 as usize {
+                let i = y * w + x;
+                if self.cells[i] == SAND && self.cells[i + w] == AIR {
+                    self.cells[i] = AIR;
+                    self.cells[i + w] = SAND;
+                    self.mark_dirty_idx(i);
+                    self.mark_dirty_idx(i + w);
+                    moved += 1;
+                    if moved >= max_moves {
+                        return moved;
+                    }
+                }
+            }
+        }
+        if moved == 0 {
+            self.unsettled_columns = None;
+        }
+        moved
+    }
+
+    /// Drop all loose sand until it rests, one pass per changed column. The end state
+    /// doesn't depend on how far earlier `settle_sand` calls got, so clients that settled
+    /// at different speeds agree afterwards. Returns true if anything moved.
+    pub fn settle_all_sand(&mut self) -> bool {
+        let Some((x1, x2)) = self.unsettled_columns else { return false };
+        let mut any = false;
+        for x in x1..=x2 {
+            any |= self.settle_sand_column(x as usize);
+        }
+        self.unsettled_columns = None;
+        any
+    }
+
+    /// Drop every grain in column `x` onto whatever is below it, bottom up, in one pass.
+    fn settle_sand_column(&mut self, x: usize) -> bool {
+        let w = self.width as usize;
+        let mut moved = false;
+        // Lowest AIR cell a grain above could fall into
+        let mut landing: Option<usize> = None;
+        for y in (0..self.height as usize).rev() {
+            let i = y * w + x;
+            match self.cells[i] {
+                AIR => {
+                    landing.get_or_insert(y);
+                }
+                SAND => {
+                    if let Some(to) = landing {
+                        self.cells[i] = AIR;
+                        self.cells[to * w + x] = SAND;
+                        self.mark_dirty_idx(i);
+                        self.mark_dirty_idx(to * w + x);
+                        // Everything from here down to the grain is now AIR
+                        landing = Some(to - 1);
+                        moved = true;
+                    }
+                }
+                _ => landing = None,
+            }
+        }
+        moved
     }
 
     /// Replay a damage log on this terrain (e.g. after regenerating from seed on reconnect)
@@ -91,6 +647,205 @@ impl Terrain {
         self.damage_log.extend_from_slice(log);
     }
 
+    /// Solid chunks not connected to the bottom of the map, as cell indices, in scan order.
+    fn floating_chunks(&self) -> Vec<Vec<usize>> {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        // 0 = air/unvisited, 1 = anchored to the map bottom, 2.. = floating chunk id
+        let mut label = vec![0u32; w * h];
+        let mut stack: Vec<usize> = Vec::new();
+
+        let mut flood = |label: &mut Vec<u32>, cells: &[u8], start: usize, id: u32, out: &mut Vec<usize>| {
+            label[start] = id;
+            stack.push(start);
+            while let Some(i) = stack.pop() {
+                out.push(i);
+                let x = i % w;
+                let y = i / w;
+                let mut visit = |n: usize| {
+                    if is_solid_cell(cells[n]) && label[n] == 0 {
+                        label[n] = id;
+                        stack.push(n);
+                    }
+                };
+                if x > 0 { visit(i - 1); }
+                if x + 1 < w { visit(i + 1); }
+                if y > 0 { visit(i - w); }
+                if y + 1 < h { visit(i + w); }
+            }
+        };
+
+        let mut anchored = Vec::new();
+        for x in 0..w {
+            let i = (h - 1) * w + x;
+            if is_solid_cell(self.cells[i]) && label[i] == 0 {
+                flood(&mut label, &self.cells, i, 1, &mut anchored);
+            }
+        }
+
+        let mut chunks: Vec<Vec<usize>> = Vec::new();
+        for i in 0..w * h {
+            if is_solid_cell(self.cells[i]) && label[i] == 0 {
+                let mut chunk = Vec::new();
+                flood(&mut label, &self.cells, i, chunks.len() as u32 + 2, &mut chunk);
+                chunks.push(chunk);
+            }
+        }
+        chunks
+    }
+
+    /// Edge cells of every floating chunk, i.e. the terrain the next collapse will drop.
+    pub fn floating_outline(&self) -> Vec<(i32, i32)> {
+        let w = self.width as i32;
+        let mut outline = Vec::new();
+        for chunk in self.floating_chunks() {
+            for i in chunk {
+                let (x, y) = (i as i32 % w, i as i32 / w);
+                let edge = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .any(|&(dx, dy)| !is_solid_cell(self.get(x + dx, y + dy)));
+                if edge {
+                    outline.push((x, y));
+                }
+            }
+        }
+        outline
+    }
+
+    /// Drop every solid chunk that is no longer connected to the bottom of the map
+    /// straight down until it lands. Chunks resolve lowest-first in scan order so every
+    /// client settles the same way. Returns true if anything moved.
+    pub fn collapse_floating(&mut self) -> bool {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        let mut chunks = self.floating_chunks();
+        if chunks.is_empty() {
+            return false;
+        }
+
+        // Lowest chunk first so chunks stacked above land on the settled ones
+        chunks.sort_by_key(|c| std::cmp::Reverse(c.iter().map(|&i| i / w).max().unwrap_or(0)));
+
+        let mut moved = false;
+        for chunk in &chunks {
+            let mats: Vec<u8> = chunk.iter().map(|&i| self.cells[i]).collect();
+            for &i in chunk {
+                self.cells[i] = AIR;
+            }
+            let mut drop = h;
+            for &i in chunk {
+                let x = i % w;
+                let y = i / w;
+                let mut d = 0;
+                while d < drop && y + d + 1 < h && !is_solid_cell(self.cells[(y + d + 1) * w + x]) {
+                    d += 1;
+                }
+                drop = drop.min(d);
+            }
+            for (&i, &m) in chunk.iter().zip(mats.iter()) {
+                self.cells[i + drop * w] = m;
+            }
+            if drop > 0 {
+                for &i in chunk {
+                    self.mark_dirty_idx(i);
+                    self.mark_dirty_idx(i + drop * w);
+                }
+                moved = true;
+            }
+        }
+        // Falling chunks overwrite any water they land in and can open up pockets
+        if moved {
+            self.settle_water();
+        }
+        moved
+    }
+
+    /// Settle water if any sits within a few cells of the given rectangle, i.e. the
+    /// terrain just changed next to a pocket. Cheap no-op everywhere else.
+    pub fn settle_water_around(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) -> bool {
+        let margin = 2;
+        let near = ((y1 - margin).max(0)..=(y2 + margin).min(self.height as i32 - 1)).any(|y| {
+            ((x1 - margin).max(0)..=(x2 + margin).min(self.width as i32 - 1)).any(|x| self.get(x, y) == WATER)
+        });
+        near && self.settle_water()
+    }
+
+    /// Run the water flow to rest. Each step, bottom-most cells first, a water cell falls
+    /// as far as its column is open; if it's resting on something it moves to the nearest
+    /// drop (air over air) within WATER_SPREAD along its row and falls from there. Water
+    /// reaching the sea or the map edge drains away. Purely cell-based, so every client
+    /// ends in the same state. Returns true if any water moved.
+    pub fn settle_water(&mut self) -> bool {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        let sea_row = (self.water_level() as usize).min(h - 1);
+        let mut water: Vec<usize> = (0..w * h).filter(|&i| self.cells[i] == WATER).collect();
+        let mut any_moved = false;
+
+        for step in 0..MAX_WATER_STEPS {
+            // Bottom rows first so columns fall together instead of spreading out
+            water.sort_unstable_by(|a, b| b.cmp(a));
+            // Alternate the preferred side each step so pockets drain evenly
+            let sides: [isize; 2] = if step % 2 == 0 { [-1, 1] } else { [1, -1] };
+            let mut moved = false;
+            for cell in water.iter_mut() {
+                let i = *cell;
+                let x = i % w;
+                let y = i / w;
+                if y + 1 >= sea_row || x == 0 || x + 1 >= w {
+                    self.cells[i] = AIR;
+                    self.mark_dirty_idx(i);
+                    *cell = usize::MAX;
+                    moved = true;
+                    continue;
+                }
+                let from = if self.cells[i + w] == AIR { Some(i) } else { self.nearest_drop(x, y, sides) };
+                let Some(mut target) = from else { continue };
+                while self.cells[target + w] == AIR && (target + w) / w < sea_row {
+                    target += w;
+                }
+                self.cells[i] = AIR;
+                self.cells[target] = WATER;
+                self.mark_dirty_idx(i);
+                self.mark_dirty_idx(target);
+                *cell = target;
+                moved = true;
+            }
+            water.retain(|&i| i != usize::MAX);
+            if !moved {
+                break;
+            }
+            any_moved = true;
+        }
+        any_moved
+    }
+
+    /// Closest air cell with air below it along the row from (x, y), reachable through
+    /// open air, if any lies within WATER_SPREAD.
+    fn nearest_drop(&self, x: usize, y: usize, sides: [isize; 2]) -> Option<usize> {
+        let w = self.width as usize;
+        let mut best: Option<(i32, usize)> = None;
+        for &d in &sides {
+            for dist in 1..=WATER_SPREAD {
+                let nx = x as isize + d * dist as isize;
+                if nx < 0 || nx >= w as isize {
+                    break;
+                }
+                let n = y * w + nx as usize;
+                if self.cells[n] != AIR {
+                    break;
+                }
+                if self.cells[n + w] == AIR {
+                    if best.is_none_or(|(bd, _)| dist < bd) {
+                        best = Some((dist, n));
+                    }
+                    break;
+                }
+            }
+        }
+        best.map(|(_, n)| n)
+    }
+
     /// Regrow grass over any rectangular area (used after drill carvings).
     pub fn refresh_grass_in_area(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
         let margin = 4;
@@ -100,12 +855,28 @@ impl Terrain {
                     continue;
                 }
                 if self.get(x, y + 1) == DIRT || self.get(x, y + 1) == STONE {
-                    self.set(x, y, GRASS);
+                    self.set(x, y, self.theme.topsoil());
                 }
             }
         }
     }
 
+    /// Deposit one cell of dirt in the first air cell just outside the crater, along the
+    /// line from its centre through (dx, dy). Dropped if the rim there is already full.
+    fn push_to_rim(&mut self, cx: i32, cy: i32, radius: i32, dx: i32, dy: i32) {
+        let len = ((dx * dx + dy * dy) as f32).sqrt();
+        let (nx, ny) = (dx as f32 / len, dy as f32 / len);
+        for step in 1..=PUSH_REACH {
+            let d = (radius + step) as f32;
+            let x = cx + (nx * d).round() as i32;
+            let y = cy + (ny * d).round() as i32;
+            if self.idx(x, y).is_some() && self.get(x, y) == AIR {
+                self.set(x, y, DIRT);
+                return;
+            }
+        }
+    }
+
     fn regrow_grass_near(&mut self, cx: i32, cy: i32, radius: i32) {
         let margin = 3;
         for dy in -(radius + margin)..=(radius + margin) {
@@ -119,18 +890,60 @@ impl Terrain {
                     continue;
                 }
                 if self.get(x, y + 1) == DIRT || self.get(x, y + 1) == STONE {
+                    self.set(x, y, self.theme.topsoil());
+                }
+            }
+        }
+    }
+
+    /// Slow regrowth for long matches: turn up to `max_cells` exposed dirt tops (dirt with
+    /// air right above) into grass. Columns are visited in a fixed order from a start
+    /// column picked by `seed`, and about one in four is seeded to grow, so every client
+    /// grows the same cells. Only the material changes, never solidity. Returns the number
+    /// of cells changed.
+    pub fn grow_grass(&mut self, seed: u32, max_cells: usize) -> usize {
+        if self.theme.topsoil() != GRASS {
+            return 0;
+        }
+        let w = self.width as i32;
+        let mut s = lcg(seed);
+        let start = (s >> 16) as i32 % w;
+        let mut grown = 0;
+        for i in 0..w {
+            if grown >= max_cells {
+                break;
+            }
+            s = lcg(s);
+            if (s >> 16) & 3 != 0 {
+                continue;
+            }
+            let x = (start + i) % w;
+            for y in 1..self.height as i32 {
+                if grown < max_cells && self.get(x, y) == DIRT && self.get(x, y - 1) == AIR {
                     self.set(x, y, GRASS);
+                    grown += 1;
                 }
             }
         }
+        grown
     }
 
     pub fn bake_image(&self) -> Image {
         let mut img = Image::gen_image_color(self.width as u16, self.height as u16, BLANK);
-        for y in 0..self.height {
-            for x in 0..self.width {
+        self.bake_rect(&mut img, 0, 0, self.width as i32, self.height as i32);
+        img
+    }
+
+    /// Re-colour only the (x, y, w, h) region of an image previously made by `bake_image`.
+    pub fn bake_rect(&self, img: &mut Image, x: i32, y: i32, w: i32, h: i32) {
+        let x1 = x.max(0) as u32;
+        let y1 = y.max(0) as u32;
+        let x2 = ((x + w) as u32).min(self.width);
+        let y2 = ((y + h) as u32).min(self.height);
+        for y in y1..y2 {
+            for x in x1..x2 {
                 let cell = self.cells[(y * self.width + x) as usize];
-                let color = cell_color(cell, x as i32, y as i32);
+                let color = cell_color(cell, x as i32, y as i32, self.theme);
                 let idx = ((y * self.width + x) * 4) as usize;
                 img.bytes[idx] = (color.r * 255.0) as u8;
                 img.bytes[idx + 1] = (color.g * 255.0) as u8;
@@ -138,22 +951,60 @@ impl Terrain {
                 img.bytes[idx + 3] = (color.a * 255.0) as u8;
             }
         }
-        img
+    }
+
+    /// Fallback for when no terrain texture could be allocated: draw the world-space
+    /// region (x1, y1)-(x2, y2) as `block`-sized rectangles, merging runs of the same
+    /// cell type along each row. Coarser and slower than the texture but never blank.
+    pub fn draw_blocks(&self, x1: f32, y1: f32, x2: f32, y2: f32, block: i32) {
+        let bx1 = (x1.max(0.0) as i32 / block) * block;
+        let by1 = (y1.max(0.0) as i32 / block) * block;
+        let bx2 = (x2 as i32).min(self.width as i32);
+        let by2 = (y2 as i32).min(self.height as i32);
+        let mut y = by1;
+        while y < by2 {
+            let mut x = bx1;
+            while x < bx2 {
+                let cell = self.get(x + block / 2, y + block / 2);
+                let start = x;
+                while x < bx2 && self.get(x + block / 2, y + block / 2) == cell {
+                    x += block;
+                }
+                if cell != AIR {
+                    let color = cell_color(cell, start, y, self.theme);
+                    draw_rectangle(start as f32, y as f32, (x - start) as f32, block as f32, color);
+                }
+            }
+            y += block;
+        }
     }
 }
 
-fn cell_color(cell: u8, x: i32, y: i32) -> Color {
+fn cell_color(cell: u8, x: i32, y: i32, theme: TerrainTheme) -> Color {
     let n = ((x.wrapping_mul(7) ^ y.wrapping_mul(13)) & 0x1F) as f32 / 31.0;
-    match cell {
-        GRASS => Color::new(0.18 + n * 0.08, 0.50 + n * 0.12, 0.12 + n * 0.06, 1.0),
-        DIRT => Color::new(0.48 + n * 0.08, 0.32 + n * 0.06, 0.18 + n * 0.04, 1.0),
-        STONE => Color::new(0.38 + n * 0.08, 0.38 + n * 0.08, 0.42 + n * 0.08, 1.0),
-        LAVA => {
+    match (cell, theme) {
+        (GRASS, TerrainTheme::Desert) => Color::new(0.62 + n * 0.06, 0.58 + n * 0.06, 0.30 + n * 0.04, 1.0),
+        (GRASS, TerrainTheme::Snow) => Color::new(0.88 + n * 0.06, 0.92 + n * 0.05, 0.96 + n * 0.03, 1.0),
+        (GRASS, TerrainTheme::Volcanic) => Color::new(0.30 + n * 0.05, 0.28 + n * 0.05, 0.27 + n * 0.05, 1.0),
+        (GRASS, _) => Color::new(0.18 + n * 0.08, 0.50 + n * 0.12, 0.12 + n * 0.06, 1.0),
+        (DIRT, TerrainTheme::Desert) => Color::new(0.84 + n * 0.06, 0.72 + n * 0.06, 0.46 + n * 0.05, 1.0),
+        (DIRT, TerrainTheme::Snow) => Color::new(0.40 + n * 0.06, 0.33 + n * 0.05, 0.28 + n * 0.04, 1.0),
+        (DIRT, TerrainTheme::Volcanic) => Color::new(0.26 + n * 0.06, 0.19 + n * 0.04, 0.16 + n * 0.03, 1.0),
+        (DIRT, _) => Color::new(0.48 + n * 0.08, 0.32 + n * 0.06, 0.18 + n * 0.04, 1.0),
+        (STONE, TerrainTheme::Desert) => Color::new(0.66 + n * 0.06, 0.50 + n * 0.05, 0.36 + n * 0.04, 1.0),
+        (STONE, TerrainTheme::Snow) => Color::new(0.44 + n * 0.08, 0.48 + n * 0.08, 0.56 + n * 0.08, 1.0),
+        (STONE, TerrainTheme::Volcanic) => Color::new(0.17 + n * 0.06, 0.16 + n * 0.05, 0.18 + n * 0.06, 1.0),
+        (STONE, _) => Color::new(0.38 + n * 0.08, 0.38 + n * 0.08, 0.42 + n * 0.08, 1.0),
+        (ICE, _) => Color::new(0.68 + n * 0.08, 0.85 + n * 0.06, 0.95 + n * 0.04, 0.95),
+        (LAVA, _) => {
             // Animated lava glow effect
             let glow = ((x + y) as f32 * 0.1).sin() * 0.15 + 0.85;
             Color::new(0.95 * glow, 0.25 * glow, 0.05 * glow, 1.0)
         }
-        WOOD => Color::new(0.35 + n * 0.1, 0.20 + n * 0.05, 0.10 + n * 0.03, 1.0),
+        (WOOD, _) => Color::new(0.35 + n * 0.1, 0.20 + n * 0.05, 0.10 + n * 0.03, 1.0),
+        (WATER, _) => Color::new(0.12 + n * 0.04, 0.35 + n * 0.06, 0.70 + n * 0.08, 0.85),
+        (SAND, _) => Color::new(0.93 + n * 0.04, 0.84 + n * 0.05, 0.58 + n * 0.05, 1.0),
+        (GAS, _) => Color::new(0.62 + n * 0.08, 0.78 + n * 0.06, 0.30 + n * 0.05, 0.35),
         _ => BLANK,
     }
 }
@@ -206,14 +1057,26 @@ fn noise_2d(x: f32, y: f32, seed: f32) -> f32 {
     (h & 0x00FFFFFF) as f32 / 16777216.0
 }
 
-pub fn generate(seed: u32) -> Terrain {
+/// Scale a feature count by terrain density; 0.5 keeps the base count unchanged.
+fn density_count(base: u32, density: f32) -> u32 {
+    (base as f32 * (0.5 + density)).round() as u32
+}
+
+/// Generate a map. `density` (0.0–1.0) trades open space for solid ground:
+/// low values give sparse sniper-friendly maps, high values dug-in close-quarters
+/// ones, and 0.5 reproduces the classic generator. `mirror` makes the right half a
+/// copy of the left, flipped, so neither side has a terrain advantage. `theme` reworks
+/// the materials once the shape is done, so every theme shares a seed's layout. `gas`
+/// fills the cave alcoves with flammable gas pockets.
+pub fn generate(seed: u32, density: f32, mirror: bool, theme: TerrainTheme, gas: bool) -> Terrain {
+    let density = density.clamp(0.0, 1.0);
     let w = WIDTH;
     let h = HEIGHT;
     let mut t = Terrain::new(w, h);
     let sf = seed as f32;
 
     // Base ground level — biased toward bottom so hills can rise prominently
-    let base_ground = h as f32 * 0.58;
+    let base_ground = h as f32 * (0.58 - (density - 0.5) * 0.12);
     let mut heights = vec![0.0f32; w as usize];
 
     // Use seed to vary the terrain style per game
@@ -222,7 +1085,7 @@ pub fn generate(seed: u32) -> Terrain {
     // ---- Layer 1: Define 4-8 distinct hill "segments" across the map ----
     // This creates the classic Balls look of distinct hills separated by valleys
     s = lcg(s);
-    let num_hills = 3 + (s >> 16) as u32 % 3; // 3-5 hills
+    let num_hills = density_count(3 + (s >> 16) as u32 % 3, density).max(1); // 3-5 hills at default density
     struct HillDef { center: f32, width: f32, height: f32, flat_top: f32 }
     let mut hill_defs: Vec<HillDef> = Vec::new();
     
@@ -269,7 +1132,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // ---- Layer 3: Seed-driven valleys (cut between hills) ----
     s = lcg(s);
-    let num_valleys = 1 + (s >> 16) as u32 % 3; // 1-3 deep valleys
+    let num_valleys = density_count(1 + (s >> 16) as u32 % 3, 1.0 - density); // 1-3 deep valleys at default density
     for _ in 0..num_valleys {
         s = lcg(s);
         let valley_x = LAND_START_X + 100.0 + (s >> 16) as f32 / 65535.0 * (PLAYABLE_LAND_WIDTH - 200.0);
@@ -345,7 +1208,7 @@ pub fn generate(seed: u32) -> Terrain {
     {
         let mut s_cave = lcg(seed.wrapping_add(2500));
         let cave_scale = 0.035; // coarser noise for big caverns
-        let cave_threshold = 0.42; // lower -> more air; slightly stricter to protect surface
+        let cave_threshold = 0.42 - (density - 0.5) * 0.2; // lower -> more air; slightly stricter to protect surface
         for x in LAND_START_X as i32..=LAND_END_X as i32 {
             if x < 0 || x >= w as i32 { continue; }
             let ground = heights[x as usize] as i32;
@@ -389,7 +1252,7 @@ pub fn generate(seed: u32) -> Terrain {
     }
 
     let mut s = lcg(seed.wrapping_add(1000));
-    let num_platforms = 3 + (s >> 16) as u32 % 4;
+    let num_platforms = density_count(3 + (s >> 16) as u32 % 4, density);
     let land_width = (LAND_END_X - LAND_START_X) as i32;
     for _ in 0..num_platforms {
         s = lcg(s);
@@ -416,7 +1279,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate improved caves with more variety
     s = lcg(s.wrapping_add(2000));
-    let num_caves = 12 + (s >> 16) as u32 % 6; // Many caves (12-17)
+    let num_caves = density_count(12 + (s >> 16) as u32 % 6, 1.0 - density); // Many caves (12-17 at default density)
     let mut cave_positions = Vec::new();
     
     for i in 0..num_caves {
@@ -451,7 +1314,8 @@ pub fn generate(seed: u32) -> Terrain {
             }
         }
         
-        // Add small alcoves to some caves for more complexity
+        // Add small alcoves to some caves for more complexity; with gas pockets on they
+        // fill with gas instead of air (no extra rolls, so the map shape is the same)
         s = lcg(s);
         if (s >> 16) % 2 == 0 {
             s = lcg(s);
@@ -465,7 +1329,7 @@ pub fn generate(seed: u32) -> Terrain {
                     let yd = (dy - alcove_h / 2) as f32;
                     let dist = ((xd * xd + yd * yd) as f32).sqrt();
                     if dist < (alcove_w.min(alcove_h) as f32) * 0.5 {
-                        t.set(cx + alcove_dx + dx - alcove_w / 2, cy + dy - alcove_h / 2, AIR);
+                        t.set(cx + alcove_dx + dx - alcove_w / 2, cy + dy - alcove_h / 2, if gas { GAS } else { AIR });
                     }
                 }
             }
@@ -549,7 +1413,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate trenches — narrow deep cuts across the terrain surface
     s = lcg(s.wrapping_add(3000));
-    let num_trenches = 2 + (s >> 16) as u32 % 2; // 2-3 trenches
+    let num_trenches = density_count(2 + (s >> 16) as u32 % 2, 1.0 - density); // 2-3 trenches at default density
     for _ in 0..num_trenches {
         s = lcg(s);
         let tx = LAND_START_X as i32 + 120 + (s >> 16) as i32 % (land_width - 240);
@@ -576,7 +1440,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate stone ruin clusters — scattered rubble from crumbled structures
     s = lcg(s.wrapping_add(3500));
-    let num_ruins = 2 + (s >> 16) as u32 % 3; // 2-4 ruin sites
+    let num_ruins = density_count(2 + (s >> 16) as u32 % 3, density); // 2-4 ruin sites at default density
     for _ in 0..num_ruins {
         s = lcg(s);
         let rx = LAND_START_X as i32 + 100 + (s >> 16) as i32 % (land_width - 200);
@@ -611,7 +1475,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate pre-made craters — as if the battlefield has already seen combat
     s = lcg(s.wrapping_add(3800));
-    let num_craters = 2 + (s >> 16) as u32 % 3; // 2-4 craters
+    let num_craters = density_count(2 + (s >> 16) as u32 % 3, 1.0 - density); // 2-4 craters at default density
     for _ in 0..num_craters {
         s = lcg(s);
         let crx = LAND_START_X as i32 + 100 + (s >> 16) as i32 % (land_width - 200);
@@ -689,7 +1553,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate buildings (multi-story structures)
     s = lcg(s.wrapping_add(5000));
-    let num_buildings = 1 + (s >> 16) as u32 % 3; // 1-3 buildings
+    let num_buildings = density_count(1 + (s >> 16) as u32 % 3, density); // 1-3 buildings at default density
     for _ in 0..num_buildings {
         s = lcg(s);
         let bx = LAND_START_X as i32 + 100 + (s >> 16) as i32 % (land_width - 200);
@@ -742,7 +1606,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate bunkers (underground reinforced structures)
     s = lcg(s.wrapping_add(6000));
-    let num_bunkers = 0 + (s >> 16) as u32 % 2; // 0-1 bunkers
+    let num_bunkers = density_count(0 + (s >> 16) as u32 % 2, density); // 0-1 bunkers at default density
     for _ in 0..num_bunkers {
         s = lcg(s);
         let bunker_x = LAND_START_X as i32 + 150 + (s >> 16) as i32 % (land_width - 300);
@@ -840,7 +1704,7 @@ pub fn generate(seed: u32) -> Terrain {
 
     // Generate stone towers/pillars
     s = lcg(s.wrapping_add(9000));
-    let num_towers = 1 + (s >> 16) as u32 % 3; // 1-3 towers
+    let num_towers = density_count(1 + (s >> 16) as u32 % 3, density); // 1-3 towers at default density
     for _ in 0..num_towers {
         s = lcg(s);
         let tower_x = LAND_START_X as i32 + 100 + (s >> 16) as i32 % (land_width - 200);
@@ -874,5 +1738,362 @@ pub fn generate(seed: u32) -> Terrain {
         }
     }
 
+    // Water pockets: small lakes in enclosed depressions
+    s = lcg(s.wrapping_add(10000));
+    let num_pockets = (s >> 16) % 3; // 0-2 lakes
+    let mut placed = 0;
+    for _ in 0..num_pockets * 4 {
+        if placed >= num_pockets {
+            break;
+        }
+        s = lcg(s);
+        let x = LAND_START_X as i32 + 60 + (s >> 16) as i32 % (land_width - 120);
+        if place_water_pocket(&mut t, x) {
+            placed += 1;
+        }
+    }
+
+    // Sand patches: a few shallow beds of loose sand just under the surface, thickest in
+    // the middle, that slump into any crater dug beneath them
+    s = lcg(s.wrapping_add(11000));
+    let num_patches = 2 + (s >> 16) % 3; // 2-4 patches
+    for _ in 0..num_patches {
+        s = lcg(s);
+        let cx = LAND_START_X as i32 + 60 + (s >> 16) as i32 % (land_width - 120);
+        s = lcg(s);
+        let half_w = 15 + (s >> 16) as i32 % 25;
+        for x in cx - half_w..=cx + half_w {
+            let Some(top) = t.find_surface_y(x) else { continue };
+            let depth = SAND_PATCH_DEPTH * (half_w - (x - cx).abs()) / half_w;
+            for y in top..top + depth {
+                if matches!(t.get(x, y), GRASS | DIRT) {
+                    t.set(x, y, SAND);
+                }
+            }
+        }
+    }
+
+    apply_theme(&mut t, seed, theme);
+
+    if mirror {
+        mirror_left_half(&mut t);
+    }
+    // Sand generated over a cave or overhang starts the match already fallen
+    t.settle_all_sand();
     t
 }
+
+/// Swap a generated map's materials for `theme`. Seeded apart from the shape passes, so
+/// grassland maps come out exactly as before.
+fn apply_theme(t: &mut Terrain, seed: u32, theme: TerrainTheme) {
+    t.theme = theme;
+    let sf = seed as f32;
+    let (w, h) = (t.width as i32, t.height as i32);
+    match theme {
+        TerrainTheme::Grassland => {}
+        TerrainTheme::Desert => {
+            // Dry pools, and soft sand down to twice the usual topsoil depth
+            for x in 0..w {
+                let mut depth = -1;
+                for y in 0..h {
+                    let c = t.get(x, y);
+                    if c == WATER {
+                        t.set(x, y, AIR);
+                    } else if c == GRASS {
+                        t.set(x, y, DIRT);
+                    }
+                    if depth < 0 && is_solid_cell(c) {
+                        depth = 0;
+                    }
+                    if depth >= 0 {
+                        if c == STONE && depth < 32 {
+                            t.set(x, y, DIRT);
+                        }
+                        depth += 1;
+                    }
+                }
+            }
+            // Loose drifts over stretches of the surface
+            for x in LAND_START_X as i32..=LAND_END_X as i32 {
+                if smooth_noise(x as f32, 60.0, sf + 9200.0) < 0.6 {
+                    continue;
+                }
+                let Some(top) = t.find_surface_y(x) else { continue };
+                for y in top..top + 8 {
+                    if t.get(x, y) == DIRT {
+                        t.set(x, y, SAND);
+                    }
+                }
+            }
+        }
+        TerrainTheme::Snow => {
+            for c in t.cells.iter_mut().filter(|c| **c == WATER) {
+                *c = ICE;
+            }
+            // Ice sheets on stretches of the surface
+            for x in LAND_START_X as i32..=LAND_END_X as i32 {
+                if smooth_noise(x as f32, 70.0, sf + 9100.0) < 0.62 {
+                    continue;
+                }
+                let Some(top) = t.find_surface_y(x) else { continue };
+                if t.get(x, top) != GRASS {
+                    continue;
+                }
+                for y in top..top + 4 {
+                    if matches!(t.get(x, y), GRASS | DIRT) {
+                        t.set(x, y, ICE);
+                    }
+                }
+            }
+        }
+        TerrainTheme::Volcanic => {
+            for c in t.cells.iter_mut() {
+                match *c {
+                    GRASS => *c = DIRT,
+                    WATER => *c = LAVA,
+                    _ => {}
+                }
+            }
+            // Thick stone under thin ash, and two wavy lava seams through the deep rock
+            for x in 0..w {
+                let mut depth = -1;
+                for y in 0..h {
+                    let c = t.get(x, y);
+                    if depth < 0 && is_solid_cell(c) {
+                        depth = 0;
+                    }
+                    if depth >= 0 {
+                        if c == DIRT && depth >= 8 {
+                            t.set(x, y, STONE);
+                        }
+                        depth += 1;
+                    }
+                }
+            }
+            for (i, base) in [620.0, 700.0].iter().enumerate() {
+                let vs = sf + 9300.0 + i as f32 * 100.0;
+                for x in 0..w {
+                    let y0 = (base + (smooth_noise(x as f32, 80.0, vs) - 0.5) * 60.0) as i32;
+                    let thick = 2 + (smooth_noise(x as f32, 25.0, vs + 50.0) * 4.0) as i32;
+                    for y in y0..y0 + thick {
+                        if t.get(x, y) == STONE {
+                            t.set(x, y, LAVA);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Overwrite the right half with the left half flipped about the vertical centreline.
+/// Features straddling the centre keep their left side and get its reflection, so they
+/// come out symmetric too. Whole columns are copied, which keeps water resting on the
+/// same ground it sat on.
+fn mirror_left_half(t: &mut Terrain) {
+    let w = t.width as usize;
+    for row in t.cells.chunks_exact_mut(w) {
+        for x in 0..w / 2 {
+            row[w - 1 - x] = row[x];
+        }
+    }
+}
+
+/// Fill the basin around the surface at column `x` with water, as deep as it stays
+/// enclosed. Returns false if there's no enclosed basin big enough there.
+fn place_water_pocket(t: &mut Terrain, x: i32) -> bool {
+    let Some(surface_y) = t.find_surface_y(x) else { return false };
+    if t.get(x, surface_y - 1) != AIR {
+        return false;
+    }
+    let mut best: Option<Vec<(i32, i32)>> = None;
+    for depth in 1..=MAX_POCKET_DEPTH {
+        match pocket_below_level(t, x, surface_y - 1, surface_y - depth) {
+            Some(cells) => best = Some(cells),
+            None => break,
+        }
+    }
+    match best {
+        Some(cells) if cells.len() >= MIN_POCKET_CELLS => {
+            for (cx, cy) in cells {
+                t.set(cx, cy, WATER);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Air cells reachable from (x, y) without rising above `level`. None if the region
+/// leaks: it grows too large, reaches the sea, or touches the edge of the land.
+fn pocket_below_level(t: &Terrain, x: i32, y: i32, level: i32) -> Option<Vec<(i32, i32)>> {
+    let mut seen = std::collections::HashSet::new();
+    let mut stack = vec![(x, y)];
+    let mut cells = Vec::new();
+    seen.insert((x, y));
+    while let Some((cx, cy)) = stack.pop() {
+        if cells.len() >= MAX_POCKET_CELLS
+            || cy as f32 >= t.water_level()
+            || cx as f32 <= t.land_start_x()
+            || cx as f32 >= t.land_end_x()
+        {
+            return None;
+        }
+        cells.push((cx, cy));
+        for (nx, ny) in [(cx - 1, cy), (cx + 1, cy), (cx, cy - 1), (cx, cy + 1)] {
+            if ny >= level && t.get(nx, ny) == AIR && seen.insert((nx, ny)) {
+                stack.push((nx, ny));
+            }
+        }
+    }
+    Some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stone_survives_outside_the_inner_blast() {
+        let mut t = Terrain::from_material_buffer(WIDTH, HEIGHT, vec![STONE; (WIDTH * HEIGHT) as usize]).unwrap();
+        t.apply_damage(700, 400, 50);
+        // Stone only carves within 60% of the radius: 30 px
+        assert_eq!(t.get(700 + 29, 400), AIR);
+        assert_eq!(t.get(700 + 31, 400), STONE);
+        assert_eq!(t.get(700, 400 - 31), STONE);
+    }
+
+    /// Sand hanging over gaps of different depths, with stone and water below.
+    fn sand_over_gaps() -> Terrain {
+        let mut t = Terrain::new(WIDTH, HEIGHT);
+        t.settle_all_sand();
+        for x in 100..140 {
+            t.set(x, 700, STONE);
+            t.set(x, 650, WATER);
+            for y in 300..300 + (x - 95) {
+                t.set(x, y, SAND);
+            }
+            t.set(x, 320 + x % 7, DIRT);
+        }
+        t
+    }
+
+    #[test]
+    fn settle_all_sand_matches_settling_step_by_step() {
+        let mut stepped = sand_over_gaps();
+        while stepped.settle_sand(usize::MAX) > 0 {}
+        let mut settled = sand_over_gaps();
+        assert!(settled.settle_all_sand());
+        assert_eq!(settled.cells, stepped.cells);
+        // Nothing left to do, and nothing changed to look at again
+        assert!(!settled.settle_all_sand());
+        assert_eq!(settled.unsettled_columns, None);
+    }
+
+    #[test]
+    fn settling_only_revisits_changed_columns() {
+        let mut t = sand_over_gaps();
+        t.settle_all_sand();
+        t.set(120, 100, SAND);
+        assert_eq!(t.unsettled_columns, Some((120, 120)));
+        assert!(t.settle_all_sand());
+        assert_eq!(t.get(120, 100), AIR);
+    }
+
+    /// Flat dirt ground with its surface at y=400.
+    fn flat_ground() -> Terrain {
+        let cells = (0..WIDTH * HEIGHT).map(|i| if i / WIDTH >= 400 { DIRT } else { AIR }).collect();
+        Terrain::from_material_buffer(WIDTH, HEIGHT, cells).unwrap()
+    }
+
+    /// Dirt above the ground after a blast at (700, 420), by distance from its centre.
+    /// (The blast also regrows a grass skin on the exposed surface.)
+    fn rim_cells(t: &Terrain) -> Vec<f32> {
+        let mut out = Vec::new();
+        for y in 300..400 {
+            for x in 600..800 {
+                if t.get(x, y) == DIRT {
+                    out.push((((x - 700) * (x - 700) + (y - 420) * (y - 420)) as f32).sqrt());
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn crater_push_piles_dirt_just_outside_the_rim() {
+        let mut plain = flat_ground();
+        // Just below the surface, so the upper part of the crater breaks out into the air
+        plain.apply_damage(700, 420, 30);
+        assert!(rim_cells(&plain).is_empty());
+
+        let mut pushed = flat_ground();
+        pushed.push_ratio = 0.5;
+        pushed.apply_damage(700, 420, 30);
+        let rim = rim_cells(&pushed);
+        assert!(!rim.is_empty());
+        assert!(rim.iter().all(|&d| d > 30.0 && d <= 30.0 + PUSH_REACH as f32 + 1.0), "{:?}", rim);
+        // Some of the carved material is lost, none is created
+        let solid = |t: &Terrain| t.cells.iter().filter(|&&c| is_solid_cell(c)).count();
+        assert!(solid(&pushed) > solid(&plain));
+        assert!(solid(&pushed) < solid(&flat_ground()));
+    }
+
+    #[test]
+    fn small_blasts_never_push() {
+        let mut t = flat_ground();
+        t.push_ratio = 1.0;
+        t.apply_damage(700, 404, MIN_PUSH_RADIUS - 1);
+        assert!(rim_cells(&t).is_empty());
+    }
+
+    #[test]
+    fn mirrored_maps_are_symmetric() {
+        for (seed, theme, gas) in [(1, TerrainTheme::Grassland, false), (77, TerrainTheme::Snow, true), (9001, TerrainTheme::Volcanic, true)] {
+            let t = generate(seed, 0.5, true, theme, gas);
+            let w = t.width as i32;
+            for y in 0..t.height as i32 {
+                for x in 0..w / 2 {
+                    assert_eq!(t.get(x, y), t.get(w - 1 - x, y), "seed {} at ({}, {})", seed, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mirroring_keeps_the_left_half() {
+        let plain = generate(42, 0.5, false, TerrainTheme::Desert, false);
+        let mirrored = generate(42, 0.5, true, TerrainTheme::Desert, false);
+        let w = plain.width as usize;
+        for (a, b) in plain.cells.chunks_exact(w).zip(mirrored.cells.chunks_exact(w)) {
+            assert_eq!(a[..w / 2], b[..w / 2]);
+        }
+    }
+
+    #[test]
+    fn denser_settings_give_more_solid_ground() {
+        let solid_ratio = |density: f32| {
+            let t = generate(1234, density, false, TerrainTheme::Grassland, false);
+            t.cells.iter().filter(|&&c| is_solid_cell(c)).count() as f32 / t.cells.len() as f32
+        };
+        let ratios: Vec<f32> = [0.0, 0.5, 1.0].iter().map(|&d| solid_ratio(d)).collect();
+        assert!(ratios[0] < ratios[1] && ratios[1] < ratios[2], "{:?}", ratios);
+    }
+
+    #[test]
+    fn half_resolution_keeps_the_map_and_scales_its_layout() {
+        let mut full = flat_ground();
+        full.set(701, 300, STONE);
+        full.set(900, 300, GAS);
+        full.set(901, 300, GAS);
+        let half = full.half_resolution();
+        assert_eq!((half.width, half.height), map_size(true));
+        // The ground surface moves to y=200; a lone stone cell is outvoted by air, and
+        // gas filling half its block wins the tie by coming first
+        assert_eq!((half.get(10, 199), half.get(10, 200)), (AIR, DIRT));
+        assert_eq!(half.get(350, 150), AIR);
+        assert_eq!(half.get(450, 150), GAS);
+        assert_eq!(half.water_level(), full.water_level() / 2.0);
+        assert_eq!((half.land_start_x(), half.land_end_x()), (10.0, 690.0));
+    }
+}
@@ -0,0 +1,79 @@
+use macroquad::miniquad::gl;
+use macroquad::prelude::*;
+
+/// Downscale factors tried in order when a full-size terrain texture can't be allocated
+/// (low-memory mobile GPUs). Drawing always stretches back to the full terrain size.
+const SCALES: [u16; 3] = [1, 2, 4];
+
+/// Upload the baked terrain image, downscaled by at least `min_scale`. Falls back to half
+/// and then quarter resolution when the GPU rejects the texture, and returns None when
+/// nothing fits so the caller can draw the terrain on the CPU instead. The second value
+/// is the downscale factor that was used.
+pub fn upload(img: &Image, min_scale: u16) -> Option<(Texture2D, u16)> {
+    // Unit tests build games without a window, and so without a GL context
+    if cfg!(test) {
+        return None;
+    }
+    let max = max_texture_size();
+    for scale in SCALES.into_iter().filter(|&s| s >= min_scale) {
+        let (w, h) = (img.width / scale, img.height / scale);
+        if w == 0 || h == 0 {
+            break;
+        }
+        if max.is_some_and(|m| w as i32 > m || h as i32 > m) {
+            continue;
+        }
+        let scaled = (scale > 1).then(|| downscale(img, scale));
+        clear_gl_errors();
+        let tex = Texture2D::from_image(scaled.as_ref().unwrap_or(img));
+        if gl_ok() {
+            tex.set_filter(FilterMode::Nearest);
+            return Some((tex, scale));
+        }
+        log_failure(w, h);
+    }
+    None
+}
+
+/// Nearest-neighbour shrink by an integer factor.
+fn downscale(img: &Image, scale: u16) -> Image {
+    let (w, h) = (img.width / scale, img.height / scale);
+    let mut out = Image::gen_image_color(w, h, BLANK);
+    let s = scale as usize;
+    for y in 0..h as usize {
+        for x in 0..w as usize {
+            let src = ((y * s) * img.width as usize + x * s) * 4;
+            let dst = (y * w as usize + x) * 4;
+            out.bytes[dst..dst + 4].copy_from_slice(&img.bytes[src..src + 4]);
+        }
+    }
+    out
+}
+
+fn max_texture_size() -> Option<i32> {
+    let mut max = 0;
+    unsafe { gl::glGetIntegerv(gl::GL_MAX_TEXTURE_SIZE, &mut max) };
+    (max > 0).then_some(max)
+}
+
+fn clear_gl_errors() {
+    // glGetError returns one queued flag per call; a handful drains any realistic backlog
+    for _ in 0..8 {
+        if gl_ok() {
+            break;
+        }
+    }
+}
+
+fn gl_ok() -> bool {
+    unsafe { gl::glGetError() == gl::GL_NO_ERROR }
+}
+
+#[allow(unused_variables)]
+fn log_failure(w: u16, h: u16) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let msg = format!("[TERRAIN] Texture {}x{} failed to allocate, trying smaller\0", w, h);
+        unsafe { crate::console_log(msg.as_ptr()); }
+    }
+}
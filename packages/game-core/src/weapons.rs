@@ -39,6 +39,43 @@ pub enum Weapon {
     Drill,
     SuperSheep,
     BuildWall,
+    Shield,
+    Foxhole,
+    Flares,
+}
+
+/// How a weapon's blast damage drops off from the centre to the edge of its reach.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Falloff {
+    /// Straight line from full damage to none
+    Linear,
+    /// Full damage anywhere inside the blast
+    Constant,
+    /// Full damage only near the centre, fading fast towards the edge
+    Steep,
+}
+
+impl Falloff {
+    /// Fraction of full damage at `t` of the way from the centre (0.0) to the edge (1.0).
+    pub fn factor(&self, t: f32) -> f32 {
+        if t >= 1.0 {
+            return 0.0;
+        }
+        let t = t.max(0.0);
+        match self {
+            Falloff::Linear => 1.0 - t,
+            Falloff::Constant => 1.0,
+            Falloff::Steep => (1.0 - t) * (1.0 - t),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Falloff::Linear => "Linear",
+            Falloff::Constant => "Even",
+            Falloff::Steep => "Steep",
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -51,6 +88,25 @@ pub enum WeaponType {
     Instant,
 }
 
+/// Everything the weapon menu's info panel shows, gathered in one place from the
+/// per-property methods so the panel always matches how the weapon behaves.
+#[derive(Clone, Copy, PartialEq)]
+pub struct WeaponStats {
+    pub weapon_type: WeaponType,
+    pub category: WeaponCategory,
+    pub damage: i32,
+    pub explosion_radius: f32,
+    pub falloff: Falloff,
+    /// Seconds until it goes off; None for impact, proximity and instant weapons
+    pub fuse: Option<f32>,
+    pub bounces: i32,
+    /// Launch speed at full charge in px/s, only for fired projectiles
+    pub max_speed: Option<f32>,
+    pub cluster_count: usize,
+    pub cluster_damage: i32,
+    pub cluster_radius: f32,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum WeaponCategory {
     Explosives,
@@ -59,6 +115,14 @@ pub enum WeaponCategory {
     Special,
 }
 
+/// Order the categories appear in the weapon menu (and keyboard cycling follows)
+pub const MENU_CATEGORIES: [WeaponCategory; 4] = [
+    WeaponCategory::Explosives,
+    WeaponCategory::Ballistics,
+    WeaponCategory::Special,
+    WeaponCategory::Utilities,
+];
+
 impl Weapon {
     pub fn name(&self) -> &str {
         match self {
@@ -86,6 +150,9 @@ impl Weapon {
             Weapon::Drill => "Drill",
             Weapon::SuperSheep => "Super Sheep",
             Weapon::BuildWall => "Build Wall",
+            Weapon::Shield => "Shield",
+            Weapon::Foxhole => "Foxhole",
+            Weapon::Flares => "Flares",
         }
     }
 
@@ -100,7 +167,8 @@ impl Weapon {
             
             Weapon::Airstrike | Weapon::NapalmStrike => WeaponType::Airstrike,
             
-            Weapon::Teleport | Weapon::Jetpack | Weapon::Parachute | Weapon::Rope | Weapon::BuildWall | Weapon::Drill => WeaponType::Utility,
+            Weapon::Teleport | Weapon::Jetpack | Weapon::Parachute | Weapon::Rope | Weapon::BuildWall | Weapon::Drill
+            | Weapon::Shield | Weapon::Foxhole | Weapon::Flares => WeaponType::Utility,
             
             Weapon::BaseballBat => WeaponType::Melee,
             
@@ -157,6 +225,39 @@ impl Weapon {
         }
     }
 
+    /// Damage falloff of the weapon's main blast. Grenades hurt evenly across their small
+    /// blast; the big bombs are devastating at the centre but fade fast.
+    pub fn falloff_kind(&self) -> Falloff {
+        match self {
+            Weapon::Grenade => Falloff::Constant,
+            Weapon::HolyHandGrenade => Falloff::Steep,
+            _ => Falloff::Linear,
+        }
+    }
+
+    /// Range of elevations (radians above the horizontal, either side) the weapon can be
+    /// aimed within, or None for any direction. The mortar only lobs upward and the drill
+    /// only bores sideways or down.
+    pub fn aim_limits(&self) -> Option<(f32, f32)> {
+        match self {
+            Weapon::Mortar => Some((0.35, 1.45)),
+            Weapon::Drill => Some((-1.45, 0.35)),
+            _ => None,
+        }
+    }
+
+    /// `angle` moved into the weapon's aim limits on the side it points to.
+    pub fn clamp_aim(&self, angle: f32) -> f32 {
+        let Some((lo, hi)) = self.aim_limits() else {
+            return angle;
+        };
+        let pi = std::f32::consts::PI;
+        let right = angle.cos() >= 0.0;
+        let elevation = if right { -angle } else if angle < 0.0 { pi + angle } else { angle - pi };
+        let e = elevation.clamp(lo, hi);
+        if right { -e } else if e > 0.0 { e - pi } else { pi + e }
+    }
+
     pub fn speed_factor(&self) -> f32 {
         match self {
             Weapon::Bazooka => 12.0,
@@ -177,6 +278,79 @@ impl Weapon {
         }
     }
 
+    /// Launch speed (px/s) per percent of charge at full charge, so the 0-100% meter maps
+    /// to a weapon-appropriate velocity: lobbed grenades stay slow, rockets fly fast.
+    /// The bazooka's 12.0 is the baseline the worker bot's aim sim assumes.
+    pub fn muzzle_speed_scale(&self) -> f32 {
+        match self {
+            Weapon::Bazooka => 12.0,
+            Weapon::Grenade => 10.0,
+            Weapon::Shotgun => 12.0,
+            Weapon::ClusterBomb => 10.5,
+            Weapon::BananaBomb => 10.0,
+            Weapon::HolyHandGrenade => 9.5,
+            Weapon::HomingMissile => 12.0,
+            Weapon::Mortar => 12.0,
+            Weapon::Sheep => 9.0,
+            Weapon::Uzi => 15.0,
+            Weapon::BananaBonanza => 10.0,
+            Weapon::SuperSheep => 13.0,
+            _ => 12.0,
+        }
+    }
+
+    /// How heavy the shell is to throw, 1.0 for the bazooka. Bends the charge curve
+    /// (see `launch_speed`): heavy shells need more charge to reach the same distance,
+    /// light ones carry far on a short charge. Full charge flies as before either way.
+    pub fn mass(&self) -> f32 {
+        match self {
+            Weapon::Grenade => 0.85,
+            Weapon::BananaBomb => 0.9,
+            Weapon::ClusterBomb => 1.15,
+            Weapon::HolyHandGrenade => 1.35,
+            Weapon::Sheep => 1.25,
+            Weapon::Mortar => 1.2,
+            _ => 1.0,
+        }
+    }
+
+    /// How hard the wind pushes the shell in flight, 1.0 for the bazooka. Light
+    /// throwables drift further than the rocket; heavy or self-propelled shells hold
+    /// their line better.
+    pub fn wind_factor(&self) -> f32 {
+        match self {
+            Weapon::Grenade => 1.4,
+            Weapon::BananaBomb | Weapon::BananaBonanza => 1.3,
+            Weapon::ClusterBomb => 1.2,
+            Weapon::Sheep => 1.5,
+            Weapon::HolyHandGrenade => 0.8,
+            Weapon::Mortar => 0.7,
+            _ => 1.0,
+        }
+    }
+
+    /// Launch speed (px/s) for a 0-100% charge: the charge is raised to the weapon's
+    /// `mass` before the muzzle scaling, so equal charges throw a light shell further.
+    pub fn launch_speed(&self, charge: f32) -> f32 {
+        let charge = charge.clamp(0.0, 100.0) / 100.0;
+        charge.powf(self.mass()) * 100.0 * self.muzzle_speed_scale()
+    }
+
+    /// Backward shove (px/s at full charge) the shooter takes when the weapon goes off.
+    /// Launched guns kick; thrown, placed and targeted items don't. Kept under a couple
+    /// of ball widths of slide so only the sniper can nudge someone off a ledge.
+    pub fn recoil(&self) -> f32 {
+        match self {
+            Weapon::Bazooka => 60.0,
+            Weapon::HomingMissile => 60.0,
+            Weapon::Mortar => 90.0,
+            Weapon::Shotgun => 80.0,
+            Weapon::Uzi => 50.0,
+            Weapon::SniperRifle => 120.0,
+            _ => 0.0,
+        }
+    }
+
     pub fn fuse_time(&self) -> f32 {
         match self {
             Weapon::Grenade => 3.0,
@@ -213,6 +387,57 @@ impl Weapon {
         }
     }
 
+    /// Blast radius of each bomblet a cluster weapon splits into.
+    pub fn cluster_radius(&self) -> f32 {
+        match self {
+            Weapon::ClusterBomb => 15.0,
+            Weapon::BananaBomb => 18.0,
+            Weapon::BananaBonanza => 20.0,
+            Weapon::Mortar => 12.0,
+            _ => 10.0,
+        }
+    }
+
+    /// Damage at the centre of each bomblet's blast, before the shooter's damage scale.
+    pub fn cluster_damage(&self) -> i32 {
+        match self {
+            Weapon::ClusterBomb => 20,
+            Weapon::BananaBomb => 25,
+            Weapon::BananaBonanza => 18,
+            Weapon::Mortar => 15,
+            _ => 10,
+        }
+    }
+
+    /// Fuse of the first bomblet; each later one burns 0.1 s longer so they go off in turn.
+    /// The same for every cluster weapon so far.
+    pub fn cluster_fuse(&self) -> f32 {
+        1.0
+    }
+
+    /// How widely bomblets scatter as (base, spread): launch speeds range over
+    /// base..base+spread px/s. The same for every cluster weapon so far.
+    pub fn cluster_spread(&self) -> (f32, f32) {
+        (100.0, 80.0)
+    }
+
+    pub fn stats(&self) -> WeaponStats {
+        let weapon_type = self.weapon_type();
+        WeaponStats {
+            weapon_type,
+            category: self.category(),
+            damage: self.base_damage(),
+            explosion_radius: self.explosion_radius(),
+            falloff: self.falloff_kind(),
+            fuse: (self.fuse_time() > 0.0).then(|| self.fuse_time()),
+            bounces: self.max_bounces(),
+            max_speed: (weapon_type == WeaponType::Projectile).then(|| 100.0 * self.muzzle_speed_scale()),
+            cluster_count: self.cluster_count(),
+            cluster_damage: self.cluster_damage(),
+            cluster_radius: self.cluster_radius(),
+        }
+    }
+
     pub fn from_key(k: u8) -> Option<Weapon> {
         match k {
             1 => Some(Weapon::Bazooka),
@@ -252,13 +477,45 @@ impl Weapon {
             "Sniper Rifle" => Some(Weapon::SniperRifle),
             "Uzi" => Some(Weapon::Uzi),
             "Banana Bonanza" => Some(Weapon::BananaBonanza),
-            "Concrete Shell" => Some(Weapon::Drill),
+            // "Concrete Shell" is the drill's old name, still accepted from older clients
+            "Drill" | "Concrete Shell" => Some(Weapon::Drill),
             "Super Sheep" => Some(Weapon::SuperSheep),
             "Build Wall" => Some(Weapon::BuildWall),
+            "Shield" => Some(Weapon::Shield),
+            "Foxhole" => Some(Weapon::Foxhole),
+            "Flares" => Some(Weapon::Flares),
             _ => None,
         }
     }
 
+    /// Every weapon in the order the menu lists them: by category, then `all()` order.
+    pub fn menu_order() -> Vec<Weapon> {
+        MENU_CATEGORIES
+            .iter()
+            .flat_map(|cat| Weapon::all().iter().copied().filter(move |w| w.category() == *cat))
+            .collect()
+    }
+
+    /// The weapon after this one in menu order that `allowed` accepts, wrapping around.
+    pub fn next_weapon(self, allowed: impl Fn(Weapon) -> bool) -> Weapon {
+        self.cycle(1, allowed)
+    }
+
+    /// The weapon before this one in menu order that `allowed` accepts, wrapping around.
+    pub fn prev_weapon(self, allowed: impl Fn(Weapon) -> bool) -> Weapon {
+        self.cycle(-1, allowed)
+    }
+
+    fn cycle(self, step: i32, allowed: impl Fn(Weapon) -> bool) -> Weapon {
+        let order = Weapon::menu_order();
+        let n = order.len() as i32;
+        let start = order.iter().position(|w| *w == self).unwrap_or(0) as i32;
+        (1..=n)
+            .map(|i| order[(start + step * i).rem_euclid(n) as usize])
+            .find(|w| allowed(*w))
+            .unwrap_or(self)
+    }
+
     pub fn all() -> &'static [Weapon] {
         &[
             Weapon::Bazooka,
@@ -278,10 +535,14 @@ impl Weapon {
             Weapon::SniperRifle,
             Weapon::Uzi,
             Weapon::Teleport,
+            Weapon::Rope,
             Weapon::BananaBonanza,
             Weapon::Drill,
             Weapon::SuperSheep,
             Weapon::BuildWall,
+            Weapon::Shield,
+            Weapon::Foxhole,
+            Weapon::Flares,
         ]
     }
     
@@ -293,7 +554,8 @@ impl Weapon {
             
             Weapon::Shotgun | Weapon::HomingMissile | Weapon::SniperRifle | Weapon::Uzi => WeaponCategory::Ballistics,
             
-            Weapon::Teleport | Weapon::Jetpack | Weapon::Parachute | Weapon::Rope | Weapon::BuildWall | Weapon::Drill => WeaponCategory::Utilities,
+            Weapon::Teleport | Weapon::Jetpack | Weapon::Parachute | Weapon::Rope | Weapon::BuildWall | Weapon::Drill
+            | Weapon::Shield | Weapon::Foxhole | Weapon::Flares => WeaponCategory::Utilities,
             
             Weapon::Sheep | Weapon::SuperSheep | Weapon::BaseballBat => WeaponCategory::Special,
         }
@@ -325,6 +587,9 @@ impl Weapon {
             Weapon::Drill => "[]",
             Weapon::SuperSheep => "@!",
             Weapon::BuildWall => "###",
+            Weapon::Shield => "()",
+            Weapon::Foxhole => "\\_/",
+            Weapon::Flares => "'*'",
         }
     }
     
@@ -354,6 +619,57 @@ impl Weapon {
             Weapon::Drill => "Drills a walkable tunnel through terrain. No damage.",
             Weapon::SuperSheep => "Flying explosive sheep!",
             Weapon::BuildWall => "Place a short wooden wall at target location",
+            Weapon::Shield => "Absorbs the next hit. Ends your turn.",
+            Weapon::Foxhole => "Digs a pit and drops you in. Ends your turn.",
+            Weapon::Flares => "Decoy that homing missiles chase. Ends your turn.",
+        }
+    }
+}
+
+/// Angle offset (within ±`spread` / 2) and speed factor (0.95 to 1.05) of each bullet in
+/// an Uzi burst, rolled from `seed`. Every client firing the same shot rolls the same burst.
+pub fn uzi_burst(seed: u32, count: usize, spread: f32) -> Vec<(f32, f32)> {
+    let mut rng = seed;
+    let mut roll = || {
+        rng = crate::lcg(rng);
+        ((rng >> 16) & 0x7FFF) as f32 / 32768.0
+    };
+    (0..count)
+        .map(|_| ((roll() - 0.5) * spread, 0.95 + roll() * 0.1))
+        .collect()
+}
+
+/// Weapons each team starts with when the random-loadout match option is on.
+pub const LOADOUT_SIZE: usize = 8;
+
+/// Seeded subset of the arsenal for `team`. Every client derives the same list from
+/// the shared map seed. The Bazooka is always included so a team is never unarmed.
+pub fn random_loadout(seed: u32, team: u32) -> Vec<Weapon> {
+    let mut pool: Vec<Weapon> = Weapon::all()
+        .iter()
+        .copied()
+        .filter(|w| *w != Weapon::Bazooka)
+        .collect();
+    let mut rng = crate::lcg(seed ^ (team + 1).wrapping_mul(0x9E37_79B9));
+    for i in (1..pool.len()).rev() {
+        rng = crate::lcg(rng);
+        let j = (rng >> 16) as usize % (i + 1);
+        pool.swap(i, j);
+    }
+    let mut loadout = vec![Weapon::Bazooka];
+    loadout.extend(pool.into_iter().take(LOADOUT_SIZE - 1));
+    loadout
+}
+
+impl WeaponType {
+    pub fn name(&self) -> &str {
+        match self {
+            WeaponType::Projectile => "Projectile",
+            WeaponType::Placed => "Placed",
+            WeaponType::Utility => "Utility",
+            WeaponType::Airstrike => "Airstrike",
+            WeaponType::Melee => "Melee",
+            WeaponType::Instant => "Hitscan",
         }
     }
 }
@@ -368,3 +684,62 @@ impl WeaponCategory {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uzi_burst_repeats_for_the_same_seed() {
+        assert_eq!(uzi_burst(1234, 10, 0.2), uzi_burst(1234, 10, 0.2));
+        assert_ne!(uzi_burst(1234, 10, 0.2), uzi_burst(1235, 10, 0.2));
+    }
+
+    #[test]
+    fn uzi_burst_stays_within_its_spread() {
+        for seed in [0, 1, 77, 0xDEAD_BEEF] {
+            let burst = uzi_burst(seed, 20, 0.2);
+            assert_eq!(burst.len(), 20);
+            for &(angle, speed) in &burst {
+                assert!((-0.1..0.1).contains(&angle), "angle {}", angle);
+                assert!((0.95..1.05).contains(&speed), "speed {}", speed);
+            }
+            // Bullets in one burst don't all fly the same way
+            assert!(burst.iter().any(|b| b != &burst[0]));
+        }
+    }
+
+    #[test]
+    fn falloff_curves_run_from_full_to_nothing() {
+        for falloff in [Falloff::Linear, Falloff::Constant, Falloff::Steep] {
+            assert_eq!(falloff.factor(0.0), 1.0);
+            assert_eq!(falloff.factor(-0.5), 1.0);
+            assert_eq!(falloff.factor(1.0), 0.0);
+            assert_eq!(falloff.factor(1.5), 0.0);
+        }
+        assert_eq!(Falloff::Linear.factor(0.5), 0.5);
+        assert_eq!(Falloff::Constant.factor(0.99), 1.0);
+        assert_eq!(Falloff::Steep.factor(0.5), 0.25);
+        // Steep never does more than linear past the centre
+        for i in 1..10 {
+            let t = i as f32 / 10.0;
+            assert!(Falloff::Steep.factor(t) < Falloff::Linear.factor(t));
+        }
+    }
+
+    #[test]
+    fn charge_curve_ends_are_the_same_for_every_mass() {
+        for w in Weapon::all() {
+            assert_eq!(w.launch_speed(0.0), 0.0);
+            assert_eq!(w.launch_speed(100.0), 100.0 * w.muzzle_speed_scale());
+            assert_eq!(w.launch_speed(150.0), w.launch_speed(100.0));
+        }
+    }
+
+    #[test]
+    fn every_weapon_is_found_by_its_name() {
+        for &w in Weapon::all() {
+            assert!(Weapon::from_name(w.name()) == Some(w), "{}", w.name());
+        }
+    }
+}